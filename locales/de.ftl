@@ -0,0 +1,151 @@
+# Deutsche Meldungen
+
+## Aufruf
+
+could-not-create-config-dir = Konfigurationsverzeichnis konnte nicht erstellt werden: { $error }
+could-not-create-data-dir = Datenverzeichnis konnte nicht erstellt werden: { $error }
+unrecognized-command = Unbekannter Befehl: { $command }
+no-editor = Kein Editor gefunden: $EDITOR oder $VISUAL setzen, oder nano oder vi installieren
+no-config = Fehler: Keine Konfigurationsdatei { $file } gefunden (erwartet unter { $path }); mit `game init` erstellen
+config-exists = Die Konfigurationsdatei existiert bereits: { $path }
+could-not-write-config = Konfigurationsdatei konnte nicht geschrieben werden: { $error }
+could-not-sync = Synchronisierung fehlgeschlagen: { $error }
+
+command-help = Die Befehle erklären
+command-installed = Festlegen, ob ein Spiel auf diesem Rechner installiert ist
+command-list = Spiele im Format „spiel_id - name“ auflisten
+command-count = Die Anzahl der Spiele ausgeben, die list auflisten würde
+command-tree = Die Spiele unter den Verzeichnissen anzeigen, in denen sie liegen
+command-play = Spiele nacheinander spielen, angegeben durch ihre Spiel-IDs
+command-tags = Alle Tags auflisten
+command-play-random = Ein zufälliges Spiel spielen
+command-init = Eine Konfigurationsdatei zum Einstieg schreiben
+command-template = Einen Beispieleintrag für ein Spiel zum Einfügen in die Konfigurationsdatei ausgeben
+command-scan = Ein Verzeichnis nach Spielen durchsuchen, die noch nicht in der Konfiguration sind
+command-edit = Die Konfigurationsdatei bearbeiten
+command-stats = Spielstatistiken anzeigen
+command-info = Details und Statistiken eines Spiels anzeigen
+command-queue = Die Spielwarteschlange verwalten
+command-sessions = Aufgezeichnete Spielsitzungen auflisten
+command-log-session = Eine anderswo gespielte Sitzung festhalten
+command-journal = Einen Eintrag zum Journal eines Spiels hinzufügen oder das Journal anzeigen
+command-milestone = Erreichte Meilensteine eines Spiels festhalten und auflisten
+command-sync = Statistiken und Sitzungsprotokoll mit einer anderen Kopie zusammenführen und beide aktualisieren
+command-backlog = Installierte Spiele auflisten, die weniger als die angegebenen Stunden gespielt wurden
+command-suggest = Ein paar Spiele vorschlagen, die als Nächstes gespielt werden könnten
+command-nudge = Installierte Spiele auflisten, die länger nicht gespielt wurden
+command-bench = Ein Spiel mit FPS-Protokoll starten und die Bildrate zusammenfassen
+command-tricks = winetricks (oder protontricks) für das Präfix eines Spiels ausführen
+command-winecfg = winecfg im Präfix eines Spiels ausführen
+command-regedit = den Registrierungseditor im Präfix eines Spiels ausführen oder eine .reg-Datei importieren
+command-cache = Shader-Caches von Spielen auflisten oder entfernen
+command-prefixes = Die von Spielen verwendeten Wine-Präfixe auflisten
+command-runners = Installierte Wine/Proton-Versionen auflisten oder die Standardversion wählen
+command-update = Die Aktualisierungsbefehle der Spiele ausführen
+command-verify = Die Dateien eines Spiels mit seinem Manifest der Datei-Hashes vergleichen
+command-check = Die Konfigurationsdatei prüfen und vor wahrscheinlichen Fehlern warnen
+command-overview = Die ganze Bibliothek zusammenfassen
+command-perf = Die Bildrate aus dem neuesten FPS-Protokoll eines Spiels zusammenfassen
+command-chart = Ein Diagramm der Spielzeit oder der Sitzungen als PNG-Bild zeichnen
+command-report = Einen Bericht über die Bibliothek und die Spielstatistiken schreiben
+command-steam-wrap = Steam-Startoptionen ausgeben, die ein Spiel über wrap-exec starten
+command-shortcut = Eine Desktop-Verknüpfung schreiben, die ein Spiel startet
+command-icon = Das Symbol aus der .exe eines Wine-Spiels extrahieren
+command-saves = Erraten, wo ein Spiel seine Spielstände speichert, für sein save_dir
+command-wrap-exec = Einen Befehl als das Spiel ausführen, mit dessen Umgebung und Statistiken
+command-tui = Bibliothek, Statistiken und Sitzungen in einer Übersicht durchsuchen
+command-watch = Sitzungen für Spiele aufzeichnen, die außerhalb von game gestartet wurden
+command-menu = Installierte Spiele für dmenu oder rofi auflisten und das gewählte spielen
+command-wrapped = Ein Jahr an Spielsitzungen zusammenfassen
+
+## Fehler
+
+no-game-id = Eine Spiel-ID ist erforderlich
+could-not-change-directory = Verzeichnis konnte nicht gewechselt werden zu: { $dir }
+no-such-game = Kein solches Spiel: { $game_id }
+command-returned-failure = Befehl fehlgeschlagen: { $command }
+execution-failed = Spiel konnte nicht ausgeführt werden
+not-installed = Spiel ist nicht installiert
+could-not-write-stats = Spielstatistiken konnten nicht geschrieben werden: { $error }
+unrecognized-subcommand = Unbekannter Unterbefehl: { $subcommand }
+queue-empty = Die Warteschlange ist leer
+no-such-queued-game = Spiel in der Warteschlange existiert nicht mehr: { $game_id }
+could-not-write-queue = Warteschlange konnte nicht geschrieben werden: { $error }
+invalid-argument = Ungültiges Argument: { $argument }
+could-not-write-sessions = Sitzungsprotokoll konnte nicht geschrieben werden: { $error }
+no-matching-games = Keine passenden Spiele
+could-not-update-config = Konfigurationsdatei konnte nicht aktualisiert werden: { $error }
+no-fps-log = Kein FPS-Protokoll für das Spiel gefunden: { $game_id }
+no-wine-prefix = Spiel { $game_id } hat kein WINEPREFIX in seiner env und keine steam_id
+no-such-runner = Kein solcher Runner: { $runner }
+no-sandbox = Sandbox nicht verfügbar: { $sandbox }
+no-update-command = Spiel hat kein update_cmd: { $game_id }
+no-game-dir = Spiel hat kein dir: { $game_id }
+no-manifest = Kein Manifest für Spiel { $game_id } (mit verify --init erstellen)
+could-not-write-manifest = Manifest konnte nicht geschrieben werden: { $error }
+could-not-write-report = Bericht konnte nicht geschrieben werden: { $error }
+could-not-write-chart = Diagramm konnte nicht geschrieben werden: { $error }
+no-wrapped-command = Nach -- ist ein auszuführender Befehl erforderlich
+could-not-listen = HTTP-Anfragen können nicht angenommen werden: { $error }
+terminal-error = Terminalfehler: { $error }
+no-such-menu-entry = Kein Spiel passt zum Menüeintrag: { $entry }
+verification-failed = Spiel { $game_id } hat { $count } fehlende oder geänderte Dateien
+could-not-write-installed-state = Installationsstatus konnte nicht geschrieben werden: { $error }
+could-not-write-journal = Journal konnte nicht geschrieben werden: { $error }
+could-not-write-milestones = Meilensteine konnten nicht geschrieben werden: { $error }
+could-not-write-shortcut = Verknüpfung konnte nicht geschrieben werden: { $error }
+no-icon = Kein Symbol in der ausführbaren Datei gefunden: { $game_id }
+could-not-write-icon = Symbol konnte nicht geschrieben werden: { $error }
+no-save-dir = Kein Verzeichnis gefunden, das die Spielstände enthalten könnte: { $game_id }
+
+## Konfigurationsfehler
+
+missing-name = Spiel ohne name: { $game_id }
+missing-command = Spiel ohne cmd: { $game_id }
+game-not-table = Der Schlüssel 'game' muss eine Tabelle sein
+missing-game-table = Eine Tabelle 'game' ist erforderlich
+no-such-directory-prefix = Spiel { $game_id } hat ein nicht vorhandenes Verzeichnispräfix: { $prefix }
+unrecognized-option = Unbekannte Option: { $option }
+invalid-option = Spiel { $game_id } hat einen ungültigen Wert für Option: { $option }
+invalid-setting = Ungültiger Wert für Einstellung: { $setting }
+invalid-game-id = Spiel-ID darf keinen Zeilenumbruch enthalten: { $game_id }
+directory-cycle = Verzeichnisse verweisen im Kreis aufeinander: { $cycle }
+no-such-required-game = Spiel { $game_id } benötigt ein nicht vorhandenes Spiel: { $required }
+
+## Statistiken
+
+session-game = Spiel: { $name } ({ $game_id })
+session-play-time = Spielzeit: { $hours }h{ $minutes }m{ $seconds }s ({ $total }s)
+session-idle-time = Leerlaufzeit: { $idle } (nicht gezählt)
+stats-heading = Statistiken für { $name } ({ $game_id })
+stats-play-time = Spielzeit: { $play_time }
+stats-last-played = Zuletzt gespielt: { $last_played }
+stats-goal = Ziel: { $goal_hours }h { $progress }
+stats-total-play-time = Gesamte Spielzeit: { $play_time }
+stats-undone = Sitzung rückgängig gemacht: { $session }
+stats-nothing-to-undo = Keine Sitzung zum Rückgängigmachen
+no-stats = Keine Statistiken gefunden
+
+## Info
+
+info-heading = { $name } ({ $game_id })
+info-version = Version: { $version }
+info-status = Status: { $status }
+info-tags = Tags: { $tags }
+info-dir = Verzeichnis: { $dir }
+info-command = Befehl: { $command }
+info-version-play-time = Gespielt mit { $version }: { $play_time }
+info-milestone = Meilenstein: { $milestone } ({ $date })
+
+## Erinnerungen
+
+nudge-played = Du hast { $name } seit { $days } Tagen nicht gespielt
+nudge-never-played = Du hast { $name } seit dem Hinzufügen vor { $days } Tagen nicht gespielt
+
+## Vorschläge
+
+suggest-favorite = { $game } (ein Favorit, zuletzt vor { $days } Tagen gespielt)
+suggest-untouched = { $game } (nie gespielt)
+suggest-untouched-length = { $game } (nie gespielt, { $hours }h lang)
+suggest-finishable = { $game } (noch { $left } bis zum Ende)
+suggest-tag = { $game } (Tag { $tag }, wie das, was du zuletzt gespielt hast)
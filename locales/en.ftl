@@ -0,0 +1,104 @@
+# English messages, which are also used for anything another catalog is missing
+
+## Usage
+
+could-not-create-config-dir = Could not create config directory: { $error }
+could-not-create-data-dir = Could not create data directory: { $error }
+unrecognized-command = Unrecognized command: { $command }
+no-editor = No editor found: set $EDITOR or $VISUAL, or install nano or vi
+no-config = Error: No { $file } config file found (expected at { $path }); run `game init` to create one
+config-exists = The config file already exists: { $path }
+could-not-write-config = Could not write config file: { $error }
+could-not-sync = Could not sync: { $error }
+
+## Errors
+
+no-game-id = A game ID is required
+could-not-change-directory = Could not change directory to: { $dir }
+no-such-game = No such game: { $game_id }
+command-returned-failure = Command failed: { $command }
+execution-failed = Could not execute game
+not-installed = Game is not installed
+could-not-write-stats = Could not write game stats: { $error }
+unrecognized-subcommand = Unrecognized subcommand: { $subcommand }
+queue-empty = The queue is empty
+no-such-queued-game = Queued game no longer exists: { $game_id }
+could-not-write-queue = Could not write queue: { $error }
+invalid-argument = Invalid argument: { $argument }
+could-not-write-sessions = Could not write session log: { $error }
+no-matching-games = No games match
+could-not-update-config = Could not update config file: { $error }
+no-fps-log = No FPS log found for game: { $game_id }
+no-wine-prefix = Game { $game_id } has no WINEPREFIX in its env and no steam_id
+no-such-runner = No such runner: { $runner }
+no-sandbox = Sandbox not available: { $sandbox }
+no-update-command = Game has no update_cmd: { $game_id }
+no-game-dir = Game has no dir: { $game_id }
+no-manifest = No manifest for game { $game_id } (create one with verify --init)
+could-not-write-manifest = Could not write manifest: { $error }
+could-not-write-report = Could not write report: { $error }
+could-not-write-chart = Could not write chart: { $error }
+no-wrapped-command = A command to run is required after --
+could-not-listen = Could not listen for HTTP requests: { $error }
+terminal-error = Terminal error: { $error }
+no-such-menu-entry = No game matches the menu entry: { $entry }
+verification-failed = Game { $game_id } has { $count } missing or changed files
+could-not-write-installed-state = Could not write installed state: { $error }
+could-not-write-journal = Could not write journal: { $error }
+could-not-write-milestones = Could not write milestones: { $error }
+could-not-write-shortcut = Could not write shortcut: { $error }
+no-icon = No icon found in the executable of: { $game_id }
+could-not-write-icon = Could not write icon: { $error }
+no-save-dir = No directory that looks like it has the saves of: { $game_id }
+
+## Config errors
+
+missing-name = Game missing name: { $game_id }
+missing-command = Game missing cmd: { $game_id }
+game-not-table = The 'game' key must correspond to a table
+missing-game-table = A 'game' table is required'
+no-such-directory-prefix = Game { $game_id } has nonexistent directory prefix: { $prefix }
+unrecognized-option = Unrecognized option: { $option }
+invalid-option = Game { $game_id } has an invalid value for option: { $option }
+invalid-setting = Invalid value for setting: { $setting }
+invalid-game-id = Game ID cannot contain a line break: { $game_id }
+directory-cycle = Directories refer to each other in a cycle: { $cycle }
+no-such-required-game = Game { $game_id } requires nonexistent game: { $required }
+
+## Stats
+
+session-game = Game: { $name } ({ $game_id })
+session-play-time = Play Time: { $hours }h{ $minutes }m{ $seconds }s ({ $total }sec)
+session-idle-time = Idle Time: { $idle } (not counted)
+stats-heading = { $name } ({ $game_id }) Statistics
+stats-play-time = Play Time: { $play_time }
+stats-last-played = Last Played: { $last_played }
+stats-goal = Goal: { $goal_hours }h { $progress }
+stats-total-play-time = Total Play Time: { $play_time }
+stats-undone = Undid session: { $session }
+stats-nothing-to-undo = No session to undo
+no-stats = No stats found
+
+## Info
+
+info-heading = { $name } ({ $game_id })
+info-version = Version: { $version }
+info-status = Status: { $status }
+info-tags = Tags: { $tags }
+info-dir = Directory: { $dir }
+info-command = Command: { $command }
+info-version-play-time = Played on { $version }: { $play_time }
+info-milestone = Milestone: { $milestone } ({ $date })
+
+## Nudges
+
+nudge-played = You haven't played { $name } in { $days } days
+nudge-never-played = You haven't played { $name } since adding it { $days } days ago
+
+## Suggestions
+
+suggest-favorite = { $game } (a favorite, last played { $days } days ago)
+suggest-untouched = { $game } (never played)
+suggest-untouched-length = { $game } (never played, { $hours }h long)
+suggest-finishable = { $game } ({ $left } left to finish)
+suggest-tag = { $game } (tagged { $tag }, like what you have been playing lately)
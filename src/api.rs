@@ -0,0 +1,188 @@
+use crate::http::{Request, Response};
+use crate::stats::GameStats;
+use crate::{Game, Games, find_game_stats, fsutil, json, play_command, read_all_stats};
+use rand::Rng;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+/// The file in the data directory with the token that requests which start
+/// games have to send
+pub const TOKEN_FILE: &str = "api_token";
+
+/// Why a request to the API is turned away, if it is: one from a web page
+/// (which has an `Origin`, or, through DNS rebinding, a `Host` that is not
+/// this machine) is refused, and one that starts a game needs the token
+pub fn refuse(request: &Request, token: &str) -> Option<Response> {
+    let host = request.header("Host").unwrap_or("");
+    if request.header("Origin").is_some() || !is_local_host(host) {
+        return Some(Response::new(403, "text/plain", "Forbidden\n".to_string()));
+    }
+    let authorized = request
+        .header("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|sent| sent.trim() == token);
+    if request.method != "GET" && !authorized {
+        return Some(Response::new(
+            401,
+            "text/plain",
+            "Unauthorized\n".to_string(),
+        ));
+    }
+    None
+}
+
+/// Whether a `Host` header names this machine, with or without a port
+fn is_local_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(""),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    matches!(name, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// A game as JSON, with its stats if it has been played
+pub fn game_json(game: &Game, stats: Option<&GameStats>) -> String {
+    let tags: Vec<String> = game.tags.iter().map(|tag| json::string(tag)).collect();
+    let mut fields = vec![
+        ("id", json::string(&game.id)),
+        ("name", json::string(&game.name)),
+        ("installed", game.is_installed().to_string()),
+        ("tags", json::array(&tags)),
+    ];
+    if let Some(stats) = stats {
+        fields.push(("play_time_seconds", stats.play_time_seconds().to_string()));
+        fields.push((
+            "last_played",
+            json::string(&stats.format_last_played_time()),
+        ));
+    }
+    json::object(&fields)
+}
+
+/// Every game as a JSON array, sorted by ID
+pub fn games_json(games: &Games, all_stats: &HashMap<String, GameStats>) -> String {
+    let mut game_ids: Vec<&String> = games.games.keys().collect();
+    game_ids.sort();
+    let items: Vec<String> = game_ids
+        .iter()
+        .map(|game_id| game_json(&games.games[*game_id], all_stats.get(*game_id)))
+        .collect();
+    json::array(&items)
+}
+
+/// The API's token from the file, or a new random one written to it that only
+/// the user can read
+pub fn token(path: &Path) -> std::io::Result<String> {
+    if let Ok(token) = fs::read_to_string(path)
+        && !token.trim().is_empty()
+    {
+        return Ok(token.trim().to_string());
+    }
+    let mut rng = rand::rng();
+    let token: String = (0..32)
+        .map(|_| format!("{:x}", rng.random_range(0..16)))
+        .collect();
+    fsutil::write_private(path, &token)?;
+    Ok(token)
+}
+
+/// Answer a request to `/api/`, with the given route being the rest of the
+/// path
+pub fn handle_api_request(games: &Games, method: &str, route: &str) -> Response {
+    let parts: Vec<&str> = route.trim_end_matches('/').split('/').collect();
+    let json = |body: String| Response::new(200, "application/json", body);
+    match (method, parts.as_slice()) {
+        ("GET", ["games"]) => json(games_json(games, &read_all_stats())),
+        ("GET", ["games", game_id]) => match games.find(game_id) {
+            Some(game) => json(game_json(game, find_game_stats(game).as_ref())),
+            None => Response::not_found(),
+        },
+        ("POST", ["games", game_id, "play"]) => {
+            if games.find(game_id).is_none() {
+                return Response::not_found();
+            }
+            info!("Starting game from API: {}", game_id);
+            let result = play_command(game_id)
+                .stdin(std::process::Stdio::null())
+                .spawn();
+            match result {
+                Ok(mut child) => {
+                    thread::spawn(move || child.wait());
+                    Response::new(202, "application/json", json::object(&[]))
+                }
+                Err(e) => {
+                    warn!("Could not start game from API: {}", e);
+                    Response::new(500, "text/plain", format!("{}\n", e))
+                }
+            }
+        }
+        (_, ["games"]) | (_, ["games", _]) | (_, ["games", _, "play"]) => {
+            Response::method_not_allowed()
+        }
+        _ => Response::not_found(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+    use crate::stats::test_stats;
+    use time::UtcDateTime;
+
+    #[test]
+    fn test_refuse() {
+        let request = |method: &str, headers: &[(&str, &str)]| Request {
+            method: method.to_string(),
+            path: "/api/games/doom/play".to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        };
+        let status = |request: &Request| refuse(request, "s3cret").map(|r| r.status);
+        let local = ("Host", "127.0.0.1:9185");
+        assert_eq!(status(&request("GET", &[local])), None);
+        assert_eq!(status(&request("GET", &[("Host", "[::1]:9185")])), None);
+        assert_eq!(
+            status(&request("GET", &[("Host", "evil.example")])),
+            Some(403)
+        );
+        assert_eq!(status(&request("GET", &[])), Some(403));
+        let from_page = [local, ("Origin", "https://evil.example")];
+        assert_eq!(status(&request("GET", &from_page)), Some(403));
+        assert_eq!(status(&request("POST", &[local])), Some(401));
+        let wrong = [local, ("Authorization", "Bearer guess")];
+        assert_eq!(status(&request("POST", &wrong)), Some(401));
+        let right = [local, ("Authorization", "Bearer s3cret")];
+        assert_eq!(status(&request("POST", &right)), None);
+    }
+
+    #[test]
+    fn test_games_json() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        tags = [\"fps\"]
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"vkquake\"
+        installed = false";
+        let games = parse_config(config).expect("Bad config");
+        let all_stats = test_stats(&[("doom", 60, UtcDateTime::now())]);
+        let json = games_json(&games, &all_stats);
+        assert!(json.starts_with(
+            "[{\"id\":\"doom\",\"name\":\"Doom\",\"installed\":true,\"tags\":[\"fps\"],\"play_time_seconds\":60,"
+        ));
+        assert!(
+            json.ends_with(
+                "{\"id\":\"quake\",\"name\":\"Quake\",\"installed\":false,\"tags\":[]}]"
+            )
+        );
+    }
+}
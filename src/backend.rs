@@ -0,0 +1,266 @@
+use crate::template;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// The directory of plugin backends, next to the config file
+pub const PLUGINS_DIR: &str = "backends";
+
+/// How a backend launches a game
+#[derive(Debug, PartialEq)]
+pub struct Launch {
+    pub command: Vec<String>,
+    pub env: HashMap<String, String>,
+}
+
+/// Something that launches games, like an emulator or a store, which a game
+/// uses by setting one of its options
+pub trait LauncherBackend: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// The game options that this backend recognizes
+    fn keys(&self) -> Vec<&str>;
+
+    /// How to launch the game, or `None` if it does not use this backend, or
+    /// the name of the option that is invalid
+    fn launch(&self, game_config: &Table) -> Option<Result<Launch, String>>;
+}
+
+/// A backend that is run with a program and the value of one option
+struct Builtin {
+    key: &'static str,
+    command: fn(&str) -> Vec<String>,
+}
+
+impl LauncherBackend for Builtin {
+    fn name(&self) -> &str {
+        self.key
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![self.key]
+    }
+
+    fn launch(&self, game_config: &Table) -> Option<Result<Launch, String>> {
+        let launch = match game_config.get(self.key)? {
+            Value::String(value) => Ok(Launch {
+                command: (self.command)(value),
+                env: HashMap::new(),
+            }),
+            _ => Err(self.key.to_string()),
+        };
+        Some(launch)
+    }
+}
+
+/// A backend defined by a file in the plugins directory, like
+/// `backends/retroarch.toml`:
+///
+/// ```toml
+/// options = ["retroarch_rom", "retroarch_core"]
+/// command = "retroarch -L /usr/lib/libretro/{retroarch_core}_libretro.so {retroarch_rom}"
+/// env = { RETROARCH_LOG = "1" }
+/// ```
+///
+/// A game uses it when it sets the first option, and every option in the
+/// command must be set.
+#[derive(Debug)]
+struct Plugin {
+    name: String,
+    options: Vec<String>,
+    command: String,
+    env: Vec<(String, String)>,
+}
+
+impl Plugin {
+    fn parse(name: &str, content: &str) -> Result<Plugin, String> {
+        let table: Table = content.parse().map_err(|e| format!("{}", e))?;
+        let options: Vec<String> = match table.get("options") {
+            Some(Value::Array(options)) => options
+                .iter()
+                .filter_map(|o| o.as_str().map(|o| o.to_string()))
+                .collect(),
+            _ => return Err("options must be a list of option names".to_string()),
+        };
+        if options.is_empty() {
+            return Err("options must not be empty".to_string());
+        }
+        let Some(Value::String(command)) = table.get("command") else {
+            return Err("command must be a string".to_string());
+        };
+        let mut env = Vec::new();
+        if let Some(Value::Table(vars)) = table.get("env") {
+            for (var, value) in vars.iter() {
+                let Value::String(value) = value else {
+                    return Err(format!("env.{} must be a string", var));
+                };
+                env.push((var.to_string(), value.to_string()));
+            }
+        }
+        Ok(Plugin {
+            name: name.to_string(),
+            options,
+            command: command.to_string(),
+            env,
+        })
+    }
+}
+
+impl LauncherBackend for Plugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.options.iter().map(|o| o.as_str()).collect()
+    }
+
+    fn launch(&self, game_config: &Table) -> Option<Result<Launch, String>> {
+        game_config.get(&self.options[0])?;
+        let mut values = Vec::new();
+        for option in self.options.iter() {
+            let value = match game_config.get(option) {
+                Some(Value::String(s)) => s.to_string(),
+                Some(Value::Integer(i)) => i.to_string(),
+                Some(_) => return Some(Err(option.to_string())),
+                None => continue,
+            };
+            values.push((option.as_str(), value));
+        }
+        let vars: Vec<(&str, &str)> = values.iter().map(|(o, v)| (*o, v.as_str())).collect();
+        let missing = self.options.iter().find(|option| {
+            let placeholder = format!("{{{}}}", option);
+            !vars.iter().any(|(o, _)| o == option)
+                && (self.command.contains(&placeholder)
+                    || self.env.iter().any(|(_, v)| v.contains(&placeholder)))
+        });
+        if let Some(option) = missing {
+            return Some(Err(option.to_string()));
+        }
+        let Some(command) = template::render_command(&self.command, &vars) else {
+            return Some(Err(self.options[0].to_string()));
+        };
+        let env = self
+            .env
+            .iter()
+            .map(|(var, value)| (var.to_string(), template::render(value, &vars)))
+            .collect();
+        Some(Ok(Launch { command, env }))
+    }
+}
+
+/// The built-in backends followed by the plugins in the directory, leaving
+/// out (with a warning) any plugin that cannot be read or that recognizes an
+/// option another backend already does, or one of the reserved game options
+pub fn all(plugins_dir: &Path, reserved: &[&str]) -> Vec<Box<dyn LauncherBackend>> {
+    let mut backends: Vec<Box<dyn LauncherBackend>> = vec![
+        Box::new(Builtin {
+            key: "dosbox_config",
+            command: |conf| vec!["dosbox".to_string(), "-conf".to_string(), conf.to_string()],
+        }),
+        Box::new(Builtin {
+            key: "scummvm_id",
+            command: |target| vec!["scummvm".to_string(), target.to_string()],
+        }),
+    ];
+    let Ok(entries) = fs::read_dir(plugins_dir) else {
+        return backends;
+    };
+    let mut paths: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let plugin = match fs::read_to_string(&path) {
+            Ok(content) => Plugin::parse(&name, &content),
+            Err(e) => Err(e.to_string()),
+        };
+        let plugin = match plugin {
+            Ok(plugin) => plugin,
+            Err(e) => {
+                warn!("Invalid backend {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let taken = plugin
+            .keys()
+            .into_iter()
+            .find(|key| reserved.contains(key) || backends.iter().any(|b| b.keys().contains(key)));
+        if let Some(key) = taken {
+            warn!(
+                "Backend {} not loaded: option {} is already recognized",
+                plugin.name, key
+            );
+            continue;
+        }
+        debug!("Loaded backend {} from {}", plugin.name, path.display());
+        backends.push(Box::new(plugin));
+    }
+    backends
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_backend() {
+        let plugin = Plugin::parse(
+            "retroarch",
+            "options = [\"rom\", \"core\"]
+            command = \"retroarch -L {core}.so {rom}\"
+            env = { GAME_ROM = \"{rom}\" }",
+        )
+        .expect("Bad plugin");
+        let game_config: Table = "rom = \"Super Metroid.sfc\"\ncore = \"snes9x\""
+            .parse()
+            .unwrap();
+        let launch = plugin.launch(&game_config).unwrap().unwrap();
+        assert_eq!(
+            launch.command,
+            vec!["retroarch", "-L", "snes9x.so", "Super Metroid.sfc"]
+        );
+        assert_eq!(launch.env["GAME_ROM"], "Super Metroid.sfc");
+
+        let without_core: Table = "rom = \"x.sfc\"".parse().unwrap();
+        assert_eq!(plugin.launch(&without_core), Some(Err("core".to_string())));
+        assert!(plugin.launch(&Table::new()).is_none());
+    }
+
+    #[test]
+    fn test_colliding_plugin() {
+        let dir = std::env::temp_dir().join(format!("game_rs_backends_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("custom.toml"),
+            "options = [\"custom_cmd\", \"cmd\"]\ncommand = \"{custom_cmd}\"",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("dosbox.toml"),
+            "options = [\"dosbox_config\"]\ncommand = \"dosbox\"",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("retroarch.toml"),
+            "options = [\"rom\"]\ncommand = \"retroarch {rom}\"",
+        )
+        .unwrap();
+        let names: Vec<String> = all(&dir, &["cmd", "name"])
+            .iter()
+            .map(|backend| backend.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["dosbox_config", "scummvm_id", "retroarch"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_plugin() {
+        assert!(Plugin::parse("x", "command = \"x\"").is_err());
+        assert!(Plugin::parse("x", "options = [\"a\"]").is_err());
+    }
+}
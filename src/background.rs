@@ -0,0 +1,95 @@
+use crate::Game;
+use crate::watch::GAME_ID_ENV_VAR;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// How many tenths of a second an interrupted process has to exit
+const INTERRUPT_WAIT_POLLS: u32 = 50;
+
+/// A helper process that runs alongside a game and is stopped when the game
+/// exits
+pub struct BackgroundProcess {
+    name: String,
+    child: Child,
+}
+
+impl BackgroundProcess {
+    pub fn spawn(command: &[String]) -> Option<BackgroundProcess> {
+        info!("Starting background process: {:?}", command);
+        let mut process = Command::new(&command[0]);
+        process.args(&command[1..]);
+        BackgroundProcess::start(&command[0], process)
+    }
+
+    /// Start a game's command in the background, from its directory and with
+    /// its environment, marked as started by `game` like any other game
+    pub fn spawn_game(game: &Game) -> Option<BackgroundProcess> {
+        if let Some(runner) = &game.missing_runner {
+            warn!("Could not start {}: no such runner: {}", game.id, runner);
+            return None;
+        }
+        if let Some(sandbox) = &game.missing_sandbox {
+            warn!(
+                "Could not start {}: sandbox not available: {}",
+                game.id, sandbox
+            );
+            return None;
+        }
+        info!("Starting game in the background: {:?}", game.command);
+        let mut process = Command::new(&game.command[0]);
+        process
+            .args(&game.command[1..])
+            .envs(game.env.iter())
+            .env(GAME_ID_ENV_VAR, &game.id);
+        if let Some(dir) = &game.dir {
+            process.current_dir(dir);
+        }
+        BackgroundProcess::start(&game.command[0], process)
+    }
+
+    fn start(name: &str, mut process: Command) -> Option<BackgroundProcess> {
+        let result = process
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match result {
+            Ok(child) => Some(BackgroundProcess {
+                name: name.to_string(),
+                child,
+            }),
+            Err(e) => {
+                warn!("Could not start {}: {}", name, e);
+                None
+            }
+        }
+    }
+
+    /// Stop the process the way Ctrl+C would, giving it a few seconds to
+    /// finish (like a recorder writing out its file) before it is killed
+    pub fn interrupt(mut self) {
+        info!("Interrupting background process: {}", self.name);
+        if let Ok(Some(_)) = self.child.try_wait() {
+            return;
+        }
+        if cfg!(unix) {
+            let _ = Command::new("kill")
+                .args(["-INT", &self.child.id().to_string()])
+                .status();
+            for _ in 0..INTERRUPT_WAIT_POLLS {
+                if let Ok(Some(_)) = self.child.try_wait() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+        self.stop();
+    }
+
+    pub fn stop(mut self) {
+        info!("Stopping background process: {}", self.name);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
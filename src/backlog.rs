@@ -0,0 +1,103 @@
+use crate::game::{Game, GameError};
+use crate::stats::{self, GameStats};
+use crate::{Games, read_all_stats};
+use std::collections::HashMap;
+
+pub fn command_backlog<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let max_hours = if args.is_empty() {
+        0.0
+    } else {
+        match args[0].parse::<f64>() {
+            Ok(hours) => hours,
+            Err(_) => return Err(GameError::InvalidArgument(&args[0])),
+        }
+    };
+    let all_stats = read_all_stats();
+    for line in backlog(games, &all_stats, max_hours) {
+        out!("{}", line);
+    }
+    Ok(())
+}
+
+/// Installed games that have been played for no more than the given number of
+/// hours, with the games that have been owned the longest listed first
+fn backlog(games: &Games, all_stats: &HashMap<String, GameStats>, max_hours: f64) -> Vec<String> {
+    let max_seconds = (max_hours * 60.0 * 60.0) as u32;
+    let mut backlog_games: Vec<(&Game, u32)> = games
+        .games
+        .values()
+        .filter(|game| game.is_installed())
+        .map(|game| {
+            let play_time = match all_stats.get(&game.id) {
+                Some(stats) => stats.play_time_seconds(),
+                None => 0,
+            };
+            (game, play_time)
+        })
+        .filter(|(_, play_time)| *play_time <= max_seconds)
+        .collect();
+    backlog_games.sort_by(|(a, _), (b, _)| match (a.added, b.added) {
+        (Some(x), Some(y)) => x.cmp(&y).then(a.id.cmp(&b.id)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.id.cmp(&b.id),
+    });
+    backlog_games
+        .iter()
+        .map(|(game, play_time)| {
+            let added = match game.added {
+                Some(date) => format!("added {}", stats::format_date(date)),
+                None => "added ?".to_string(),
+            };
+            let played = if *play_time > 0 {
+                format!("played {}", stats::format_play_time(*play_time))
+            } else {
+                "never played".to_string()
+            };
+            format!("{} ({}, {})", game.format(), added, played)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+    use time::UtcDateTime;
+
+    #[test]
+    fn test_backlog_lists_oldest_unplayed_games_first() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        added = 2024-05-01
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"vkquake\"
+        added = 2023-01-15
+
+        [games.hexen]
+        name = \"Hexen\"
+        cmd = \"dsda-doom -iwad HEXEN.WAD\"
+
+        [games.heretic]
+        name = \"Heretic\"
+        cmd = \"dsda-doom -iwad HERETIC.WAD\"";
+        let games = parse_config(config).expect("Bad config");
+        let all_stats = stats::test_stats(&[("heretic", 3600, UtcDateTime::now())]);
+        let backlog_games = backlog(&games, &all_stats, 0.0);
+        assert_eq!(
+            backlog_games,
+            vec![
+                "quake - Quake (added 2023-01-15, never played)",
+                "doom - Doom (added 2024-05-01, never played)",
+                "hexen - Hexen (added ?, never played)",
+            ]
+        );
+        let backlog_games = backlog(&games, &all_stats, 2.0);
+        assert_eq!(backlog_games.len(), 4);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::game::GameError;
+use crate::{Game, Games, fsutil, steam_dir};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A shader cache belonging to a game
+pub struct ShaderCache {
+    pub kind: &'static str,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+impl ShaderCache {
+    fn new(kind: &'static str, path: PathBuf) -> ShaderCache {
+        let size = fsutil::dir_size(&path);
+        ShaderCache { kind, path, size }
+    }
+
+    pub fn remove(&self) -> std::io::Result<()> {
+        if self.path.is_dir() {
+            fs::remove_dir_all(&self.path)
+        } else {
+            fs::remove_file(&self.path)
+        }
+    }
+}
+
+/// Find the DXVK, vkd3d-proton, Mesa, and Steam shader caches for a game
+pub fn find_caches(game: &Game, steam_dir: Option<&Path>) -> Vec<ShaderCache> {
+    let mut cache_dirs = Vec::new();
+    if let Some(dir) = &game.dir {
+        cache_dirs.push(PathBuf::from(dir));
+    }
+    for var in ["DXVK_STATE_CACHE_PATH", "VKD3D_SHADER_CACHE_PATH"] {
+        if let Some(dir) = game.env.get(var) {
+            cache_dirs.push(PathBuf::from(dir));
+        }
+    }
+
+    let mut caches = Vec::new();
+    for dir in cache_dirs.iter() {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|entry| entry.ok()).map(|e| e.path()) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if let Some(kind) = cache_kind(&name) {
+                caches.push(ShaderCache::new(kind, path));
+            }
+        }
+    }
+    // Only the directories Mesa makes in it, since it may hold other things
+    if let Some(dir) = game.env.get("MESA_SHADER_CACHE_DIR") {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_dir()
+                    && path
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().starts_with("mesa_shader_cache"))
+            })
+            .collect();
+        paths.sort();
+        caches.extend(paths.into_iter().map(|path| ShaderCache::new("mesa", path)));
+    }
+    if let Some(steam_id) = &game.steam_id
+        && let Some(steam_dir) = steam_dir
+    {
+        let path = steam_dir
+            .join("steamapps")
+            .join("shadercache")
+            .join(steam_id);
+        if path.exists() {
+            caches.push(ShaderCache::new("steam", path));
+        }
+    }
+    caches
+}
+
+/// The kind of shader cache a file is, judging by its name
+fn cache_kind(file_name: &str) -> Option<&'static str> {
+    if file_name.ends_with(".dxvk-cache") {
+        Some("dxvk")
+    } else if file_name.starts_with("vkd3d-proton.cache") {
+        Some("vkd3d")
+    } else {
+        None
+    }
+}
+
+pub fn command_cache<'a>(games: &'a Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let subcommand = if args.is_empty() { "list" } else { &args[0] };
+    if subcommand != "list" && subcommand != "clean" {
+        return Err(GameError::UnrecognizedSubcommand(&args[0]));
+    }
+    let all = args.get(1).is_some_and(|arg| arg == "--all");
+    let game_ids = if all {
+        &args[2..]
+    } else {
+        args.get(1..).unwrap_or_default()
+    };
+    if let Some(arg) = game_ids.iter().find(|arg| arg.starts_with("--")) {
+        return Err(GameError::InvalidArgument(arg));
+    }
+    let mut selected_games: Vec<&Game> = if !game_ids.is_empty() {
+        if all {
+            return Err(GameError::InvalidArgument(&game_ids[0]));
+        }
+        let mut selected = Vec::new();
+        for game_id in game_ids.iter() {
+            match games.find(game_id) {
+                Some(game) => selected.push(game),
+                None => return Err(GameError::NoSuchGame(game_id)),
+            }
+        }
+        selected
+    } else if all || subcommand == "list" {
+        games.games.values().collect()
+    } else {
+        // Removing every game's caches is only done when asked for
+        return Err(GameError::NoGameId);
+    };
+    selected_games.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let steam_dir = steam_dir();
+    let mut total_size = 0;
+    for game in selected_games.iter() {
+        for cache in find_caches(game, steam_dir.as_deref()) {
+            out!(
+                "{} {} {} {}",
+                game.id,
+                cache.kind,
+                fsutil::format_size(cache.size),
+                cache.path.display()
+            );
+            if subcommand == "clean"
+                && let Err(e) = cache.remove()
+            {
+                warn!("Could not remove {}: {}", cache.path.display(), e);
+                continue;
+            }
+            total_size += cache.size;
+        }
+    }
+    if subcommand == "clean" {
+        out!("Removed: {}", fsutil::format_size(total_size));
+    } else {
+        out!("Total: {}", fsutil::format_size(total_size));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mesa_cache() {
+        let dir = std::env::temp_dir().join(format!("game_rs_mesa_cache_{}", std::process::id()));
+        fs::create_dir_all(dir.join("mesa_shader_cache_db")).unwrap();
+        fs::create_dir_all(dir.join("saves")).unwrap();
+        let mut game = Game::default();
+        game.env.insert(
+            "MESA_SHADER_CACHE_DIR".to_string(),
+            dir.to_string_lossy().to_string(),
+        );
+        let caches = find_caches(&game, None);
+        let paths: Vec<&Path> = caches.iter().map(|cache| cache.path.as_path()).collect();
+        assert_eq!(paths, vec![dir.join("mesa_shader_cache_db")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_kind() {
+        assert_eq!(cache_kind("Morrowind.dxvk-cache"), Some("dxvk"));
+        assert_eq!(cache_kind("vkd3d-proton.cache.write"), Some("vkd3d"));
+        assert_eq!(cache_kind("Morrowind.exe"), None);
+    }
+}
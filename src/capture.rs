@@ -0,0 +1,118 @@
+use crate::background::BackgroundProcess;
+use crate::template;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use time::{OffsetDateTime, UtcOffset};
+
+/// The recorder started for games with `record = true`
+pub const DEFAULT_CAPTURE_CMD: &str = "gpu-screen-recorder -w screen -f 60 -o {output}";
+
+/// The directory recordings are saved in, under the home directory
+pub const DEFAULT_CAPTURE_DIR: &str = "Videos";
+
+/// The file extension of recordings, which should match the recorder's format
+pub const DEFAULT_CAPTURE_EXT: &str = "mp4";
+
+/// How a game's sessions are recorded: a command started with the game,
+/// where `{output}` is the file to record to and `{id}` is the game's ID, and
+/// optionally one run when it exits, for recorders that are controlled
+/// through a separate command (like `obs-cmd recording stop`)
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pub command: String,
+    pub stop_command: Option<String>,
+    pub dir: PathBuf,
+    pub ext: String,
+}
+
+impl Capture {
+    /// The file a session started at the given time is recorded to, named
+    /// after the game and the time, like `doom-2025-11-04_21-30-00.mp4`
+    pub fn output(&self, game_id: &str, start: OffsetDateTime) -> PathBuf {
+        let format =
+            time::format_description::parse("[year]-[month]-[day]_[hour]-[minute]-[second]")
+                .expect("Bad format");
+        let timestamp = start.format(&format).expect("Bad format");
+        self.dir
+            .join(format!("{}-{}.{}", game_id, timestamp, self.ext))
+    }
+
+    /// Start recording a session of the game, which is stopped when the
+    /// returned recording is
+    pub fn start(&self, game_id: &str) -> Recording {
+        let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+        let output = self.output(game_id, OffsetDateTime::now_utc().to_offset(offset));
+        if let Err(e) = fs::create_dir_all(&self.dir) {
+            warn!(
+                "Could not create capture directory {}: {}",
+                self.dir.display(),
+                e
+            );
+        }
+        let output = output.to_string_lossy();
+        let vars = [("output", output.as_ref()), ("id", game_id)];
+        info!("Recording to: {}", output);
+        let process = template::render_command(&self.command, &vars)
+            .filter(|command| !command.is_empty())
+            .and_then(|command| BackgroundProcess::spawn(&command));
+        let stop_command = self
+            .stop_command
+            .as_ref()
+            .and_then(|stop_command| template::render_command(stop_command, &vars))
+            .filter(|command| !command.is_empty());
+        Recording {
+            process,
+            stop_command,
+        }
+    }
+}
+
+/// A recording in progress
+pub struct Recording {
+    process: Option<BackgroundProcess>,
+    stop_command: Option<Vec<String>>,
+}
+
+impl Recording {
+    /// Stop recording, letting the recorder finish writing the file
+    pub fn stop(self) {
+        if let Some(stop_command) = &self.stop_command {
+            info!("Stopping recording: {:?}", stop_command);
+            let status = Command::new(&stop_command[0])
+                .args(&stop_command[1..])
+                .status();
+            if !status.is_ok_and(|status| status.success()) {
+                warn!("Command failed: {}", stop_command.join(" "));
+            }
+        }
+        if let Some(process) = self.process {
+            process.interrupt();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::{Date, Month};
+
+    #[test]
+    fn test_output() {
+        let capture = Capture {
+            command: DEFAULT_CAPTURE_CMD.to_string(),
+            stop_command: None,
+            dir: PathBuf::from("/home/test/Videos"),
+            ext: DEFAULT_CAPTURE_EXT.to_string(),
+        };
+        let start = Date::from_calendar_date(2025, Month::November, 4)
+            .unwrap()
+            .with_hms(21, 30, 5)
+            .unwrap()
+            .assume_offset(UtcOffset::from_hms(1, 0, 0).unwrap());
+        assert_eq!(
+            capture.output("doom", start),
+            PathBuf::from("/home/test/Videos/doom-2025-11-04_21-30-05.mp4")
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use crate::game::GameError;
+use crate::sessions::Session;
+use crate::stats::format_date;
+use crate::{Games, read_sessions, summary};
+use plotters::prelude::*;
+use plotters::style::register_font;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use time::{Date, UtcOffset};
+
+const WIDTH: u32 = 1024;
+const HEIGHT: u32 = 640;
+const FONT_FAMILY: &str = "sans-serif";
+const MAX_BARS: usize = 10;
+
+/// Where `chart` writes to without `-o`
+const CHART_FILE_NAME: &str = "chart.png";
+
+/// Fonts that are commonly installed, used for the chart's title and labels
+const FONT_PATHS: [&str; 7] = [
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/usr/share/fonts/liberation/LiberationSans-Regular.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+
+pub enum ChartKind {
+    Playtime,
+    Sessions,
+}
+
+impl ChartKind {
+    pub fn parse(s: &str) -> Option<ChartKind> {
+        match s {
+            "playtime" => Some(ChartKind::Playtime),
+            "sessions" => Some(ChartKind::Sessions),
+            _ => None,
+        }
+    }
+}
+
+/// The total hours played by the end of each day that had a session, with
+/// each day given as a Julian day number
+pub fn cumulative_hours(sessions: &[&Session], offset: UtcOffset) -> Vec<(i32, f64)> {
+    let mut days: BTreeMap<i32, u32> = BTreeMap::new();
+    for session in sessions.iter() {
+        let day = session
+            .start_time()
+            .to_offset(offset)
+            .date()
+            .to_julian_day();
+        *days.entry(day).or_default() += session.play_time_seconds();
+    }
+    let mut total_seconds = 0;
+    days.into_iter()
+        .map(|(day, seconds)| {
+            total_seconds += seconds;
+            (day, total_seconds as f64 / 3600.0)
+        })
+        .collect()
+}
+
+/// The number of sessions for each game, most first, keeping only the games
+/// with the most sessions
+pub fn sessions_per_game(sessions: &[&Session]) -> Vec<(String, u32)> {
+    let mut counts: HashMap<&str, u32> = HashMap::new();
+    for session in sessions.iter() {
+        *counts.entry(session.id()).or_default() += 1;
+    }
+    let mut bars: Vec<(String, u32)> = counts
+        .into_iter()
+        .map(|(id, count)| (id.to_string(), count))
+        .collect();
+    bars.sort_by(|(a_id, a), (b_id, b)| b.cmp(a).then(a_id.cmp(b_id)));
+    bars.truncate(MAX_BARS);
+    bars
+}
+
+/// Register the first font that can be found, since charts cannot be drawn
+/// without one
+pub fn load_font() -> Result<(), String> {
+    for path in FONT_PATHS.iter() {
+        let Ok(bytes) = fs::read(path) else {
+            continue;
+        };
+        debug!("Using font: {}", path);
+        let bytes: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+        return register_font(FONT_FAMILY, FontStyle::Normal, bytes)
+            .map_err(|_| format!("Invalid font: {}", path));
+    }
+    Err("No font found".to_string())
+}
+
+pub fn draw_line(path: &Path, title: &str, points: &[(i32, f64)]) -> Result<(), String> {
+    let root = BitMapBackend::new(path, (WIDTH, HEIGHT)).into_drawing_area();
+    root.fill(&WHITE).map_err(|e| e.to_string())?;
+    let first = points.first().map_or(0, |(day, _)| *day);
+    let last = points.last().map_or(0, |(day, _)| *day).max(first + 1);
+    let most = points.last().map_or(0.0, |(_, hours)| *hours).max(1.0);
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, (FONT_FAMILY, 28))
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(first..last, 0.0..most * 1.05)
+        .map_err(|e| e.to_string())?;
+    chart
+        .configure_mesh()
+        .x_labels(6)
+        .x_label_formatter(&|day| match Date::from_julian_day(*day) {
+            Ok(date) => format_date(date),
+            Err(_) => String::new(),
+        })
+        .y_desc("Hours")
+        .label_style((FONT_FAMILY, 16))
+        .draw()
+        .map_err(|e| e.to_string())?;
+    chart
+        .draw_series(LineSeries::new(
+            points.iter().copied(),
+            BLUE.stroke_width(2),
+        ))
+        .map_err(|e| e.to_string())?;
+    root.present().map_err(|e| e.to_string())
+}
+
+pub fn draw_bars(path: &Path, title: &str, bars: &[(String, u32)]) -> Result<(), String> {
+    let root = BitMapBackend::new(path, (WIDTH, HEIGHT)).into_drawing_area();
+    root.fill(&WHITE).map_err(|e| e.to_string())?;
+    let most = bars.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+    // The segments include the end of the range, so it is the last bar
+    let last_bar = bars.len().max(1) as u32 - 1;
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, (FONT_FAMILY, 28))
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d((0..last_bar).into_segmented(), 0..most + 1)
+        .map_err(|e| e.to_string())?;
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(bars.len())
+        .x_label_formatter(&|value| match value {
+            SegmentValue::CenterOf(i) => bars
+                .get(*i as usize)
+                .map_or(String::new(), |(label, _)| label.clone()),
+            _ => String::new(),
+        })
+        .y_desc("Sessions")
+        .label_style((FONT_FAMILY, 16))
+        .draw()
+        .map_err(|e| e.to_string())?;
+    chart
+        .draw_series(bars.iter().enumerate().map(|(i, (_, count))| {
+            let i = i as u32;
+            let mut bar = Rectangle::new(
+                [
+                    (SegmentValue::Exact(i), 0),
+                    (SegmentValue::Exact(i + 1), *count),
+                ],
+                BLUE.filled(),
+            );
+            bar.set_margin(0, 0, 8, 8);
+            bar
+        }))
+        .map_err(|e| e.to_string())?;
+    root.present().map_err(|e| e.to_string())
+}
+
+pub fn command_chart<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let Some(kind_arg) = args.first() else {
+        return Err(GameError::UnrecognizedSubcommand(""));
+    };
+    let Some(kind) = ChartKind::parse(kind_arg) else {
+        return Err(GameError::UnrecognizedSubcommand(kind_arg));
+    };
+    let mut game = None;
+    let mut output = PathBuf::from(CHART_FILE_NAME);
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => match args.next() {
+                Some(path) => output = PathBuf::from(path),
+                None => return Err(GameError::InvalidArgument(arg)),
+            },
+            _ if arg.starts_with('-') || game.is_some() => {
+                return Err(GameError::InvalidArgument(arg));
+            }
+            _ => match games.find(arg) {
+                Some(g) => game = Some(g),
+                None => return Err(GameError::NoSuchGame(arg)),
+            },
+        }
+    }
+    let all_sessions = read_sessions();
+    let sessions: Vec<&Session> = all_sessions
+        .iter()
+        .filter(|session| game.is_none_or(|g| session.id() == g.id))
+        .collect();
+    let offset = time::UtcOffset::current_local_offset().unwrap_or(time::UtcOffset::UTC);
+    let name = game.map_or("All Games", |g| g.name.as_str());
+    let result = load_font().and_then(|_| match kind {
+        ChartKind::Playtime => draw_line(
+            &output,
+            &format!("Play Time: {}", name),
+            &cumulative_hours(&sessions, offset),
+        ),
+        ChartKind::Sessions if game.is_some() => draw_bars(
+            &output,
+            &format!("Sessions per Month: {}", name),
+            &summary::sessions_per_month(&sessions, offset),
+        ),
+        ChartKind::Sessions => {
+            draw_bars(&output, "Sessions per Game", &sessions_per_game(&sessions))
+        }
+    });
+    if let Err(e) = result {
+        return Err(GameError::CouldNotWriteChart(e));
+    }
+    out!("Wrote {}", output.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::{Month, Time, UtcDateTime};
+
+    fn session(id: &str, month: Month, day: u8, seconds: u32) -> Session {
+        let date = Date::from_calendar_date(2025, month, day).unwrap();
+        let start_time = UtcDateTime::new(date, Time::from_hms(12, 0, 0).unwrap());
+        Session::new(id.to_string(), start_time, seconds, String::new())
+    }
+
+    #[test]
+    fn test_cumulative_hours() {
+        let sessions = [
+            session("doom", Month::March, 1, 3600),
+            session("doom", Month::March, 1, 1800),
+            session("doom", Month::March, 3, 1800),
+        ];
+        let sessions: Vec<&Session> = sessions.iter().collect();
+        let points = cumulative_hours(&sessions, UtcOffset::UTC);
+        let day = Date::from_calendar_date(2025, Month::March, 1)
+            .unwrap()
+            .to_julian_day();
+        assert_eq!(points, vec![(day, 1.5), (day + 2, 2.0)]);
+    }
+
+    #[test]
+    fn test_session_bars() {
+        let sessions = [
+            session("doom", Month::March, 1, 60),
+            session("quake", Month::March, 2, 60),
+            session("quake", Month::April, 3, 60),
+        ];
+        let sessions: Vec<&Session> = sessions.iter().collect();
+        assert_eq!(
+            sessions_per_game(&sessions),
+            vec![("quake".to_string(), 2), ("doom".to_string(), 1)]
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use crate::GameCommand;
+use crate::global_options::{ErrorFormat, GlobalOptions};
+use crate::i18n;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// The arguments of a command, which are passed through to its handler
+const COMMAND_ARGS: &str = "args";
+
+fn global_args(cli: Command) -> Command {
+    cli.arg(
+        Arg::new("config")
+            .long("config")
+            .value_name("PATH")
+            .global(true)
+            .help("Use this config file instead of games.toml in the config directory"),
+    )
+    .arg(
+        Arg::new("data-dir")
+            .long("data-dir")
+            .value_name("PATH")
+            .global(true)
+            .help("Keep stats, sessions and logs in this directory"),
+    )
+    .arg(
+        Arg::new("profile")
+            .long("profile")
+            .value_name("NAME")
+            .global(true)
+            .help("Keep stats and sessions separately for this person"),
+    )
+    .arg(
+        Arg::new("error-format")
+            .long("error-format")
+            .value_name("FORMAT")
+            .value_parser(["text", "json"])
+            .global(true)
+            .help("Print errors as text or as JSON"),
+    )
+    .arg(
+        Arg::new("json")
+            .long("json")
+            .action(ArgAction::SetTrue)
+            .global(true)
+            .help("The same as --error-format json"),
+    )
+    .arg(
+        Arg::new("verbose")
+            .short('v')
+            .long("verbose")
+            .action(ArgAction::Count)
+            .global(true)
+            .help("Log to stderr (-v info, -vv debug, -vvv trace)"),
+    )
+    .arg(
+        Arg::new("quiet")
+            .short('q')
+            .long("quiet")
+            .action(ArgAction::SetTrue)
+            .global(true)
+            .help("Only print errors"),
+    )
+    .arg(
+        Arg::new("timings")
+            .long("timings")
+            .action(ArgAction::SetTrue)
+            .global(true)
+            .help("Report how long loading the config, the stats and the game took"),
+    )
+}
+
+/// The command-line interface, with a subcommand for each command
+pub fn command(commands: &HashMap<&str, GameCommand>) -> Command {
+    let mut names: Vec<&&str> = commands.keys().collect();
+    names.sort();
+    let mut cli = global_args(Command::new("game"))
+        .disable_help_subcommand(true)
+        .subcommand_required(true)
+        .arg_required_else_help(true);
+    for name in names {
+        let c = &commands[*name];
+        let desc = i18n::get()
+            .get(&format!("command-{}", c.cmd))
+            .unwrap_or(c.desc)
+            .to_string();
+        let args: Vec<String> = c.args.iter().map(|a| usage_arg(a)).collect();
+        let usage = format!("game {} {}", c.cmd, args.join(" "));
+        let subcommand = Command::new(c.cmd)
+            .about(desc)
+            .override_usage(usage.trim_end().to_string())
+            .arg(
+                Arg::new(COMMAND_ARGS)
+                    .num_args(0..)
+                    .trailing_var_arg(true)
+                    .allow_hyphen_values(true)
+                    .hide(true),
+            );
+        cli = cli.subcommand(subcommand);
+    }
+    cli
+}
+
+/// An argument in a command's usage, where an optional one is marked with a
+/// `?` in the commands table, like `--listen? ADDRESS`, and is shown in
+/// brackets
+fn usage_arg(arg: &str) -> String {
+    if arg.contains('?') {
+        format!("[{}]", arg.replace('?', ""))
+    } else {
+        arg.to_string()
+    }
+}
+
+/// The arguments to pass to the command's handler
+pub fn command_args(matches: &ArgMatches) -> Vec<String> {
+    match matches.get_many::<String>(COMMAND_ARGS) {
+        Some(args) => args.cloned().collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Read just the global options, ignoring any problems with the rest of the
+/// arguments, so that the config can be read before the arguments are checked
+pub fn global_options(args: &[String]) -> GlobalOptions {
+    let cli = global_args(Command::new("game"))
+        .disable_help_flag(true)
+        .ignore_errors(true)
+        .arg(
+            Arg::new(COMMAND_ARGS)
+                .num_args(0..)
+                .allow_hyphen_values(true),
+        );
+    match cli.try_get_matches_from(args) {
+        Ok(matches) => from_matches(&matches),
+        Err(_) => GlobalOptions::default(),
+    }
+}
+
+pub fn from_matches(matches: &ArgMatches) -> GlobalOptions {
+    let json = matches.get_flag("json")
+        || matches
+            .get_one::<String>("error-format")
+            .is_some_and(|f| f == "json");
+    GlobalOptions {
+        config_file: matches.get_one::<String>("config").map(PathBuf::from),
+        data_dir: matches.get_one::<String>("data-dir").map(PathBuf::from),
+        profile: matches.get_one::<String>("profile").cloned(),
+        verbosity: matches.get_count("verbose"),
+        quiet: matches.get_flag("quiet"),
+        timings: matches.get_flag("timings"),
+        error_format: if json {
+            ErrorFormat::Json
+        } else {
+            ErrorFormat::Text
+        },
+    }
+}
+
+/// Move global options that come after a command's arguments, like the `-v`
+/// in `game play doom -v`, to before the command, and put the command's own
+/// arguments after a `--`, since clap passes everything after a command's
+/// first argument through to it and would take a command's option with the
+/// same name as a global one (like `menu --json`) for the global one. Options
+/// that the command itself takes, and anything after `--`, are left alone.
+pub fn hoist_global_options(commands: &HashMap<&str, GameCommand>, args: &[String]) -> Vec<String> {
+    let cli = global_args(Command::new("game"));
+    let globals: Vec<&Arg> = cli.get_arguments().collect();
+    let mut hoisted = Vec::new();
+    let mut command_name = Vec::new();
+    let mut command_args = Vec::new();
+    let mut command: Option<&GameCommand> = None;
+    let mut args = args.iter().peekable();
+    hoisted.extend(args.next().cloned());
+    while let Some(arg) = args.next() {
+        if arg == "--" && command.is_some() {
+            command_args.push(arg.clone());
+            command_args.extend(args.by_ref().cloned());
+            break;
+        }
+        let own_option = command.is_some_and(|c| takes_option(c, arg));
+        match global_option(&globals, arg) {
+            // Left after the command, so that clap reports the missing value
+            Some(true) if args.peek().is_none() => command_name.push(arg.clone()),
+            Some(takes_value) if !own_option => {
+                hoisted.push(arg.clone());
+                if takes_value {
+                    hoisted.extend(args.next().cloned());
+                }
+            }
+            _ if command.is_none() => {
+                command = commands.get(arg.as_str());
+                command_name.push(arg.clone());
+            }
+            _ => command_args.push(arg.clone()),
+        }
+    }
+    hoisted.extend(command_name);
+    // Help for the command is still shown by clap
+    let asks_for_help = matches!(
+        command_args.first().map(|a| a.as_str()),
+        Some("-h" | "--help")
+    );
+    if !command_args.is_empty() && !asks_for_help {
+        hoisted.push("--".to_string());
+    }
+    hoisted.extend(command_args);
+    hoisted
+}
+
+/// Whether the argument is one of the global options, and if so, whether its
+/// value is the next argument
+fn global_option(globals: &[&Arg], arg: &str) -> Option<bool> {
+    if let Some(long) = arg.strip_prefix("--") {
+        let (name, value) = match long.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (long, None),
+        };
+        let global = globals.iter().find(|a| a.get_long() == Some(name))?;
+        return Some(global.get_action().takes_values() && value.is_none());
+    }
+    let shorts = arg.strip_prefix('-').filter(|s| !s.is_empty())?;
+    let is_flag = |c: char| {
+        globals
+            .iter()
+            .any(|a| a.get_short() == Some(c) && !a.get_action().takes_values())
+    };
+    shorts.chars().all(is_flag).then_some(false)
+}
+
+/// Whether an option is one the command takes, by how its arguments are
+/// described
+fn takes_option(command: &GameCommand, arg: &str) -> bool {
+    command
+        .args
+        .iter()
+        .flat_map(|a| a.split([' ', '|']))
+        .any(|word| word.trim_end_matches('?') == arg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::initialize_commands;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Parse the arguments, returning the global options, the command, and
+    /// its arguments
+    fn parse(args: &[&str]) -> Result<(GlobalOptions, String, Vec<String>), clap::Error> {
+        let commands = initialize_commands();
+        let args = hoist_global_options(&commands, &to_args(args));
+        let matches = command(&commands).try_get_matches_from(args)?;
+        let (cmd, command_matches) = matches.subcommand().unwrap();
+        Ok((
+            from_matches(command_matches),
+            cmd.to_string(),
+            command_args(command_matches),
+        ))
+    }
+
+    #[test]
+    fn test_parse_global_options() {
+        let (options, cmd, args) = parse(&[
+            "game",
+            "--config",
+            "kids.toml",
+            "list",
+            "--data-dir",
+            "/tmp/game_rs",
+            "fps",
+            "--profile",
+            "kid",
+        ])
+        .expect("Bad options");
+        assert_eq!(options.config_file, Some(PathBuf::from("kids.toml")));
+        assert_eq!(options.data_dir, Some(PathBuf::from("/tmp/game_rs")));
+        assert_eq!(options.profile, Some("kid".to_string()));
+        assert_eq!(cmd, "list");
+        assert_eq!(args, vec!["fps"]);
+    }
+
+    #[test]
+    fn test_options_after_double_dash_are_not_parsed() {
+        let (options, _, args) =
+            parse(&["game", "wrap-exec", "doom", "--", "--config", "x"]).expect("Bad options");
+        assert!(options.config_file.is_none());
+        assert_eq!(args, vec!["doom", "--", "--config", "x"]);
+    }
+
+    #[test]
+    fn test_verbosity_flags() {
+        let (options, _, args) =
+            parse(&["game", "-vv", "play", "doom", "-v"]).expect("Bad options");
+        assert_eq!(options.verbosity, 3);
+        assert_eq!(args, vec!["doom"]);
+    }
+
+    #[test]
+    fn test_quiet_flag() {
+        let (options, _, args) = parse(&["game", "play", "--quiet", "doom"]).expect("Bad options");
+        assert!(options.quiet);
+        assert_eq!(args, vec!["doom"]);
+    }
+
+    #[test]
+    fn test_error_format() {
+        let (options, _, _) =
+            parse(&["game", "--error-format", "json", "list"]).expect("Bad options");
+        assert_eq!(options.error_format, ErrorFormat::Json);
+        let (options, _, _) = parse(&["game", "list", "--json"]).expect("Bad options");
+        assert_eq!(options.error_format, ErrorFormat::Json);
+
+        let e = parse(&["game", "--error-format", "xml", "list"])
+            .err()
+            .unwrap();
+        assert_eq!(e.kind(), clap::error::ErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn test_missing_option_value() {
+        let e = parse(&["game", "list", "--config"]).err().unwrap();
+        assert_eq!(e.kind(), clap::error::ErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn test_command_options_are_not_global() {
+        let (options, _, args) = parse(&["game", "menu", "--json"]).expect("Bad options");
+        assert_eq!(options.error_format, ErrorFormat::Text);
+        assert_eq!(args, vec!["--json"]);
+    }
+
+    #[test]
+    fn test_usage_arg() {
+        assert_eq!(usage_arg("--listen? ADDRESS"), "[--listen ADDRESS]");
+        assert_eq!(usage_arg("GAME_ID..."), "GAME_ID...");
+    }
+
+    #[test]
+    fn test_misspelled_command() {
+        let e = parse(&["game", "plya", "doom"]).err().unwrap();
+        assert_eq!(e.kind(), clap::error::ErrorKind::InvalidSubcommand);
+        assert!(e.to_string().contains("'play'"));
+    }
+}
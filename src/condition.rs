@@ -0,0 +1,91 @@
+use crate::host;
+use crate::platform::Platform;
+use std::env::var;
+use toml::{Table, Value};
+
+/// The key of a game's table of conditional options
+pub const WHEN: &str = "when";
+
+/// The conditions that hold for this session: the operating system (`linux`,
+/// `macos` or `windows`), the session type (`wayland` or `x11`) and the
+/// desktops in `XDG_CURRENT_DESKTOP` (like `kde` or `gnome`)
+pub fn detect(platform: Platform) -> Vec<String> {
+    let mut conditions = vec![
+        match platform {
+            Platform::Linux => "linux",
+            Platform::MacOs => "macos",
+            Platform::Windows => "windows",
+        }
+        .to_string(),
+    ];
+    let session_type = match var("XDG_SESSION_TYPE") {
+        Ok(session_type) if session_type == "wayland" || session_type == "x11" => {
+            Some(session_type)
+        }
+        _ if var("WAYLAND_DISPLAY").is_ok() => Some("wayland".to_string()),
+        _ if var("DISPLAY").is_ok() => Some("x11".to_string()),
+        _ => None,
+    };
+    conditions.extend(session_type);
+    if let Ok(desktops) = var("XDG_CURRENT_DESKTOP") {
+        conditions.extend(
+            desktops
+                .split(':')
+                .filter(|d| !d.is_empty())
+                .map(|d| d.to_lowercase()),
+        );
+    }
+    debug!("Conditions: {}", conditions.join(", "));
+    conditions
+}
+
+/// The game's options with those under `when.<condition>` added for each
+/// condition that holds, or the name of the option that is not a table
+pub fn apply(game_config: &Table, conditions: &[String]) -> Result<Table, String> {
+    let mut options = game_config.clone();
+    let blocks = match options.remove(WHEN) {
+        Some(Value::Table(blocks)) => blocks,
+        Some(_) => return Err(WHEN.to_string()),
+        None => return Ok(options),
+    };
+    for (condition, block) in blocks {
+        let Value::Table(block) = block else {
+            return Err(format!("{}.{}", WHEN, condition));
+        };
+        if conditions.contains(&condition) {
+            host::merge(&mut options, block);
+        }
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_matching_blocks() {
+        let game_config: Table = "
+            cmd = \"game\"
+            env = { A = \"1\" }
+            [when.wayland]
+            env = { B = \"2\" }
+            [when.x11]
+            cmd = \"game --x11\"
+        "
+        .parse()
+        .unwrap();
+        let conditions = vec!["linux".to_string(), "wayland".to_string()];
+        let options = apply(&game_config, &conditions).unwrap();
+        assert!(options.get(WHEN).is_none());
+        assert_eq!(options["cmd"].as_str(), Some("game"));
+        assert_eq!(options["env"]["A"].as_str(), Some("1"));
+        assert_eq!(options["env"]["B"].as_str(), Some("2"));
+    }
+
+    #[test]
+    fn test_invalid_block() {
+        let game_config: Table = "when = { x11 = 1 }".parse().unwrap();
+        assert_eq!(apply(&game_config, &[]), Err("when.x11".to_string()));
+    }
+}
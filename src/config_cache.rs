@@ -0,0 +1,487 @@
+use crate::Game;
+use crate::capture::Capture;
+use crate::wm_rules::{WindowManager, WmRules};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use time::Date;
+
+/// The file the parsed games are cached in, in the data directory
+pub const CACHE_FILE: &str = "config.cache";
+
+/// Changed whenever what is cached changes, so that older caches are ignored
+const FORMAT: &[u8] = b"game config cache 3\n";
+
+/// The games parsed from the config by an earlier run
+pub struct Cache {
+    /// The config without its games, as TOML
+    pub rest: String,
+    pub games: HashMap<String, Game>,
+}
+
+/// When a file or directory was last modified and how big it is, or `None`
+/// if it does not exist
+type Stamp = Option<(Duration, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((modified, metadata.len()))
+}
+
+/// The cached games, if they were cached with the same key and none of the
+/// files they were parsed from have changed since
+pub fn read(path: &Path, key: &str) -> Option<Cache> {
+    let data = fs::read(path).ok()?;
+    let mut input = data.strip_prefix(FORMAT)?;
+    if String::read(&mut input)? != key {
+        debug!("Config cache is for another environment");
+        return None;
+    }
+    let stamps = Vec::<(PathBuf, Stamp)>::read(&mut input)?;
+    if let Some((changed, _)) = stamps.iter().find(|(path, old)| stamp(path) != *old) {
+        debug!("Config cache is out of date: {} changed", changed.display());
+        return None;
+    }
+    let rest = String::read(&mut input)?;
+    let games = Vec::<Game>::read(&mut input)?
+        .into_iter()
+        .map(|game| (game.id.clone(), game))
+        .collect();
+    input.is_empty().then_some(Cache { rest, games })
+}
+
+/// Cache the games parsed from the config, along with the rest of the config
+/// and the state of the files (and directories) they depend on
+pub fn write(
+    path: &Path,
+    key: &str,
+    sources: &[PathBuf],
+    rest: &str,
+    games: &HashMap<String, Game>,
+) -> io::Result<()> {
+    let mut out = FORMAT.to_vec();
+    key.to_string().write(&mut out);
+    let stamps: Vec<(PathBuf, Stamp)> = sources
+        .iter()
+        .map(|source| (source.clone(), stamp(source)))
+        .collect();
+    stamps.write(&mut out);
+    rest.to_string().write(&mut out);
+    let mut ids: Vec<&String> = games.keys().collect();
+    ids.sort();
+    (ids.len() as u64).write(&mut out);
+    for id in ids {
+        games[id].write(&mut out);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Another run may be reading the cache at the same time
+    let partial = path.with_extension("partial");
+    fs::write(&partial, out)?;
+    fs::rename(&partial, path)
+}
+
+/// A value that can be written to the cache and read back
+trait Cached: Sized {
+    fn write(&self, out: &mut Vec<u8>);
+    fn read(input: &mut &[u8]) -> Option<Self>;
+}
+
+fn take<'a>(input: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if input.len() < n {
+        return None;
+    }
+    let (taken, rest) = input.split_at(n);
+    *input = rest;
+    Some(taken)
+}
+
+impl Cached for u64 {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read(input: &mut &[u8]) -> Option<u64> {
+        Some(u64::from_le_bytes(take(input, 8)?.try_into().ok()?))
+    }
+}
+
+impl Cached for u32 {
+    fn write(&self, out: &mut Vec<u8>) {
+        (*self as u64).write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<u32> {
+        u64::read(input)?.try_into().ok()
+    }
+}
+
+impl Cached for f64 {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.to_bits().write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<f64> {
+        Some(f64::from_bits(u64::read(input)?))
+    }
+}
+
+impl Cached for bool {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<bool> {
+        match take(input, 1)? {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Cached for String {
+    fn write(&self, out: &mut Vec<u8>) {
+        (self.len() as u64).write(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn read(input: &mut &[u8]) -> Option<String> {
+        let len = u64::read(input)? as usize;
+        String::from_utf8(take(input, len)?.to_vec()).ok()
+    }
+}
+
+impl Cached for PathBuf {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.to_string_lossy().to_string().write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<PathBuf> {
+        String::read(input).map(PathBuf::from)
+    }
+}
+
+impl Cached for Duration {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.as_secs().write(out);
+        self.subsec_nanos().write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<Duration> {
+        Some(Duration::new(u64::read(input)?, u32::read(input)?))
+    }
+}
+
+impl Cached for Date {
+    fn write(&self, out: &mut Vec<u8>) {
+        (self.to_julian_day() as i64 as u64).write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<Date> {
+        let day = i32::try_from(u64::read(input)? as i64).ok()?;
+        Date::from_julian_day(day).ok()
+    }
+}
+
+impl<T: Cached> Cached for Option<T> {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.is_some().write(out);
+        if let Some(value) = self {
+            value.write(out);
+        }
+    }
+
+    fn read(input: &mut &[u8]) -> Option<Option<T>> {
+        if bool::read(input)? {
+            Some(Some(T::read(input)?))
+        } else {
+            Some(None)
+        }
+    }
+}
+
+impl<T: Cached> Cached for Vec<T> {
+    fn write(&self, out: &mut Vec<u8>) {
+        (self.len() as u64).write(out);
+        for value in self.iter() {
+            value.write(out);
+        }
+    }
+
+    fn read(input: &mut &[u8]) -> Option<Vec<T>> {
+        let len = u64::read(input)?;
+        (0..len).map(|_| T::read(input)).collect()
+    }
+}
+
+impl<A: Cached, B: Cached> Cached for (A, B) {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.0.write(out);
+        self.1.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<(A, B)> {
+        Some((A::read(input)?, B::read(input)?))
+    }
+}
+
+impl Cached for HashMap<String, String> {
+    fn write(&self, out: &mut Vec<u8>) {
+        let mut pairs: Vec<(String, String)> = self
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        pairs.sort();
+        pairs.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<HashMap<String, String>> {
+        Some(Vec::<(String, String)>::read(input)?.into_iter().collect())
+    }
+}
+
+impl Cached for Capture {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.command.write(out);
+        self.stop_command.write(out);
+        self.dir.write(out);
+        self.ext.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<Capture> {
+        Some(Capture {
+            command: String::read(input)?,
+            stop_command: Option::read(input)?,
+            dir: PathBuf::read(input)?,
+            ext: String::read(input)?,
+        })
+    }
+}
+
+impl Cached for WindowManager {
+    fn write(&self, out: &mut Vec<u8>) {
+        let n: u64 = match self {
+            WindowManager::Hyprland => 0,
+            WindowManager::Sway => 1,
+            WindowManager::Wmctrl => 2,
+        };
+        n.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<WindowManager> {
+        match u64::read(input)? {
+            0 => Some(WindowManager::Hyprland),
+            1 => Some(WindowManager::Sway),
+            2 => Some(WindowManager::Wmctrl),
+            _ => None,
+        }
+    }
+}
+
+impl Cached for WmRules {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.window_manager.write(out);
+        self.workspace.write(out);
+        self.fullscreen.write(out);
+        self.delay_seconds.write(out);
+        self.class.write(out);
+        self.title.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<WmRules> {
+        Some(WmRules {
+            window_manager: Option::read(input)?,
+            workspace: Option::read(input)?,
+            fullscreen: bool::read(input)?,
+            delay_seconds: u64::read(input)?,
+            class: Option::read(input)?,
+            title: Option::read(input)?,
+        })
+    }
+}
+
+impl Cached for Game {
+    fn write(&self, out: &mut Vec<u8>) {
+        // Taken apart so that a new field can not be forgotten here
+        let Game {
+            id,
+            name,
+            version,
+            goal_hours,
+            length_hours,
+            session_hours,
+            rating,
+            dir,
+            dir_prefix,
+            command,
+            env,
+            tags,
+            installed,
+            track_stats,
+            added,
+            status,
+            favorite,
+            remind_after_minutes,
+            controller_command,
+            capture,
+            before_launch,
+            after_exit,
+            wm_rules,
+            fps_log_dir,
+            time_limit,
+            steam_id,
+            update_command,
+            companions,
+            registry_commands,
+            requires,
+            stop_required,
+            kill_wineserver,
+            process_name,
+            missing_runner,
+            missing_sandbox,
+            cover,
+            save_dir,
+            launch_config,
+        } = self;
+        id.write(out);
+        name.write(out);
+        version.write(out);
+        goal_hours.write(out);
+        length_hours.write(out);
+        session_hours.write(out);
+        rating.write(out);
+        dir.write(out);
+        dir_prefix.write(out);
+        command.write(out);
+        env.write(out);
+        tags.write(out);
+        installed.write(out);
+        track_stats.write(out);
+        added.write(out);
+        status.write(out);
+        favorite.write(out);
+        remind_after_minutes.write(out);
+        controller_command.write(out);
+        capture.write(out);
+        before_launch.write(out);
+        after_exit.write(out);
+        wm_rules.write(out);
+        fps_log_dir.write(out);
+        time_limit.write(out);
+        steam_id.write(out);
+        update_command.write(out);
+        companions.write(out);
+        registry_commands.write(out);
+        requires.write(out);
+        stop_required.write(out);
+        kill_wineserver.write(out);
+        process_name.write(out);
+        missing_runner.write(out);
+        missing_sandbox.write(out);
+        cover.write(out);
+        save_dir.write(out);
+        launch_config.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<Game> {
+        Some(Game {
+            id: Cached::read(input)?,
+            name: Cached::read(input)?,
+            version: Cached::read(input)?,
+            goal_hours: Cached::read(input)?,
+            length_hours: Cached::read(input)?,
+            session_hours: Cached::read(input)?,
+            rating: Cached::read(input)?,
+            dir: Cached::read(input)?,
+            dir_prefix: Cached::read(input)?,
+            command: Cached::read(input)?,
+            env: Cached::read(input)?,
+            tags: Cached::read(input)?,
+            installed: Cached::read(input)?,
+            track_stats: Cached::read(input)?,
+            added: Cached::read(input)?,
+            status: Cached::read(input)?,
+            favorite: Cached::read(input)?,
+            remind_after_minutes: Cached::read(input)?,
+            controller_command: Cached::read(input)?,
+            capture: Cached::read(input)?,
+            before_launch: Cached::read(input)?,
+            after_exit: Cached::read(input)?,
+            wm_rules: Cached::read(input)?,
+            fps_log_dir: Cached::read(input)?,
+            time_limit: Cached::read(input)?,
+            steam_id: Cached::read(input)?,
+            update_command: Cached::read(input)?,
+            companions: Cached::read(input)?,
+            registry_commands: Cached::read(input)?,
+            requires: Cached::read(input)?,
+            stop_required: Cached::read(input)?,
+            kill_wineserver: Cached::read(input)?,
+            process_name: Cached::read(input)?,
+            missing_runner: Cached::read(input)?,
+            missing_sandbox: Cached::read(input)?,
+            cover: Cached::read(input)?,
+            save_dir: Cached::read(input)?,
+            launch_config: Cached::read(input)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let dir = std::env::temp_dir().join(format!("game_rs_config_cache_{}", std::process::id()));
+        let config = dir.join("games.toml");
+        let cache = dir.join(CACHE_FILE);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&config, "[games.doom]").unwrap();
+        let mut game = Game {
+            id: "doom".to_string(),
+            name: "Doom".to_string(),
+            rating: Some(8.5),
+            command: vec!["gzdoom".to_string()],
+            added: Date::from_calendar_date(2025, time::Month::March, 1).ok(),
+            time_limit: Some(Duration::from_secs(90)),
+            wm_rules: Some(WmRules {
+                window_manager: Some(WindowManager::Sway),
+                workspace: Some("3".to_string()),
+                fullscreen: true,
+                delay_seconds: 2,
+                class: Some("gzdoom".to_string()),
+                title: None,
+            }),
+            ..Default::default()
+        };
+        game.env
+            .insert("DOOMWADDIR".to_string(), "/wads".to_string());
+        let games = HashMap::from([("doom".to_string(), game)]);
+        let sources = vec![config.clone(), dir.join("missing.toml")];
+        write(&cache, "key", &sources, "[settings]\n", &games).unwrap();
+
+        let cached = read(&cache, "key").expect("No cache");
+        assert_eq!(cached.rest, "[settings]\n");
+        let doom = &cached.games["doom"];
+        assert_eq!(doom.rating, Some(8.5));
+        assert_eq!(doom.added, games["doom"].added);
+        assert_eq!(doom.time_limit, Some(Duration::from_secs(90)));
+        assert_eq!(doom.env["DOOMWADDIR"], "/wads");
+        assert_eq!(
+            doom.wm_rules.as_ref().unwrap().workspace.as_deref(),
+            Some("3")
+        );
+
+        assert!(read(&cache, "other key").is_none());
+        fs::write(dir.join("missing.toml"), "").unwrap();
+        assert!(read(&cache, "key").is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+use std::fs;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Set an option on a game in the config file, preserving the formatting and
+/// comments of the rest of the file
+pub fn set_game_option(path: &Path, game_id: &str, key: &str, value: Value) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let updated = with_game_option(&content, game_id, key, value)?;
+    fs::write(path, updated).map_err(|e| e.to_string())
+}
+
+/// Set a value in the `[settings]` table of the config file, creating the
+/// table if needed
+pub fn set_setting(path: &Path, key: &str, value: Value) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let updated = with_setting(&content, key, value)?;
+    fs::write(path, updated).map_err(|e| e.to_string())
+}
+
+/// Add a game to the config file, with the given options
+pub fn add_game(path: &Path, game_id: &str, options: &[(&str, Value)]) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let updated = with_game(&content, game_id, options)?;
+    fs::write(path, updated).map_err(|e| e.to_string())
+}
+
+/// A game's table on its own, as it would be added to the config file
+pub fn game_entry(game_id: &str, options: &[(&str, Value)]) -> String {
+    with_game("", game_id, options).unwrap_or_default()
+}
+
+fn with_game(content: &str, game_id: &str, options: &[(&str, Value)]) -> Result<String, String> {
+    let mut doc = content.parse::<DocumentMut>().map_err(|e| e.to_string())?;
+    let games = doc
+        .entry("games")
+        .or_insert(Item::Table(implicit_table()))
+        .as_table_mut()
+        .ok_or("The 'games' key must correspond to a table".to_string())?;
+    if games.contains_key(game_id) {
+        return Err(format!("Game already in config: {}", game_id));
+    }
+    let mut game = toml_edit::Table::new();
+    for (key, value) in options.iter() {
+        game.insert(key, Item::Value(value.clone()));
+    }
+    games.insert(game_id, Item::Table(game));
+    Ok(doc.to_string())
+}
+
+/// A table that is not written out on its own, only its subtables
+fn implicit_table() -> toml_edit::Table {
+    let mut table = toml_edit::Table::new();
+    table.set_implicit(true);
+    table
+}
+
+fn with_setting(content: &str, key: &str, value: Value) -> Result<String, String> {
+    let mut doc = content.parse::<DocumentMut>().map_err(|e| e.to_string())?;
+    let settings = doc
+        .entry("settings")
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .ok_or("The 'settings' key must correspond to a table".to_string())?;
+    settings.insert(key, Item::Value(value));
+    Ok(doc.to_string())
+}
+
+fn with_game_option(
+    content: &str,
+    game_id: &str,
+    key: &str,
+    value: Value,
+) -> Result<String, String> {
+    let mut doc = content.parse::<DocumentMut>().map_err(|e| e.to_string())?;
+    let game = doc
+        .get_mut("games")
+        .and_then(|games| games.get_mut(game_id))
+        .and_then(|game| game.as_table_like_mut())
+        .ok_or(format!("No such game in config: {}", game_id))?;
+    game.insert(key, Item::Value(value));
+    Ok(doc.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_game_option_preserves_comments() {
+        let config = "# My games\n[games]\n[games.doom]\nname = \"Doom\" # the classic\ncmd = \"dsda-doom\"\n";
+        let updated =
+            with_game_option(config, "doom", "status", Value::from("dropped")).expect("Bad edit");
+        assert_eq!(
+            updated,
+            "# My games\n[games]\n[games.doom]\nname = \"Doom\" # the classic\ncmd = \"dsda-doom\"\nstatus = \"dropped\"\n"
+        );
+    }
+
+    #[test]
+    fn test_set_option_on_nonexistent_game() {
+        let config = "[games]\n[games.doom]\nname = \"Doom\"\ncmd = \"dsda-doom\"\n";
+        assert!(with_game_option(config, "quake", "favorite", Value::from(true)).is_err());
+    }
+
+    #[test]
+    fn test_add_game() {
+        let config = "[games]\n[games.doom]\nname = \"Doom\"\ncmd = \"dsda-doom\"\n";
+        let options = [
+            ("name", Value::from("Quake")),
+            ("cmd", Value::from("quakespasm")),
+        ];
+        let updated = with_game(config, "quake", &options).expect("Bad edit");
+        assert!(updated.ends_with("\n[games.quake]\nname = \"Quake\"\ncmd = \"quakespasm\"\n"));
+        assert!(with_game(&updated, "quake", &options).is_err());
+        assert_eq!(
+            game_entry("quake", &options),
+            "[games.quake]\nname = \"Quake\"\ncmd = \"quakespasm\"\n"
+        );
+    }
+
+    #[test]
+    fn test_set_setting_creates_table() {
+        let config = "[games]\n[games.doom]\nname = \"Doom\"\ncmd = \"dsda-doom\"\n";
+        let updated =
+            with_setting(config, "runner", Value::from("GE-Proton9-20")).expect("Bad edit");
+        assert!(updated.contains("[settings]\nrunner = \"GE-Proton9-20\"\n"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use toml_edit::{Document, Item, TableLike};
+
+/// A config file's name and content
+pub type Source<'a> = (&'a str, &'a str);
+
+/// Where a value was set, and the value without any whitespace or comments
+struct Setting<'a> {
+    location: Location<'a>,
+    value: String,
+}
+
+/// A place in a config file, whose line is only worked out when it is shown
+#[derive(Clone, Copy)]
+struct Location<'a> {
+    source: Source<'a>,
+    offset: Option<usize>,
+}
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (file, content) = self.source;
+        match self.offset {
+            Some(offset) => {
+                let line = content[..offset].matches('\n').count() + 1;
+                write!(f, "{}:{}", file, line)
+            }
+            None => write!(f, "{}", file),
+        }
+    }
+}
+
+/// Find what the overlays for the host would override without it being
+/// obvious: an option set differently by the host's `[host]` table and by
+/// its overlay file, where the file silently wins, and game IDs that differ
+/// only in case or in `-` and `_`, which are separate games when one was
+/// probably meant to override the other
+pub fn find(config: Source, host: Option<&str>, overlay: Option<Source>) -> Vec<String> {
+    let Ok(doc) = Document::parse(config.1) else {
+        return Vec::new();
+    };
+    let overlay_doc = overlay.and_then(|(_, content)| Document::parse(content).ok());
+    let host_section = host.and_then(|host| doc.get("host")?.get(host));
+
+    let mut conflicts = Vec::new();
+    if let Some(host_section) = host_section.and_then(|s| s.as_table_like())
+        && let (Some(overlay), Some(overlay_doc)) = (overlay, overlay_doc.as_ref())
+    {
+        let mut from_section = BTreeMap::new();
+        settings(config, host_section, "", &mut from_section);
+        let mut from_file = BTreeMap::new();
+        settings(overlay, overlay_doc.as_table(), "", &mut from_file);
+        for (key, setting) in from_file.iter() {
+            if let Some(other) = from_section.get(key)
+                && other.value != setting.value
+            {
+                conflicts.push(format!(
+                    "{} is set to {} at {} and to {} at {}, which is used",
+                    key, other.value, other.location, setting.value, setting.location
+                ));
+            }
+        }
+    }
+
+    let mut game_ids: BTreeMap<String, Vec<(String, Location)>> = BTreeMap::new();
+    let mut layers = vec![(config, doc.get("games"))];
+    if let Some(host_section) = host_section {
+        layers.push((config, host_section.get("games")));
+    }
+    if let (Some(overlay), Some(overlay_doc)) = (overlay, overlay_doc.as_ref()) {
+        layers.push((overlay, overlay_doc.get("games")));
+    }
+    for (source, games) in layers {
+        let Some(games) = games.and_then(|g| g.as_table_like()) else {
+            continue;
+        };
+        for (game_id, _) in games.iter() {
+            let location = location(source, games, game_id);
+            game_ids
+                .entry(normalize(game_id))
+                .or_default()
+                .push((game_id.to_string(), location));
+        }
+    }
+    for ids in game_ids.values() {
+        if ids.iter().any(|(id, _)| *id != ids[0].0) {
+            let ids: Vec<String> = ids
+                .iter()
+                .map(|(id, location)| format!("{} ({})", id, location))
+                .collect();
+            conflicts.push(format!(
+                "game IDs differ only in case or '-' and '_': {}",
+                ids.join(", ")
+            ));
+        }
+    }
+    conflicts
+}
+
+/// Whether `find` might find anything, which is much quicker to check than
+/// finding it: only if there is an overlay file, or some game IDs (after the
+/// overlays are applied) are the same apart from case or `-` and `_`
+pub fn possible(config: &toml::Table, has_overlay_file: bool) -> bool {
+    if has_overlay_file {
+        return true;
+    }
+    let Some(games) = config.get("games").and_then(|g| g.as_table()) else {
+        return false;
+    };
+    let mut normalized: Vec<String> = games.keys().map(|id| normalize(id)).collect();
+    normalized.sort();
+    normalized.windows(2).any(|ids| ids[0] == ids[1])
+}
+
+/// A game ID in lower case with `-` replaced by `_`
+fn normalize(game_id: &str) -> String {
+    game_id.to_lowercase().replace('-', "_")
+}
+
+/// Collect every value under the table, by its dotted key
+fn settings<'a>(
+    source: Source<'a>,
+    table: &dyn TableLike,
+    prefix: &str,
+    found: &mut BTreeMap<String, Setting<'a>>,
+) {
+    for (key, item) in table.iter() {
+        let dotted = format!("{}{}", prefix, key);
+        if let Some(subtable) = item.as_table_like() {
+            settings(source, subtable, &format!("{}.", dotted), found);
+            continue;
+        }
+        let Item::Value(value) = item else {
+            continue;
+        };
+        let mut value = value.clone();
+        value.decor_mut().clear();
+        let setting = Setting {
+            location: location(source, table, key),
+            value: value.to_string(),
+        };
+        found.insert(dotted, setting);
+    }
+}
+
+/// Where a key in a table is
+fn location<'a>(source: Source<'a>, table: &dyn TableLike, key: &str) -> Location<'a> {
+    let span = table.get_key_value(key).and_then(|(key, _)| key.span());
+    Location {
+        source,
+        offset: span.map(|span| span.start),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflicting_host_overlays() {
+        let config = "[settings]
+width = 2560
+
+[host.deck.settings]
+width = 1280
+height = 800 # the screen
+
+[games.doom]
+name = \"Doom\"
+cmd = \"gzdoom\"
+";
+        let overlay = "[settings]\nwidth = 1024\nheight = 800\n\n[games.Doom]\ninstalled = false\n";
+        let conflicts = find(
+            ("games.toml", config),
+            Some("deck"),
+            Some(("games.deck.toml", overlay)),
+        );
+        assert_eq!(
+            conflicts,
+            vec![
+                "settings.width is set to 1280 at games.toml:5 and to 1024 at games.deck.toml:2, which is used",
+                "game IDs differ only in case or '-' and '_': doom (games.toml:8), Doom (games.deck.toml:5)",
+            ]
+        );
+        assert!(find(("games.toml", config), Some("laptop"), None).is_empty());
+
+        let table: toml::Table = config.parse().unwrap();
+        assert!(!possible(&table, false));
+        assert!(possible(&table, true));
+        let table: toml::Table = "[games.doom]\n[games.Doom]".parse().unwrap();
+        assert!(possible(&table, false));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::Game;
+use crate::template;
+use toml::{Table, Value};
+
+/// The config table of commands defined by the user
+pub const COMMANDS: &str = "commands";
+
+/// The fields of a game that a command can use
+const FIELDS: [&str; 4] = ["id", "name", "dir", "save_dir"];
+
+/// A command from the `[commands]` table, like
+/// `moddir = "xdg-open {dir}/mods"`
+pub struct CustomCommand<'a> {
+    pub name: &'a str,
+    pub template: &'a str,
+}
+
+/// Whether a command is run for a game, which it is when it uses any of the
+/// game's fields
+pub fn uses_game(template: &str) -> bool {
+    FIELDS
+        .iter()
+        .any(|field| template.contains(&format!("{{{}}}", field)))
+}
+
+/// The commands in the `[commands]` table, leaving out (with a warning) any
+/// that is not a command line or that has the name of a built-in command
+pub fn parse<'a>(config: &'a Table, is_builtin: impl Fn(&str) -> bool) -> Vec<CustomCommand<'a>> {
+    let Some(Value::Table(commands)) = config.get(COMMANDS) else {
+        return Vec::new();
+    };
+    let mut custom = Vec::new();
+    for (name, value) in commands.iter() {
+        let template = match value {
+            Value::String(template)
+                if shell_words::split(template).is_ok_and(|w| !w.is_empty()) =>
+            {
+                template
+            }
+            _ => {
+                warn!("Invalid command in [{}]: {}", COMMANDS, name);
+                continue;
+            }
+        };
+        if is_builtin(name) {
+            warn!("Command {} in [{}] is a built-in command", name, COMMANDS);
+            continue;
+        }
+        custom.push(CustomCommand { name, template });
+    }
+    custom
+}
+
+/// The command line with the game's fields filled in and the extra arguments
+/// added to the end, or `None` if it uses the game's directory or save
+/// directory and the game does not have one
+pub fn render(template: &str, game: Option<&Game>, args: &[String]) -> Option<Vec<String>> {
+    let mut vars = Vec::new();
+    let save_dir = game
+        .and_then(|game| game.save_dir.as_ref())
+        .map(|dir| dir.to_string_lossy().to_string());
+    if let Some(game) = game {
+        vars.push(("id", game.id.as_str()));
+        vars.push(("name", game.name.as_str()));
+        if let Some(dir) = &game.dir {
+            vars.push(("dir", dir.as_str()));
+        } else if template.contains("{dir}") {
+            return None;
+        }
+        if let Some(save_dir) = &save_dir {
+            vars.push(("save_dir", save_dir.as_str()));
+        } else if template.contains("{save_dir}") {
+            return None;
+        }
+    }
+    let mut command = template::render_command(template, &vars)?;
+    command.extend(args.iter().cloned());
+    Some(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let config: Table = "
+            [commands]
+            moddir = \"xdg-open {dir}/mods\"
+            backup = \"restic backup /home/test/saves\"
+            list = \"ls\"
+            broken = 1
+        "
+        .parse()
+        .unwrap();
+        let commands = parse(&config, |name| name == "list");
+        let names: Vec<&str> = commands.iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["backup", "moddir"]);
+        assert!(!uses_game(commands[0].template));
+        assert!(uses_game(commands[1].template));
+    }
+
+    #[test]
+    fn test_render() {
+        let game = Game {
+            id: "doom".to_string(),
+            name: "Doom".to_string(),
+            dir: Some("/games/My Doom".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            render("xdg-open {dir}/mods", Some(&game), &["-v".to_string()]),
+            Some(vec![
+                "xdg-open".to_string(),
+                "/games/My Doom/mods".to_string(),
+                "-v".to_string()
+            ])
+        );
+        assert_eq!(render("restic backup {save_dir}", Some(&game), &[]), None);
+        let with_saves = Game {
+            save_dir: Some("/saves/doom".into()),
+            ..game.clone()
+        };
+        assert_eq!(
+            render("restic backup {save_dir}", Some(&with_saves), &[]),
+            Some(vec![
+                "restic".to_string(),
+                "backup".to_string(),
+                "/saves/doom".to_string()
+            ])
+        );
+        let without_dir = Game { dir: None, ..game };
+        assert_eq!(render("xdg-open {dir}", Some(&without_dir), &[]), None);
+    }
+}
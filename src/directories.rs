@@ -0,0 +1,136 @@
+use crate::parse_error::ParseError;
+use toml::{Table, Value};
+
+/// Expand the references to other directories in each entry of the
+/// `[directories]` table, like the `{games_dir}` in
+/// `wine_games = "{games_dir}/wine"`
+pub fn resolve(directories: &Table) -> Result<Table, ParseError> {
+    let mut resolved = Table::new();
+    for name in directories.keys() {
+        resolve_one(name, directories, &mut Vec::new(), &mut resolved)?;
+    }
+    Ok(resolved)
+}
+
+/// Resolve one directory, where `resolving` holds the directories whose
+/// references are being followed, to find cycles
+fn resolve_one(
+    name: &str,
+    directories: &Table,
+    resolving: &mut Vec<String>,
+    resolved: &mut Table,
+) -> Result<String, ParseError> {
+    if let Some(Value::String(dir)) = resolved.get(name) {
+        return Ok(dir.to_string());
+    }
+    let dir = match directories.get(name) {
+        Some(Value::String(dir)) => dir,
+        // Anything else is left for the games that use it to report
+        Some(value) => {
+            resolved.insert(name.to_string(), value.clone());
+            return Ok(String::new());
+        }
+        None => return Ok(String::new()),
+    };
+    if resolving.iter().any(|n| n == name) {
+        resolving.push(name.to_string());
+        return Err(ParseError::DirectoryCycle(resolving.join(" -> ")));
+    }
+    resolving.push(name.to_string());
+    let mut expanded = String::new();
+    let mut rest = dir.as_str();
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let reference = &rest[start + 1..start + end];
+        // Braces around anything but the name of another directory, which
+        // can be part of a path, are kept as they are
+        if !matches!(directories.get(reference), Some(Value::String(_))) {
+            expanded.push_str(&rest[..start + 1]);
+            rest = &rest[start + 1..];
+            continue;
+        }
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&resolve_one(reference, directories, resolving, resolved)?);
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    resolving.pop();
+    trace!("Directory {} is {}", name, expanded);
+    resolved.insert(name.to_string(), Value::String(expanded.clone()));
+    Ok(expanded)
+}
+
+/// The names in braces in a directory, whether or not they are the names of
+/// other directories
+pub fn references(dir: &str) -> Vec<&str> {
+    let mut references = Vec::new();
+    let mut rest = dir;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let reference = &rest[start + 1..start + end];
+        if !reference.is_empty() && !reference.contains('{') {
+            references.push(reference);
+        }
+        rest = &rest[start + 1..];
+    }
+    references
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_nested_directories() {
+        let directories: Table = "
+            games_dir = \"/home/test/Games\"
+            wine_games = \"{games_dir}/wine\"
+            gog_games = \"{wine_games}/GOG Games\"
+        "
+        .parse()
+        .unwrap();
+        let resolved = resolve(&directories).expect("Bad directories");
+        assert_eq!(
+            resolved["gog_games"].as_str(),
+            Some("/home/test/Games/wine/GOG Games")
+        );
+        assert_eq!(resolved["games_dir"].as_str(), Some("/home/test/Games"));
+    }
+
+    #[test]
+    fn test_directory_cycle() {
+        let directories: Table = "a = \"{b}/a\"\nb = \"{a}/b\"".parse().unwrap();
+        match resolve(&directories) {
+            Err(ParseError::DirectoryCycle(cycle)) => assert_eq!(cycle, "a -> b -> a"),
+            _ => panic!("Cycle not found"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_reference() {
+        let directories: Table = "
+            games_dir = \"/home/test/Games\"
+            a = \"{games_dir}/{games}/{a\"
+            b = \"/{{games_dir}}/{}\"
+        "
+        .parse()
+        .unwrap();
+        let resolved = resolve(&directories).expect("Bad directories");
+        assert_eq!(resolved["a"].as_str(), Some("/home/test/Games/{games}/{a"));
+        assert_eq!(resolved["b"].as_str(), Some("/{/home/test/Games}/{}"));
+    }
+
+    #[test]
+    fn test_references() {
+        assert_eq!(
+            references("{games_dir}/{games}/{a"),
+            vec!["games_dir", "games"]
+        );
+        assert_eq!(references("/{{games_dir}}/{}"), vec!["games_dir"]);
+        assert!(references("/home/test/Games").is_empty());
+    }
+}
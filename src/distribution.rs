@@ -0,0 +1,101 @@
+use crate::stats::format_play_time;
+
+const BAR_WIDTH: usize = 40;
+
+/// Upper bounds, in minutes, of the histogram buckets; the last bucket holds
+/// everything longer
+const BUCKET_MINUTES: [u32; 5] = [15, 30, 60, 120, 240];
+
+/// How long a game's sessions tend to last
+pub struct Distribution {
+    lengths: Vec<u32>,
+}
+
+impl Distribution {
+    pub fn new(mut lengths: Vec<u32>) -> Distribution {
+        lengths.sort();
+        Distribution { lengths }
+    }
+
+    /// The session length below which the given percentage of sessions fall,
+    /// using the nearest-rank method
+    pub fn percentile(&self, p: u32) -> u32 {
+        if self.lengths.is_empty() {
+            return 0;
+        }
+        let rank = (p as usize * self.lengths.len()).div_ceil(100).max(1);
+        self.lengths[rank - 1]
+    }
+
+    pub fn max(&self) -> u32 {
+        self.lengths.last().copied().unwrap_or(0)
+    }
+
+    /// The number of sessions in each bucket, with the bucket's label
+    pub fn histogram(&self) -> Vec<(String, usize)> {
+        let mut counts = vec![0; BUCKET_MINUTES.len() + 1];
+        for length in self.lengths.iter() {
+            let bucket = BUCKET_MINUTES
+                .iter()
+                .position(|minutes| *length < minutes * 60)
+                .unwrap_or(BUCKET_MINUTES.len());
+            counts[bucket] += 1;
+        }
+        let mut labels = Vec::new();
+        let mut lower = 0;
+        for minutes in BUCKET_MINUTES.iter() {
+            labels.push(format!(
+                "{}-{}",
+                format_play_time(lower * 60),
+                format_play_time(minutes * 60)
+            ));
+            lower = *minutes;
+        }
+        labels.push(format!("{}+", format_play_time(lower * 60)));
+        labels.into_iter().zip(counts).collect()
+    }
+
+    pub fn format(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Sessions: {}", self.lengths.len()),
+            format!("p50: {}", format_play_time(self.percentile(50))),
+            format!("p90: {}", format_play_time(self.percentile(90))),
+            format!("Max: {}", format_play_time(self.max())),
+        ];
+        let histogram = self.histogram();
+        let most = histogram.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+        let label_width = histogram.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+        for (label, count) in histogram.iter() {
+            let bar = "#".repeat(count * BAR_WIDTH / most);
+            lines.push(format!(
+                "{:>width$} | {} {}",
+                label,
+                bar,
+                count,
+                width = label_width
+            ));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let distribution = Distribution::new((1..=10).map(|m| m * 60).collect());
+        assert_eq!(distribution.percentile(50), 5 * 60);
+        assert_eq!(distribution.percentile(90), 9 * 60);
+        assert_eq!(distribution.max(), 10 * 60);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let distribution = Distribution::new(vec![5 * 60, 45 * 60, 50 * 60, 5 * 60 * 60]);
+        let counts: Vec<usize> = distribution.histogram().iter().map(|(_, n)| *n).collect();
+        assert_eq!(counts, vec![1, 0, 2, 0, 0, 1]);
+        assert_eq!(distribution.histogram()[5].0, "4h+");
+    }
+}
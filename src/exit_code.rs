@@ -0,0 +1,9 @@
+//! Process exit codes, so that wrapper scripts can tell what went wrong
+
+pub const FAILURE: i32 = 1;
+pub const USAGE_ERROR: i32 = 2;
+pub const CONFIG_ERROR: i32 = 3;
+pub const UNKNOWN_COMMAND: i32 = 4;
+pub const UNKNOWN_GAME: i32 = 5;
+pub const LAUNCH_FAILURE: i32 = 6;
+pub const GAME_CRASHED: i32 = 7;
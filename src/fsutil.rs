@@ -0,0 +1,88 @@
+use std::env::{split_paths, var_os};
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const KIB: u64 = 1024;
+const MIB: u64 = 1024 * KIB;
+const GIB: u64 = 1024 * MIB;
+
+/// The total size in bytes of a file, or of all the files under a directory
+pub fn dir_size(path: &Path) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    match fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| dir_size(&entry.path()))
+            .sum(),
+        Err(_) => 0,
+    }
+}
+
+/// Write a file that only the user can read, like a secret token, creating
+/// its directory if needed
+pub fn write_private(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+/// Find a program in the directories of a `PATH`-style list
+pub fn find_in_path(program: &str, path: &OsStr) -> Option<PathBuf> {
+    split_paths(path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Whether a program can be found on the `PATH`
+pub fn on_path(program: &str) -> bool {
+    var_os("PATH").is_some_and(|path| find_in_path(program, &path).is_some())
+}
+
+pub fn format_size(bytes: u64) -> String {
+    if bytes >= GIB {
+        format!("{:.1} GiB", bytes as f64 / GIB as f64)
+    } else if bytes >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB as f64)
+    } else if bytes >= KIB {
+        format!("{:.1} KiB", bytes as f64 / KIB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * GIB), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_find_in_path() {
+        let root = std::env::temp_dir().join(format!("game_rs_path_{}", std::process::id()));
+        let bin = root.join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        fs::write(bin.join("nano"), "").unwrap();
+        let path = std::env::join_paths([root.join("missing"), bin.clone()]).unwrap();
+        assert_eq!(find_in_path("nano", &path), Some(bin.join("nano")));
+        assert_eq!(find_in_path("vi", &path), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,64 @@
+const MATCH_SCORE: i64 = 16;
+const CONSECUTIVE_BONUS: i64 = 8;
+const WORD_START_BONUS: i64 = 8;
+const GAP_PENALTY: i64 = 1;
+
+/// How well the query matches the text, if every character of the query
+/// appears in the text in order (ignoring case); consecutive characters and
+/// characters at the start of a word score higher
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|i| text[*i] == q)?;
+        score += MATCH_SCORE;
+        match previous_match {
+            Some(previous) if previous + 1 == found => score += CONSECUTIVE_BONUS,
+            Some(previous) => score -= (found - previous - 1) as i64 * GAP_PENALTY,
+            None => score -= found as i64 * GAP_PENALTY,
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+        previous_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+/// The indexes of the items that match the query, best match first
+pub fn filter(query: &str, items: &[String]) -> Vec<usize> {
+    let mut matches: Vec<(usize, i64)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| Some((i, score(query, item)?)))
+        .collect();
+    matches.sort_by(|(a, a_score), (b, b_score)| b_score.cmp(a_score).then(a.cmp(b)));
+    matches.into_iter().map(|(i, _)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        assert!(score("dm", "Doom").is_some());
+        assert!(score("md", "Doom").is_none());
+        assert!(score("", "Doom").is_some());
+        assert!(score("DOOM", "doom").is_some());
+    }
+
+    #[test]
+    fn test_filter_ranks_word_starts_and_runs_first() {
+        let items: Vec<String> = ["Half-Life 2", "Hades", "Hollow Knight"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(filter("hl", &items), vec![0, 2]);
+        assert_eq!(filter("hades", &items), vec![1]);
+        assert_eq!(filter("kn", &items), vec![2]);
+    }
+}
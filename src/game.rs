@@ -1,19 +1,77 @@
+use crate::background::BackgroundProcess;
+use crate::capture::Capture;
+use crate::exit_code;
+use crate::parse_error::ParseError;
+use crate::platform::Platform;
+use crate::reminder::Reminder;
+use crate::timings;
+use crate::watch::GAME_ID_ENV_VAR;
+use crate::wm_rules::WmRules;
 use std::collections::HashMap;
 use std::env;
-use std::path::Path;
-use std::process::Command;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use time::Date;
 
 const EXIT_SUCCESS: i32 = 0;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
 
-#[derive(Debug)]
+#[derive(Debug, Default, Clone)]
 pub struct Game {
     pub id: String,
     pub name: String,
+    pub version: Option<String>,
+    pub goal_hours: Option<f64>,
+    pub length_hours: Option<f64>,
+    /// How long a sitting of the game usually takes
+    pub session_hours: Option<f64>,
+    /// How good the game is, out of 10
+    pub rating: Option<f64>,
     pub dir: Option<String>,
+    /// The name of the shared directory (from `[directories]`) the game's
+    /// directory is in
+    pub dir_prefix: Option<String>,
     pub command: Vec<String>,
     pub env: HashMap<String, String>,
     pub tags: Vec<String>,
     pub installed: bool,
+    /// Whether play sessions are recorded in the stats
+    pub track_stats: bool,
+    pub added: Option<Date>,
+    pub status: Option<String>,
+    pub favorite: bool,
+    pub remind_after_minutes: Option<u32>,
+    pub controller_command: Option<Vec<String>>,
+    /// How sessions are recorded, for games with `record = true`
+    pub capture: Option<Capture>,
+    pub before_launch: Vec<String>,
+    pub after_exit: Vec<String>,
+    pub wm_rules: Option<WmRules>,
+    pub fps_log_dir: Option<PathBuf>,
+    pub time_limit: Option<Duration>,
+    pub steam_id: Option<String>,
+    pub update_command: Option<Vec<String>>,
+    pub companions: Vec<Vec<String>>,
+    pub registry_commands: Vec<Vec<String>>,
+    pub requires: Vec<String>,
+    pub stop_required: bool,
+    pub kill_wineserver: Option<Vec<String>>,
+    pub process_name: Option<String>,
+    /// The runner the game is set to use when it is not installed, which
+    /// stops the game from being launched
+    pub missing_runner: Option<String>,
+    /// The sandbox the game is set to run in when it is not available, which
+    /// also stops the game from being launched
+    pub missing_sandbox: Option<String>,
+    pub cover: Option<PathBuf>,
+    /// Where the game keeps its saves, for backing them up
+    pub save_dir: Option<PathBuf>,
+    /// The settings the game is launched with that sessions record, like the
+    /// runner and whether gamescope is used
+    pub launch_config: Vec<(String, String)>,
 }
 
 impl Game {
@@ -21,23 +79,112 @@ impl Game {
         format!("{} - {}", self.id, self.name)
     }
 
+    /// The launch configuration as it is stored with a session, like
+    /// `runner=GE-Proton9-20,gamescope=on,mangohud=on,fps_limit=60`
+    pub fn format_launch_config(&self) -> String {
+        self.launch_config
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    /// An error from running a copy of the game (like the one `bench` runs),
+    /// as if it came from running the game itself, which has the same
+    /// directory, runner and sandbox
+    pub fn error_from_copy(&self, error: GameError) -> GameError<'_> {
+        match error {
+            GameError::NotInstalled => GameError::NotInstalled,
+            GameError::CommandReturnedFailure(command) => {
+                GameError::CommandReturnedFailure(command)
+            }
+            GameError::CouldNotChangeDirectory(_) => {
+                GameError::CouldNotChangeDirectory(self.dir.as_deref().unwrap_or_default())
+            }
+            GameError::NoSuchRunner(_) => {
+                GameError::NoSuchRunner(self.missing_runner.as_deref().unwrap_or_default())
+            }
+            GameError::NoSandbox(_) => {
+                GameError::NoSandbox(self.missing_sandbox.as_deref().unwrap_or_default())
+            }
+            _ => GameError::ExecutionFailed,
+        }
+    }
+
+    /// A copy of the game that runs the given command instead, from the
+    /// current directory, for when something else (like Steam) has already
+    /// worked out how to start it
+    pub fn wrap(&self, command: Vec<String>) -> Game {
+        let mut game = self.clone();
+        game.command = command;
+        game.dir = None;
+        game.missing_runner = None;
+        game.missing_sandbox = None;
+        game.installed = true;
+        game
+    }
+
     pub fn run<'a>(&'a self) -> Result<(), GameError<'a>> {
         if !self.installed {
             return Err(GameError::NotInstalled);
         }
-
-        if let Some(dir) = &self.dir {
-            let path = Path::new(dir);
-            if env::set_current_dir(path).is_err() {
-                return Err(GameError::CouldNotChangeDirectory(dir));
-            }
+        if let Some(runner) = &self.missing_runner {
+            return Err(GameError::NoSuchRunner(runner));
         }
+        if let Some(sandbox) = &self.missing_sandbox {
+            return Err(GameError::NoSandbox(sandbox));
+        }
+
+        self.change_directory()?;
         let mut command = Command::new(&self.command[0]);
         command.args(&self.command[1..]);
         for (k, v) in self.env.iter() {
+            debug!("Setting environment variable: {}={}", k, v);
             command.env(k, v);
         }
-        match command.status() {
+        command.env(GAME_ID_ENV_VAR, &self.id);
+        if let Some(dir) = &self.fps_log_dir
+            && let Err(e) = fs::create_dir_all(dir)
+        {
+            warn!(
+                "Could not create FPS log directory {}: {}",
+                dir.display(),
+                e
+            );
+        }
+        for registry_command in self.registry_commands.iter() {
+            self.run_wine_helper(registry_command);
+        }
+        for command_line in self.before_launch.iter() {
+            run_shell_command(command_line);
+        }
+        let controller = match &self.controller_command {
+            Some(controller_command) => BackgroundProcess::spawn(controller_command),
+            None => None,
+        };
+        let companions: Vec<BackgroundProcess> = self
+            .companions
+            .iter()
+            .filter_map(|companion| BackgroundProcess::spawn(companion))
+            .collect();
+        let recording = self.capture.as_ref().map(|capture| capture.start(&self.id));
+        let result = self.launch(&mut command);
+        if let Some(recording) = recording {
+            recording.stop();
+        }
+        for companion in companions {
+            companion.stop();
+        }
+        if let Some(controller) = controller {
+            controller.stop();
+        }
+        for command_line in self.after_exit.iter() {
+            run_shell_command(command_line);
+        }
+        if let Some(kill_wineserver) = &self.kill_wineserver {
+            self.run_wine_helper(kill_wineserver);
+        }
+        match result {
             Ok(status) => {
                 if let Some(code) = status.code()
                     && code != EXIT_SUCCESS
@@ -54,11 +201,135 @@ impl Game {
         Ok(())
     }
 
+    /// Run the game's update command from the game's directory
+    pub fn update<'a>(&'a self) -> Result<(), GameError<'a>> {
+        let update_command = match &self.update_command {
+            Some(update_command) => update_command,
+            None => return Err(GameError::NoUpdateCommand(self.id.clone())),
+        };
+        self.change_directory()?;
+        info!("Running command: {:?}", update_command);
+        let status = Command::new(&update_command[0])
+            .args(&update_command[1..])
+            .envs(self.env.iter())
+            .status();
+        match status {
+            Ok(status) if status.success() => Ok(()),
+            Ok(_) => Err(GameError::CommandReturnedFailure(update_command.join(" "))),
+            Err(_) => Err(GameError::ExecutionFailed),
+        }
+    }
+
+    fn change_directory<'a>(&'a self) -> Result<(), GameError<'a>> {
+        if let Some(dir) = &self.dir {
+            debug!("Changing directory: {}", dir);
+            let path = Path::new(dir);
+            if env::set_current_dir(path).is_err() {
+                return Err(GameError::CouldNotChangeDirectory(dir));
+            }
+        }
+        Ok(())
+    }
+
+    /// Start the game and wait for it to exit
+    fn launch(&self, command: &mut Command) -> std::io::Result<ExitStatus> {
+        info!("Running command: {:?}", self.command);
+        let mut child = timings::time("game start", || command.spawn())?;
+        if let Some(wm_rules) = &self.wm_rules {
+            wm_rules.apply(child.id());
+        }
+        let reminder = self
+            .remind_after_minutes
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| Reminder::start(&self.name, minutes));
+        let result = match self.time_limit {
+            Some(limit) => wait_with_time_limit(&mut child, limit),
+            None => child.wait(),
+        };
+        if let Some(reminder) = reminder {
+            reminder.stop();
+        }
+        result
+    }
+
+    /// Run one of wine's own commands in the game's prefix, where failure is
+    /// reported but not fatal
+    fn run_wine_helper(&self, command: &[String]) {
+        debug!("Running: {:?}", command);
+        let status = Command::new(&command[0])
+            .args(&command[1..])
+            .envs(self.env.iter())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        match status {
+            Ok(status) if status.success() => (),
+            _ => warn!("Command failed: {}", command.join(" ")),
+        }
+    }
+
+    /// The winetricks command for the game's prefix, or the protontricks
+    /// command for a Steam game
+    pub fn tricks_command(&self, verbs: &[String]) -> Option<Vec<String>> {
+        let mut command = match &self.steam_id {
+            Some(steam_id) => vec!["protontricks".to_string(), steam_id.to_string()],
+            None if self.env.contains_key("WINEPREFIX") => vec!["winetricks".to_string()],
+            None => return None,
+        };
+        command.extend(verbs.iter().cloned());
+        Some(command)
+    }
+
+    /// The command that runs one of wine's own tools (like `winecfg`) in the
+    /// game's prefix, through protontricks for a Steam game
+    pub fn wine_tool_command(&self, tool: &str, args: &[String]) -> Option<Vec<String>> {
+        let mut command = match &self.steam_id {
+            Some(steam_id) => vec!["protontricks".to_string(), steam_id.to_string()],
+            None if self.env.contains_key("WINEPREFIX") => {
+                let wine = self.env.get("WINE").map(|w| w.as_str()).unwrap_or("wine");
+                vec![wine.to_string()]
+            }
+            None => return None,
+        };
+        command.push(tool.to_string());
+        command.extend(args.iter().cloned());
+        Some(command)
+    }
+
+    /// Whether the game is installed, from the config or this machine's
+    /// installed state. This is checked for every game whenever the library is
+    /// listed, so it only reads what was parsed and never the filesystem.
     pub fn is_installed(&self) -> bool {
         self.installed
     }
 }
 
+/// Wait for the game to exit, stopping it if it is still running once the
+/// time limit has passed
+fn wait_with_time_limit(child: &mut Child, limit: Duration) -> std::io::Result<ExitStatus> {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if start.elapsed() >= limit {
+            info!("Time limit reached, stopping game");
+            child.kill()?;
+            return child.wait();
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Run a helper command line, where failure is reported but not fatal
+fn run_shell_command(command_line: &str) {
+    info!("Running: {}", command_line);
+    match Platform::current().shell_command(command_line).status() {
+        Ok(status) if status.success() => (),
+        _ => warn!("Command failed: {}", command_line),
+    }
+}
+
 pub enum GameError<'a> {
     NoGameId,
     CouldNotChangeDirectory(&'a str),
@@ -67,4 +338,124 @@ pub enum GameError<'a> {
     ExecutionFailed,
     NotInstalled,
     CouldNotWriteStats(String),
+    UnrecognizedSubcommand(&'a str),
+    QueueEmpty,
+    NoSuchQueuedGame(String),
+    CouldNotWriteQueue(String),
+    InvalidArgument(&'a str),
+    CouldNotWriteSessions(String),
+    NoMatchingGames,
+    CouldNotUpdateConfig(String),
+    NoFpsLog(String),
+    NoWinePrefix(String),
+    NoSuchRunner(&'a str),
+    NoUpdateCommand(String),
+    NoGameDir(String),
+    NoManifest(String),
+    CouldNotWriteManifest(String),
+    CouldNotWriteReport(String),
+    CouldNotWriteChart(String),
+    NoWrappedCommand,
+    CouldNotListen(String),
+    TerminalError(String),
+    NoSuchMenuEntry(String),
+    VerificationFailed(String, usize),
+    CouldNotWriteInstalledState(String),
+    CouldNotWriteJournal(String),
+    CouldNotWriteMilestones(String),
+    CouldNotWriteShortcut(String),
+    NoIcon(&'a str),
+    CouldNotWriteIcon(String),
+    NoSaveDir(&'a str),
+    NoSandbox(&'a str),
+    InvalidConfig(ParseError),
+}
+
+impl GameError<'_> {
+    /// A short, machine-readable name for the error
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GameError::NoGameId => "no_game_id",
+            GameError::CouldNotChangeDirectory(_) => "could_not_change_directory",
+            GameError::NoSuchGame(_) => "no_such_game",
+            GameError::CommandReturnedFailure(_) => "command_returned_failure",
+            GameError::ExecutionFailed => "execution_failed",
+            GameError::NotInstalled => "not_installed",
+            GameError::CouldNotWriteStats(_) => "could_not_write_stats",
+            GameError::UnrecognizedSubcommand(_) => "unrecognized_subcommand",
+            GameError::QueueEmpty => "queue_empty",
+            GameError::NoSuchQueuedGame(_) => "no_such_queued_game",
+            GameError::CouldNotWriteQueue(_) => "could_not_write_queue",
+            GameError::InvalidArgument(_) => "invalid_argument",
+            GameError::CouldNotWriteSessions(_) => "could_not_write_sessions",
+            GameError::NoMatchingGames => "no_matching_games",
+            GameError::CouldNotUpdateConfig(_) => "could_not_update_config",
+            GameError::NoFpsLog(_) => "no_fps_log",
+            GameError::NoWinePrefix(_) => "no_wine_prefix",
+            GameError::NoSuchRunner(_) => "no_such_runner",
+            GameError::NoUpdateCommand(_) => "no_update_command",
+            GameError::NoGameDir(_) => "no_game_dir",
+            GameError::NoManifest(_) => "no_manifest",
+            GameError::CouldNotWriteManifest(_) => "could_not_write_manifest",
+            GameError::CouldNotWriteReport(_) => "could_not_write_report",
+            GameError::CouldNotWriteChart(_) => "could_not_write_chart",
+            GameError::NoWrappedCommand => "no_wrapped_command",
+            GameError::CouldNotListen(_) => "could_not_listen",
+            GameError::TerminalError(_) => "terminal_error",
+            GameError::NoSuchMenuEntry(_) => "no_such_menu_entry",
+            GameError::VerificationFailed(_, _) => "verification_failed",
+            GameError::CouldNotWriteInstalledState(_) => "could_not_write_installed_state",
+            GameError::CouldNotWriteJournal(_) => "could_not_write_journal",
+            GameError::CouldNotWriteMilestones(_) => "could_not_write_milestones",
+            GameError::CouldNotWriteShortcut(_) => "could_not_write_shortcut",
+            GameError::NoIcon(_) => "no_icon",
+            GameError::CouldNotWriteIcon(_) => "could_not_write_icon",
+            GameError::NoSaveDir(_) => "no_save_dir",
+            GameError::NoSandbox(_) => "no_sandbox",
+            GameError::InvalidConfig(e) => e.kind(),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GameError::NoGameId
+            | GameError::InvalidArgument(_)
+            | GameError::NoWinePrefix(_)
+            | GameError::NoSuchRunner(_)
+            | GameError::NoUpdateCommand(_)
+            | GameError::NoGameDir(_)
+            | GameError::NoWrappedCommand
+            | GameError::UnrecognizedSubcommand(_) => exit_code::USAGE_ERROR,
+            GameError::NoSuchGame(_)
+            | GameError::NoSuchQueuedGame(_)
+            | GameError::NoSuchMenuEntry(_)
+            | GameError::NoMatchingGames => exit_code::UNKNOWN_GAME,
+            GameError::CouldNotChangeDirectory(_)
+            | GameError::ExecutionFailed
+            | GameError::NotInstalled
+            | GameError::NoSandbox(_) => exit_code::LAUNCH_FAILURE,
+            GameError::CommandReturnedFailure(_) => exit_code::GAME_CRASHED,
+            GameError::CouldNotWriteStats(_)
+            | GameError::QueueEmpty
+            | GameError::CouldNotWriteQueue(_)
+            | GameError::CouldNotWriteSessions(_)
+            | GameError::CouldNotUpdateConfig(_)
+            | GameError::NoFpsLog(_)
+            | GameError::NoManifest(_)
+            | GameError::CouldNotWriteManifest(_)
+            | GameError::CouldNotWriteReport(_)
+            | GameError::CouldNotWriteChart(_)
+            | GameError::CouldNotListen(_)
+            | GameError::TerminalError(_)
+            | GameError::VerificationFailed(_, _)
+            | GameError::CouldNotWriteInstalledState(_)
+            | GameError::CouldNotWriteJournal(_)
+            | GameError::CouldNotWriteMilestones(_)
+            | GameError::CouldNotWriteShortcut(_)
+            | GameError::NoIcon(_)
+            | GameError::CouldNotWriteIcon(_)
+            | GameError::NoSaveDir(_) => exit_code::FAILURE,
+            GameError::InvalidConfig(_) => exit_code::CONFIG_ERROR,
+        }
+    }
 }
@@ -1,8 +1,17 @@
 use crate::Game;
 use crate::ParseError;
 use crate::Settings;
+use crate::backend::Launch;
+use crate::capture::Capture;
+use crate::fsutil;
+use crate::runners;
+use crate::sandbox::Sandbox;
+use crate::template;
+use crate::wine_registry::RegistryTweak;
+use crate::wm_rules::WmRules;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use time::Date;
 use toml::{Table, Value};
 
 pub struct GameBuilder<'a> {
@@ -20,7 +29,39 @@ pub struct GameBuilder<'a> {
     use_gamescope: bool,
     use_vk: bool,
     installed: bool,
-    is_steam: bool,
+    steam_id: Option<String>,
+    added: Option<Date>,
+    status: Option<String>,
+    favorite: bool,
+    remind_after_minutes: Option<u32>,
+    controller_profile: Option<String>,
+    pause_compositor: bool,
+    prefer_output: Option<String>,
+    display: Option<i64>,
+    wm_rules: Option<WmRules>,
+    mangohud_config: Vec<(String, String)>,
+    capture_fps: bool,
+    record: bool,
+    runner: Option<String>,
+    update_command: Option<Vec<String>>,
+    companions: Vec<Vec<String>>,
+    requires: Vec<String>,
+    stop_required: bool,
+    track_stats: bool,
+    kill_wineserver: bool,
+    sandbox: Option<Sandbox>,
+    language: Option<String>,
+    version: Option<String>,
+    goal_hours: Option<f64>,
+    length_hours: Option<f64>,
+    session_hours: Option<f64>,
+    rating: Option<f64>,
+    wine_dpi: Option<u32>,
+    wine_registry: Vec<RegistryTweak>,
+    process_name: Option<String>,
+    cover: Option<PathBuf>,
+    save_dir: Option<PathBuf>,
+    invalid_option: Option<String>,
 }
 
 impl<'a> GameBuilder<'a> {
@@ -40,7 +81,39 @@ impl<'a> GameBuilder<'a> {
             use_gamescope: false,
             use_vk: true,
             installed: true,
-            is_steam: false,
+            steam_id: None,
+            added: None,
+            status: None,
+            favorite: false,
+            remind_after_minutes: None,
+            controller_profile: None,
+            pause_compositor: false,
+            prefer_output: None,
+            display: None,
+            wm_rules: None,
+            mangohud_config: Vec::new(),
+            capture_fps: false,
+            record: false,
+            runner: None,
+            update_command: None,
+            companions: Vec::new(),
+            requires: Vec::new(),
+            stop_required: false,
+            track_stats: true,
+            kill_wineserver: false,
+            sandbox: None,
+            language: None,
+            version: None,
+            goal_hours: None,
+            length_hours: None,
+            session_hours: None,
+            rating: None,
+            wine_dpi: None,
+            wine_registry: Vec::new(),
+            process_name: None,
+            cover: None,
+            save_dir: None,
+            invalid_option: None,
         }
     }
 
@@ -54,6 +127,16 @@ impl<'a> GameBuilder<'a> {
         self
     }
 
+    /// Launch the game with a backend, whose environment variables are set
+    /// unless the game's `env` sets them
+    pub fn launch(mut self, launch: Launch) -> Self {
+        self.command = launch.command;
+        for (var, value) in launch.env {
+            self.env.entry(var).or_insert(value);
+        }
+        self
+    }
+
     pub fn dir_prefix(mut self, dir_prefix: String) -> Self {
         self.dir_prefix = dir_prefix;
         self
@@ -98,21 +181,191 @@ impl<'a> GameBuilder<'a> {
         self
     }
 
-    pub fn not_installed(mut self) -> Self {
-        self.installed = false;
+    pub fn installed(mut self, installed: bool) -> Self {
+        self.installed = installed;
         self
     }
 
     pub fn steam_id(mut self, steam_game_id: &str) -> Self {
-        let cmd = vec![
-            "steam".to_string(),
-            format!("steam://rungameid/{}", steam_game_id),
-        ];
-        self.is_steam = true;
+        let url = format!("steam://rungameid/{}", steam_game_id);
+        let cmd = self.settings.platform.open_url_command(&url);
+        self.steam_id = Some(steam_game_id.to_string());
         self.command(cmd)
     }
 
+    pub fn added(mut self, date: Date) -> Self {
+        self.added = Some(date);
+        self
+    }
+
+    pub fn status(mut self, status: String) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn favorite(mut self) -> Self {
+        self.favorite = true;
+        self
+    }
+
+    pub fn remind_after_minutes(mut self, minutes: u32) -> Self {
+        self.remind_after_minutes = Some(minutes);
+        self
+    }
+
+    pub fn controller_profile(mut self, profile: String) -> Self {
+        self.controller_profile = Some(profile);
+        self
+    }
+
+    pub fn pause_compositor(mut self) -> Self {
+        self.pause_compositor = true;
+        self
+    }
+
+    pub fn prefer_output(mut self, output: String) -> Self {
+        self.prefer_output = Some(output);
+        self
+    }
+
+    pub fn display(mut self, display: i64) -> Self {
+        self.display = Some(display);
+        self
+    }
+
+    pub fn wm_rules(mut self, wm_rules: WmRules) -> Self {
+        self.wm_rules = Some(wm_rules);
+        self
+    }
+
+    /// Extra MangoHud settings, as key/value pairs, where an empty value means
+    /// the key is given on its own
+    pub fn mangohud_config(mut self, config: Vec<(String, String)>) -> Self {
+        self.mangohud_config = config;
+        self
+    }
+
+    pub fn capture_fps(mut self) -> Self {
+        self.capture_fps = true;
+        self
+    }
+
+    pub fn record(mut self) -> Self {
+        self.record = true;
+        self
+    }
+
+    pub fn runner(mut self, runner: String) -> Self {
+        self.runner = Some(runner);
+        self
+    }
+
+    pub fn update_command(mut self, update_command: Vec<String>) -> Self {
+        self.update_command = Some(update_command);
+        self
+    }
+
+    pub fn companions(mut self, companions: Vec<Vec<String>>) -> Self {
+        self.companions = companions;
+        self
+    }
+
+    pub fn requires(mut self, requires: Vec<String>) -> Self {
+        self.requires = requires;
+        self
+    }
+
+    pub fn stop_required(mut self) -> Self {
+        self.stop_required = true;
+        self
+    }
+
+    pub fn no_stats(mut self) -> Self {
+        self.track_stats = false;
+        self
+    }
+
+    pub fn kill_wineserver(mut self) -> Self {
+        self.kill_wineserver = true;
+        self
+    }
+
+    pub fn sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    pub fn wine_dpi(mut self, dpi: u32) -> Self {
+        self.wine_dpi = Some(dpi);
+        self
+    }
+
+    pub fn wine_registry(mut self, tweaks: Vec<RegistryTweak>) -> Self {
+        self.wine_registry = tweaks;
+        self
+    }
+
+    pub fn goal_hours(mut self, hours: f64) -> Self {
+        self.goal_hours = Some(hours);
+        self
+    }
+
+    pub fn length_hours(mut self, hours: f64) -> Self {
+        self.length_hours = Some(hours);
+        self
+    }
+
+    pub fn session_hours(mut self, hours: f64) -> Self {
+        self.session_hours = Some(hours);
+        self
+    }
+
+    pub fn rating(mut self, rating: f64) -> Self {
+        self.rating = Some(rating);
+        self
+    }
+
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    /// The locale (like `de_DE.UTF-8`) the game runs in
+    pub fn language(mut self, locale: String) -> Self {
+        self.language = Some(locale);
+        self
+    }
+
+    pub fn process_name(mut self, process_name: &str) -> Self {
+        self.process_name = Some(process_name.to_string());
+        self
+    }
+
+    pub fn cover(mut self, cover: PathBuf) -> Self {
+        self.cover = Some(cover);
+        self
+    }
+
+    pub fn save_dir(mut self, save_dir: PathBuf) -> Self {
+        self.save_dir = Some(save_dir);
+        self
+    }
+
+    /// Record that an option had a value that could not be understood, which
+    /// causes the build to fail
+    pub fn invalid_option(mut self, option: &str) -> Self {
+        self.invalid_option = Some(option.to_string());
+        self
+    }
+
     pub fn build(self) -> Result<Game, ParseError> {
+        if let Some(option) = self.invalid_option {
+            return Err(ParseError::InvalidOption(self.id, option));
+        }
+        // Steam starts the game itself, so it could not be sandboxed
+        if self.sandbox.is_some() && self.steam_id.is_some() {
+            return Err(ParseError::InvalidOption(self.id, "sandbox".to_string()));
+        }
         if self.name.is_none() {
             return Err(ParseError::MissingName(self.id.clone()));
         }
@@ -122,9 +375,16 @@ impl<'a> GameBuilder<'a> {
 
         let is_wine = self.is_wine();
 
+        let dir_prefix_name = Some(self.dir_prefix.clone()).filter(|p| !p.is_empty());
         let dir_prefix = if !self.dir_prefix.is_empty() {
             match self.directories.get(&self.dir_prefix) {
-                Some(Value::String(s)) => s.to_string(),
+                Some(Value::String(s)) => {
+                    trace!(
+                        "Game {}: directory prefix {} is {}",
+                        self.id, self.dir_prefix, s
+                    );
+                    s.to_string()
+                }
                 _ => {
                     return Err(ParseError::NoSuchDirectoryPrefix(
                         self.id.clone(),
@@ -137,7 +397,10 @@ impl<'a> GameBuilder<'a> {
         };
 
         let dir = match self.directories.get(&self.dir) {
-            Some(Value::String(d)) => d.to_string(),
+            Some(Value::String(d)) => {
+                trace!("Game {}: directory {} is {}", self.id, self.dir, d);
+                d.to_string()
+            }
             _ => self.dir,
         };
 
@@ -147,17 +410,147 @@ impl<'a> GameBuilder<'a> {
             .unwrap()
             .to_string();
 
-        let use_mangohud = self.use_mangohud.is_some() && self.use_mangohud.unwrap()
-            || self.use_mangohud.is_none() && is_wine;
+        let has_linux_tools = self.settings.platform.has_linux_tools();
+        let capture_fps = has_linux_tools && self.capture_fps;
+        let use_mangohud = capture_fps
+            || has_linux_tools
+                && (self.use_mangohud.is_some() && self.use_mangohud.unwrap()
+                    || self.use_mangohud.is_none() && is_wine);
+        let fps_log_dir = if capture_fps {
+            Some(self.settings.fps_log_dir.join(&self.id))
+        } else {
+            None
+        };
+
+        // Windows executables are run directly where wine is not needed
+        let mut command = self.command;
+        let mut env = self.env;
+        let mut runner_name = None;
+        let mut missing_runner = None;
+        if is_wine && !self.settings.platform.needs_wine() {
+            command.remove(0);
+        } else if let Some(name) =
+            self.runner
+                .as_ref()
+                .or(self.settings.runner.as_ref().filter(|_| is_wine))
+        {
+            match runners::find(self.settings.runners(), name) {
+                Some(runner) => {
+                    let wine = runner.wine.to_string_lossy().to_string();
+                    trace!("Game {}: runner {} is {}", self.id, name, wine);
+                    runner_name = Some(runner.name);
+                    if is_wine {
+                        command[0] = wine.clone();
+                    }
+                    env.insert("WINE".to_string(), wine);
+                }
+                // A runner that is not installed only stops this game from
+                // being launched, not the rest of the library from being used,
+                // so it is reported then and by `check` rather than here
+                None => missing_runner = Some(name.to_string()),
+            }
+        }
+
+        // The values are set with wine's own `reg`, in the prefix from the
+        // game's env, where wine is needed
+        let mut tweaks = self.wine_registry;
+        if let Some(dpi) = self.wine_dpi {
+            tweaks.push(RegistryTweak::dpi(dpi));
+        }
+        let reg = if self.settings.platform.needs_wine() {
+            let wine = env.get("WINE").map(|w| w.as_str()).unwrap_or("wine");
+            vec![wine.to_string(), "reg".to_string()]
+        } else {
+            vec!["reg".to_string()]
+        };
+        let registry_commands = tweaks
+            .iter()
+            .map(|tweak| [reg.clone(), tweak.reg_add_args()].concat())
+            .collect();
+
+        // The runner's wineserver is next to its wine
+        let kill_wineserver = if self.kill_wineserver && self.settings.platform.needs_wine() {
+            let wineserver = match env.get("WINE") {
+                Some(wine) => Path::new(wine)
+                    .with_file_name("wineserver")
+                    .to_string_lossy()
+                    .to_string(),
+                None => "wineserver".to_string(),
+            };
+            Some(vec![wineserver, "-k".to_string()])
+        } else {
+            None
+        };
+
+        // wine takes the locale of its programs from these too, but a
+        // variable set in the game's own env wins
+        if let Some(locale) = &self.language {
+            let language = locale.split('.').next().unwrap_or(locale);
+            for (var, value) in [
+                ("LANG", locale.as_str()),
+                ("LC_ALL", locale),
+                ("LANGUAGE", language),
+            ] {
+                env.entry(var.to_string())
+                    .or_insert_with(|| value.to_string());
+            }
+        }
 
-        let command = if self.is_steam {
-            self.command
-        } else if self.settings.use_gamescope {
+        // A game that should be sandboxed is never run outside of one
+        let mut missing_sandbox = None;
+        if let Some(sandbox) = &self.sandbox {
+            if has_linux_tools && fsutil::on_path(sandbox.tool()) {
+                let mut writable = Vec::new();
+                if !game_dir.is_empty() {
+                    writable.push(game_dir.clone());
+                }
+                if let Some(prefix) = env.get("WINEPREFIX") {
+                    writable.push(prefix.to_string());
+                } else if is_wine && let Some(home) = std::env::home_dir() {
+                    writable.push(home.join(".wine").to_string_lossy().to_string());
+                }
+                if let Some(dir) = &fps_log_dir {
+                    writable.push(dir.to_string_lossy().to_string());
+                }
+                command = sandbox.wrap(command, &writable);
+            } else {
+                missing_sandbox = Some(sandbox.tool().to_string());
+            }
+        }
+
+        let use_gamescope =
+            self.steam_id.is_none() && self.settings.use_gamescope && has_linux_tools;
+        // What sessions record about how the game was run, to compare when its
+        // performance changes
+        let mut launch_config = Vec::new();
+        if let Some(runner) = runner_name {
+            launch_config.push(("runner".to_string(), runner));
+        } else if is_wine && self.settings.platform.needs_wine() {
+            launch_config.push(("runner".to_string(), "wine".to_string()));
+        }
+        let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+        launch_config.push(("gamescope".to_string(), on_off(use_gamescope)));
+        launch_config.push(("mangohud".to_string(), on_off(use_mangohud)));
+        if let Some(limit) = self.fps_limit {
+            launch_config.push(("fps_limit".to_string(), limit.to_string()));
+        }
+
+        let command = if self.steam_id.is_some() {
+            command
+        } else if use_gamescope {
             let cmd = format!(
                 "gamescope -W {} -H {} -f --force-grab-cursor",
                 self.settings.width, self.settings.height
             );
             let mut c = shell_words::split(&cmd).expect("Failed to split gamescope command");
+            if let Some(output) = self
+                .prefer_output
+                .as_ref()
+                .or(self.settings.prefer_output.as_ref())
+            {
+                c.push("--prefer-output".to_string());
+                c.push(output.to_string());
+            }
             if let Some(i) = self.fps_limit {
                 c.push("-r".to_string());
                 c.push(i.to_string());
@@ -166,48 +559,148 @@ impl<'a> GameBuilder<'a> {
                 c.push("--mangoapp".to_string());
             }
             c.push("--".to_string());
-            for x in self.command.into_iter() {
+            for x in command.into_iter() {
                 c.push(x);
             }
             c
         } else if use_mangohud {
             let mut c = Vec::new();
             c.push("mangohud".to_string());
-            for x in self.command.into_iter() {
+            for x in command.into_iter() {
                 c.push(x);
             }
             c
         } else {
-            self.command
+            command
         };
 
-        let mut env = self.env;
-        if use_mangohud && let Some(limit) = self.fps_limit {
+        if use_mangohud {
+            let mut mangohud_config = Vec::new();
+            if let Some(limit) = self.fps_limit {
+                mangohud_config.push(format!("fps_limit={}", limit));
+            }
+            for (key, value) in self.mangohud_config.iter() {
+                if value.is_empty() {
+                    mangohud_config.push(key.to_string());
+                } else {
+                    mangohud_config.push(format!("{}={}", key, value));
+                }
+            }
+            if let Some(dir) = &fps_log_dir {
+                mangohud_config.push(format!("output_folder={}", dir.display()));
+                mangohud_config.push("autostart_log=1".to_string());
+            }
+            if !mangohud_config.is_empty() {
+                env.insert("MANGOHUD_CONFIG".to_string(), mangohud_config.join(","));
+            }
+        }
+
+        if let Some(display) = self.display.or(self.settings.display) {
             env.insert(
-                "MANGOHUD_CONFIG".to_string(),
-                format!("fps_limit={}", limit),
+                "SDL_VIDEO_FULLSCREEN_DISPLAY".to_string(),
+                display.to_string(),
             );
+            env.insert("SDL_VIDEO_FULLSCREEN_HEAD".to_string(), display.to_string());
         }
 
-        if !self.use_vk {
+        if !self.use_vk && self.settings.platform.needs_wine() {
             env.insert(
                 "WINEDLLOVERRIDES".to_string(),
                 "*d3d9,*d3d10,*d3d10_1,*d3d10core,*d3d11,*dxgi=b".to_string(),
             );
         }
 
+        let controller_command = match &self.controller_profile {
+            Some(profile) => {
+                let vars = [("profile", profile.as_str())];
+                match template::render_command(&self.settings.controller_mapper, &vars) {
+                    Some(c) if !c.is_empty() => Some(c),
+                    _ => return Err(ParseError::InvalidSetting("controller_mapper".to_string())),
+                }
+            }
+            None => None,
+        };
+
+        let capture = if self.record {
+            let is_command = |template: &str| {
+                template::render_command(template, &[]).is_some_and(|c| !c.is_empty())
+            };
+            if !is_command(&self.settings.capture_cmd) {
+                return Err(ParseError::InvalidSetting("capture_cmd".to_string()));
+            }
+            if let Some(stop_cmd) = &self.settings.capture_stop_cmd
+                && !is_command(stop_cmd)
+            {
+                return Err(ParseError::InvalidSetting("capture_stop_cmd".to_string()));
+            }
+            Some(Capture {
+                command: self.settings.capture_cmd.clone(),
+                stop_command: self.settings.capture_stop_cmd.clone(),
+                dir: self.settings.capture_dir.clone(),
+                ext: self.settings.capture_ext.clone(),
+            })
+        } else {
+            None
+        };
+
+        let mut before_launch = Vec::new();
+        let mut after_exit = Vec::new();
+        if self.pause_compositor {
+            before_launch.push(self.settings.compositor_pause_cmd.clone());
+            after_exit.push(self.settings.compositor_resume_cmd.clone());
+        }
+
+        let wm_rules = self.wm_rules.map(|mut rules| {
+            rules.window_manager = self.settings.window_manager;
+            rules
+        });
+
+        debug!("Game {}: command is {:?}", self.id, command);
         Ok(Game {
             id: self.id,
             name: self.name.unwrap(),
+            version: self.version,
+            goal_hours: self.goal_hours,
+            length_hours: self.length_hours,
+            session_hours: self.session_hours,
+            rating: self.rating,
             command,
             dir: if !game_dir.is_empty() {
                 Some(game_dir)
             } else {
                 None
             },
+            dir_prefix: dir_prefix_name,
             env,
             tags: self.tags,
             installed: self.installed,
+            track_stats: self.track_stats,
+            added: self.added,
+            status: self.status,
+            favorite: self.favorite,
+            remind_after_minutes: self
+                .remind_after_minutes
+                .or(self.settings.remind_after_minutes),
+            controller_command,
+            capture,
+            before_launch,
+            after_exit,
+            wm_rules,
+            fps_log_dir,
+            time_limit: None,
+            steam_id: self.steam_id,
+            update_command: self.update_command,
+            companions: self.companions,
+            registry_commands,
+            requires: self.requires,
+            missing_runner,
+            missing_sandbox,
+            stop_required: self.stop_required,
+            kill_wineserver,
+            process_name: self.process_name,
+            cover: self.cover,
+            save_dir: self.save_dir,
+            launch_config,
         })
     }
 }
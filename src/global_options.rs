@@ -0,0 +1,61 @@
+use std::env::var_os;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const CONFIG_ENV_VAR: &str = "GAME_RS_CONFIG";
+const DATA_DIR_ENV_VAR: &str = "GAME_RS_DATA_DIR";
+const PROFILE_ENV_VAR: &str = "GAME_RS_PROFILE";
+
+static GLOBAL_OPTIONS: OnceLock<GlobalOptions> = OnceLock::new();
+
+#[derive(Debug, Default, PartialEq)]
+pub enum ErrorFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Default)]
+pub struct GlobalOptions {
+    pub config_file: Option<PathBuf>,
+    pub data_dir: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub verbosity: u8,
+    pub quiet: bool,
+    /// Report how long parsing the config, reading and writing the stats, and
+    /// starting the game took
+    pub timings: bool,
+    pub error_format: ErrorFormat,
+}
+
+impl GlobalOptions {
+    /// Fill in any options that were not given as flags from the environment
+    pub fn with_env(mut self) -> GlobalOptions {
+        if self.config_file.is_none() {
+            self.config_file = var_os(CONFIG_ENV_VAR)
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from);
+        }
+        if self.data_dir.is_none() {
+            self.data_dir = var_os(DATA_DIR_ENV_VAR)
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from);
+        }
+        if self.profile.is_none() {
+            self.profile = var_os(PROFILE_ENV_VAR)
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string_lossy().to_string());
+        }
+        self
+    }
+}
+
+pub fn set(options: GlobalOptions) {
+    GLOBAL_OPTIONS
+        .set(options)
+        .expect("Global options already set");
+}
+
+pub fn get() -> &'static GlobalOptions {
+    GLOBAL_OPTIONS.get_or_init(GlobalOptions::default)
+}
@@ -0,0 +1,103 @@
+use crate::Game;
+use crate::watch::GAME_ID_ENV_VAR;
+use std::path::Path;
+use std::process::Command;
+
+/// The directory of hook scripts, next to the config file
+pub const HOOKS_DIR: &str = "hooks";
+
+/// When a hook script is run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hook {
+    /// Before `play` starts a game
+    PrePlay,
+    /// After a game started by `play` exits
+    PostPlay,
+    /// When a session starts, whether from `play` or noticed by `watch`
+    PreSession,
+    /// After a session has been recorded
+    PostSession,
+}
+
+impl Hook {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hook::PrePlay => "pre-play",
+            Hook::PostPlay => "post-play",
+            Hook::PreSession => "pre-session",
+            Hook::PostSession => "post-session",
+        }
+    }
+}
+
+/// The environment variables that tell a hook about the game, along with any
+/// given for the hook in particular
+pub fn env(hook: Hook, game: &Game, extra: &[(&str, String)]) -> Vec<(String, String)> {
+    let mut env = vec![
+        ("GAME_RS_HOOK".to_string(), hook.name().to_string()),
+        (GAME_ID_ENV_VAR.to_string(), game.id.clone()),
+        ("GAME_RS_GAME_NAME".to_string(), game.name.clone()),
+        ("GAME_RS_GAME_TAGS".to_string(), game.tags.join(",")),
+    ];
+    if let Some(dir) = &game.dir {
+        env.push(("GAME_RS_GAME_DIR".to_string(), dir.clone()));
+    }
+    if let Some(save_dir) = &game.save_dir {
+        env.push((
+            "GAME_RS_SAVE_DIR".to_string(),
+            save_dir.to_string_lossy().to_string(),
+        ));
+    }
+    env.extend(
+        extra
+            .iter()
+            .map(|(var, value)| (var.to_string(), value.clone())),
+    );
+    env
+}
+
+/// Run the hook's script, if there is one, or describe how it failed
+pub fn run(hooks_dir: &Path, hook: Hook, env: &[(String, String)]) -> Result<(), String> {
+    let path = hooks_dir.join(hook.name());
+    if !path.is_file() {
+        return Ok(());
+    }
+    info!("Running {} hook: {}", hook.name(), path.display());
+    match Command::new(&path).envs(env.iter().cloned()).status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} ({})", path.display(), status)),
+        Err(e) => Err(format!("{} ({})", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_env() {
+        let game = Game {
+            id: "doom".to_string(),
+            name: "Doom".to_string(),
+            tags: vec!["fps".to_string(), "classic".to_string()],
+            ..Default::default()
+        };
+        let env = env(
+            Hook::PostPlay,
+            &game,
+            &[("GAME_RS_PLAY_TIME_SECONDS", "60".to_string())],
+        );
+        let get = |var: &str| env.iter().find(|(v, _)| v == var).map(|(_, value)| value);
+        assert_eq!(get("GAME_RS_HOOK").unwrap(), "post-play");
+        assert_eq!(get(GAME_ID_ENV_VAR).unwrap(), "doom");
+        assert_eq!(get("GAME_RS_GAME_TAGS").unwrap(), "fps,classic");
+        assert_eq!(get("GAME_RS_PLAY_TIME_SECONDS").unwrap(), "60");
+        assert!(get("GAME_RS_GAME_DIR").is_none());
+    }
+
+    #[test]
+    fn test_missing_hook_is_skipped() {
+        let dir = Path::new("/nonexistent/hooks");
+        assert_eq!(run(dir, Hook::PrePlay, &[]), Ok(()));
+    }
+}
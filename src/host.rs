@@ -0,0 +1,111 @@
+use std::env::var;
+use std::fs;
+use std::process::Command;
+use toml::{Table, Value};
+
+const HOST_ENV_VAR: &str = "GAME_RS_HOST";
+const HOSTNAME_FILES: [&str; 2] = ["/proc/sys/kernel/hostname", "/etc/hostname"];
+
+/// The name of this machine, without any domain, which chooses the overlays
+/// that apply to it
+pub fn name() -> Option<String> {
+    let name = var(HOST_ENV_VAR)
+        .ok()
+        .or_else(|| {
+            HOSTNAME_FILES
+                .iter()
+                .find_map(|f| fs::read_to_string(f).ok())
+        })
+        .or_else(|| {
+            let output = Command::new("hostname").output().ok()?;
+            String::from_utf8(output.stdout).ok()
+        })?;
+    let name = name.trim().split('.').next().unwrap_or_default();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// Apply the `[host.<name>]` section for this machine, and then the host's
+/// overlay file, on top of the config. The `[host]` table is removed, so
+/// overlays for other machines are ignored.
+pub fn apply(config: &mut Table, host: Option<&str>, overlay: Option<Table>) {
+    let hosts = config.remove("host");
+    let Some(host) = host else {
+        return;
+    };
+    if let Some(Value::Table(mut hosts)) = hosts {
+        match hosts.remove(host) {
+            Some(Value::Table(section)) => {
+                debug!("Applying config overlay for host: {}", host);
+                merge(config, section);
+            }
+            Some(_) => warn!("Host overlay is not a table: {}", host),
+            None => (),
+        }
+    }
+    if let Some(overlay) = overlay {
+        merge(config, overlay);
+    }
+}
+
+/// Merge the overlay into the config, where tables are merged key by key and
+/// any other value replaces the one in the config
+pub fn merge(config: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (config.get_mut(&key), value) {
+            (Some(Value::Table(tbl)), Value::Table(overlay)) => merge(tbl, overlay),
+            (_, value) => {
+                config.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_host_section() {
+        let mut config: Table = "
+            [settings]
+            width = 2560
+            height = 1440
+            [games.doom]
+            name = \"Doom\"
+            cmd = \"gzdoom\"
+            [host.deck.settings]
+            width = 1280
+            [host.deck.games.doom]
+            installed = false
+            [host.laptop.settings]
+            width = 1920
+        "
+        .parse()
+        .unwrap();
+        apply(&mut config, Some("deck"), None);
+        assert!(config.get("host").is_none());
+        assert_eq!(config["settings"]["width"].as_integer(), Some(1280));
+        assert_eq!(config["settings"]["height"].as_integer(), Some(1440));
+        assert_eq!(config["games"]["doom"]["name"].as_str(), Some("Doom"));
+        assert_eq!(config["games"]["doom"]["installed"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn test_overlay_file_is_applied_last() {
+        let mut config: Table = "
+            [settings]
+            width = 2560
+            [host.deck.settings]
+            width = 1280
+        "
+        .parse()
+        .unwrap();
+        let overlay: Table = "[settings]\nwidth = 800".parse().unwrap();
+        apply(&mut config, Some("deck"), Some(overlay));
+        assert_eq!(config["settings"]["width"].as_integer(), Some(800));
+    }
+}
@@ -0,0 +1,144 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// How long a client has to send its request, or to take the response, so
+/// that one that stalls does not hold up the others
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The parts of an HTTP request that the daemon looks at
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    /// The value of a header, whatever the case of its name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: String) -> Response {
+        Response {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    pub fn not_found() -> Response {
+        Response::new(404, "text/plain", "Not Found\n".to_string())
+    }
+
+    pub fn method_not_allowed() -> Response {
+        Response::new(405, "text/plain", "Method Not Allowed\n".to_string())
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            202 => "Accepted",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Answer requests one at a time, forever
+pub fn serve(listener: TcpListener, handler: impl Fn(&Request) -> Response) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle_connection(stream, &handler) {
+                    debug!("HTTP connection failed: {}", e);
+                }
+            }
+            Err(e) => warn!("Could not accept HTTP connection: {}", e),
+        }
+    }
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    handler: &impl Fn(&Request) -> Response,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut headers = Vec::new();
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && header.trim_end() != "" {
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+        header.clear();
+    }
+    let response = match parse_request_line(&request_line) {
+        Some(mut request) => {
+            request.headers = headers;
+            debug!("HTTP request: {} {}", request.method, request.path);
+            handler(&request)
+        }
+        None => Response::new(400, "text/plain", "Bad Request\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )
+}
+
+fn parse_request_line(line: &str) -> Option<Request> {
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    // Query strings are not used
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    Some(Request {
+        method,
+        path,
+        headers: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_line() {
+        let request = parse_request_line("GET /metrics?x=1 HTTP/1.1\r\n").unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/metrics");
+        assert!(parse_request_line("\r\n").is_none());
+
+        let request = Request {
+            headers: vec![("Host".to_string(), "localhost:9185".to_string())],
+            ..request
+        };
+        assert_eq!(request.header("host"), Some("localhost:9185"));
+        assert_eq!(request.header("Origin"), None);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::env::var;
+use std::sync::OnceLock;
+
+const ENGLISH: &str = include_str!("../locales/en.ftl");
+const CATALOGS: [(&str, &str); 2] = [("en", ENGLISH), ("de", include_str!("../locales/de.ftl"))];
+const LOCALE_ENV_VARS: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Messages in one language, with English for any that have not been
+/// translated
+pub struct Catalog {
+    messages: HashMap<String, String>,
+    english: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// The built-in catalog for the language, if there is one, with the
+    /// messages from an extra catalog added on top
+    pub fn new(language: &str, extra: Option<&str>) -> Catalog {
+        let mut messages = match CATALOGS.iter().find(|(l, _)| *l == language) {
+            Some((_, content)) => parse(content),
+            None => HashMap::new(),
+        };
+        if let Some(extra) = extra {
+            messages.extend(parse(extra));
+        }
+        Catalog {
+            messages,
+            english: parse(ENGLISH),
+        }
+    }
+
+    /// The message in this language only, without falling back to English
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(|s| s.as_str())
+    }
+
+    pub fn message(&self, key: &str, args: &[(&str, String)]) -> String {
+        match self.messages.get(key).or(self.english.get(key)) {
+            Some(template) => format(template, args),
+            None => key.to_string(),
+        }
+    }
+}
+
+/// Parse the subset of the Fluent syntax that catalogs use: one
+/// `key = value` message per line, with `#` comments
+fn parse(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Replace each `{ $name }` in the message with the value of that argument
+fn format(template: &str, args: &[(&str, String)]) -> String {
+    let mut formatted = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        formatted.push_str(&rest[..start]);
+        let placeable = &rest[start + 1..start + end];
+        let name = placeable.trim().trim_start_matches('$');
+        match args.iter().find(|(n, _)| *n == name) {
+            Some((_, value)) => formatted.push_str(value),
+            None => formatted.push_str(&rest[start..start + end + 1]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    formatted.push_str(rest);
+    formatted
+}
+
+/// The language of a locale like `de_DE.UTF-8`
+fn locale_language(locale: &str) -> Option<String> {
+    let language = locale.split(['_', '.', '@']).next()?.to_lowercase();
+    match language.as_str() {
+        "" | "c" | "posix" => None,
+        _ => Some(language),
+    }
+}
+
+/// The configured language, or else the one from the locale, or else English
+pub fn language(setting: Option<&str>) -> String {
+    if let Some(setting) = setting {
+        return setting.to_lowercase();
+    }
+    LOCALE_ENV_VARS
+        .iter()
+        .filter_map(|name| var(name).ok())
+        .find_map(|locale| locale_language(&locale))
+        .unwrap_or("en".to_string())
+}
+
+pub fn init(catalog: Catalog) {
+    if CATALOG.set(catalog).is_err() {
+        warn!("Messages were used before the language was chosen");
+    }
+}
+
+pub fn get() -> &'static Catalog {
+    CATALOG.get_or_init(|| Catalog::new(&language(None), None))
+}
+
+/// Look up a message in the user's language, filling in the named arguments
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::get().message($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::get().message($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_falls_back_to_english() {
+        let catalog = Catalog::new("de", Some("no-game-id = Spiel-ID fehlt\n"));
+        assert_eq!(catalog.message("no-game-id", &[]), "Spiel-ID fehlt");
+        let args = [("game_id", "doom".to_string())];
+        assert_eq!(
+            catalog.message("no-such-game", &args),
+            "Kein solches Spiel: doom"
+        );
+        let catalog = Catalog::new("xx", None);
+        assert_eq!(catalog.message("no-such-game", &args), "No such game: doom");
+        assert_eq!(catalog.message("not-a-message", &[]), "not-a-message");
+    }
+
+    #[test]
+    fn test_format() {
+        let args = [("a", "1".to_string())];
+        assert_eq!(format("{ $a } and {$a}", &args), "1 and 1");
+        assert_eq!(format("{ $b }", &args), "{ $b }");
+        assert_eq!(format("no args", &args), "no args");
+    }
+
+    #[test]
+    fn test_locale_language() {
+        assert_eq!(locale_language("de_DE.UTF-8"), Some("de".to_string()));
+        assert_eq!(locale_language("fr"), Some("fr".to_string()));
+        assert_eq!(locale_language("C.UTF-8"), None);
+    }
+
+    #[test]
+    fn test_catalogs_have_every_english_message() {
+        let english = parse(ENGLISH);
+        for (language, content) in CATALOGS.iter() {
+            let messages = parse(content);
+            for key in english.keys() {
+                assert!(
+                    messages.contains_key(key),
+                    "{} is missing {}",
+                    language,
+                    key
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use crate::game::{Game, GameError};
+use crate::{Games, data_dir};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The directory of icons extracted from games, in the data directory
+pub const ICONS_DIR: &str = "icons";
+
+/// The resource types of an icon image and of the group listing an icon's
+/// sizes
+const RT_ICON: u32 = 3;
+const RT_GROUP_ICON: u32 = 14;
+
+/// An icon taken from an executable, as the contents of an image file
+pub struct Icon {
+    pub data: Vec<u8>,
+    /// `png` for icons stored as PNG images (as large icons usually are),
+    /// otherwise `ico`
+    pub extension: &'static str,
+}
+
+/// The icon extracted for the game, if there is one
+pub fn find(icons_dir: &Path, game_id: &str) -> Option<PathBuf> {
+    ["png", "ico"]
+        .iter()
+        .map(|extension| icons_dir.join(format!("{}.{}", game_id, extension)))
+        .find(|path| path.is_file())
+}
+
+/// Extract the largest image of the first icon in a Windows executable
+pub fn extract(exe: &[u8]) -> Option<Icon> {
+    let pe = Pe::parse(exe)?;
+    let group = pe.resources(RT_GROUP_ICON).into_iter().next()?;
+    let count = read_u16(group, 4)? as usize;
+    // Each entry of the group is 14 bytes after the 6-byte header, and
+    // describes an image whose ID is its last field
+    let best = (0..count)
+        .filter_map(|i| group.get(6 + i * 14..6 + (i + 1) * 14))
+        .max_by_key(|entry| {
+            let width = if entry[0] == 0 { 256 } else { entry[0] as u32 };
+            (width, read_u16(entry, 6).unwrap_or(0))
+        })?;
+    let id = read_u16(best, 12)? as u32;
+    let image = pe.resource(RT_ICON, id)?;
+    if image.starts_with(b"\x89PNG") {
+        return Some(Icon {
+            data: image.to_vec(),
+            extension: "png",
+        });
+    }
+    // An .ico file of the one image: the header, its entry (the group entry
+    // with the image's offset in place of its ID), then the image
+    let mut data = vec![0, 0, 1, 0, 1, 0];
+    data.extend_from_slice(&best[..12]);
+    data.extend_from_slice(&22u32.to_le_bytes());
+    data.extend_from_slice(image);
+    Some(Icon {
+        data,
+        extension: "ico",
+    })
+}
+
+/// The sections of a PE executable, with its resources
+struct Pe<'a> {
+    data: &'a [u8],
+    /// The virtual address, size, and file offset of each section
+    sections: Vec<(u32, u32, u32)>,
+    resources_rva: u32,
+}
+
+impl Pe<'_> {
+    fn parse(data: &[u8]) -> Option<Pe<'_>> {
+        if !data.starts_with(b"MZ") {
+            return None;
+        }
+        let pe_offset = read_u32(data, 0x3c)? as usize;
+        if data.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+            return None;
+        }
+        let coff = pe_offset + 4;
+        let section_count = read_u16(data, coff + 2)? as usize;
+        let optional_size = read_u16(data, coff + 16)? as usize;
+        let optional = coff + 20;
+        // The data directories come after 96 bytes of the optional header in
+        // 32-bit executables and 112 in 64-bit ones; resources are the third
+        let directories = match read_u16(data, optional)? {
+            0x10b => optional + 96,
+            0x20b => optional + 112,
+            _ => return None,
+        };
+        let resources_rva = read_u32(data, directories + 2 * 8)?;
+        let section_table = optional + optional_size;
+        let sections = (0..section_count)
+            .filter_map(|i| {
+                let section = section_table + i * 40;
+                Some((
+                    read_u32(data, section + 12)?,
+                    read_u32(data, section + 16)?,
+                    read_u32(data, section + 20)?,
+                ))
+            })
+            .collect();
+        Some(Pe {
+            data,
+            sections,
+            resources_rva,
+        })
+    }
+
+    /// The file offset of a virtual address
+    fn offset(&self, rva: u32) -> Option<usize> {
+        self.sections
+            .iter()
+            .find(|(address, size, _)| rva >= *address && rva - address < *size)
+            .and_then(|(address, _, offset)| (rva - address).checked_add(*offset))
+            .map(|offset| offset as usize)
+    }
+
+    /// The entries of a resource directory, as their IDs (or name offsets)
+    /// and where they point, relative to the start of the resources
+    fn directory(&self, offset: usize) -> Vec<(u32, u32)> {
+        let Some(start) = self.offset(self.resources_rva) else {
+            return Vec::new();
+        };
+        let at = start + offset;
+        let named = read_u16(self.data, at + 12).unwrap_or(0) as usize;
+        let ids = read_u16(self.data, at + 14).unwrap_or(0) as usize;
+        (0..named + ids)
+            .filter_map(|i| {
+                let entry = at + 16 + i * 8;
+                Some((read_u32(self.data, entry)?, read_u32(self.data, entry + 4)?))
+            })
+            .collect()
+    }
+
+    /// The data of the first language of each resource of the given type
+    fn resources(&self, resource_type: u32) -> Vec<&[u8]> {
+        self.resources_with_ids(resource_type)
+            .into_iter()
+            .map(|(_, data)| data)
+            .collect()
+    }
+
+    /// The data of the resource with the given type and ID
+    fn resource(&self, resource_type: u32, id: u32) -> Option<&[u8]> {
+        self.resources_with_ids(resource_type)
+            .into_iter()
+            .find(|(resource_id, _)| *resource_id == id)
+            .map(|(_, data)| data)
+    }
+
+    fn resources_with_ids(&self, resource_type: u32) -> Vec<(u32, &[u8])> {
+        const SUBDIRECTORY: u32 = 0x8000_0000;
+        let Some((_, types)) = self
+            .directory(0)
+            .into_iter()
+            .find(|(id, _)| *id == resource_type)
+        else {
+            return Vec::new();
+        };
+        if types & SUBDIRECTORY == 0 {
+            return Vec::new();
+        }
+        let mut found = Vec::new();
+        for (id, names) in self.directory((types & !SUBDIRECTORY) as usize) {
+            if names & SUBDIRECTORY == 0 {
+                continue;
+            }
+            let languages = self.directory((names & !SUBDIRECTORY) as usize);
+            let Some((_, leaf)) = languages.first() else {
+                continue;
+            };
+            if let Some(data) = self.data_entry(*leaf as usize) {
+                found.push((id, data));
+            }
+        }
+        found
+    }
+
+    /// The data that a resource data entry points to
+    fn data_entry(&self, offset: usize) -> Option<&[u8]> {
+        let at = self.offset(self.resources_rva)? + offset;
+        let rva = read_u32(self.data, at)?;
+        let size = read_u32(self.data, at + 4)? as usize;
+        let start = self.offset(rva)?;
+        self.data.get(start..start + size)
+    }
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// The game's cover, or else the icon extracted from its executable
+pub fn game_icon(game: &Game) -> Option<PathBuf> {
+    game.cover
+        .clone()
+        .or_else(|| find(&data_dir().join(ICONS_DIR), &game.id))
+}
+
+/// Extract the icon from a wine game's executable into the data directory,
+/// where shortcuts and menus pick it up when the game has no cover
+pub fn command_icon<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let game_id = match args {
+        [subcommand, game_id] if subcommand == "extract" => game_id,
+        [subcommand] if subcommand == "extract" => return Err(GameError::NoGameId),
+        [] => return Err(GameError::NoGameId),
+        [arg, ..] => return Err(GameError::InvalidArgument(arg)),
+    };
+    let Some(game) = games.find(game_id) else {
+        return Err(GameError::NoSuchGame(game_id));
+    };
+    let Some(exe) = game
+        .command
+        .iter()
+        .find(|arg| arg.to_lowercase().ends_with(".exe"))
+    else {
+        return Err(GameError::NoIcon(game_id));
+    };
+    let exe = match &game.dir {
+        Some(dir) => Path::new(dir).join(exe),
+        None => PathBuf::from(exe),
+    };
+    debug!("Reading icon from: {}", exe.display());
+    let Some(icon) = fs::read(&exe).ok().and_then(|bytes| extract(&bytes)) else {
+        return Err(GameError::NoIcon(game_id));
+    };
+    let dir = data_dir().join(ICONS_DIR);
+    let path = dir.join(format!("{}.{}", game.id, icon.extension));
+    let old = find(&dir, &game.id);
+    fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(&path, &icon.data))
+        .map_err(|e| GameError::CouldNotWriteIcon(e.to_string()))?;
+    // An icon extracted before, in the other format, would be found first
+    if let Some(old) = old
+        && old != path
+    {
+        let _ = fs::remove_file(old);
+    }
+    out!("{}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 32-bit executable whose only section holds the resources: a group
+    /// icon with a 16x16 and a 32x32 image, and the two images
+    fn test_exe(image_32: &[u8]) -> Vec<u8> {
+        const SECTION_RVA: u32 = 0x1000;
+        const SECTION_OFFSET: usize = 0x200;
+        let mut exe = vec![0u8; SECTION_OFFSET];
+        exe[0..2].copy_from_slice(b"MZ");
+        exe[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        exe[0x40..0x44].copy_from_slice(b"PE\0\0");
+        let coff = 0x44;
+        exe[coff + 2..coff + 4].copy_from_slice(&1u16.to_le_bytes());
+        exe[coff + 16..coff + 18].copy_from_slice(&224u16.to_le_bytes());
+        let optional = coff + 20;
+        exe[optional..optional + 2].copy_from_slice(&0x10bu16.to_le_bytes());
+        let resources = optional + 96 + 16;
+        exe[resources..resources + 4].copy_from_slice(&SECTION_RVA.to_le_bytes());
+        let section = optional + 224;
+
+        // The resources: directories, then data entries, then data
+        let mut rsrc: Vec<u8> = Vec::new();
+        let directory = |rsrc: &mut Vec<u8>, entries: &[(u32, u32)]| {
+            rsrc.extend_from_slice(&[0u8; 14]);
+            rsrc.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+            for (id, offset) in entries {
+                rsrc.extend_from_slice(&id.to_le_bytes());
+                rsrc.extend_from_slice(&offset.to_le_bytes());
+            }
+        };
+        let sub = 0x8000_0000;
+        // Type directory at 0 (32 bytes), ID directories at 32 and 56 (24 and
+        // 32 bytes), language directories at 88, 112, 136 (24 bytes each),
+        // data entries at 160, 176, 192 (16 bytes each), data from 208
+        directory(&mut rsrc, &[(RT_ICON, sub | 56), (RT_GROUP_ICON, sub | 32)]);
+        directory(&mut rsrc, &[(1, sub | 88)]);
+        directory(&mut rsrc, &[(2, sub | 112), (7, sub | 136)]);
+        directory(&mut rsrc, &[(1033, 160)]);
+        directory(&mut rsrc, &[(1033, 176)]);
+        directory(&mut rsrc, &[(1033, 192)]);
+        let mut group = vec![0, 0, 1, 0, 2, 0];
+        group.extend_from_slice(&[16, 16, 0, 0, 1, 0, 32, 0, 4, 0, 0, 0, 2, 0]);
+        group.extend_from_slice(&[32, 32, 0, 0, 1, 0, 32, 0]);
+        group.extend_from_slice(&(image_32.len() as u32).to_le_bytes());
+        group.extend_from_slice(&7u16.to_le_bytes());
+        let image_16 = b"tiny";
+        let data_start = 208;
+        let offsets = [
+            data_start,
+            data_start + group.len(),
+            data_start + group.len() + image_16.len(),
+        ];
+        for (offset, size) in offsets
+            .iter()
+            .zip([group.len(), image_16.len(), image_32.len()])
+        {
+            rsrc.extend_from_slice(&(SECTION_RVA + *offset as u32).to_le_bytes());
+            rsrc.extend_from_slice(&(size as u32).to_le_bytes());
+            rsrc.extend_from_slice(&[0u8; 8]);
+        }
+        assert_eq!(rsrc.len(), data_start);
+        rsrc.extend_from_slice(&group);
+        rsrc.extend_from_slice(image_16);
+        rsrc.extend_from_slice(image_32);
+
+        exe[section + 8..section + 12].copy_from_slice(&(rsrc.len() as u32).to_le_bytes());
+        exe[section + 12..section + 16].copy_from_slice(&SECTION_RVA.to_le_bytes());
+        exe[section + 16..section + 20].copy_from_slice(&(rsrc.len() as u32).to_le_bytes());
+        exe[section + 20..section + 24].copy_from_slice(&(SECTION_OFFSET as u32).to_le_bytes());
+        exe.extend_from_slice(&rsrc);
+        exe
+    }
+
+    #[test]
+    fn test_extract_largest_icon() {
+        let icon = extract(&test_exe(b"\x89PNG big")).expect("No icon");
+        assert_eq!(icon.extension, "png");
+        assert_eq!(icon.data, b"\x89PNG big");
+
+        let icon = extract(&test_exe(b"bitmap")).expect("No icon");
+        assert_eq!(icon.extension, "ico");
+        assert_eq!(&icon.data[..8], &[0, 0, 1, 0, 1, 0, 32, 32]);
+        assert_eq!(&icon.data[18..22], &22u32.to_le_bytes());
+        assert_eq!(&icon.data[22..], b"bitmap");
+    }
+
+    #[test]
+    fn test_not_an_executable() {
+        assert!(extract(b"#!/bin/sh\n").is_none());
+    }
+
+    #[test]
+    fn test_section_offset_overflow() {
+        let pe = Pe {
+            data: &[],
+            sections: vec![(0x1000, 0x1000, u32::MAX - 0x10)],
+            resources_rva: 0x1000,
+        };
+        assert_eq!(pe.offset(0x1008), Some(u32::MAX as usize - 0x8));
+        assert_eq!(pe.offset(0x1020), None);
+    }
+}
@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How often the idle time and the pause file are checked
+const POLL_SECONDS: u32 = 15;
+
+/// Measures how much of a session was spent away from the keyboard, either
+/// going by how long there has been no input (from `xprintidle`) or by a
+/// file that exists while the player has paused
+pub struct IdleTracker {
+    sender: Sender<()>,
+    handle: JoinHandle<u32>,
+}
+
+impl IdleTracker {
+    pub fn start(idle_after_minutes: Option<u32>, pause_file: Option<PathBuf>) -> IdleTracker {
+        let (sender, receiver) = mpsc::channel();
+        let interval = Duration::from_secs(POLL_SECONDS as u64);
+        let handle = thread::spawn(move || {
+            let mut idle = Idle::new(idle_after_minutes.map(|minutes| minutes * 60));
+            // Anything other than a timeout means the game has exited
+            while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(interval) {
+                let paused = pause_file.as_ref().is_some_and(|file| file.exists());
+                let input_idle = idle_after_minutes.and_then(|_| input_idle_seconds());
+                idle.update(input_idle, paused, POLL_SECONDS);
+            }
+            idle.finish()
+        });
+        IdleTracker { sender, handle }
+    }
+
+    /// Stop tracking, giving the number of seconds that were idle
+    pub fn stop(self) -> u32 {
+        let _ = self.sender.send(());
+        self.handle.join().unwrap_or(0)
+    }
+}
+
+/// The seconds since the last keyboard or mouse input, if it can be told
+fn input_idle_seconds() -> Option<u32> {
+    let output = Command::new("xprintidle").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let millis = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some((millis / 1000) as u32)
+}
+
+/// The idle time seen so far, from one check to the next
+struct Idle {
+    threshold_seconds: Option<u32>,
+    total_seconds: u32,
+    /// How long the current stretch without input has been going, once it is
+    /// long enough to count
+    stretch_seconds: u32,
+    /// How long tracking has been going, which no stretch can be longer than
+    elapsed_seconds: u32,
+}
+
+impl Idle {
+    fn new(threshold_seconds: Option<u32>) -> Idle {
+        Idle {
+            threshold_seconds,
+            total_seconds: 0,
+            stretch_seconds: 0,
+            elapsed_seconds: 0,
+        }
+    }
+
+    fn update(&mut self, input_idle_seconds: Option<u32>, paused: bool, interval: u32) {
+        self.elapsed_seconds += interval;
+        let input_idle = input_idle_seconds.unwrap_or(0);
+        if input_idle < self.stretch_seconds {
+            // There has been input since the last check, so the stretch is over
+            self.total_seconds += self.stretch_seconds;
+            self.stretch_seconds = 0;
+        }
+        let is_idle = self
+            .threshold_seconds
+            .is_some_and(|threshold| input_idle >= threshold);
+        if is_idle {
+            // The whole stretch counts, including the minutes before it was
+            // long enough to notice, but not any of it from before the game
+            // was started
+            self.stretch_seconds = input_idle.min(self.elapsed_seconds);
+        } else if paused {
+            self.total_seconds += interval;
+        }
+    }
+
+    fn finish(self) -> u32 {
+        self.total_seconds + self.stretch_seconds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_stretches() {
+        let mut idle = Idle::new(Some(300));
+        idle.update(Some(10), false, 300);
+        idle.update(Some(299), false, 300);
+        assert_eq!(idle.stretch_seconds, 0);
+        idle.update(Some(310), false, 300);
+        idle.update(Some(370), false, 300);
+        idle.update(Some(5), false, 300);
+        assert_eq!(idle.total_seconds, 370);
+        idle.update(Some(400), false, 300);
+        assert_eq!(idle.finish(), 770);
+    }
+
+    #[test]
+    fn test_idle_before_start() {
+        // Input had already stopped an hour before the game was started
+        let mut idle = Idle::new(Some(300));
+        idle.update(Some(3615), false, 15);
+        idle.update(Some(3630), false, 15);
+        assert_eq!(idle.stretch_seconds, 30);
+        idle.update(Some(1), false, 15);
+        assert_eq!(idle.finish(), 30);
+    }
+
+    #[test]
+    fn test_pause_file() {
+        let mut idle = Idle::new(None);
+        idle.update(None, true, 15);
+        idle.update(None, true, 15);
+        idle.update(None, false, 15);
+        assert_eq!(idle.finish(), 30);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{
+    DEFAULT_HEIGHT, DEFAULT_WIDTH, UtilityCommandError, ask, ask_yes_no, config_file_path, fsutil,
+};
+use std::env::home_dir;
+use std::fs;
+
+/// Answers from `init --interactive`, which are written into the starter
+/// config file
+pub struct Setup {
+    pub games_dir: Option<String>,
+    pub use_gamescope: bool,
+    pub has_mangohud: bool,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for Setup {
+    fn default() -> Setup {
+        Setup {
+            games_dir: None,
+            use_gamescope: false,
+            has_mangohud: true,
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+        }
+    }
+}
+
+/// Parse a screen resolution like `1920x1080`
+pub fn parse_resolution(s: &str) -> Option<(u32, u32)> {
+    let (width, height) = s.trim().split_once(['x', 'X'])?;
+    let width = width.trim().parse().ok().filter(|w| *w > 0)?;
+    let height = height.trim().parse().ok().filter(|h| *h > 0)?;
+    Some((width, height))
+}
+
+/// A line of the config file, which is commented out if it only repeats the
+/// default
+fn line(is_default: bool, line: &str) -> String {
+    if is_default {
+        format!("# {}", line)
+    } else {
+        line.to_string()
+    }
+}
+
+/// A starter config file, with the settings that were not chosen commented
+/// out at their defaults and an example game that is marked as not installed
+pub fn starter_config(setup: &Setup) -> String {
+    let is_default_size = setup.width == DEFAULT_WIDTH && setup.height == DEFAULT_HEIGHT;
+    let games_dir = setup.games_dir.as_deref().unwrap_or("/home/me/Games");
+    let mangohud_note = if setup.has_mangohud {
+        ""
+    } else {
+        "# MangoHud is not installed, so it is turned off for the example game; wine
+# games use it unless they set use_mangohud = false
+"
+    };
+    format!(
+        "# Games for the `game` command. Run `game help` to see what it can do, and
+# `game check` after editing this file to look for mistakes.
+{mangohud_note}
+[settings]
+# The screen size used for gamescope
+{width}
+{height}
+# Run every game in gamescope unless a game sets use_gamescope = false
+{use_gamescope}
+# Ask for a note about each session after a game exits
+# session_notes = false
+# Show a desktop notification every so many minutes while a game is running
+# remind_after_minutes = 60
+# Leave out stretches of this many minutes without input from the play time
+# idle_after_minutes = 10
+# Mention installed games in `game nudge` once they go this many days unplayed
+# nudge_after_days = 30
+
+[directories]
+# Names for directories that games share, for use as a game's dir_prefix
+{games_dir}
+
+[games]
+
+# Each game is a table named after its ID, which is what `game play` takes.
+# This one is marked as not installed, so it is not listed; change it to
+# a real game, or remove it and add your own.
+[games.example]
+name = \"Example Game\"
+cmd = \"example-game --fullscreen\"
+{dir_prefix}
+{dir}
+# tags = [\"rpg\", \"wine\"]
+{use_mangohud}installed = false
+",
+        width = line(is_default_size, &format!("width = {}", setup.width)),
+        height = line(is_default_size, &format!("height = {}", setup.height)),
+        use_gamescope = line(
+            !setup.use_gamescope,
+            &format!("use_gamescope = {}", setup.use_gamescope)
+        ),
+        games_dir = line(
+            setup.games_dir.is_none(),
+            &format!("games_dir = {}", toml::Value::from(games_dir))
+        ),
+        dir_prefix = line(setup.games_dir.is_none(), "dir_prefix = \"games_dir\""),
+        dir = line(setup.games_dir.is_none(), "dir = \"example\""),
+        use_mangohud = if setup.has_mangohud {
+            ""
+        } else {
+            "use_mangohud = false\n"
+        },
+    )
+}
+
+/// A ready-to-paste entry for a game of the given kind, with the options that
+/// are needed filled in with placeholders and the usual optional ones
+/// commented out
+pub fn game_template(kind: &str, game_id: &str) -> Option<String> {
+    let launch = match kind {
+        "native" => {
+            "cmd = \"./start.sh --fullscreen\"
+dir = \"/home/me/Games/new-game\"
+# use_gamescope = true
+# use_mangohud = true"
+        }
+        "wine" => {
+            "wine_exe = \"Game.exe\"
+dir = \"/home/me/Games/new-game\"
+# The wine prefix is set in env
+# env = { WINEPREFIX = \"/home/me/.wine-new-game\" }
+# runner = \"wine-ge-8-26\"
+# use_vk = true
+# use_mangohud = true
+# process_name = \"Game.exe\""
+        }
+        "dosbox" => {
+            "dosbox_config = \"new-game.conf\"
+dir = \"/home/me/Games/new-game\""
+        }
+        "scummvm" => "scummvm_id = \"new-game\"",
+        "steam" => {
+            "steam_id = \"220\"
+# The name of the game's process, so that `watch` notices it running
+# process_name = \"hl2_linux\""
+        }
+        _ => return None,
+    };
+    Some(format!(
+        "[games.{game_id}]
+name = \"New Game\"
+{launch}
+# dir_prefix = \"games_dir\"
+# tags = [\"{kind}\"]
+# added = \"2024-05-01\"
+# fps_limit = 60
+"
+    ))
+}
+
+pub fn command_init(args: &[String]) -> Result<(), UtilityCommandError> {
+    let mut interactive = false;
+    for arg in args.iter() {
+        match arg.as_str() {
+            "--interactive" | "-i" => interactive = true,
+            _ => return Err(UtilityCommandError::InvalidArgument(arg.to_string())),
+        }
+    }
+    let path = config_file_path();
+    if path.exists() {
+        return Err(UtilityCommandError::ConfigExists(path));
+    }
+    let setup = if interactive {
+        ask_setup()
+    } else {
+        Setup::default()
+    };
+    debug!("Writing starter config file: {}", path.display());
+    if let Err(e) = fs::write(&path, starter_config(&setup)) {
+        return Err(UtilityCommandError::CouldNotWriteConfig(e.to_string()));
+    }
+    out!("Wrote {}", path.display());
+    Ok(())
+}
+
+pub fn command_template(args: &[String]) -> Result<(), UtilityCommandError> {
+    let kind = args.first().map_or("native", |kind| kind.as_str());
+    let game_id = args.get(1).map_or("new_game", |game_id| game_id.as_str());
+    if let Some(arg) = args.get(2) {
+        return Err(UtilityCommandError::InvalidArgument(arg.to_string()));
+    }
+    match game_template(kind, game_id) {
+        Some(template) => {
+            out!("{}", template.trim_end());
+            Ok(())
+        }
+        None => Err(UtilityCommandError::InvalidArgument(kind.to_string())),
+    }
+}
+
+/// Ask the questions for `init --interactive`, suggesting what is detected
+fn ask_setup() -> Setup {
+    let mut setup = Setup::default();
+    let suggested_dir = home_dir().unwrap().join("Games");
+    let games_dir = ask(
+        "Directory where your games are installed",
+        &suggested_dir.to_string_lossy(),
+    );
+    setup.games_dir = Some(games_dir);
+
+    if fsutil::on_path("gamescope") {
+        setup.use_gamescope = ask_yes_no("gamescope is installed; run games in it", false);
+    } else {
+        println!("gamescope is not installed, so games will not be run in it");
+    }
+    setup.has_mangohud = fsutil::on_path("mangohud");
+    if !setup.has_mangohud {
+        println!("MangoHud is not installed, so the example game will not use it");
+    }
+
+    let default_size = format!("{}x{}", DEFAULT_WIDTH, DEFAULT_HEIGHT);
+    loop {
+        let answer = ask("Screen resolution", &default_size);
+        match parse_resolution(&answer) {
+            Some((width, height)) => {
+                setup.width = width;
+                setup.height = height;
+                break;
+            }
+            None => println!("Enter a resolution like 1920x1080"),
+        }
+    }
+    setup
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+
+    #[test]
+    fn test_starter_config_parses() {
+        let games = parse_config(&starter_config(&Setup::default())).expect("Bad starter config");
+        let game = games.find("example").expect("No example game");
+        assert!(!game.is_installed());
+    }
+
+    #[test]
+    fn test_starter_config_with_setup() {
+        let setup = Setup {
+            games_dir: Some("/mnt/games".to_string()),
+            use_gamescope: true,
+            has_mangohud: false,
+            width: 2560,
+            height: 1440,
+        };
+        let config = starter_config(&setup);
+        let games = parse_config(&config).expect("Bad starter config");
+        assert_eq!(games.settings.width, 2560);
+        assert!(games.settings.use_gamescope);
+        let game = games.find("example").expect("No example game");
+        assert_eq!(game.dir.as_deref(), Some("/mnt/games/example"));
+    }
+
+    #[test]
+    fn test_game_templates_parse() {
+        for kind in ["native", "wine", "dosbox", "scummvm", "steam"] {
+            let template = game_template(kind, "new_game").unwrap();
+            let config = format!("[games]\n{}", template);
+            let games = parse_config(&config).expect("Bad template");
+            assert!(games.find("new_game").is_some(), "{}", kind);
+        }
+        assert!(game_template("amiga", "new_game").is_none());
+    }
+
+    #[test]
+    fn test_parse_resolution() {
+        assert_eq!(parse_resolution("1920x1080\n"), Some((1920, 1080)));
+        assert_eq!(parse_resolution("0x1080"), None);
+        assert_eq!(parse_resolution("big"), None);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::game::GameError;
+use crate::{Game, Games, INSTALLED_FILE, data_dir, tsv};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+const INSTALLED: &str = "yes";
+const NOT_INSTALLED: &str = "no";
+
+/// Whether games are installed on this machine, which takes the place of the
+/// `installed` option in the (possibly shared) config file
+#[derive(Debug, Default)]
+pub struct InstalledState {
+    installed: BTreeMap<String, bool>,
+}
+
+impl InstalledState {
+    /// Parse lines of a game ID and `yes` or `no`, separated by a tab
+    pub fn parse(content: &str) -> InstalledState {
+        let mut installed = BTreeMap::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let fields = tsv::split_line(line);
+            match fields.get(1).map(|s| s.as_str()) {
+                Some(INSTALLED) => installed.insert(fields[0].clone(), true),
+                Some(NOT_INSTALLED) => installed.insert(fields[0].clone(), false),
+                _ => {
+                    warn!("Invalid line in installed state file: {}", line);
+                    continue;
+                }
+            };
+        }
+        InstalledState { installed }
+    }
+
+    pub fn format(&self) -> String {
+        let mut s = String::new();
+        for (game_id, installed) in self.installed.iter() {
+            let installed = if *installed { INSTALLED } else { NOT_INSTALLED };
+            s.push_str(&tsv::format_line(&[game_id, installed]));
+            s.push('\n');
+        }
+        s
+    }
+
+    pub fn get(&self, game_id: &str) -> Option<bool> {
+        self.installed.get(game_id).copied()
+    }
+
+    /// Set whether the game is installed, or with `None`, go back to using
+    /// the config
+    pub fn set(&mut self, game_id: &str, installed: Option<bool>) {
+        match installed {
+            Some(installed) => self.installed.insert(game_id.to_string(), installed),
+            None => self.installed.remove(game_id),
+        };
+    }
+
+    /// Mark the games as installed or not, where the state has been set
+    pub fn apply(&self, games: &mut HashMap<String, Game>) {
+        for (game_id, installed) in self.installed.iter() {
+            if let Some(game) = games.get_mut(game_id) {
+                game.installed = *installed;
+            }
+        }
+    }
+}
+
+pub fn command_installed<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let Some(game_id) = args.first() else {
+        return Err(GameError::NoGameId);
+    };
+    let game = match games.find(game_id) {
+        Some(game) => game,
+        None => return Err(GameError::NoSuchGame(game_id)),
+    };
+    let mut state = read_installed_state();
+    let installed = match args.get(1).map(|s| s.as_str()) {
+        None => {
+            let installed = if game.is_installed() { "yes" } else { "no" };
+            match state.get(game_id) {
+                Some(_) => out!("{} (set on this machine)", installed),
+                None => out!("{}", installed),
+            }
+            return Ok(());
+        }
+        Some("yes") => Some(true),
+        Some("no") => Some(false),
+        Some("default") => None,
+        Some(_) => return Err(GameError::InvalidArgument(&args[1])),
+    };
+    state.set(game_id, installed);
+    match fs::write(installed_file_path(), state.format()) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(GameError::CouldNotWriteInstalledState(e.to_string())),
+    }
+}
+
+/// Which games are installed on this machine, where that has been set with
+/// the `installed` command
+pub fn read_installed_state() -> InstalledState {
+    match fs::read_to_string(installed_file_path()) {
+        Ok(content) => InstalledState::parse(&content),
+        Err(_) => InstalledState::default(),
+    }
+}
+
+fn installed_file_path() -> PathBuf {
+    data_dir().join(INSTALLED_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let mut state = InstalledState::parse("doom\tno\nquake\tyes\nbad line\n");
+        assert_eq!(state.get("doom"), Some(false));
+        assert_eq!(state.get("quake"), Some(true));
+        assert_eq!(state.get("bad line"), None);
+        state.set("quake", None);
+        state.set("hexen", Some(true));
+        assert_eq!(state.format(), "doom\tno\nhexen\tyes\n");
+    }
+}
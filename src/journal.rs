@@ -0,0 +1,113 @@
+use crate::game::GameError;
+use crate::stats::{format_local, format_utc, parse_utc};
+use crate::{Games, data_dir, tsv};
+use std::fs;
+use std::io::Write;
+use time::UtcDateTime;
+
+/// The directory of journals in the data directory, one file per game
+pub const JOURNAL_DIR: &str = "journals";
+
+/// A dated note about a game, like a mod that was installed or a setting that
+/// was changed
+pub struct Entry {
+    time: UtcDateTime,
+    text: String,
+}
+
+impl Entry {
+    pub fn new(time: UtcDateTime, text: &str) -> Entry {
+        // Entries are a single line, like session notes
+        Entry {
+            time,
+            text: text.replace(['\t', '\n', '\r'], " "),
+        }
+    }
+
+    pub fn to_tsv(&self) -> String {
+        tsv::format_line(&[&format_utc(self.time), &self.text])
+    }
+
+    pub fn from_tsv(line: &str) -> Entry {
+        let parts = tsv::split_line(line);
+        Entry {
+            time: parse_utc(&parts[0]).expect("Bad timestamp"),
+            text: parts.get(1).cloned().unwrap_or_default(),
+        }
+    }
+
+    pub fn format(&self) -> String {
+        format!("{} {}", format_local(self.time), self.text)
+    }
+}
+
+/// Parse a journal file, one entry per line, in the order they were made
+pub fn parse(content: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = content
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(Entry::from_tsv)
+        .collect();
+    entries.sort_by_key(|entry| entry.time);
+    entries
+}
+
+/// Add the text as an entry in the game's journal, or show the journal if
+/// there is no text
+pub fn command_journal<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let Some(game_id) = args.first() else {
+        return Err(GameError::NoGameId);
+    };
+    let Some(game) = games.find(game_id) else {
+        return Err(GameError::NoSuchGame(game_id));
+    };
+    let path = data_dir()
+        .join(JOURNAL_DIR)
+        .join(format!("{}.tsv", game.id));
+    if args.len() > 1 {
+        let entry = Entry::new(UtcDateTime::now(), &args[1..].join(" "));
+        debug!("Appending to journal: {}", path.display());
+        let result = fs::create_dir_all(path.parent().unwrap()).and_then(|_| {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?;
+            writeln!(file, "{}", entry.to_tsv())
+        });
+        return result.map_err(|e| GameError::CouldNotWriteJournal(e.to_string()));
+    }
+    debug!("Reading journal: {}", path.display());
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    for entry in parse(&content) {
+        out!("{}", entry.format());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_journal() {
+        let entries = parse(
+            "2025-11-04 10:00:00\tinstalled the unofficial patch\n\
+             2025-11-03 19:07:00\tstarted a new save\n",
+        );
+        let lines: Vec<String> = entries.iter().map(|e| e.to_tsv()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "2025-11-03 19:07:00\tstarted a new save",
+                "2025-11-04 10:00:00\tinstalled the unofficial patch"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_entry_is_one_line() {
+        let time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let entry = Entry::new(time, "enabled\tthe\nHD mod");
+        assert_eq!(entry.to_tsv(), "1970-01-01 00:00:00\tenabled the HD mod");
+    }
+}
@@ -0,0 +1,56 @@
+/// Format a string as a JSON string literal, including the surrounding quotes
+pub fn string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Format key/value pairs, whose values are already JSON, as a JSON object
+pub fn object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(k, v)| format!("{}:{}", string(k), v))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Format values, which are already JSON, as a JSON array
+pub fn array(items: &[String]) -> String {
+    format!("[{}]", items.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_string() {
+        assert_eq!(string("plain"), "\"plain\"");
+        assert_eq!(string("a \"b\"\n\\c"), "\"a \\\"b\\\"\\n\\\\c\"");
+        assert_eq!(string("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn test_object() {
+        let obj = object(&[("id", string("doom")), ("seconds", 60.to_string())]);
+        assert_eq!(obj, "{\"id\":\"doom\",\"seconds\":60}");
+    }
+
+    #[test]
+    fn test_array() {
+        assert_eq!(array(&[string("a"), 1.to_string()]), "[\"a\",1]");
+        assert_eq!(array(&[]), "[]");
+    }
+}
@@ -0,0 +1,234 @@
+use crate::directories;
+use crate::game::GameError;
+use crate::{Games, full_config};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Environment variables that are set by game options, which replace any value
+/// given for them in a game's `env` table
+const GENERATED_ENV: [(&str, &[&str]); 4] = [
+    ("WINEDLLOVERRIDES", &["use_vk"]),
+    (
+        "MANGOHUD_CONFIG",
+        &["fps_limit", "mangohud_config", "capture_fps"],
+    ),
+    ("WINE", &["runner"]),
+    ("SDL_VIDEO_FULLSCREEN_DISPLAY", &["display"]),
+];
+
+/// Find things in the config that are valid but probably mistakes
+pub fn lint(config: &Table, games: &Games) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut game_ids: Vec<&String> = games.games.keys().collect();
+    game_ids.sort();
+
+    let mut commands: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    let mut tag_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for game_id in game_ids.iter() {
+        let game = &games.games[*game_id];
+        commands
+            .entry(game.command.join(" "))
+            .or_default()
+            .push(game_id);
+        for tag in game.tags.iter() {
+            *tag_counts.entry(tag).or_default() += 1;
+        }
+        if let Some(dir) = &game.dir
+            && game.is_installed()
+            && !Path::new(dir).is_dir()
+        {
+            warnings.push(format!("{}: dir does not exist: {}", game_id, dir));
+        }
+        if let Some(runner) = &game.missing_runner {
+            warnings.push(format!("{}: runner is not installed: {}", game_id, runner));
+        }
+    }
+    for (command, ids) in commands.iter().filter(|(_, ids)| ids.len() > 1) {
+        warnings.push(format!(
+            "{}: games have the same command: {}",
+            ids.join(", "),
+            command
+        ));
+    }
+    for (tag, _) in tag_counts.iter().filter(|(_, count)| **count == 1) {
+        warnings.push(format!("tag is only used once: {}", tag));
+    }
+
+    let game_tables = match config.get("games") {
+        Some(Value::Table(tbl)) => tbl,
+        _ => return warnings,
+    };
+    let mut used_directories = HashSet::new();
+    for (game_id, game_config) in game_tables.iter() {
+        let Value::Table(game_config) = game_config else {
+            continue;
+        };
+        for key in ["dir", "dir_prefix"] {
+            if let Some(Value::String(s)) = game_config.get(key) {
+                used_directories.insert(s.as_str());
+            }
+        }
+        if let Some(Value::Table(env)) = game_config.get("env") {
+            for (var, options) in GENERATED_ENV.iter() {
+                if let Some(option) = options.iter().find(|o| sets_env(game_config, o))
+                    && env.contains_key(*var)
+                {
+                    warnings.push(format!(
+                        "{}: env {} may be overridden by option: {}",
+                        game_id, var, option
+                    ));
+                }
+            }
+        }
+    }
+    if let Some(Value::Table(dirs)) = config.get("directories") {
+        let mut referenced = HashSet::new();
+        for (name, dir) in dirs.iter() {
+            let Value::String(dir) = dir else {
+                continue;
+            };
+            for reference in directories::references(dir) {
+                referenced.insert(reference);
+                // Such braces are kept, but are more likely a misspelled name
+                // than part of a path
+                if !matches!(dirs.get(reference), Some(Value::String(_))) {
+                    warnings.push(format!(
+                        "directory {} refers to unknown directory: {}",
+                        name, reference
+                    ));
+                }
+            }
+        }
+        for name in dirs.keys() {
+            if !used_directories.contains(name.as_str()) && !referenced.contains(name.as_str()) {
+                warnings.push(format!("directory is never used: {}", name));
+            }
+        }
+    }
+    warnings
+}
+
+/// Whether the option is given a value that causes it to set its environment
+/// variable; `use_vk` only does so when it is turned off
+fn sets_env(game_config: &Table, option: &str) -> bool {
+    match game_config.get(option) {
+        Some(Value::Boolean(b)) => *b != (option == "use_vk"),
+        Some(_) => true,
+        None => false,
+    }
+}
+
+pub fn command_check<'a>(games: &Games, _: &'a [String]) -> Result<(), GameError<'a>> {
+    let config = full_config();
+    let warnings = lint(&config, games);
+    for warning in warnings.iter() {
+        out!("warning: {}", warning);
+    }
+    out!("{} games, {} warnings", games.games.len(), warnings.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+
+    #[test]
+    fn test_lint() {
+        let config = "
+        [directories]
+        gog = \"/games/gog\"
+        unused = \"/games/unused\"
+
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        tags = [\"fps\", \"id\"]
+
+        [games.doom2]
+        name = \"Doom II\"
+        cmd = \"dsda-doom\"
+        tags = [\"fps\"]
+        installed = false
+        dir = \"/nonexistent/doom2\"
+
+        [games.morrowind]
+        name = \"Morrowind\"
+        wine_exe = \"Morrowind.exe\"
+        dir_prefix = \"gog\"
+        installed = false
+        use_vk = false
+        env = { WINEDLLOVERRIDES = \"d3d9=n\" }";
+        let games = parse_config(config).expect("Bad config");
+        let table = config.parse::<Table>().unwrap();
+        assert_eq!(
+            lint(&table, &games),
+            vec![
+                "doom, doom2: games have the same command: dsda-doom",
+                "tag is only used once: id",
+                "morrowind: env WINEDLLOVERRIDES may be overridden by option: use_vk",
+                "directory is never used: unused",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_referenced_directory() {
+        let config = "
+        [directories]
+        root = \"/games\"
+        gog = \"{root}/gog\"
+
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        wine_exe = \"Morrowind.exe\"
+        dir_prefix = \"gog\"
+        installed = false";
+        let games = parse_config(config).expect("Bad config");
+        let table = config.parse::<Table>().unwrap();
+        assert!(lint(&table, &games).is_empty());
+    }
+
+    #[test]
+    fn test_lint_missing_runner() {
+        let config = "
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        cmd = \"openmw\"
+        runner = \"/nonexistent/wine-staging\"";
+        let games = parse_config(config).expect("Bad config");
+        let table = config.parse::<Table>().unwrap();
+        assert_eq!(
+            lint(&table, &games),
+            vec!["morrowind: runner is not installed: /nonexistent/wine-staging"]
+        );
+    }
+
+    #[test]
+    fn test_lint_unknown_directory_reference() {
+        let config = "
+        [directories]
+        root = \"/games\"
+        gog = \"{roots}/gog\"
+
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        wine_exe = \"Morrowind.exe\"
+        dir_prefix = \"gog\"
+        installed = false";
+        let games = parse_config(config).expect("Bad config");
+        let table = config.parse::<Table>().unwrap();
+        assert_eq!(
+            lint(&table, &games),
+            vec![
+                "directory gog refers to unknown directory: roots",
+                "directory is never used: root",
+            ]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const LOG_ENV_VAR: &str = "GAME_RS_LOG";
+
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Warn as u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum LogLevel {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+impl LogLevel {
+    pub fn parse(s: &str) -> Option<LogLevel> {
+        match s.to_lowercase().as_str() {
+            "error" => Some(LogLevel::Error),
+            "warn" => Some(LogLevel::Warn),
+            "info" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            "trace" => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+
+    /// The log level for the given number of `-v` flags
+    pub fn from_verbosity(verbosity: u8) -> LogLevel {
+        match verbosity {
+            0 => LogLevel::Warn,
+            1 => LogLevel::Info,
+            2 => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+/// Set the log level from the number of `-v` flags, unless a level is given
+/// in the environment
+pub fn init(verbosity: u8) {
+    let level = if verbosity > 0 {
+        LogLevel::from_verbosity(verbosity)
+    } else {
+        match std::env::var(LOG_ENV_VAR) {
+            Ok(s) => LogLevel::parse(&s).unwrap_or(LogLevel::Warn),
+            Err(_) => LogLevel::Warn,
+        }
+    };
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: LogLevel) -> bool {
+    level as u8 <= LOG_LEVEL.load(Ordering::Relaxed)
+}
+
+pub fn log(level: LogLevel, args: fmt::Arguments) {
+    if enabled(level) {
+        eprintln!("[{}] {}", level.label(), args);
+    }
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::LogLevel::Warn, format_args!($($arg)*))
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::LogLevel::Info, format_args!($($arg)*))
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::LogLevel::Debug, format_args!($($arg)*))
+    };
+}
+
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::LogLevel::Trace, format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_level() {
+        assert_eq!(LogLevel::parse("DEBUG"), Some(LogLevel::Debug));
+        assert_eq!(LogLevel::parse("trace"), Some(LogLevel::Trace));
+        assert_eq!(LogLevel::parse("loud"), None);
+    }
+
+    #[test]
+    fn test_verbosity() {
+        assert_eq!(LogLevel::from_verbosity(0), LogLevel::Warn);
+        assert_eq!(LogLevel::from_verbosity(1), LogLevel::Info);
+        assert_eq!(LogLevel::from_verbosity(2), LogLevel::Debug);
+        assert_eq!(LogLevel::from_verbosity(5), LogLevel::Trace);
+    }
+}
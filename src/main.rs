@@ -1,3 +1,19 @@
+#[macro_use]
+mod log;
+
+#[macro_use]
+mod i18n;
+use i18n::Catalog;
+
+/// Print non-error output unless `--quiet` was given
+macro_rules! out {
+    ($($arg:tt)*) => {
+        if !$crate::global_options::get().quiet {
+            println!($($arg)*)
+        }
+    };
+}
+
 mod game;
 use game::{Game, GameError};
 
@@ -15,11 +31,14 @@ use parse_error::ParseError;
 mod tag;
 use tag::TagGroup;
 
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::env;
-use std::env::{home_dir, var};
+use std::env::{home_dir, var, var_os};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 use toml::{Table, Value};
 
 use time::UtcDateTime;
@@ -27,22 +46,181 @@ use time::UtcDateTime;
 mod stats;
 use stats::GameStats;
 
-const USAGE: &str = "USAGE: game [COMMAND]";
+mod queue;
+
+mod backlog;
+
+mod api;
+
+mod backend;
+use backend::LauncherBackend;
+
+mod background;
+use background::BackgroundProcess;
+
+mod reminder;
+
+mod template;
+
+mod sessions;
+use sessions::Session;
+
+mod platform;
+use platform::Platform;
+
+mod exit_code;
+
+mod global_options;
+use global_options::ErrorFormat;
+
+mod cli;
+use clap::error::ErrorKind;
+
+mod json;
+
+mod condition;
+
+mod config_cache;
+
+mod config_edit;
+
+mod conflicts;
+
+mod custom_command;
+use custom_command::CustomCommand;
+
+mod capture;
+
+mod chart;
+
+mod cache;
+
+mod directories;
+
+mod distribution;
+use distribution::Distribution;
+
+mod fsutil;
+
+mod fuzzy;
+
+mod hooks;
+use hooks::Hook;
+
+mod icon;
+
+mod idle;
+use idle::IdleTracker;
+
+mod host;
+
+mod http;
+
+mod init;
+
+mod installed;
+
+mod journal;
+
+mod lint;
+
+mod manifest;
+
+mod milestones;
+
+mod menu;
+
+mod metrics;
+
+mod mqtt;
+use mqtt::Mqtt;
+
+mod nudge;
+
+mod perf;
+
+mod report;
+
+mod runners;
+
+mod sandbox;
+use sandbox::Sandbox;
+
+mod scan;
+
+mod saves;
+
+mod shortcut;
+
+mod suggest;
+
+mod summary;
+
+mod sync;
+
+mod timings;
+
+mod tree;
+
+mod tsv;
+
+mod tui;
+
+mod watch;
+
+mod wine;
+
+mod wine_registry;
+use wine_registry::{RegValue, RegistryTweak};
+
+mod wm_rules;
+use wm_rules::{WindowManager, WmRules};
+
 const CONFIG_FILE_NAME: &str = "games.toml";
 const DEFAULT_WIDTH: u32 = 1280;
 const DEFAULT_HEIGHT: u32 = 720;
-const CONFIG_DIR: &str = ".config";
 const APP_NAME: &str = "game_rs";
-const DATA_DIR: &str = ".local/share/";
 const STATS_FILE: &str = "game_stats.tsv";
+const QUEUE_FILE: &str = "game_queue.txt";
+const INSTALLED_FILE: &str = "installed.tsv";
+const SESSIONS_FILE: &str = "game_sessions.tsv";
+const FPS_LOG_DIR: &str = "fps_logs";
+const LOCALES_DIR: &str = "locales";
+const MANIFEST_DIR: &str = "manifests";
+const PROFILES_DIR: &str = "profiles";
+const DEFAULT_MQTT_TOPIC: &str = "game_rs";
+const STATUS_DROPPED: &str = "dropped";
+const DEFAULT_CONTROLLER_MAPPER: &str = "antimicrox --hidden --profile {profile}";
+const DEFAULT_COMPOSITOR_PAUSE_CMD: &str =
+    "xfconf-query -c xfwm4 -p /general/use_compositing -s false";
+const DEFAULT_COMPOSITOR_RESUME_CMD: &str =
+    "xfconf-query -c xfwm4 -p /general/use_compositing -s true";
+
+/// The config for this machine, or `None` if there is no config file
+static CONFIG: OnceLock<Option<Result<Table, ParseError>>> = OnceLock::new();
+/// The games cached by an earlier run, when the config has not changed since,
+/// in which case `CONFIG` is everything else in it
+static CACHED_GAMES: Mutex<Option<HashMap<String, Game>>> = Mutex::new(None);
+/// The commands from the `[commands]` table of the config
+static CUSTOM_COMMANDS: OnceLock<Vec<CustomCommand<'static>>> = OnceLock::new();
 
 enum UtilityCommandError {
     NoEditor,
+    NoSuchCommand(String),
+    InvalidArgument(String),
+    ConfigExists(PathBuf),
+    CouldNotWriteConfig(String),
+    CouldNotSync(String),
 }
 
 enum CommandHandler {
     Utility(UtilityCommandHandler),
     Config(ConfigCommandHandler),
+    /// A config command that, when its arguments are all game IDs, only
+    /// needs those games to be parsed
+    Games(ConfigCommandHandler),
+    /// A command from the `[commands]` table, with its command line
+    Custom(&'static str),
 }
 
 type UtilityCommandHandler = fn(args: &[String]) -> Result<(), UtilityCommandError>;
@@ -58,137 +236,573 @@ struct GameCommand {
 }
 
 fn main() {
+    let mut commands = initialize_commands();
+    let args = cli::hoist_global_options(&commands, &env::args().collect::<Vec<String>>());
+    let options = cli::global_options(&args);
+    log::init(options.verbosity);
+    global_options::set(options.with_env());
+    init_language();
+    add_custom_commands(&mut commands);
+
+    let matches = match cli::command(&commands).try_get_matches_from(&args) {
+        Ok(matches) => matches,
+        Err(e) => fail_usage(e),
+    };
+    let Some((cmd, command_matches)) = matches.subcommand() else {
+        unreachable!("A command is required");
+    };
+    let command_args = cli::command_args(command_matches);
+
     // Create the necessary config directory if it doesn't already exist
-    match std::fs::create_dir_all(config_dir()) {
-        Ok(_) => (),
-        Err(e) => {
-            println!("Could not create config directory: {}", e);
-            std::process::exit(1);
-        }
+    if let Err(e) = std::fs::create_dir_all(config_dir()) {
+        let message = tr!("could-not-create-config-dir", error = e);
+        fail("io_error", &message, exit_code::FAILURE);
+    }
+
+    if let Some(profile) = profile()
+        && !is_valid_profile(&profile)
+    {
+        let message = tr!("invalid-argument", argument = profile);
+        fail("invalid_argument", &message, exit_code::USAGE_ERROR);
     }
 
     // Create the necessary datadirectory if it doesn't already exist
-    match std::fs::create_dir_all(data_dir()) {
-        Ok(_) => (),
-        Err(e) => {
-            println!("Could not create data directory: {}", e);
-            std::process::exit(1);
+    if let Err(e) = std::fs::create_dir_all(data_dir().join(profile_path(""))) {
+        let message = tr!("could-not-create-data-dir", error = e);
+        fail("io_error", &message, exit_code::FAILURE);
+    }
+
+    match commands[cmd].exec {
+        CommandHandler::Utility(handler) => handle_utility_command(handler, &command_args),
+        CommandHandler::Config(handler) => handle_config_file_command(handler, &command_args, None),
+        CommandHandler::Games(handler) => {
+            let all_game_ids =
+                !command_args.is_empty() && !command_args.iter().any(|arg| arg.starts_with('-'));
+            let game_ids = all_game_ids.then_some(command_args.as_slice());
+            handle_config_file_command(handler, &command_args, game_ids)
+        }
+        CommandHandler::Custom(template) => {
+            let game_ids = if custom_command::uses_game(template) {
+                command_args.get(..1).unwrap_or_default()
+            } else {
+                &[]
+            };
+            let args = [vec![template.to_string()], command_args.clone()].concat();
+            handle_config_file_command(command_custom, &args, Some(game_ids))
         }
     }
+    timings::report();
+}
 
-    let args: Vec<String> = env::args().collect();
-    let commands = initialize_commands();
+/// Report a problem with the arguments, or show the help that was asked for
+fn fail_usage(e: clap::Error) -> ! {
+    let (kind, code) = match e.kind() {
+        ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => e.exit(),
+        ErrorKind::InvalidSubcommand => ("unknown_command", exit_code::UNKNOWN_COMMAND),
+        _ => ("usage_error", exit_code::USAGE_ERROR),
+    };
+    if global_options::get().error_format == ErrorFormat::Text {
+        let _ = e.print();
+        std::process::exit(code);
+    }
+    let rendered = e.to_string();
+    let message = rendered.lines().next().unwrap_or_default();
+    fail(kind, message.trim_start_matches("error: "), code);
+}
 
-    if args.len() < 2 {
-        println!("{}", USAGE);
-        std::process::exit(1);
+/// Choose the language of messages from the `language` setting or the locale,
+/// adding any messages from a catalog of that language in the config directory
+fn init_language() {
+    let language = i18n::language(configured_language().as_deref());
+    let catalog_path = config_dir()
+        .join(LOCALES_DIR)
+        .join(format!("{}.ftl", language));
+    let extra = fs::read_to_string(&catalog_path).ok();
+    if extra.is_some() {
+        debug!("Read message catalog: {}", catalog_path.display());
     }
-    let cmd = args[1].as_str();
-    if !commands.contains_key(cmd) {
-        println!("Unrecognized command: {}", cmd);
-        std::process::exit(1);
+    i18n::init(Catalog::new(&language, extra.as_deref()));
+}
+
+/// The `language` setting, which is read before the rest of the config so
+/// that problems with the config can be reported in that language
+fn configured_language() -> Option<String> {
+    let Some(Ok(config)) = load_config() else {
+        return None;
+    };
+    match config.get("settings")?.get("language")? {
+        Value::String(language) => Some(language.to_string()),
+        _ => None,
     }
-    let command = &commands[cmd];
+}
 
-    match command.exec {
-        CommandHandler::Utility(handler) => handle_utility_command(handler, &args),
-        CommandHandler::Config(handler) => handle_config_file_command(handler, &args),
+/// Report an error on stderr, in the format chosen by `--error-format`, and
+/// exit with the given code
+fn fail(kind: &str, message: &str, code: i32) -> ! {
+    timings::report();
+    match global_options::get().error_format {
+        ErrorFormat::Text => eprintln!("{}", message),
+        ErrorFormat::Json => eprintln!(
+            "{}",
+            json::object(&[
+                ("error", json::string(kind)),
+                ("message", json::string(message)),
+                ("exit_code", code.to_string()),
+            ])
+        ),
     }
+    std::process::exit(code);
 }
 
 fn handle_utility_command(handler: UtilityCommandHandler, args: &[String]) {
     if let Err(e) = handler(args) {
         match e {
-            UtilityCommandError::NoEditor => println!("No default editor in $EDITOR"),
+            UtilityCommandError::NoEditor => {
+                fail("no_editor", &tr!("no-editor"), exit_code::FAILURE)
+            }
+            UtilityCommandError::NoSuchCommand(cmd) => fail(
+                "unknown_command",
+                &tr!("unrecognized-command", command = cmd),
+                exit_code::UNKNOWN_COMMAND,
+            ),
+            UtilityCommandError::InvalidArgument(arg) => fail(
+                "invalid_argument",
+                &tr!("invalid-argument", argument = arg),
+                exit_code::USAGE_ERROR,
+            ),
+            UtilityCommandError::ConfigExists(path) => fail(
+                "config_exists",
+                &tr!("config-exists", path = path.display()),
+                exit_code::FAILURE,
+            ),
+            UtilityCommandError::CouldNotWriteConfig(s) => fail(
+                "could_not_write_config",
+                &tr!("could-not-write-config", error = s),
+                exit_code::FAILURE,
+            ),
+            UtilityCommandError::CouldNotSync(s) => fail(
+                "could_not_sync",
+                &tr!("could-not-sync", error = s),
+                exit_code::FAILURE,
+            ),
         }
     }
 }
 
-fn handle_config_file_command(handler: ConfigCommandHandler, args: &[String]) {
-    let config_contents_result = read_config();
-    if config_contents_result.is_err() {
-        println!(
-            "Error: No {} config file found (expected at $HOME/{}/{}/{})",
-            CONFIG_FILE_NAME, CONFIG_DIR, APP_NAME, CONFIG_FILE_NAME
-        );
-        std::process::exit(1);
-    }
-    let config_contents = config_contents_result.unwrap();
-    match parse_config(&config_contents) {
-        Ok(games) => match handler(&games, &args[2..]) {
-            Ok(()) => (),
-            Err(e) => match e {
-                GameError::NoGameId => println!("A game ID is required"),
-                GameError::CouldNotChangeDirectory(dir) => {
-                    println!("Could not change directory to: {}", dir)
-                }
-                GameError::NoSuchGame(game_id) => println!("No such game: {}", game_id),
-                GameError::CommandReturnedFailure(cmd) => println!("Command failed: {}", cmd),
-                GameError::ExecutionFailed => println!("Could not execute game"),
-                GameError::NotInstalled => println!("Game is not installed"),
-                GameError::CouldNotWriteStats(s) => {
-                    println!("Could not write game stats: {}", s)
-                }
-            },
-        },
-        Err(e) => match e {
-            ParseError::MissingName(id) => println!("Game missing name: {}", id),
-            ParseError::MissingCommand(id) => println!("Game missing cmd: {}", id),
-            ParseError::GameNotTable => println!("The 'game' key must correspond to a table"),
-            ParseError::MissingGameTable => println!("A 'game' table is required'"),
-            ParseError::NoSuchDirectoryPrefix(game_id, prefix) => println!(
-                "Game {} has nonexistent directory prefix: {}",
-                game_id, prefix
-            ),
-            ParseError::TomlError(message) => println!("{}", message),
-            ParseError::UnrecognizedOption(option) => {
-                println!("Unrecognized option: {}", option)
+/// Run a command with the games from the config, or only the given ones
+fn handle_config_file_command(
+    handler: ConfigCommandHandler,
+    args: &[String],
+    game_ids: Option<&[String]>,
+) {
+    let config = match load_config() {
+        Some(Ok(config)) => config,
+        Some(Err(e)) => fail(e.kind(), &parse_error_message(e), exit_code::CONFIG_ERROR),
+        None => {
+            let message = tr!(
+                "no-config",
+                file = CONFIG_FILE_NAME,
+                path = config_file_path().display()
+            );
+            fail("no_config", &message, exit_code::CONFIG_ERROR);
+        }
+    };
+    let cached_games = CACHED_GAMES.lock().ok().and_then(|mut games| games.take());
+    let games = match cached_games {
+        Some(cached_games) => {
+            parse_settings_and_directories(config).map(|(settings, directories)| Games {
+                games: cached_games,
+                settings,
+                directories,
+            })
+        }
+        None => parse_and_cache_games(config, game_ids),
+    };
+    match games {
+        Ok(mut games) => {
+            installed::read_installed_state().apply(&mut games.games);
+            if let Err(e) = handler(&games, args) {
+                fail(e.kind(), &game_error_message(&e), e.exit_code());
             }
-        },
+        }
+        Err(e) => fail(e.kind(), &parse_error_message(&e), exit_code::CONFIG_ERROR),
+    }
+}
+
+fn game_error_message(e: &GameError) -> String {
+    match e {
+        GameError::NoGameId => tr!("no-game-id"),
+        GameError::CouldNotChangeDirectory(dir) => tr!("could-not-change-directory", dir = dir),
+        GameError::NoSuchGame(game_id) => tr!("no-such-game", game_id = game_id),
+        GameError::CommandReturnedFailure(cmd) => tr!("command-returned-failure", command = cmd),
+        GameError::ExecutionFailed => tr!("execution-failed"),
+        GameError::NotInstalled => tr!("not-installed"),
+        GameError::CouldNotWriteStats(s) => tr!("could-not-write-stats", error = s),
+        GameError::UnrecognizedSubcommand(s) => tr!("unrecognized-subcommand", subcommand = s),
+        GameError::QueueEmpty => tr!("queue-empty"),
+        GameError::NoSuchQueuedGame(game_id) => tr!("no-such-queued-game", game_id = game_id),
+        GameError::CouldNotWriteQueue(s) => tr!("could-not-write-queue", error = s),
+        GameError::InvalidArgument(arg) => tr!("invalid-argument", argument = arg),
+        GameError::CouldNotWriteSessions(s) => tr!("could-not-write-sessions", error = s),
+        GameError::NoMatchingGames => tr!("no-matching-games"),
+        GameError::CouldNotUpdateConfig(s) => tr!("could-not-update-config", error = s),
+        GameError::NoFpsLog(game_id) => tr!("no-fps-log", game_id = game_id),
+        GameError::NoWinePrefix(game_id) => tr!("no-wine-prefix", game_id = game_id),
+        GameError::NoSuchRunner(name) => tr!("no-such-runner", runner = name),
+        GameError::NoSandbox(sandbox) => tr!("no-sandbox", sandbox = sandbox),
+        GameError::NoUpdateCommand(game_id) => tr!("no-update-command", game_id = game_id),
+        GameError::NoGameDir(game_id) => tr!("no-game-dir", game_id = game_id),
+        GameError::NoManifest(game_id) => tr!("no-manifest", game_id = game_id),
+        GameError::CouldNotWriteManifest(s) => tr!("could-not-write-manifest", error = s),
+        GameError::CouldNotWriteReport(s) => tr!("could-not-write-report", error = s),
+        GameError::CouldNotWriteChart(s) => tr!("could-not-write-chart", error = s),
+        GameError::NoWrappedCommand => tr!("no-wrapped-command"),
+        GameError::CouldNotListen(s) => tr!("could-not-listen", error = s),
+        GameError::TerminalError(s) => tr!("terminal-error", error = s),
+        GameError::NoSuchMenuEntry(s) => tr!("no-such-menu-entry", entry = s),
+        GameError::VerificationFailed(game_id, count) => {
+            tr!("verification-failed", game_id = game_id, count = count)
+        }
+        GameError::CouldNotWriteInstalledState(s) => {
+            tr!("could-not-write-installed-state", error = s)
+        }
+        GameError::CouldNotWriteJournal(s) => tr!("could-not-write-journal", error = s),
+        GameError::CouldNotWriteMilestones(s) => tr!("could-not-write-milestones", error = s),
+        GameError::CouldNotWriteShortcut(s) => tr!("could-not-write-shortcut", error = s),
+        GameError::NoIcon(game_id) => tr!("no-icon", game_id = game_id),
+        GameError::CouldNotWriteIcon(s) => tr!("could-not-write-icon", error = s),
+        GameError::NoSaveDir(game_id) => tr!("no-save-dir", game_id = game_id),
+        GameError::InvalidConfig(e) => parse_error_message(e),
+    }
+}
+
+fn parse_error_message(e: &ParseError) -> String {
+    match e {
+        ParseError::MissingName(id) => tr!("missing-name", game_id = id),
+        ParseError::MissingCommand(id) => tr!("missing-command", game_id = id),
+        ParseError::GameNotTable => tr!("game-not-table"),
+        ParseError::MissingGameTable => tr!("missing-game-table"),
+        ParseError::NoSuchDirectoryPrefix(game_id, prefix) => {
+            tr!(
+                "no-such-directory-prefix",
+                game_id = game_id,
+                prefix = prefix
+            )
+        }
+        ParseError::TomlError(message) => message.to_string(),
+        ParseError::UnrecognizedOption(option) => tr!("unrecognized-option", option = option),
+        ParseError::InvalidOption(game_id, option) => {
+            tr!("invalid-option", game_id = game_id, option = option)
+        }
+        ParseError::InvalidSetting(setting) => tr!("invalid-setting", setting = setting),
+        ParseError::InvalidGameId(game_id) => {
+            tr!("invalid-game-id", game_id = format!("{:?}", game_id))
+        }
+        ParseError::DirectoryCycle(cycle) => tr!("directory-cycle", cycle = cycle),
+        ParseError::NoSuchRequiredGame(game_id, required) => {
+            tr!(
+                "no-such-required-game",
+                game_id = game_id,
+                required = required
+            )
+        }
     }
 }
 
 fn config_dir() -> PathBuf {
-    home_dir().unwrap().join(CONFIG_DIR).join(APP_NAME)
+    if let Some(config_file) = &global_options::get().config_file
+        && let Some(parent) = config_file.parent()
+    {
+        return parent.to_path_buf();
+    }
+    let home = home_dir().unwrap();
+    Platform::current().config_base_dir(&home).join(APP_NAME)
+}
+
+fn config_file_path() -> PathBuf {
+    match &global_options::get().config_file {
+        Some(config_file) => config_file.clone(),
+        None => config_dir().join(CONFIG_FILE_NAME),
+    }
 }
 
 fn read_config() -> std::io::Result<String> {
-    let config_path = config_dir().join(CONFIG_FILE_NAME);
-    fs::read_to_string(&config_path)
+    fs::read_to_string(config_file_path())
+}
+
+/// The overlay file for a host, like `games.deck.toml` next to `games.toml`
+fn host_config_path(host: &str) -> PathBuf {
+    let path = config_file_path();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, host, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, host),
+    };
+    path.with_file_name(name)
+}
+
+fn read_host_overlay(host: &str) -> Result<Option<Table>, ParseError> {
+    let path = host_config_path(host);
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    debug!("Reading host overlay file: {}", path.display());
+    match content.parse::<Table>() {
+        Ok(overlay) => Ok(Some(overlay)),
+        Err(e) => Err(ParseError::TomlError(format!("{}: {}", path.display(), e))),
+    }
+}
+
+/// The config for this machine, with its overlays applied, which is only read
+/// and parsed once however many times it is needed. If the games parsed from
+/// it by an earlier run are cached, this is the rest of the config instead,
+/// and the games are in `CACHED_GAMES`.
+fn load_config() -> Option<&'static Result<Table, ParseError>> {
+    CONFIG
+        .get_or_init(|| {
+            let host = host::name();
+            let key = config_cache_key(host.as_deref());
+            let cache = timings::time("config cache read", || {
+                config_cache::read(&config_cache_path(), &key)
+            });
+            if let Some(cache) = cache
+                && let Ok(rest) = cache.rest.parse::<Table>()
+            {
+                debug!(
+                    "Using the games cached in {}",
+                    config_cache_path().display()
+                );
+                if let Ok(mut cached_games) = CACHED_GAMES.lock() {
+                    *cached_games = Some(cache.games);
+                    return Some(Ok(rest));
+                }
+            }
+            let content = timings::time("config read", read_config).ok()?;
+            debug!("Parsing config file: {}", config_file_path().display());
+            let config = timings::time("config parse", || config_table(&content, host.as_deref()));
+            let has_overlay = host
+                .as_deref()
+                .is_some_and(|host| host_config_path(host).is_file());
+            if let Ok(config) = &config
+                && conflicts::possible(config, has_overlay)
+            {
+                warn_about_conflicts(&content, host.as_deref());
+            }
+            Some(config)
+        })
+        .as_ref()
+}
+
+/// The whole config, with its games even when they were taken from the cache
+/// (once the config has been parsed successfully to get the games)
+fn full_config() -> Cow<'static, Table> {
+    match load_config() {
+        Some(Ok(config)) if config.contains_key("games") => Cow::Borrowed(config),
+        _ => Cow::Owned(
+            read_config()
+                .ok()
+                .and_then(|content| config_table(&content, host::name().as_deref()).ok())
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+fn config_cache_path() -> PathBuf {
+    data_dir().join(config_cache::CACHE_FILE)
+}
+
+/// What the games parsed from the config depend on, apart from the files in
+/// `config_cache_sources`, so that a cache made under other conditions is not
+/// used
+fn config_cache_key(host: Option<&str>) -> String {
+    format!(
+        "{} {:?} {:?} {:?} {:?} {:?} {:?}",
+        env!("CARGO_PKG_VERSION"),
+        config_file_path(),
+        host,
+        condition::detect(Platform::current()),
+        data_dir(),
+        home_dir(),
+        var_os("PATH")
+    )
+}
+
+/// The files and directories that the games parsed from the config depend
+/// on: the config and its overlay, the plugin backends, the directories the
+/// runners are installed in, the directories on the `PATH` (for the sandbox
+/// tools), and the program itself
+fn config_cache_sources(host: Option<&str>, settings: &Settings) -> Vec<PathBuf> {
+    let mut sources = vec![config_file_path()];
+    sources.extend(host.map(host_config_path));
+    let plugins_dir = config_dir().join(backend::PLUGINS_DIR);
+    let mut plugins: Vec<PathBuf> = fs::read_dir(&plugins_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    plugins.sort();
+    sources.push(plugins_dir);
+    sources.extend(plugins);
+    sources.extend(settings.runner_search_dirs.iter().cloned());
+    if let Some(path) = var_os("PATH") {
+        sources.extend(env::split_paths(&path));
+    }
+    sources.extend(env::current_exe());
+    sources
+}
+
+/// Parse the games in the config, caching them for the next run. When only
+/// some games are needed and the others have problems, only those are parsed
+/// (and nothing is cached), so that the problems do not get in the way.
+fn parse_and_cache_games(config: &Table, game_ids: Option<&[String]>) -> Result<Games, ParseError> {
+    match timings::time("games parse", || parse_games(config, None)) {
+        Ok(games) => {
+            let mut rest = config.clone();
+            rest.remove("games");
+            let host = host::name();
+            let result = timings::time("config cache write", || {
+                config_cache::write(
+                    &config_cache_path(),
+                    &config_cache_key(host.as_deref()),
+                    &config_cache_sources(host.as_deref(), &games.settings),
+                    &rest.to_string(),
+                    &games.games,
+                )
+            });
+            if let Err(e) = result {
+                debug!("Could not write the config cache: {}", e);
+            }
+            Ok(games)
+        }
+        Err(_) if game_ids.is_some() => parse_games(config, game_ids),
+        Err(e) => Err(e),
+    }
+}
+
+/// Warn about overlays for the host that would otherwise silently override
+/// each other
+fn warn_about_conflicts(config_content: &str, host: Option<&str>) {
+    let config_path = config_file_path().display().to_string();
+    let overlay_path = host.map(|host| host_config_path(host).display().to_string());
+    let overlay_content = overlay_path
+        .as_ref()
+        .and_then(|p| fs::read_to_string(p).ok());
+    let overlay = match (&overlay_path, &overlay_content) {
+        (Some(path), Some(content)) => Some((path.as_str(), content.as_str())),
+        _ => None,
+    };
+    for conflict in conflicts::find((&config_path, config_content), host, overlay) {
+        warn!("{}", conflict);
+    }
+}
+
+/// The config as a table, with the overlays for the host applied
+fn config_table(config_content: &str, host: Option<&str>) -> Result<Table, ParseError> {
+    let mut config = match config_content.parse::<Table>() {
+        Ok(t) => t,
+        Err(e) => return Err(ParseError::TomlError(e.to_string())),
+    };
+    let overlay = match host {
+        Some(host) => read_host_overlay(host)?,
+        None => None,
+    };
+    host::apply(&mut config, host, overlay);
+    Ok(config)
 }
 
 fn initialize_commands() -> HashMap<&'static str, GameCommand> {
     let cmds = vec![
         GameCommand {
             cmd: "help",
-            args: Vec::new(),
+            args: vec!["COMMAND?"],
             exec: CommandHandler::Utility(command_help),
             desc: "Explain the commands",
         },
         GameCommand {
             cmd: "list",
-            args: vec!["TAG?"],
+            args: vec![
+                "--not-installed?",
+                "--with-stats?",
+                "--long?",
+                "--sort? id|stale|rating",
+                "--group-by? tag|prefix|platform",
+                "--status? STATUS",
+                "--platform? PLATFORM",
+                "--min-hours? HOURS",
+                "--max-hours? HOURS",
+                "--min-rating? RATING",
+                "--played-since? DATE",
+                "--not-played-since? DATE",
+                "TAG?",
+            ],
             exec: CommandHandler::Config(command_list),
             desc: "List games in the format \"game_id - name\"",
         },
+        GameCommand {
+            cmd: "count",
+            args: vec![
+                "--not-installed?",
+                "--status? STATUS",
+                "--platform? PLATFORM",
+                "--min-hours? HOURS",
+                "--max-hours? HOURS",
+                "--min-rating? RATING",
+                "--played-since? DATE",
+                "--not-played-since? DATE",
+                "TAG?",
+            ],
+            exec: CommandHandler::Config(command_count),
+            desc: "Print the number of games that list would list",
+        },
+        GameCommand {
+            cmd: "tree",
+            args: vec![],
+            exec: CommandHandler::Config(tree::command_tree),
+            desc: "Show the games under the directories they are in",
+        },
+        GameCommand {
+            cmd: "installed",
+            args: vec!["GAME_ID", "yes|no|default?"],
+            exec: CommandHandler::Config(installed::command_installed),
+            desc: "Set whether a game is installed on this machine",
+        },
         GameCommand {
             cmd: "play",
-            args: vec!["GAME_ID"],
-            exec: CommandHandler::Config(command_play),
-            desc: "Play a game, specified by its game ID",
+            args: vec!["GAME_ID...?", "--from-menu?", "--no-stats?"],
+            exec: CommandHandler::Games(command_play),
+            desc: "Play games one after another, specified by their game IDs",
         },
         GameCommand {
             cmd: "tags",
-            args: Vec::new(),
+            args: vec!["TAGS...?"],
             exec: CommandHandler::Config(command_tags),
             desc: "List all tags",
         },
         GameCommand {
             cmd: "play-random",
-            args: vec!["TAGS"],
+            args: vec!["TAGS", "--under? TIME"],
             exec: CommandHandler::Config(command_play_random),
             desc: "Play a random game",
         },
+        GameCommand {
+            cmd: "init",
+            args: vec!["--interactive?"],
+            exec: CommandHandler::Utility(init::command_init),
+            desc: "Write a starter config file",
+        },
+        GameCommand {
+            cmd: "template",
+            args: vec!["native|wine|dosbox|scummvm|steam", "GAME_ID?"],
+            exec: CommandHandler::Utility(init::command_template),
+            desc: "Print an example game entry to paste into the config file",
+        },
+        GameCommand {
+            cmd: "scan",
+            args: vec!["DIR?", "--scummvm?", "--print?"],
+            exec: CommandHandler::Config(scan::command_scan),
+            desc: "Look for games in a directory that are not in the config yet",
+        },
         GameCommand {
             cmd: "edit",
             args: Vec::new(),
@@ -197,10 +811,214 @@ fn initialize_commands() -> HashMap<&'static str, GameCommand> {
         },
         GameCommand {
             cmd: "stats",
-            args: vec!["GAME_ID"],
+            args: vec![
+                "GAME_ID...",
+                "--distribution?",
+                "--by-launcher?",
+                "edit GAME_ID --set-playtime? TIME --set-last-played? DATE",
+                "undo GAME_ID?",
+            ],
             exec: CommandHandler::Config(command_stats),
             desc: "Show game statistics",
         },
+        GameCommand {
+            cmd: "info",
+            args: vec!["GAME_ID"],
+            exec: CommandHandler::Config(command_info),
+            desc: "Show a game's details and statistics",
+        },
+        GameCommand {
+            cmd: "queue",
+            args: vec!["add GAME_ID...", "list", "next", "clear"],
+            exec: CommandHandler::Config(queue::command_queue),
+            desc: "Manage the play queue",
+        },
+        GameCommand {
+            cmd: "sessions",
+            args: vec!["GAME_ID...", "--launch-config?"],
+            exec: CommandHandler::Config(sessions::command_sessions),
+            desc: "List recorded play sessions",
+        },
+        GameCommand {
+            cmd: "log-session",
+            args: vec!["GAME_ID", "TIME", "--date? DATE"],
+            exec: CommandHandler::Config(sessions::command_log_session),
+            desc: "Record a session played somewhere else",
+        },
+        GameCommand {
+            cmd: "journal",
+            args: vec!["GAME_ID", "TEXT...?"],
+            exec: CommandHandler::Config(journal::command_journal),
+            desc: "Add an entry to a game's journal, or show the journal",
+        },
+        GameCommand {
+            cmd: "milestone",
+            args: vec!["add GAME_ID TEXT...", "list GAME_ID"],
+            exec: CommandHandler::Config(milestones::command_milestone),
+            desc: "Record and list the milestones reached in a game",
+        },
+        GameCommand {
+            cmd: "sync",
+            args: vec!["DIR|RCLONE_REMOTE", "--play-time? sum|max"],
+            exec: CommandHandler::Utility(sync::command_sync),
+            desc: "Merge the stats and session log with another copy, and update both",
+        },
+        GameCommand {
+            cmd: "backlog",
+            args: vec!["HOURS?"],
+            exec: CommandHandler::Config(backlog::command_backlog),
+            desc: "List installed games played for less than the given hours",
+        },
+        GameCommand {
+            cmd: "suggest",
+            args: vec!["COUNT?", "--finishable? HOURS"],
+            exec: CommandHandler::Config(suggest::command_suggest),
+            desc: "Suggest a few games to play next",
+        },
+        GameCommand {
+            cmd: "nudge",
+            args: vec!["DAYS?", "--notify?"],
+            exec: CommandHandler::Config(nudge::command_nudge),
+            desc: "List installed games that have not been played for a while",
+        },
+        GameCommand {
+            cmd: "bench",
+            args: vec!["GAME_ID", "SECONDS?"],
+            exec: CommandHandler::Config(perf::command_bench),
+            desc: "Run a game with FPS logging and summarize the frame rate",
+        },
+        GameCommand {
+            cmd: "tricks",
+            args: vec!["GAME_ID", "VERBS..."],
+            exec: CommandHandler::Config(wine::command_tricks),
+            desc: "Run winetricks (or protontricks) against a game's prefix",
+        },
+        GameCommand {
+            cmd: "winecfg",
+            args: vec!["GAME_ID"],
+            exec: CommandHandler::Config(wine::command_winecfg),
+            desc: "Run winecfg in a game's prefix",
+        },
+        GameCommand {
+            cmd: "regedit",
+            args: vec!["GAME_ID", "REG_FILE?"],
+            exec: CommandHandler::Config(wine::command_regedit),
+            desc: "Run the registry editor in a game's prefix, or import a .reg file",
+        },
+        GameCommand {
+            cmd: "cache",
+            args: vec!["list GAME_ID?", "clean GAME_ID?", "clean --all?"],
+            exec: CommandHandler::Config(cache::command_cache),
+            desc: "List or remove games' shader caches",
+        },
+        GameCommand {
+            cmd: "prefixes",
+            args: Vec::new(),
+            exec: CommandHandler::Config(wine::command_prefixes),
+            desc: "List the wine prefixes used by games",
+        },
+        GameCommand {
+            cmd: "runners",
+            args: vec!["list", "use RUNNER"],
+            exec: CommandHandler::Config(runners::command_runners),
+            desc: "List installed wine/Proton builds or choose the default",
+        },
+        GameCommand {
+            cmd: "update",
+            args: vec!["GAME_ID..."],
+            exec: CommandHandler::Config(command_update),
+            desc: "Run games' update commands",
+        },
+        GameCommand {
+            cmd: "verify",
+            args: vec!["--init? GAME_ID"],
+            exec: CommandHandler::Config(manifest::command_verify),
+            desc: "Check a game's files against its manifest of file hashes",
+        },
+        GameCommand {
+            cmd: "check",
+            args: Vec::new(),
+            exec: CommandHandler::Config(lint::command_check),
+            desc: "Validate the config file and warn about likely mistakes",
+        },
+        GameCommand {
+            cmd: "overview",
+            args: Vec::new(),
+            exec: CommandHandler::Config(summary::command_overview),
+            desc: "Summarize the whole library",
+        },
+        GameCommand {
+            cmd: "perf",
+            args: vec!["GAME_ID"],
+            exec: CommandHandler::Config(perf::command_perf),
+            desc: "Summarize the frame rate from a game's latest FPS log",
+        },
+        GameCommand {
+            cmd: "chart",
+            args: vec!["playtime|sessions", "GAME_ID?", "-o? FILE"],
+            exec: CommandHandler::Config(chart::command_chart),
+            desc: "Draw a chart of play time or sessions as a PNG image",
+        },
+        GameCommand {
+            cmd: "report",
+            args: vec!["--format? markdown | --html DIR"],
+            exec: CommandHandler::Config(report::command_report),
+            desc: "Write a report of the library and play statistics",
+        },
+        GameCommand {
+            cmd: "shortcut",
+            args: vec!["GAME_ID", "--output? DIR"],
+            exec: CommandHandler::Config(shortcut::command_shortcut),
+            desc: "Write a desktop shortcut that plays a game",
+        },
+        GameCommand {
+            cmd: "saves",
+            args: vec!["detect GAME_ID --write?"],
+            exec: CommandHandler::Config(saves::command_saves),
+            desc: "Guess where a game keeps its saves, for its save_dir",
+        },
+        GameCommand {
+            cmd: "icon",
+            args: vec!["extract GAME_ID"],
+            exec: CommandHandler::Config(icon::command_icon),
+            desc: "Extract the icon from a wine game's .exe",
+        },
+        GameCommand {
+            cmd: "steam-wrap",
+            args: vec!["GAME_ID"],
+            exec: CommandHandler::Config(command_steam_wrap),
+            desc: "Print Steam launch options that run a game through wrap-exec",
+        },
+        GameCommand {
+            cmd: "wrap-exec",
+            args: vec!["GAME_ID", "--", "COMMAND..."],
+            exec: CommandHandler::Config(command_wrap_exec),
+            desc: "Run a command as the game, with its env and stats",
+        },
+        GameCommand {
+            cmd: "tui",
+            args: Vec::new(),
+            exec: CommandHandler::Config(tui::command_tui),
+            desc: "Browse the library, stats and sessions in a dashboard",
+        },
+        GameCommand {
+            cmd: "watch",
+            args: vec!["--listen? ADDRESS", "--api?"],
+            exec: CommandHandler::Config(watch::command_watch),
+            desc: "Record sessions for games that were started outside of game",
+        },
+        GameCommand {
+            cmd: "menu",
+            args: vec!["--dmenu|--rofi|--json", "--play?", "ENTRY?"],
+            exec: CommandHandler::Config(menu::command_menu),
+            desc: "List installed games for dmenu or rofi, and play the chosen one",
+        },
+        GameCommand {
+            cmd: "wrapped",
+            args: vec!["YEAR?"],
+            exec: CommandHandler::Config(summary::command_wrapped),
+            desc: "Summarize a year of play sessions",
+        },
     ];
     let mut commands: HashMap<&str, GameCommand> = HashMap::new();
     for c in cmds.into_iter() {
@@ -209,48 +1027,373 @@ fn initialize_commands() -> HashMap<&'static str, GameCommand> {
     commands
 }
 
-fn command_help(_args: &[String]) -> Result<(), UtilityCommandError> {
-    let commands_hash = initialize_commands();
-    let mut commands: Vec<&GameCommand> = commands_hash.values().collect();
-    commands.sort_by(|a, b| a.cmd.cmp(b.cmd));
-
-    println!("{}", USAGE);
-    println!();
-    println!("Commands: ");
-    for c in commands.iter() {
-        let args_str = if c.args.is_empty() {
-            String::new()
+/// Add the commands from the `[commands]` table of the config
+fn add_custom_commands(commands: &mut HashMap<&'static str, GameCommand>) {
+    // Parsed once, so that problems with them are only reported once
+    let custom_commands = CUSTOM_COMMANDS.get_or_init(|| match load_config() {
+        Some(Ok(config)) => custom_command::parse(config, |name| commands.contains_key(name)),
+        _ => Vec::new(),
+    });
+    for command in custom_commands.iter() {
+        let args = if custom_command::uses_game(command.template) {
+            vec!["GAME_ID", "ARGS?"]
         } else {
-            format!(" [{}]", c.args.join("|"))
+            vec!["ARGS?"]
         };
-        println!("\t{}{} - {}", c.cmd, args_str, c.desc);
+        commands.insert(
+            command.name,
+            GameCommand {
+                cmd: command.name,
+                args,
+                exec: CommandHandler::Custom(command.template),
+                desc: command.template,
+            },
+        );
     }
-    Ok(())
 }
 
-fn command_list<'a>(games: &Games, args: &[String]) -> Result<(), GameError<'a>> {
-    for game in list_games(games, args) {
-        println!("{}", game);
-    }
-    Ok(())
+fn command_help(args: &[String]) -> Result<(), UtilityCommandError> {
+    let mut commands = initialize_commands();
+    add_custom_commands(&mut commands);
+    let mut cli = cli::command(&commands);
+    // Global options are only added to each command once it is built
+    cli.build();
+    let help = match args.first() {
+        Some(cmd) => match cli.find_subcommand_mut(cmd) {
+            Some(subcommand) => subcommand.render_help(),
+            None => return Err(UtilityCommandError::NoSuchCommand(cmd.to_string())),
+        },
+        None => cli.render_help(),
+    };
+    out!("{}", help);
+    Ok(())
+}
+
+/// Run a command from the `[commands]` table, whose command line is the first
+/// argument, followed by the game ID if it uses the game's fields
+fn command_custom<'a>(games: &'a Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let template = &args[0];
+    let (game, args) = if custom_command::uses_game(template) {
+        let Some(game_id) = args.get(1) else {
+            return Err(GameError::NoGameId);
+        };
+        match games.find(game_id) {
+            Some(game) => (Some(game), &args[2..]),
+            None => return Err(GameError::NoSuchGame(game_id)),
+        }
+    } else {
+        (None, &args[1..])
+    };
+    let Some(command_line) = custom_command::render(template, game, args) else {
+        let game = game.expect("Only a game's field can be missing");
+        return Err(GameError::NoGameDir(game.id.clone()));
+    };
+    info!("Running command: {:?}", command_line);
+    let mut process = std::process::Command::new(&command_line[0]);
+    process.args(&command_line[1..]);
+    if let Some(game) = game {
+        process.envs(game.env.iter());
+    }
+    match process.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(_) => Err(GameError::CommandReturnedFailure(command_line.join(" "))),
+        Err(_) => Err(GameError::ExecutionFailed),
+    }
+}
+
+fn command_list<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let (options, tags) = ListOptions::parse(args)?;
+    let all_stats = if options.needs_stats() {
+        read_all_stats()
+    } else {
+        HashMap::new()
+    };
+    let Some(group_by) = options.group_by else {
+        for game in list_games(games, &tags, &options, &all_stats) {
+            out!("{}", game);
+        }
+        return Ok(());
+    };
+    let listed = listed_games(games, &tags, &options, &all_stats);
+    for (i, (group, games_in_group)) in group_games(&listed, group_by).iter().enumerate() {
+        if i > 0 {
+            out!();
+        }
+        out!("{}:", group);
+        for game in games_in_group.iter() {
+            out!("  {}", format_listed_game(game, &options, &all_stats));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct ListOptions {
+    not_installed: bool,
+    with_stats: bool,
+    sort: ListSort,
+    group_by: Option<ListGroup>,
+    status: Option<String>,
+    /// How the games are launched, as in `stats --by-launcher`
+    platform: Option<String>,
+    min_hours: Option<f64>,
+    max_hours: Option<f64>,
+    min_rating: Option<f64>,
+    /// Show each game's rating as well as its stats
+    long: bool,
+    played_since: Option<UtcDateTime>,
+    not_played_since: Option<UtcDateTime>,
+}
+
+/// The order games are listed in
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum ListSort {
+    #[default]
+    Id,
+    /// Never played first, then the ones played longest ago
+    Stale,
+    /// The highest rated first, and the ones without a rating last
+    Rating,
+}
+
+/// What games are listed under headings by
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ListGroup {
+    Tag,
+    /// The shared directory the game is in
+    Prefix,
+    /// How the game is launched, like `wine` or `dosbox`
+    Platform,
+}
+
+impl ListOptions {
+    /// Whether the stats are needed to show, sort, or filter the games
+    fn needs_stats(&self) -> bool {
+        self.with_stats
+            || self.long
+            || self.sort == ListSort::Stale
+            || self.min_hours.is_some()
+            || self.max_hours.is_some()
+            || self.played_since.is_some()
+            || self.not_played_since.is_some()
+    }
+
+    /// Separate the `list` flags from the tags
+    fn parse(args: &[String]) -> Result<(ListOptions, Vec<String>), GameError<'_>> {
+        let mut options = ListOptions::default();
+        let mut tags = Vec::new();
+        let now = UtcDateTime::now();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--not-installed" => options.not_installed = true,
+                "--with-stats" => options.with_stats = true,
+                "--long" => options.long = true,
+                "--sort" => {
+                    options.sort = match args.next().map(|s| s.as_str()) {
+                        Some("id") => ListSort::Id,
+                        Some("stale") => ListSort::Stale,
+                        Some("rating") => ListSort::Rating,
+                        _ => return Err(GameError::InvalidArgument(arg)),
+                    }
+                }
+                "--status" | "--platform" => {
+                    let Some(value) = args.next() else {
+                        return Err(GameError::InvalidArgument(arg));
+                    };
+                    match arg.as_str() {
+                        "--status" => options.status = Some(value.to_string()),
+                        _ => options.platform = Some(value.to_string()),
+                    }
+                }
+                "--min-rating" => match args.next().and_then(|s| s.parse::<f64>().ok()) {
+                    Some(rating) => options.min_rating = Some(rating),
+                    None => return Err(GameError::InvalidArgument(arg)),
+                },
+                "--min-hours" | "--max-hours" => {
+                    let Some(hours) = args.next().and_then(|s| suggest::parse_hours(s)) else {
+                        return Err(GameError::InvalidArgument(arg));
+                    };
+                    match arg.as_str() {
+                        "--min-hours" => options.min_hours = Some(hours),
+                        _ => options.max_hours = Some(hours),
+                    }
+                }
+                "--played-since" | "--not-played-since" => {
+                    let since = args.next().and_then(|s| stats::parse_since(s, now));
+                    let Some(since) = since else {
+                        return Err(GameError::InvalidArgument(arg));
+                    };
+                    match arg.as_str() {
+                        "--played-since" => options.played_since = Some(since),
+                        _ => options.not_played_since = Some(since),
+                    }
+                }
+                "--group-by" => {
+                    options.group_by = match args.next().map(|s| s.as_str()) {
+                        Some("tag") => Some(ListGroup::Tag),
+                        Some("prefix") => Some(ListGroup::Prefix),
+                        Some("platform") => Some(ListGroup::Platform),
+                        _ => return Err(GameError::InvalidArgument(arg)),
+                    }
+                }
+                _ if arg.starts_with("--") => return Err(GameError::InvalidArgument(arg)),
+                _ => tags.push(arg.to_string()),
+            }
+        }
+        Ok((options, tags))
+    }
+}
+
+fn list_games(
+    games: &Games,
+    tags: &[String],
+    options: &ListOptions,
+    all_stats: &HashMap<String, GameStats>,
+) -> Vec<String> {
+    listed_games(games, tags, options, all_stats)
+        .into_iter()
+        .map(|game| format_listed_game(game, options, all_stats))
+        .collect()
 }
 
-fn list_games(games: &Games, args: &[String]) -> Vec<String> {
+/// The games to list, in order
+fn listed_games<'g>(
+    games: &'g Games,
+    tags: &[String],
+    options: &ListOptions,
+    all_stats: &HashMap<String, GameStats>,
+) -> Vec<&'g Game> {
     let mut game_ids: Vec<&String> = games.games.keys().collect();
     game_ids.sort();
-
-    let tags = &args[0..];
+    if options.sort == ListSort::Stale {
+        // The sort is stable, so games never played stay in ID order
+        game_ids.sort_by_key(|game_id| all_stats.get(*game_id).map(|s| s.last_played_time()));
+    }
+    if options.sort == ListSort::Rating {
+        let rating = |game_id: &str| games.find(game_id).and_then(|game| game.rating);
+        // Unrated games go last, in ID order
+        game_ids.sort_by(|a, b| {
+            let (a, b) = (rating(a), rating(b));
+            b.is_some()
+                .cmp(&a.is_some())
+                .then(b.unwrap_or(0.0).total_cmp(&a.unwrap_or(0.0)))
+        });
+    }
 
     // List all games having any of the given tags
     game_ids
         .iter()
         .map(|game_id| games.find(game_id).unwrap())
-        .filter(|game| game.is_installed())
-        .filter(|game| args.is_empty() || game_matches_tags(game, tags))
-        .map(|game| game.format())
+        .filter(|game| game.is_installed() != options.not_installed)
+        .filter(|game| tags.is_empty() || game_matches_tags(game, tags))
+        .filter(|game| {
+            options
+                .status
+                .as_ref()
+                .is_none_or(|status| game.status.as_ref() == Some(status))
+        })
+        .filter(|game| {
+            options
+                .platform
+                .as_ref()
+                .is_none_or(|platform| summary::launcher(game) == platform)
+        })
+        .filter(|game| {
+            options
+                .min_rating
+                .is_none_or(|min| game.rating.is_some_and(|rating| rating >= min))
+        })
+        .filter(|game| {
+            // Games never played count as played for no time at all
+            let seconds = all_stats.get(&game.id).map_or(0, |s| s.play_time_seconds());
+            let hours = seconds as f64 / (60.0 * 60.0);
+            options.min_hours.is_none_or(|min| hours >= min)
+                && options.max_hours.is_none_or(|max| hours <= max)
+        })
+        .filter(|game| {
+            let last_played = all_stats.get(&game.id).map(|s| s.last_played_time());
+            options
+                .played_since
+                .is_none_or(|since| last_played.is_some_and(|time| time >= since))
+                && options
+                    .not_played_since
+                    .is_none_or(|since| last_played.is_none_or(|time| time < since))
+        })
         .collect()
 }
 
+/// Print how many games `list` would list with the same arguments
+fn command_count<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let (options, tags) = ListOptions::parse(args)?;
+    let all_stats = if options.needs_stats() {
+        read_all_stats()
+    } else {
+        HashMap::new()
+    };
+    let count = listed_games(games, &tags, &options, &all_stats).len();
+    out!("{}", count);
+    Ok(())
+}
+
+fn format_listed_game(
+    game: &Game,
+    options: &ListOptions,
+    all_stats: &HashMap<String, GameStats>,
+) -> String {
+    if !options.with_stats && !options.long {
+        return game.format();
+    }
+    let played = match all_stats.get(&game.id) {
+        Some(stats) => format!(
+            "played {}, last {}",
+            stats.format_play_time(),
+            stats.format_last_played_time()
+        ),
+        None => "never played".to_string(),
+    };
+    if !options.long {
+        return format!("{} ({})", game.format(), played);
+    }
+    let rating = match game.rating {
+        Some(rating) => format!("rated {}", rating),
+        None => "not rated".to_string(),
+    };
+    format!("{} ({}, {})", game.format(), rating, played)
+}
+
+/// The games under each heading, in order of the headings, with the games
+/// that have no tag or prefix last; a game with several tags is under each
+fn group_games<'g>(games: &[&'g Game], group_by: ListGroup) -> Vec<(String, Vec<&'g Game>)> {
+    let mut groups: Vec<(String, Vec<&Game>)> = Vec::new();
+    let mut ungrouped: Vec<&Game> = Vec::new();
+    for game in games.iter() {
+        let names = match group_by {
+            ListGroup::Tag => game.tags.clone(),
+            ListGroup::Prefix => game.dir_prefix.iter().cloned().collect(),
+            ListGroup::Platform => vec![summary::launcher(game).to_string()],
+        };
+        if names.is_empty() {
+            ungrouped.push(game);
+        }
+        for name in names {
+            match groups.iter_mut().find(|(group, _)| *group == name) {
+                Some((_, group)) => group.push(game),
+                None => groups.push((name, vec![game])),
+            }
+        }
+    }
+    groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+    if !ungrouped.is_empty() {
+        let heading = match group_by {
+            ListGroup::Tag => "(no tags)",
+            _ => "(none)",
+        };
+        groups.push((heading.to_string(), ungrouped));
+    }
+    groups
+}
+
 fn game_matches_tags(game: &Game, tag_groups_raw: &[String]) -> bool {
     let tags: Vec<&str> = game.tags.iter().map(|t| t.as_str()).collect();
     tag_groups_raw
@@ -259,149 +1402,724 @@ fn game_matches_tags(game: &Game, tag_groups_raw: &[String]) -> bool {
         .any(|tag_group| tag_group.matches(&tags) || tag_group.matches(&[game.id.as_str()]))
 }
 
-fn command_tags<'a>(games: &Games, _args: &[String]) -> Result<(), GameError<'a>> {
-    let game_ids: Vec<&String> = games.games.keys().collect();
-    let tags = game_ids
-        .iter()
-        .flat_map(|game_id| {
-            let game = games.find(game_id).unwrap();
-            game.tags.iter().cloned()
-        })
+fn command_tags<'a>(games: &Games, args: &[String]) -> Result<(), GameError<'a>> {
+    for tag in tags_among(games, args) {
+        out!("{}", tag);
+    }
+    Ok(())
+}
+
+/// The tags of the games matching the given tag groups (or of every game if
+/// there are none), leaving out the tags the groups already ask for
+fn tags_among(games: &Games, tag_groups: &[String]) -> Vec<String> {
+    let mut required: HashSet<String> = HashSet::new();
+    for group in tag_groups.iter().map(|g| TagGroup::parse(g)) {
+        required.extend(group.required().map(str::to_string));
+    }
+    let tags = games
+        .games
+        .values()
+        .filter(|game| tag_groups.is_empty() || game_matches_tags(game, tag_groups))
+        .flat_map(|game| game.tags.iter().cloned())
+        .filter(|tag| !required.contains(tag))
         .collect::<HashSet<String>>();
     let mut tags = tags.into_iter().collect::<Vec<String>>();
     tags.sort();
-    let tags = tags;
-    for tag in tags.iter() {
-        println!("{}", tag);
-    }
-    Ok(())
+    tags
 }
 
 fn command_play<'a>(games: &'a Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    // Nothing is recorded for a guest or a quick test
+    let no_stats = args.iter().any(|arg| arg == "--no-stats");
+    let args: Vec<&'a String> = args.iter().filter(|arg| *arg != "--no-stats").collect();
+    if args.len() == 1 && args[0] == "--from-menu" {
+        return menu::play_from_menu(games);
+    }
     if args.is_empty() {
-        return Err(GameError::NoGameId);
+        if !std::io::stdin().is_terminal() {
+            return Err(GameError::NoGameId);
+        }
+        return match select_game(games)? {
+            Some(game) => play_game_with_stats(games, game, game.track_stats && !no_stats),
+            None => Ok(()),
+        };
+    }
+    // Make sure every game exists before starting any of them
+    let mut games_to_play = Vec::new();
+    for game_id in args.into_iter() {
+        match games.find(game_id) {
+            Some(game) => games_to_play.push(game),
+            None => return Err(GameError::NoSuchGame(game_id)),
+        }
+    }
+    for game in games_to_play.into_iter() {
+        play_game_with_stats(games, game, game.track_stats && !no_stats)?;
     }
-    let game_id = &args[0];
-    match games.find(game_id) {
-        Some(game) => play_game(game),
-        None => Err(GameError::NoSuchGame(game_id)),
+    Ok(())
+}
+
+/// Choose an installed game with the fuzzy selector
+fn select_game<'a>(games: &'a Games) -> Result<Option<&'a Game>, GameError<'a>> {
+    let installed = menu::installed_games(games);
+    let items: Vec<String> = installed.iter().map(|game| menu::line(game)).collect();
+    match tui::select(&items) {
+        Ok(selected) => Ok(selected.map(|i| installed[i])),
+        Err(e) => Err(GameError::TerminalError(e.to_string())),
     }
 }
 
 fn command_play_random<'a>(games: &'a Games, args: &'a [String]) -> Result<(), GameError<'a>> {
-    let game = games.random(args);
-    play_game(game)
+    let mut tags = Vec::new();
+    let mut under = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--under" {
+            match args.next().and_then(|time| suggest::parse_hours(time)) {
+                Some(hours) => under = Some(hours),
+                None => return Err(GameError::InvalidArgument(arg)),
+            }
+        } else {
+            tags.push(arg.to_string());
+        }
+    }
+    let game = match games.random(&tags, under) {
+        Some(game) => game,
+        None => return Err(GameError::NoMatchingGames),
+    };
+    play_game(games, game)?;
+    if games.settings.roulette_prompt {
+        prompt_for_verdict(game)?;
+    }
+    Ok(())
+}
+
+/// Ask whether a randomly chosen game should be kept in the rotation, dropped
+/// from it, or marked as a favorite
+fn prompt_for_verdict<'a>(game: &Game) -> Result<(), GameError<'a>> {
+    if global_options::get().quiet || !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+    loop {
+        print!("Keep, drop, or favorite {}? [k/d/f] ", game.name);
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).is_err() {
+            return Ok(());
+        }
+        let (key, value) = match answer.trim().to_lowercase().as_str() {
+            "" | "k" | "keep" => return Ok(()),
+            "d" | "drop" => ("status", toml_edit::Value::from(STATUS_DROPPED)),
+            "f" | "favorite" => ("favorite", toml_edit::Value::from(true)),
+            _ => continue,
+        };
+        debug!(
+            "Setting {} for {} in {}",
+            key,
+            game.id,
+            config_file_path().display()
+        );
+        return match config_edit::set_game_option(&config_file_path(), &game.id, key, value) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GameError::CouldNotUpdateConfig(e)),
+        };
+    }
 }
 
-fn play_game<'a>(game: &'a Game) -> Result<(), GameError<'a>> {
+fn play_game<'a>(games: &Games, game: &'a Game) -> Result<(), GameError<'a>> {
+    play_game_with_stats(games, game, game.track_stats)
+}
+
+/// Play the game, and record the session in the stats unless told not to
+fn play_game_with_stats<'a>(
+    games: &Games,
+    game: &'a Game,
+    track_stats: bool,
+) -> Result<(), GameError<'a>> {
+    // The game is run from its own directory, so the hooks are found first
+    let hooks_dir = hooks_dir();
+    if let Err(e) = run_hook(&hooks_dir, Hook::PrePlay, game, &[]) {
+        return Err(GameError::CommandReturnedFailure(e));
+    }
     let start_time = UtcDateTime::now();
-    match game.run() {
-        Ok(_) => {
-            let end_time = UtcDateTime::now();
-            let duration = end_time - start_time;
-            let hours = duration.whole_hours();
-            let minutes = duration.whole_minutes() - hours * 60;
-            let seconds = duration.whole_seconds() - minutes * 60 - hours * 60 * 60;
-
-            let play_time = duration.whole_seconds() as u32;
-
-            println!("Game: {} ({})", game.name, game.id);
-            println!(
-                "Play Time: {}h{}m{}s ({}sec)",
-                hours, minutes, seconds, play_time,
-            );
+    let session_start = [(
+        "GAME_RS_SESSION_START",
+        start_time.unix_timestamp().to_string(),
+    )];
+    if let Err(e) = run_hook(&hooks_dir, Hook::PreSession, game, &session_start) {
+        warn!("Hook failed: {}", e);
+    }
+    let published_start = games
+        .settings
+        .mqtt
+        .as_ref()
+        .map(|mqtt| mqtt.publish("start", mqtt_event(game, None)));
+    let required = start_required_games(games, game);
+    let settings = &games.settings;
+    let idle_tracker = (track_stats
+        && (settings.idle_after_minutes.is_some() || settings.pause_file.is_some()))
+    .then(|| IdleTracker::start(settings.idle_after_minutes, settings.pause_file.clone()));
+    let result = game.run();
+    let idle_seconds = idle_tracker.map_or(0, |tracker| tracker.stop());
+    if game.stop_required {
+        for process in required {
+            process.stop();
+        }
+    }
+    let wall_time = (UtcDateTime::now() - start_time).whole_seconds() as u32;
+    // Time away from the keyboard is not play time
+    let play_time = wall_time.saturating_sub(idle_seconds);
+    let duration = time::Duration::seconds(play_time as i64);
+    drop(published_start);
+    let _published_stop = games
+        .settings
+        .mqtt
+        .as_ref()
+        .map(|mqtt| mqtt.publish("stop", mqtt_event(game, Some(play_time))));
+    let exit = [
+        ("GAME_RS_PLAY_TIME_SECONDS", play_time.to_string()),
+        ("GAME_RS_SUCCESS", result.is_ok().to_string()),
+    ];
+    if let Err(e) = run_hook(&hooks_dir, Hook::PostPlay, game, &exit) {
+        warn!("Hook failed: {}", e);
+    }
+    result?;
 
-            // Update the stats file
-            let mut all_stats: Vec<GameStats> = Vec::new();
-            let mut found = false;
-            if let Ok(content) = read_stats() {
-                for line in content.lines() {
-                    if line.is_empty() {
-                        continue;
-                    }
-                    let mut stats = GameStats::from_tsv(line);
-                    if stats.id() == game.id {
-                        stats.add_time(play_time);
-                        stats.update_last_played_time(start_time);
-                        found = true;
-                    }
-                    all_stats.push(stats);
+    let hours = duration.whole_hours();
+    let minutes = duration.whole_minutes() - hours * 60;
+    let seconds = duration.whole_seconds() - minutes * 60 - hours * 60 * 60;
+
+    out!(
+        "{}",
+        tr!("session-game", name = game.name, game_id = game.id)
+    );
+    out!(
+        "{}",
+        tr!(
+            "session-play-time",
+            hours = hours,
+            minutes = minutes,
+            seconds = seconds,
+            total = play_time,
+        )
+    );
+    if idle_seconds > 0 {
+        out!(
+            "{}",
+            tr!(
+                "session-idle-time",
+                idle = stats::format_play_time(wall_time - play_time)
+            )
+        );
+    }
+
+    if !track_stats {
+        info!("Not recording the session of {}", game.id);
+        return Ok(());
+    }
+    let note = if games.settings.session_notes {
+        prompt_for_note()
+    } else {
+        String::new()
+    };
+    let mut session = Session::new(game.id.clone(), start_time, play_time, note);
+    if let Some(version) = &game.version {
+        session = session.with_version(version);
+    }
+    session = session
+        .with_wall_time(wall_time)
+        .with_config(&game.format_launch_config());
+    record_session(&session)?;
+    run_session_hook(&hooks_dir, game, &session);
+    Ok(())
+}
+
+/// The payload of an MQTT event about the game, with the session's play time
+/// once it is over
+fn mqtt_event(game: &Game, play_time: Option<u32>) -> String {
+    let mut fields = vec![
+        ("game", json::string(&game.id)),
+        ("name", json::string(&game.name)),
+    ];
+    if let Some(play_time) = play_time {
+        fields.push(("play_time_seconds", play_time.to_string()));
+    }
+    json::object(&fields)
+}
+
+/// Where the hook scripts are, as an absolute path so that they can still be
+/// found once a game has changed directory
+fn hooks_dir() -> PathBuf {
+    let dir = config_dir().join(hooks::HOOKS_DIR);
+    std::path::absolute(&dir).unwrap_or(dir)
+}
+
+/// Run a hook script for the game, if there is one
+fn run_hook(
+    hooks_dir: &Path,
+    hook: Hook,
+    game: &Game,
+    extra: &[(&str, String)],
+) -> Result<(), String> {
+    hooks::run(hooks_dir, hook, &hooks::env(hook, game, extra))
+}
+
+/// Run the `post-session` hook for a session that has been recorded
+fn run_session_hook(hooks_dir: &Path, game: &Game, session: &Session) {
+    let extra = [
+        (
+            "GAME_RS_SESSION_START",
+            session.start_time().unix_timestamp().to_string(),
+        ),
+        (
+            "GAME_RS_PLAY_TIME_SECONDS",
+            session.play_time_seconds().to_string(),
+        ),
+        (
+            "GAME_RS_WALL_TIME_SECONDS",
+            session.wall_time_seconds().to_string(),
+        ),
+    ];
+    if let Err(e) = run_hook(hooks_dir, Hook::PostSession, game, &extra) {
+        warn!("Hook failed: {}", e);
+    }
+}
+
+/// Start the games that the game requires, unless they are already running
+fn start_required_games(games: &Games, game: &Game) -> Vec<BackgroundProcess> {
+    if game.requires.is_empty() {
+        return Vec::new();
+    }
+    let processes = watch::list_processes();
+    let mut started = Vec::new();
+    for required in game.requires.iter().filter_map(|id| games.find(id)) {
+        if watch::is_running(required, &processes) {
+            debug!("Required game is already running: {}", required.id);
+            continue;
+        }
+        out!("Starting {} ({})", required.name, required.id);
+        started.extend(BackgroundProcess::spawn_game(required));
+    }
+    started
+}
+
+/// Ask for a one-line note about the session that just ended, if there is
+/// someone to ask
+fn prompt_for_note() -> String {
+    if global_options::get().quiet || !std::io::stdin().is_terminal() {
+        return String::new();
+    }
+    print!("Note (leave blank to skip): ");
+    let _ = std::io::stdout().flush();
+    let mut note = String::new();
+    match std::io::stdin().read_line(&mut note) {
+        Ok(_) => note.trim().to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Add a session to the game's stats and to the session log
+fn record_session<'a>(session: &Session) -> Result<(), GameError<'a>> {
+    let game_id = session.id();
+    let play_time = session.play_time_seconds();
+    let start_time = session.start_time();
+
+    // Update the stats file
+    let mut all_stats: Vec<GameStats> = Vec::new();
+    let mut found = false;
+    debug!("Reading stats file: {}", stats_file_path().display());
+    if let Ok(content) = read_stats() {
+        for mut stats in stats::parse_stats_file(&content) {
+            if stats.id() == game_id {
+                trace!("Adding {}sec to existing stats for {}", play_time, game_id);
+                stats.add_time(play_time);
+                // A session logged afterwards may be older than the last one
+                if start_time > stats.last_played_time() {
+                    stats.update_last_played_time(start_time);
                 }
+                found = true;
             }
+            all_stats.push(stats);
+        }
+    }
 
-            if !found {
-                let stats = GameStats::new(game.id.clone(), play_time, start_time);
-                all_stats.push(stats);
-            }
+    if !found {
+        trace!("Creating new stats for {}", game_id);
+        let stats = GameStats::new(game_id.to_string(), play_time, start_time);
+        all_stats.push(stats);
+    }
 
-            let mut updated_stats = all_stats
-                .iter()
-                .map(|stats| stats.to_tsv())
-                .collect::<Vec<String>>()
-                .join("\n");
-            updated_stats.push('\n');
-            let updated_stats = updated_stats;
-
-            match fs::write(stats_file_path(), updated_stats) {
-                Ok(_) => Ok(()),
-                Err(e) => Err(GameError::CouldNotWriteStats(e.to_string())),
-            }
+    write_stats(&all_stats)?;
+
+    debug!(
+        "Appending to session log: {}",
+        sessions_file_path().display()
+    );
+    let mut line = session.to_tsv();
+    line.push('\n');
+    let result = timings::time("sessions write", || {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(sessions_file_path())
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+    });
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(GameError::CouldNotWriteSessions(e.to_string())),
+    }
+}
+
+fn write_stats<'a>(all_stats: &[GameStats]) -> Result<(), GameError<'a>> {
+    let updated_stats = stats::format_stats_file(all_stats);
+    debug!("Writing stats file: {}", stats_file_path().display());
+    timings::time("stats write", || {
+        fs::write(stats_file_path(), updated_stats)
+    })
+    .map_err(|e| GameError::CouldNotWriteStats(e.to_string()))
+}
+
+/// Fix a game's play time or last played time by hand, noting the change in
+/// the audit log
+fn command_stats_edit<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let Some(game_id) = args.first() else {
+        return Err(GameError::NoGameId);
+    };
+    let Some(game) = games.find(game_id) else {
+        return Err(GameError::NoSuchGame(game_id));
+    };
+    let mut play_time = None;
+    let mut last_played = None;
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--set-playtime" => match args.next().and_then(|s| stats::parse_play_time(s)) {
+                Some(seconds) => play_time = Some(seconds),
+                None => return Err(GameError::InvalidArgument(arg)),
+            },
+            "--set-last-played" => match args.next().and_then(|s| stats::parse_date(s)) {
+                Some(date) => last_played = Some(stats::local_midnight(date)),
+                None => return Err(GameError::InvalidArgument(arg)),
+            },
+            _ => return Err(GameError::InvalidArgument(arg)),
         }
-        Err(e) => Err(e),
     }
+    if play_time.is_none() && last_played.is_none() {
+        return Err(GameError::InvalidArgument("edit"));
+    }
+
+    let mut all_stats = match read_stats() {
+        Ok(content) => stats::parse_stats_file(&content),
+        Err(_) => Vec::new(),
+    };
+    let index = match all_stats.iter().position(|stats| stats.id() == game.id) {
+        Some(index) => index,
+        None => {
+            let last_played = last_played.unwrap_or(UtcDateTime::now());
+            all_stats.push(GameStats::new(game.id.clone(), 0, last_played));
+            all_stats.len() - 1
+        }
+    };
+    let stats = &mut all_stats[index];
+    let mut changes = Vec::new();
+    if let Some(seconds) = play_time {
+        let old = stats.format_play_time();
+        stats.set_play_time(seconds);
+        changes.push(("play_time", old, stats.format_play_time()));
+    }
+    if let Some(time) = last_played {
+        let old = stats.format_last_played_time();
+        stats.update_last_played_time(time);
+        changes.push(("last_played", old, stats.format_last_played_time()));
+    }
+    write_stats(&all_stats)?;
+
+    let now = UtcDateTime::now();
+    let mut lines = String::new();
+    for (field, old, new) in changes.iter() {
+        out!("{}: {} -> {}", field, old, new);
+        lines.push_str(&stats::audit_line(now, &game.id, field, old, new));
+        lines.push('\n');
+    }
+    append_to_audit_log(&lines)
+}
+
+fn append_to_audit_log<'a>(lines: &str) -> Result<(), GameError<'a>> {
+    let audit_path = data_dir().join(profile_path(stats::AUDIT_FILE));
+    debug!("Appending to audit log: {}", audit_path.display());
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&audit_path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .map_err(|e| GameError::CouldNotWriteStats(e.to_string()))
+}
+
+/// Take back the last recorded session, of the given game or of any game,
+/// like one from launching a game by accident
+fn command_stats_undo<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let game_id = match args {
+        [] => None,
+        [game_id] => match games.find(game_id) {
+            Some(game) => Some(game.id.as_str()),
+            None => return Err(GameError::NoSuchGame(game_id)),
+        },
+        [_, arg, ..] => return Err(GameError::InvalidArgument(arg)),
+    };
+    let mut all_stats = match read_stats() {
+        Ok(content) => stats::parse_stats_file(&content),
+        Err(_) => Vec::new(),
+    };
+    let mut sessions = read_sessions();
+    let play_times: HashMap<String, u32> = all_stats
+        .iter()
+        .map(|stats| (stats.id().to_string(), stats.play_time_seconds()))
+        .collect();
+    let Some(session) = stats::undo_session(&mut all_stats, &mut sessions, game_id) else {
+        out!("{}", tr!("stats-nothing-to-undo"));
+        return Ok(());
+    };
+    write_stats(&all_stats)?;
+    debug!("Writing session log: {}", sessions_file_path().display());
+    fs::write(sessions_file_path(), sessions::format_log(&sessions))
+        .map_err(|e| GameError::CouldNotWriteSessions(e.to_string()))?;
+    out!("{}", tr!("stats-undone", session = session.format()));
+
+    let old = play_times.get(session.id()).copied().unwrap_or(0);
+    let new = all_stats
+        .iter()
+        .find(|stats| stats.id() == session.id())
+        .map_or(0, |stats| stats.play_time_seconds());
+    let line = stats::audit_line(
+        UtcDateTime::now(),
+        session.id(),
+        "play_time",
+        &stats::format_play_time(old),
+        &stats::format_play_time(new),
+    );
+    append_to_audit_log(&format!("{}\n", line))
+}
+
+fn read_sessions() -> Vec<Session> {
+    debug!("Reading session log: {}", sessions_file_path().display());
+    match timings::time("sessions read", || fs::read_to_string(sessions_file_path())) {
+        Ok(content) => sessions::parse_log(&content),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn sessions_file_path() -> PathBuf {
+    data_dir().join(profile_path(SESSIONS_FILE))
 }
 
 fn find_game_stats(game: &Game) -> Option<GameStats> {
+    debug!("Reading stats file: {}", stats_file_path().display());
+    match read_stats() {
+        Ok(content) => stats::parse_stats_file(&content)
+            .into_iter()
+            .find(|stats| stats.id() == game.id),
+        Err(_) => None,
+    }
+}
+
+fn read_all_stats() -> HashMap<String, GameStats> {
+    let mut all_stats = HashMap::new();
+    debug!("Reading stats file: {}", stats_file_path().display());
     if let Ok(content) = read_stats() {
-        for line in content.lines() {
-            if line.is_empty() {
-                continue;
-            }
-            let stats = GameStats::from_tsv(line);
-            if stats.id() == game.id {
-                return Some(stats);
-            }
+        for stats in stats::parse_stats_file(&content) {
+            all_stats.insert(stats.id().to_string(), stats);
         }
-        None
-    } else {
-        None
     }
+    all_stats
 }
 
 fn read_stats() -> std::io::Result<String> {
     let file_path = stats_file_path();
-    fs::read_to_string(&file_path)
+    timings::time("stats read", || fs::read_to_string(&file_path))
 }
 
 fn stats_file_path() -> PathBuf {
-    data_dir().join(STATS_FILE)
+    data_dir().join(profile_path(STATS_FILE))
+}
+
+/// The profile whose stats and sessions are used: the one given with
+/// `--profile`, or else the `default_profile` setting
+fn profile() -> Option<String> {
+    if let Some(profile) = &global_options::get().profile {
+        return Some(profile.clone());
+    }
+    let Some(Ok(config)) = load_config() else {
+        return None;
+    };
+    match config.get("settings")?.get("default_profile")? {
+        Value::String(profile) => Some(profile.to_string()),
+        _ => None,
+    }
+}
+
+/// A profile name is used as a directory name, so it must be one
+fn is_valid_profile(profile: &str) -> bool {
+    !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// The path of a file kept for each profile, relative to the data directory
+/// (or a sync remote), where the files of the default profile are at the top
+fn profile_path(file_name: &str) -> String {
+    match profile() {
+        Some(profile) => format!("{}/{}/{}", PROFILES_DIR, profile, file_name),
+        None => file_name.to_string(),
+    }
 }
 
 fn data_dir() -> PathBuf {
-    home_dir().unwrap().join(DATA_DIR).join(APP_NAME)
+    if let Some(data_dir) = &global_options::get().data_dir {
+        return data_dir.clone();
+    }
+    let home = home_dir().unwrap();
+    Platform::current().data_base_dir(&home).join(APP_NAME)
+}
+
+/// Ask a question, returning the answer, or the default if there is none
+fn ask(question: &str, default: &str) -> String {
+    print!("{} [{}]: ", question, default);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(_) if !answer.trim().is_empty() => answer.trim().to_string(),
+        _ => default.to_string(),
+    }
+}
+
+fn ask_yes_no(question: &str, default: bool) -> bool {
+    let answer = ask(question, if default { "Y/n" } else { "y/N" });
+    match answer.to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    }
 }
 
 fn command_edit(_: &[String]) -> Result<(), UtilityCommandError> {
-    let config_file_path = config_dir().join(CONFIG_FILE_NAME);
-    match var("EDITOR") {
-        Ok(editor) => {
-            std::process::Command::new(editor)
-                .arg(&config_file_path)
-                .status()
-                .expect("Could nolt edit config file");
-            Ok(())
+    let configured = ["EDITOR", "VISUAL"]
+        .iter()
+        .filter_map(|name| var(name).ok())
+        .find(|editor| !editor.trim().is_empty());
+    let editor = match configured {
+        Some(editor) => shell_words::split(&editor).unwrap_or(vec![editor]),
+        None => match Platform::current().default_editor() {
+            Some(editor) => editor,
+            None => return Err(UtilityCommandError::NoEditor),
+        },
+    };
+    debug!("Editing config file with: {}", editor.join(" "));
+    std::process::Command::new(&editor[0])
+        .args(&editor[1..])
+        .arg(config_file_path())
+        .status()
+        .expect("Could not edit config file");
+    Ok(())
+}
+
+fn command_info<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let Some(game_id) = args.first() else {
+        return Err(GameError::NoGameId);
+    };
+    let Some(game) = games.find(game_id) else {
+        return Err(GameError::NoSuchGame(game_id));
+    };
+    out!(
+        "{}",
+        tr!("info-heading", name = game.name, game_id = game.id)
+    );
+    if let Some(version) = &game.version {
+        out!("{}", tr!("info-version", version = version));
+    }
+    if let Some(status) = &game.status {
+        out!("{}", tr!("info-status", status = status));
+    }
+    if !game.tags.is_empty() {
+        out!("{}", tr!("info-tags", tags = game.tags.join(", ")));
+    }
+    if let Some(dir) = &game.dir {
+        out!("{}", tr!("info-dir", dir = dir));
+    }
+    out!(
+        "{}",
+        tr!("info-command", command = shell_words::join(&game.command))
+    );
+    if let Some(stats) = find_game_stats(game) {
+        out!(
+            "{}",
+            tr!("stats-play-time", play_time = stats.format_play_time())
+        );
+        out!(
+            "{}",
+            tr!(
+                "stats-last-played",
+                last_played = stats.format_last_played_time()
+            )
+        );
+    }
+    let play_time = find_game_stats(game).map_or(0, |stats| stats.play_time_seconds());
+    milestones::print_goal(game, play_time);
+    milestones::print_milestones(game);
+    for (version, seconds) in version_play_times(&read_sessions(), &game.id) {
+        out!(
+            "{}",
+            tr!(
+                "info-version-play-time",
+                version = version,
+                play_time = stats::format_play_time(seconds)
+            )
+        );
+    }
+    Ok(())
+}
+
+/// The time the game was played on each version, from the sessions that
+/// recorded one, in the order the versions were first played
+fn version_play_times<'s>(sessions: &'s [Session], game_id: &str) -> Vec<(&'s str, u32)> {
+    let mut play_times: Vec<(&str, u32)> = Vec::new();
+    for session in sessions.iter().filter(|s| s.id() == game_id) {
+        let Some(version) = session.version() else {
+            continue;
+        };
+        match play_times.iter_mut().find(|(v, _)| *v == version) {
+            Some((_, seconds)) => *seconds += session.play_time_seconds(),
+            None => play_times.push((version, session.play_time_seconds())),
         }
-        Err(_) => Err(UtilityCommandError::NoEditor),
     }
+    play_times
 }
 
 fn command_stats<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
-    if args.is_empty() {
+    if args.first().is_some_and(|arg| arg == "edit") {
+        return command_stats_edit(games, &args[1..]);
+    }
+    if args.first().is_some_and(|arg| arg == "undo") {
+        return command_stats_undo(games, &args[1..]);
+    }
+    if args.iter().any(|arg| arg == "--by-launcher") {
+        let all_stats = read_all_stats();
+        for (launcher, seconds) in summary::launcher_breakdown(games, &all_stats) {
+            out!("{}: {}", launcher, stats::format_play_time(seconds));
+        }
+        return Ok(());
+    }
+    let show_distribution = args.iter().any(|arg| arg == "--distribution");
+    let game_ids: Vec<&'a String> = args.iter().filter(|arg| *arg != "--distribution").collect();
+    if game_ids.is_empty() {
         return Err(GameError::NoGameId);
     }
+    let sessions = if show_distribution {
+        read_sessions()
+    } else {
+        Vec::new()
+    };
     let mut total_seconds = 0;
     let mut count = 0;
-    let game_ids = args;
     for game_id in game_ids.iter() {
         match games.find(game_id) {
             Some(game) => match find_game_stats(game) {
@@ -409,15 +2127,39 @@ fn command_stats<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<
                     count += 1;
                     total_seconds += stats.play_time_seconds();
                     if count > 1 {
-                        println!();
+                        out!();
+                    }
+                    out!(
+                        "{}",
+                        tr!("stats-heading", name = game.name, game_id = game.id)
+                    );
+                    out!(
+                        "{}",
+                        tr!("stats-play-time", play_time = stats.format_play_time())
+                    );
+                    out!(
+                        "{}",
+                        tr!(
+                            "stats-last-played",
+                            last_played = stats.format_last_played_time()
+                        )
+                    );
+                    milestones::print_goal(game, stats.play_time_seconds());
+                    milestones::print_milestones(game);
+                    if show_distribution {
+                        let lengths = sessions
+                            .iter()
+                            .filter(|session| session.id() == game.id)
+                            .map(|session| session.play_time_seconds())
+                            .collect();
+                        for line in Distribution::new(lengths).format() {
+                            out!("{}", line);
+                        }
                     }
-                    println!("{} ({}) Statistics", game.name, game.id);
-                    println!("Play Time: {}", stats.format_play_time());
-                    println!("Last Played: {}", stats.format_last_played_time());
                 }
                 None => {
                     if game_ids.len() == 1 {
-                        println!("No stats found");
+                        out!("{}", tr!("no-stats"));
                     }
                 }
             },
@@ -428,14 +2170,116 @@ fn command_stats<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<
     }
     if count > 1 {
         let formatted_play_time = stats::format_play_time(total_seconds);
-        println!();
-        println!("Total Play Time: {}", formatted_play_time);
+        out!();
+        out!(
+            "{}",
+            tr!("stats-total-play-time", play_time = formatted_play_time)
+        );
     }
     Ok(())
 }
 
-struct Games {
+fn command_update<'a>(games: &'a Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    if args.is_empty() {
+        return Err(GameError::NoGameId);
+    }
+    let mut to_update = Vec::new();
+    for game_id in args.iter() {
+        match games.find(game_id) {
+            Some(game) => to_update.push(game),
+            None => return Err(GameError::NoSuchGame(game_id)),
+        }
+    }
+    for game in to_update {
+        out!("Updating: {} ({})", game.name, game.id);
+        game.update()?;
+    }
+    Ok(())
+}
+
+fn steam_dir() -> Option<PathBuf> {
+    home_dir().and_then(|home| Platform::current().steam_dir(&home))
+}
+
+fn command_steam_wrap<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let game_id = match args.first() {
+        Some(game_id) => game_id,
+        None => return Err(GameError::NoGameId),
+    };
+    if games.find(game_id).is_none() {
+        return Err(GameError::NoSuchGame(game_id));
+    }
+    let mut words = self_command();
+    words.push("wrap-exec".to_string());
+    words.push(game_id.to_string());
+    let launch_options = format!("{} -- %command%", shell_words::join(words));
+    out!("{}", launch_options);
+    Ok(())
+}
+
+/// The command that runs this program again with the same config file and
+/// data directory
+fn self_command() -> Vec<String> {
+    let exe = match env::current_exe() {
+        Ok(exe) => exe.to_string_lossy().to_string(),
+        Err(_) => "game".to_string(),
+    };
+    let mut words = vec![exe];
+    let options = global_options::get();
+    if let Some(config_file) = &options.config_file {
+        words.push("--config".to_string());
+        words.push(config_file.to_string_lossy().to_string());
+    }
+    if let Some(data_dir) = &options.data_dir {
+        words.push("--data-dir".to_string());
+        words.push(data_dir.to_string_lossy().to_string());
+    }
+    if let Some(profile) = &options.profile {
+        words.push("--profile".to_string());
+        words.push(profile.to_string());
+    }
+    words
+}
+
+/// A command that plays the game in another game process
+fn play_command(game_id: &str) -> std::process::Command {
+    let mut words = self_command();
+    words.push("play".to_string());
+    words.push(game_id.to_string());
+    let mut command = std::process::Command::new(&words[0]);
+    command.args(&words[1..]);
+    command
+}
+
+fn command_wrap_exec<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let game_id = match args.first() {
+        Some(game_id) => game_id,
+        None => return Err(GameError::NoGameId),
+    };
+    let game = match games.find(game_id) {
+        Some(game) => game,
+        None => return Err(GameError::NoSuchGame(game_id)),
+    };
+    let command = match args.get(1) {
+        Some(separator) if separator == "--" && args.len() > 2 => args[2..].to_vec(),
+        Some(arg) if arg != "--" => return Err(GameError::InvalidArgument(arg)),
+        _ => return Err(GameError::NoWrappedCommand),
+    };
+    let wrapped = game.wrap(command);
+    match play_game(games, &wrapped) {
+        Ok(()) => Ok(()),
+        Err(GameError::CommandReturnedFailure(cmd)) => Err(GameError::CommandReturnedFailure(cmd)),
+        Err(GameError::CouldNotWriteStats(e)) => Err(GameError::CouldNotWriteStats(e)),
+        Err(GameError::CouldNotWriteSessions(e)) => Err(GameError::CouldNotWriteSessions(e)),
+        Err(_) => Err(GameError::ExecutionFailed),
+    }
+}
+
+struct Games {
     games: HashMap<String, Game>,
+    settings: Settings,
+    /// The `[directories]`, with references to each other resolved
+    directories: Table,
 }
 
 impl Games {
@@ -443,19 +2287,37 @@ impl Games {
         self.games.get(id)
     }
 
-    fn random(&self, args: &[String]) -> &Game {
+    fn random(&self, args: &[String], under_hours: Option<f64>) -> Option<&Game> {
         let mut rng = rand::rng();
-        let installed_games = self.games.values().filter(|g| g.is_installed());
-        let matching_games: Vec<&Game> = if args.is_empty() {
-            installed_games.collect()
-        } else {
-            installed_games
-                .filter(|g| game_matches_tags(g, args))
-                .collect()
-        };
-        let games_count = matching_games.len();
-        let index = rng.random_range(0..games_count);
-        matching_games[index]
+        let candidates = self.roulette_candidates(args, under_hours);
+        if candidates.is_empty() {
+            return None;
+        }
+        let index = rng.random_range(0..candidates.len());
+        Some(candidates[index])
+    }
+
+    /// The games that may be picked at random, where dropped games (and,
+    /// given a number of hours, games too long for them) are left out and
+    /// favorites are twice as likely to be picked
+    fn roulette_candidates(&self, args: &[String], under_hours: Option<f64>) -> Vec<&Game> {
+        let mut game_ids: Vec<&String> = self.games.keys().collect();
+        game_ids.sort();
+        let matching_games = game_ids
+            .iter()
+            .map(|game_id| &self.games[*game_id])
+            .filter(|g| g.is_installed())
+            .filter(|g| g.status.as_deref() != Some(STATUS_DROPPED))
+            .filter(|g| args.is_empty() || game_matches_tags(g, args))
+            .filter(|g| under_hours.is_none_or(|hours| suggest::fits_session(g, hours)));
+        let mut candidates = Vec::new();
+        for game in matching_games {
+            candidates.push(game);
+            if game.favorite {
+                candidates.push(game);
+            }
+        }
+        candidates
     }
 }
 
@@ -472,57 +2334,238 @@ impl GetStr for Table {
     }
 }
 
+/// Parse a config as if for a machine without any overlays
+#[cfg(test)]
 fn parse_config(config_content: &str) -> Result<Games, ParseError> {
-    let mut games = HashMap::new();
-    let config = match config_content.parse::<Table>() {
-        Ok(t) => t,
-        Err(e) => return Err(ParseError::TomlError(e.to_string())),
-    };
+    parse_games(&config_table(config_content, None)?, None)
+}
 
-    let settings = match config.get("settings") {
-        Some(Value::Table(tbl)) => {
-            let width = match tbl.get("width") {
-                Some(Value::Integer(i)) => *i as u32,
-                _ => DEFAULT_WIDTH,
-            };
-            let height = match tbl.get("height") {
-                Some(Value::Integer(i)) => *i as u32,
-                _ => DEFAULT_HEIGHT,
-            };
-            let use_gamescope = match tbl.get("use_gamescope") {
-                Some(Value::Boolean(b)) => *b,
-                _ => false,
-            };
-            Settings {
-                width,
-                height,
-                use_gamescope,
+/// Parse the games in the config, or only the given ones, so that problems
+/// with the others do not get in the way
+fn parse_games(config: &Table, game_ids: Option<&[String]>) -> Result<Games, ParseError> {
+    let mut games = HashMap::new();
+    let (settings, directories) = parse_settings_and_directories(config)?;
+    if let Value::Table(games_config) = &config["games"] {
+        let game_ids = game_ids.map(|ids| with_required_games(games_config, ids));
+        for (game_id, value) in games_config.iter() {
+            if game_ids.as_ref().is_some_and(|ids| !ids.contains(game_id)) {
+                continue;
+            }
+            // Each game's stats are stored on a single line
+            if game_id.contains(['\n', '\r']) {
+                return Err(ParseError::InvalidGameId(game_id.clone()));
             }
+            if let Value::Table(game_config) = &value {
+                let game = parse_game_config(game_id, game_config, &directories, &settings)?;
+                games.insert(game_id.clone(), game);
+            } else {
+                return Err(ParseError::GameNotTable);
+            }
+        }
+    } else {
+        return Err(ParseError::MissingGameTable);
+    }
+    for game in games.values() {
+        if let Some(required) = game.requires.iter().find(|id| !games.contains_key(*id)) {
+            return Err(ParseError::NoSuchRequiredGame(
+                game.id.clone(),
+                required.to_string(),
+            ));
         }
+    }
+    debug!("Parsed {} games", games.len());
+    Ok(Games {
+        games,
+        settings,
+        directories,
+    })
+}
+
+/// The settings and the `[directories]` of the config, with references to
+/// each other resolved
+fn parse_settings_and_directories(config: &Table) -> Result<(Settings, Table), ParseError> {
+    let settings = match config.get("settings") {
+        Some(Value::Table(tbl)) => parse_settings(tbl),
         _ => Settings {
             height: 0,
             width: 0,
-            use_gamescope: false,
+            ..parse_settings(&Table::new())
         },
     };
-
     let directories = match config.get("directories") {
-        Some(Value::Table(tbl)) => tbl,
-        _ => &Table::new(),
+        Some(Value::Table(tbl)) => directories::resolve(tbl)?,
+        _ => Table::new(),
     };
-    if let Value::Table(games_config) = &config["games"] {
-        for (game_id, value) in games_config.iter() {
-            if let Value::Table(game_config) = &value {
-                let game = parse_game_config(game_id, game_config, directories, &settings)?;
-                games.insert(game_id.clone(), game);
-            } else {
-                return Err(ParseError::GameNotTable);
+    Ok((settings, directories))
+}
+
+/// The games along with the ones they require, and the ones those require in
+/// turn, so that all of them are parsed too
+fn with_required_games(games_config: &Table, game_ids: &[String]) -> Vec<String> {
+    let mut with_required = game_ids.to_vec();
+    let mut i = 0;
+    while i < with_required.len() {
+        let required = games_config
+            .get(&with_required[i])
+            .and_then(|game| game.get("requires"));
+        if let Some(Value::Array(required)) = required {
+            for id in required.iter().filter_map(|id| id.as_str()) {
+                if !with_required.iter().any(|seen| seen == id) {
+                    with_required.push(id.to_string());
+                }
             }
         }
-    } else {
-        return Err(ParseError::MissingGameTable);
+        i += 1;
+    }
+    with_required
+}
+
+/// A path from the config, where one starting with `~/` is under the home
+/// directory
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().unwrap().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+fn parse_settings(tbl: &Table) -> Settings {
+    let width = match tbl.get("width") {
+        Some(Value::Integer(i)) => *i as u32,
+        _ => DEFAULT_WIDTH,
+    };
+    let height = match tbl.get("height") {
+        Some(Value::Integer(i)) => *i as u32,
+        _ => DEFAULT_HEIGHT,
+    };
+    let use_gamescope = match tbl.get("use_gamescope") {
+        Some(Value::Boolean(b)) => *b,
+        _ => false,
+    };
+    let session_notes = match tbl.get("session_notes") {
+        Some(Value::Boolean(b)) => *b,
+        _ => false,
+    };
+    let roulette_prompt = match tbl.get("roulette_prompt") {
+        Some(Value::Boolean(b)) => *b,
+        _ => false,
+    };
+    let remind_after_minutes = match tbl.get("remind_after_minutes") {
+        Some(Value::Integer(i)) if *i >= 0 => Some(*i as u32),
+        _ => None,
+    };
+    let idle_after_minutes = match tbl.get("idle_after_minutes") {
+        Some(Value::Integer(i)) if *i > 0 => Some(*i as u32),
+        _ => None,
+    };
+    let pause_file = match tbl.get("pause_file") {
+        Some(Value::String(s)) => Some(expand_home(s)),
+        _ => None,
+    };
+    let nudge_after_days = match tbl.get("nudge_after_days") {
+        Some(Value::Integer(i)) if *i >= 0 => *i as u32,
+        _ => nudge::DEFAULT_NUDGE_AFTER_DAYS,
+    };
+    let controller_mapper = match tbl.get("controller_mapper") {
+        Some(Value::String(s)) => s.to_string(),
+        _ => DEFAULT_CONTROLLER_MAPPER.to_string(),
+    };
+    let capture_cmd = match tbl.get("capture_cmd") {
+        Some(Value::String(s)) => s.to_string(),
+        _ => capture::DEFAULT_CAPTURE_CMD.to_string(),
+    };
+    let capture_stop_cmd = match tbl.get("capture_stop_cmd") {
+        Some(Value::String(s)) => Some(s.to_string()),
+        _ => None,
+    };
+    let capture_dir = match tbl.get("capture_dir") {
+        Some(Value::String(s)) => expand_home(s),
+        _ => home_dir().unwrap().join(capture::DEFAULT_CAPTURE_DIR),
+    };
+    let capture_ext = match tbl.get("capture_ext") {
+        Some(Value::String(s)) => s.trim_start_matches('.').to_string(),
+        _ => capture::DEFAULT_CAPTURE_EXT.to_string(),
+    };
+    let compositor_pause_cmd = match tbl.get("compositor_pause_cmd") {
+        Some(Value::String(s)) => s.to_string(),
+        _ => DEFAULT_COMPOSITOR_PAUSE_CMD.to_string(),
+    };
+    let compositor_resume_cmd = match tbl.get("compositor_resume_cmd") {
+        Some(Value::String(s)) => s.to_string(),
+        _ => DEFAULT_COMPOSITOR_RESUME_CMD.to_string(),
+    };
+    let prefer_output = match tbl.get("prefer_output") {
+        Some(Value::String(s)) => Some(s.to_string()),
+        _ => None,
+    };
+    let display = match tbl.get("display") {
+        Some(Value::Integer(i)) => Some(*i),
+        _ => None,
+    };
+    let window_manager = match tbl.get("window_manager") {
+        Some(Value::String(s)) => match WindowManager::parse(s) {
+            Some(wm) => Some(wm),
+            None => {
+                warn!("Unrecognized window manager: {}", s);
+                WindowManager::detect()
+            }
+        },
+        _ => WindowManager::detect(),
+    };
+    let runner = match tbl.get("runner") {
+        Some(Value::String(s)) => Some(s.to_string()),
+        _ => None,
+    };
+    let runner_search_dirs = runners::search_dirs(&home_dir().unwrap());
+    let setting = |key: &str| match tbl.get(key) {
+        Some(Value::String(s)) => Some(s.to_string()),
+        _ => None,
+    };
+    let mqtt = setting("mqtt_broker").map(|broker| Mqtt {
+        broker,
+        topic: setting("mqtt_topic").unwrap_or(DEFAULT_MQTT_TOPIC.to_string()),
+        username: setting("mqtt_username"),
+        password: setting("mqtt_password"),
+    });
+    Settings {
+        width,
+        height,
+        use_gamescope,
+        session_notes,
+        roulette_prompt,
+        remind_after_minutes,
+        idle_after_minutes,
+        pause_file,
+        nudge_after_days,
+        controller_mapper,
+        capture_cmd,
+        capture_stop_cmd,
+        capture_dir,
+        capture_ext,
+        compositor_pause_cmd,
+        compositor_resume_cmd,
+        prefer_output,
+        display,
+        window_manager,
+        fps_log_dir: data_dir().join(FPS_LOG_DIR),
+        runner,
+        runners: OnceLock::new(),
+        runner_search_dirs,
+        mqtt,
+        platform: Platform::current(),
+        conditions: condition::detect(Platform::current()),
+        backends: backends(),
     }
-    Ok(Games { games })
+}
+
+/// The launcher backends, with the plugins read from the config directory the
+/// first time they are needed
+fn backends() -> &'static [Box<dyn LauncherBackend>] {
+    static BACKENDS: OnceLock<Vec<Box<dyn LauncherBackend>>> = OnceLock::new();
+    BACKENDS.get_or_init(|| {
+        let reserved: Vec<&str> = option_parsers().into_keys().collect();
+        backend::all(&config_dir().join(backend::PLUGINS_DIR), &reserved)
+    })
 }
 
 type OptionParser = for<'a, 'b> fn(GameBuilder<'a>, &'b Table) -> GameBuilder<'a>;
@@ -535,15 +2578,6 @@ fn parse_name<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<
     }
 }
 
-fn parse_scummvm_id<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
-    if let Some(Value::String(scummvm_id)) = game_config.get("scummvm_id") {
-        let command = vec!["scummvm".to_string(), scummvm_id.to_string()];
-        builder.command(command)
-    } else {
-        builder
-    }
-}
-
 fn parse_wine_exe<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
     if let Some(Value::String(wine_exe)) = game_config.get("wine_exe") {
         let mut cmd_parts = Vec::new();
@@ -557,19 +2591,6 @@ fn parse_wine_exe<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuil
     }
 }
 
-fn parse_dosbox_conf<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
-    if let Some(Value::String(dosbox_conf_file)) = game_config.get("dosbox_config") {
-        let cmd = vec![
-            "dosbox".to_string(),
-            "-conf".to_string(),
-            dosbox_conf_file.to_string(),
-        ];
-        builder.command(cmd)
-    } else {
-        builder
-    }
-}
-
 fn parse_dir_prefix<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
     let dir_prefix = game_config.get_str("dir_prefix");
     if !dir_prefix.is_empty() {
@@ -588,6 +2609,51 @@ fn parse_cmd<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'
     }
 }
 
+fn parse_cover<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("cover") {
+        Some(Value::String(cover)) => builder.cover(PathBuf::from(cover)),
+        _ => builder.invalid_option("cover"),
+    }
+}
+
+fn parse_save_dir<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("save_dir") {
+        Some(Value::String(s)) => builder.save_dir(expand_home(s)),
+        _ => builder.invalid_option("save_dir"),
+    }
+}
+
+fn parse_process_name<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("process_name") {
+        Some(Value::String(process_name)) => builder.process_name(process_name),
+        _ => builder.invalid_option("process_name"),
+    }
+}
+
+fn parse_update_cmd<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("update_cmd") {
+        Some(Value::String(cmd)) => match shell_words::split(cmd) {
+            Ok(command_parts) if !command_parts.is_empty() => builder.update_command(command_parts),
+            _ => builder.invalid_option("update_cmd"),
+        },
+        _ => builder.invalid_option("update_cmd"),
+    }
+}
+
+fn parse_companions<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    let Some(Value::Array(companions)) = game_config.get("companions") else {
+        return builder.invalid_option("companions");
+    };
+    let mut commands = Vec::new();
+    for companion in companions.iter() {
+        match companion.as_str().map(shell_words::split) {
+            Some(Ok(command_parts)) if !command_parts.is_empty() => commands.push(command_parts),
+            _ => return builder.invalid_option("companions"),
+        }
+    }
+    builder.companions(commands)
+}
+
 fn parse_dir<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
     if let Some(Value::String(s)) = game_config.get("dir") {
         builder.dir(s.to_string())
@@ -610,6 +2676,44 @@ fn parse_env<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'
     }
 }
 
+fn parse_requires<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    let Some(Value::Array(required)) = game_config.get("requires") else {
+        return builder.invalid_option("requires");
+    };
+    let mut game_ids = Vec::new();
+    for game_id in required.iter() {
+        match game_id {
+            Value::String(game_id) => game_ids.push(game_id.to_string()),
+            _ => return builder.invalid_option("requires"),
+        }
+    }
+    builder.requires(game_ids)
+}
+
+fn parse_kill_wineserver<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("kill_wineserver") {
+        Some(Value::Boolean(true)) => builder.kill_wineserver(),
+        Some(Value::Boolean(false)) => builder,
+        _ => builder.invalid_option("kill_wineserver"),
+    }
+}
+
+fn parse_track_stats<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("track_stats") {
+        Some(Value::Boolean(true)) => builder,
+        Some(Value::Boolean(false)) => builder.no_stats(),
+        _ => builder.invalid_option("track_stats"),
+    }
+}
+
+fn parse_stop_required<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("stop_required") {
+        Some(Value::Boolean(true)) => builder.stop_required(),
+        Some(Value::Boolean(false)) => builder,
+        _ => builder.invalid_option("stop_required"),
+    }
+}
+
 fn parse_tags<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
     if let Some(Value::Array(tags_array)) = game_config.get("tags") {
         let tags = tags_array
@@ -641,9 +2745,17 @@ fn parse_fps_limit<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBui
     }
 }
 
+fn parse_goal_hours<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("goal_hours") {
+        Some(Value::Integer(hours)) if *hours > 0 => builder.goal_hours(*hours as f64),
+        Some(Value::Float(hours)) if *hours > 0.0 => builder.goal_hours(*hours),
+        _ => builder.invalid_option("goal_hours"),
+    }
+}
+
 fn parse_installed<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
     if let Some(Value::Boolean(b)) = game_config.get("installed") {
-        if !b { builder.not_installed() } else { builder }
+        builder.installed(*b)
     } else {
         builder
     }
@@ -673,98 +2785,416 @@ fn parse_steam_id<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuil
     }
 }
 
-fn parse_game_config(
-    game_id: &str,
-    game_config: &Table,
-    directories: &Table,
-    settings: &Settings,
-) -> Result<Game, ParseError> {
-    let mut option_parsers: HashMap<&str, OptionParser> = HashMap::new();
-    option_parsers.insert("cmd", parse_cmd);
-    option_parsers.insert("dir", parse_dir);
-    option_parsers.insert("dir_prefix", parse_dir_prefix);
-    option_parsers.insert("dosbox_config", parse_dosbox_conf);
-    option_parsers.insert("env", parse_env);
-    option_parsers.insert("fps_limit", parse_fps_limit);
-    option_parsers.insert("installed", parse_installed);
-    option_parsers.insert("name", parse_name);
-    option_parsers.insert("scummvm_id", parse_scummvm_id);
-    option_parsers.insert("tags", parse_tags);
-    option_parsers.insert("use_gamescope", parse_use_gamescope);
-    option_parsers.insert("use_mangohud", parse_use_mangohud);
-    option_parsers.insert("use_vk", parse_use_vk);
-    option_parsers.insert("wine_exe", parse_wine_exe);
-    option_parsers.insert("steam_id", parse_steam_id);
-    let option_parsers = option_parsers;
+fn parse_added<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    let date = match game_config.get("added") {
+        Some(Value::Datetime(dt)) => dt.date.and_then(|d| {
+            let month = time::Month::try_from(d.month).ok()?;
+            time::Date::from_calendar_date(d.year as i32, month, d.day).ok()
+        }),
+        Some(Value::String(s)) => stats::parse_date(s),
+        _ => None,
+    };
+    match date {
+        Some(date) => builder.added(date),
+        None => builder.invalid_option("added"),
+    }
+}
 
-    let mut builder = GameBuilder::new(game_id.to_string(), directories, settings);
-    for key in game_config.keys() {
-        if !option_parsers.contains_key(key.as_str()) {
-            return Err(ParseError::UnrecognizedOption(key.to_string()));
-        }
-        let parse_option = &option_parsers[key.as_str()];
-        builder = parse_option(builder, game_config);
+fn parse_sandbox<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config
+        .get("sandbox")
+        .and_then(|s| s.as_str())
+        .and_then(Sandbox::parse)
+    {
+        Some(sandbox) => builder.sandbox(sandbox),
+        None => builder.invalid_option("sandbox"),
     }
+}
 
-    builder.build()
+fn parse_status<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    if let Some(Value::String(status)) = game_config.get("status") {
+        builder.status(status.to_string())
+    } else {
+        builder.invalid_option("status")
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn parse_favorite<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    if let Some(Value::Boolean(b)) = game_config.get("favorite") {
+        if *b { builder.favorite() } else { builder }
+    } else {
+        builder.invalid_option("favorite")
+    }
+}
 
-    #[test]
-    fn test_game_exists() {
-        let config = "[games]\n[games.morrowind]\nname = \"Morrowind\"\ncmd = \"openmw\"";
-        let games = parse_config(config).expect("Bad config");
-        assert!(games.find("morrowind").is_some());
+fn parse_remind_after_minutes<'a>(
+    builder: GameBuilder<'a>,
+    game_config: &Table,
+) -> GameBuilder<'a> {
+    match game_config.get("remind_after_minutes") {
+        Some(Value::Integer(i)) if *i >= 0 => builder.remind_after_minutes(*i as u32),
+        _ => builder.invalid_option("remind_after_minutes"),
     }
+}
 
-    #[test]
-    fn test_format_game() {
-        let config = "[games]\n[games.morrowind]\nname = \"Morrowind\"\ncmd = \"openmw\"";
-        let games = parse_config(config).expect("Bad config");
-        if let Some(game) = games.find("morrowind") {
-            let s = game.format();
-            assert_eq!(s, "morrowind - Morrowind");
-        } else {
-            panic!("Game not found");
-        }
+fn parse_controller_profile<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    if let Some(Value::String(profile)) = game_config.get("controller_profile") {
+        builder.controller_profile(profile.to_string())
+    } else {
+        builder.invalid_option("controller_profile")
     }
+}
 
-    #[test]
-    fn test_parse_game() {
-        let config = "[games]\n[games.morrowind]\nname = \"Morrowind\"\ncmd = \"openmw\"";
-        let games = parse_config(config).expect("Bad config");
-        if let Some(game) = games.find("morrowind") {
-            assert_eq!(game.command, vec!["openmw"]);
+fn parse_pause_compositor<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    if let Some(Value::Boolean(b)) = game_config.get("pause_compositor") {
+        if *b {
+            builder.pause_compositor()
         } else {
-            panic!("Game not found");
+            builder
         }
+    } else {
+        builder.invalid_option("pause_compositor")
     }
+}
 
-    #[test]
-    fn test_parse_game_with_directory() {
-        let config = "[games]\n[games.quake]\nname = \"Quake\"\ndir = \"/home/test/Games/quake\"\ncmd=\"vkquake\"";
-        let games = parse_config(config).expect("Bad config");
-        if let Some(game) = games.find("quake") {
-            assert_eq!(game.dir.as_ref().unwrap(), "/home/test/Games/quake");
-        } else {
-            panic!("Game not found");
+fn parse_prefer_output<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    if let Some(Value::String(output)) = game_config.get("prefer_output") {
+        builder.prefer_output(output.to_string())
+    } else {
+        builder.invalid_option("prefer_output")
+    }
+}
+
+fn parse_display<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("display") {
+        Some(Value::Integer(i)) if *i >= 0 => builder.display(*i),
+        _ => builder.invalid_option("display"),
+    }
+}
+
+fn parse_wm_rules<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    let Some(Value::Table(tbl)) = game_config.get("wm_rules") else {
+        return builder.invalid_option("wm_rules");
+    };
+    let mut rules = WmRules::new();
+    for (key, value) in tbl.iter() {
+        match (key.as_str(), value) {
+            ("workspace", Value::String(s)) => rules.workspace = Some(s.to_string()),
+            ("workspace", Value::Integer(i)) => rules.workspace = Some(i.to_string()),
+            ("fullscreen", Value::Boolean(b)) => rules.fullscreen = *b,
+            ("delay_seconds", Value::Integer(i)) if *i >= 0 => rules.delay_seconds = *i as u64,
+            ("class", Value::String(s)) => rules.class = Some(s.to_string()),
+            ("title", Value::String(s)) => rules.title = Some(s.to_string()),
+            _ => return builder.invalid_option("wm_rules"),
         }
     }
+    builder.wm_rules(rules)
+}
 
-    #[test]
-    fn test_game_with_directory_prefix() {
-        let config = "
-        [directories]
-        games_dir=\"/home/test/Games\"
+/// A locale like `de_DE`, which gets a UTF-8 encoding unless it has one, or
+/// `de_DE.ISO-8859-1`
+fn parse_language<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    let Some(Value::String(language)) = game_config.get("language") else {
+        return builder.invalid_option("language");
+    };
+    let is_valid = !language.is_empty()
+        && language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.@-".contains(c));
+    if !is_valid {
+        builder.invalid_option("language")
+    } else if language.contains(['.', '@']) || !language.contains('_') {
+        builder.language(language.to_string())
+    } else {
+        builder.language(format!("{}.UTF-8", language))
+    }
+}
 
-        [games]
-        
-        [games.quake]
-        name = \"Quake\"
-        dir_prefix=\"games_dir\"
+fn parse_length_hours<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("length_hours") {
+        Some(Value::Integer(hours)) if *hours > 0 => builder.length_hours(*hours as f64),
+        Some(Value::Float(hours)) if *hours > 0.0 => builder.length_hours(*hours),
+        _ => builder.invalid_option("length_hours"),
+    }
+}
+
+fn parse_session_length<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("session_length") {
+        Some(Value::String(s)) => match suggest::parse_hours(s) {
+            Some(hours) => builder.session_hours(hours),
+            None => builder.invalid_option("session_length"),
+        },
+        Some(Value::Integer(hours)) if *hours > 0 => builder.session_hours(*hours as f64),
+        Some(Value::Float(hours)) if *hours > 0.0 => builder.session_hours(*hours),
+        _ => builder.invalid_option("session_length"),
+    }
+}
+
+fn parse_rating<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("rating") {
+        Some(Value::Integer(rating)) if (0..=10).contains(rating) => builder.rating(*rating as f64),
+        Some(Value::Float(rating)) if (0.0..=10.0).contains(rating) => builder.rating(*rating),
+        _ => builder.invalid_option("rating"),
+    }
+}
+
+fn parse_mangohud_config<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    let Some(Value::Table(tbl)) = game_config.get("mangohud_config") else {
+        return builder.invalid_option("mangohud_config");
+    };
+    let mut config = Vec::new();
+    for (key, value) in tbl.iter() {
+        let value = match value {
+            Value::Boolean(true) => "".to_string(),
+            Value::Boolean(false) => "0".to_string(),
+            Value::String(s) => s.to_string(),
+            Value::Integer(i) => i.to_string(),
+            Value::Float(f) => f.to_string(),
+            _ => return builder.invalid_option("mangohud_config"),
+        };
+        config.push((key.to_string(), value));
+    }
+    builder.mangohud_config(config)
+}
+
+fn parse_version<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("version") {
+        Some(Value::String(version)) if !version.is_empty() => builder.version(version),
+        Some(Value::Integer(version)) => builder.version(&version.to_string()),
+        _ => builder.invalid_option("version"),
+    }
+}
+
+fn parse_wine_dpi<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("wine_dpi") {
+        Some(Value::Integer(dpi)) if *dpi > 0 && *dpi <= u32::MAX as i64 => {
+            builder.wine_dpi(*dpi as u32)
+        }
+        _ => builder.invalid_option("wine_dpi"),
+    }
+}
+
+/// A table of registry keys, each a table of the values to set in it, where
+/// an integer is a `REG_DWORD` and a string a `REG_SZ`
+fn parse_wine_registry<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    let Some(Value::Table(keys)) = game_config.get("wine_registry") else {
+        return builder.invalid_option("wine_registry");
+    };
+    let mut tweaks = Vec::new();
+    for (key, values) in keys.iter() {
+        let Value::Table(values) = values else {
+            return builder.invalid_option("wine_registry");
+        };
+        for (name, value) in values.iter() {
+            let value = match value {
+                Value::Integer(n) if *n >= 0 && *n <= u32::MAX as i64 => RegValue::Dword(*n as u32),
+                Value::String(s) => RegValue::Sz(s.to_string()),
+                _ => return builder.invalid_option("wine_registry"),
+            };
+            tweaks.push(RegistryTweak {
+                key: key.to_string(),
+                name: name.to_string(),
+                value,
+            });
+        }
+    }
+    builder.wine_registry(tweaks)
+}
+
+fn parse_record<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("record") {
+        Some(Value::Boolean(true)) => builder.record(),
+        Some(Value::Boolean(false)) => builder,
+        _ => builder.invalid_option("record"),
+    }
+}
+
+fn parse_capture_fps<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    match game_config.get("capture_fps") {
+        Some(Value::Boolean(true)) => builder.capture_fps(),
+        Some(Value::Boolean(false)) => builder,
+        _ => builder.invalid_option("capture_fps"),
+    }
+}
+
+fn parse_runner<'a>(builder: GameBuilder<'a>, game_config: &Table) -> GameBuilder<'a> {
+    if let Some(Value::String(runner)) = game_config.get("runner") {
+        builder.runner(runner.to_string())
+    } else {
+        builder.invalid_option("runner")
+    }
+}
+
+/// The parsers of the game options, by the option's name
+fn option_parsers() -> HashMap<&'static str, OptionParser> {
+    let mut option_parsers: HashMap<&'static str, OptionParser> = HashMap::new();
+    option_parsers.insert("added", parse_added);
+    option_parsers.insert("capture_fps", parse_capture_fps);
+    option_parsers.insert("cmd", parse_cmd);
+    option_parsers.insert("companions", parse_companions);
+    option_parsers.insert("controller_profile", parse_controller_profile);
+    option_parsers.insert("cover", parse_cover);
+    option_parsers.insert("dir", parse_dir);
+    option_parsers.insert("dir_prefix", parse_dir_prefix);
+    option_parsers.insert("display", parse_display);
+    option_parsers.insert("env", parse_env);
+    option_parsers.insert("favorite", parse_favorite);
+    option_parsers.insert("fps_limit", parse_fps_limit);
+    option_parsers.insert("goal_hours", parse_goal_hours);
+    option_parsers.insert("installed", parse_installed);
+    option_parsers.insert("kill_wineserver", parse_kill_wineserver);
+    option_parsers.insert("language", parse_language);
+    option_parsers.insert("length_hours", parse_length_hours);
+    option_parsers.insert("mangohud_config", parse_mangohud_config);
+    option_parsers.insert("name", parse_name);
+    option_parsers.insert("pause_compositor", parse_pause_compositor);
+    option_parsers.insert("prefer_output", parse_prefer_output);
+    option_parsers.insert("process_name", parse_process_name);
+    option_parsers.insert("rating", parse_rating);
+    option_parsers.insert("record", parse_record);
+    option_parsers.insert("remind_after_minutes", parse_remind_after_minutes);
+    option_parsers.insert("requires", parse_requires);
+    option_parsers.insert("runner", parse_runner);
+    option_parsers.insert("sandbox", parse_sandbox);
+    option_parsers.insert("save_dir", parse_save_dir);
+    option_parsers.insert("session_length", parse_session_length);
+    option_parsers.insert("status", parse_status);
+    option_parsers.insert("stop_required", parse_stop_required);
+    option_parsers.insert("tags", parse_tags);
+    option_parsers.insert("track_stats", parse_track_stats);
+    option_parsers.insert("update_cmd", parse_update_cmd);
+    option_parsers.insert("use_gamescope", parse_use_gamescope);
+    option_parsers.insert("use_mangohud", parse_use_mangohud);
+    option_parsers.insert("use_vk", parse_use_vk);
+    option_parsers.insert("version", parse_version);
+    option_parsers.insert("wine_dpi", parse_wine_dpi);
+    option_parsers.insert("wine_exe", parse_wine_exe);
+    option_parsers.insert("wine_registry", parse_wine_registry);
+    option_parsers.insert("wm_rules", parse_wm_rules);
+    option_parsers.insert("steam_id", parse_steam_id);
+    option_parsers
+}
+
+fn parse_game_config(
+    game_id: &str,
+    game_config: &Table,
+    directories: &Table,
+    settings: &Settings,
+) -> Result<Game, ParseError> {
+    game_builder(game_id, game_config, directories, settings)?.build()
+}
+
+/// A builder with every option of the game applied, so that callers can
+/// change it further before building
+fn game_builder<'a>(
+    game_id: &str,
+    game_config: &Table,
+    directories: &'a Table,
+    settings: &'a Settings,
+) -> Result<GameBuilder<'a>, ParseError> {
+    let option_parsers = option_parsers();
+
+    let game_config = match condition::apply(game_config, &settings.conditions) {
+        Ok(options) => options,
+        Err(option) => return Err(ParseError::InvalidOption(game_id.to_string(), option)),
+    };
+    let mut builder = GameBuilder::new(game_id.to_string(), directories, settings);
+    for key in game_config.keys() {
+        let Some(parse_option) = option_parsers.get(key.as_str()) else {
+            let is_backend_option = settings
+                .backends
+                .iter()
+                .any(|backend| backend.keys().contains(&key.as_str()));
+            if !is_backend_option {
+                return Err(ParseError::UnrecognizedOption(key.to_string()));
+            }
+            continue;
+        };
+        trace!("Game {}: parsing option {}", game_id, key);
+        builder = parse_option(builder, &game_config);
+    }
+    for backend in settings.backends.iter() {
+        match backend.launch(&game_config) {
+            Some(Ok(launch)) => {
+                trace!("Game {}: launched by backend {}", game_id, backend.name());
+                builder = builder.launch(launch);
+            }
+            Some(Err(option)) => {
+                return Err(ParseError::InvalidOption(game_id.to_string(), option));
+            }
+            None => {}
+        }
+    }
+
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_exists() {
+        let config = "[games]\n[games.morrowind]\nname = \"Morrowind\"\ncmd = \"openmw\"";
+        let games = parse_config(config).expect("Bad config");
+        assert!(games.find("morrowind").is_some());
+    }
+
+    #[test]
+    fn test_parse_only_some_games() {
+        let config = "[games]\n[games.morrowind]\nname = \"Morrowind\"\ncmd = \"openmw\"\n[games.broken]\ncmd = \"x\"";
+        assert!(parse_config(config).is_err());
+        let table = config_table(config, None).unwrap();
+        let game_ids = vec!["morrowind".to_string()];
+        let games = parse_games(&table, Some(&game_ids)).expect("Bad config");
+        assert_eq!(games.games.len(), 1);
+    }
+
+    #[test]
+    fn test_format_game() {
+        let config = "[games]\n[games.morrowind]\nname = \"Morrowind\"\ncmd = \"openmw\"";
+        let games = parse_config(config).expect("Bad config");
+        if let Some(game) = games.find("morrowind") {
+            let s = game.format();
+            assert_eq!(s, "morrowind - Morrowind");
+        } else {
+            panic!("Game not found");
+        }
+    }
+
+    #[test]
+    fn test_parse_game() {
+        let config = "[games]\n[games.morrowind]\nname = \"Morrowind\"\ncmd = \"openmw\"";
+        let games = parse_config(config).expect("Bad config");
+        if let Some(game) = games.find("morrowind") {
+            assert_eq!(game.command, vec!["openmw"]);
+        } else {
+            panic!("Game not found");
+        }
+    }
+
+    #[test]
+    fn test_parse_game_with_directory() {
+        let config = "[games]\n[games.quake]\nname = \"Quake\"\ndir = \"/home/test/Games/quake\"\ncmd=\"vkquake\"";
+        let games = parse_config(config).expect("Bad config");
+        if let Some(game) = games.find("quake") {
+            assert_eq!(game.dir.as_ref().unwrap(), "/home/test/Games/quake");
+        } else {
+            panic!("Game not found");
+        }
+    }
+
+    #[test]
+    fn test_game_with_directory_prefix() {
+        let config = "
+        [directories]
+        games_dir=\"/home/test/Games\"
+
+        [games]
+        
+        [games.quake]
+        name = \"Quake\"
+        dir_prefix=\"games_dir\"
         dir = \"quake\"
         cmd=\"vkquake\"
         ";
@@ -1111,6 +3541,7 @@ mod tests {
             env: HashMap::new(),
             tags: vec!["tag1".to_string(), "tag2".to_string(), "tag3".to_string()],
             installed: true,
+            ..Default::default()
         };
         let tags = ["tag2".to_string(), "tag4".to_string()];
         assert!(game_matches_tags(&game, &tags));
@@ -1126,6 +3557,7 @@ mod tests {
             env: HashMap::new(),
             tags: vec!["tag1".to_string(), "tag2".to_string()],
             installed: true,
+            ..Default::default()
         };
         let tags_matching = ["tag1,tag2".to_string()];
         assert!(game_matches_tags(&game, &tags_matching));
@@ -1156,6 +3588,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_play_checks_every_game_first() {
+        // The game could not be played, so getting as far as playing it would
+        // give `NotInstalled` instead
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        installed = false";
+
+        let games = parse_config(config).expect("Bad config");
+        for ids in [["doom", "quake"], ["quake", "doom"]] {
+            let mut args: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+            args.push("--no-stats".to_string());
+            assert!(matches!(
+                command_play(&games, &args),
+                Err(GameError::NoSuchGame("quake"))
+            ));
+        }
+    }
+
     #[test]
     fn test_list_does_not_show_games_that_are_not_installed() {
         let config = "
@@ -1172,63 +3626,1132 @@ mod tests {
         wine_exe = \"TestGame2.exe\"";
 
         let games = parse_config(config).expect("Bad config");
-        let game_list = list_games(&games, &[String::new(); 0]);
+        let game_list = list_games(&games, &[], &ListOptions::default(), &HashMap::new());
         assert_eq!(game_list.len(), 1);
         assert_eq!(&game_list[0], "testgame2 - Test Game 2");
     }
 
     #[test]
-    fn test_game_whose_title_matches_the_tag_is_included_in_matches() {
-        let game = Game {
-            id: "test_game".to_string(),
-            name: "Test Game".to_string(),
-            dir: None,
-            command: vec!["test_game".to_string()],
-            env: HashMap::new(),
-            tags: vec!["tag1".to_string(), "tag2".to_string()],
-            installed: true,
+    fn test_list_not_installed_with_stats() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        installed = false
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"quakespasm\"
+        installed = false
+
+        [games.hexen]
+        name = \"Hexen\"
+        cmd = \"dsda-doom\"";
+        let games = parse_config(config).expect("Bad config");
+        let args = vec!["--not-installed".to_string(), "--with-stats".to_string()];
+        let Ok((options, tags)) = ListOptions::parse(&args) else {
+            panic!("Bad arguments");
         };
-        let tags = vec!["test_game".to_string()];
-        assert!(game_matches_tags(&game, &tags));
+        let all_stats = stats::test_stats(&[("doom", 7200, UtcDateTime::now())]);
+        let game_list = list_games(&games, &tags, &options, &all_stats);
+        assert_eq!(game_list.len(), 2);
+        assert!(game_list[0].starts_with("doom - Doom (played 2h, last "));
+        assert_eq!(game_list[1], "quake - Quake (never played)");
     }
 
     #[test]
-    fn test_steam_game() {
-        let config = "[games]\n[games.hades2]\nname = \"Hades II\"\nsteam_id=\"1145350\"";
+    fn test_valid_profile() {
+        assert!(is_valid_profile("alex"));
+        assert!(is_valid_profile("kid-2"));
+        assert!(!is_valid_profile(""));
+        assert!(!is_valid_profile("../alex"));
+    }
+
+    #[test]
+    fn test_list_sorted_by_stale() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"quakespasm\"
+
+        [games.hexen]
+        name = \"Hexen\"
+        cmd = \"dsda-doom\"
+
+        [games.heretic]
+        name = \"Heretic\"
+        cmd = \"dsda-doom\"";
         let games = parse_config(config).expect("Bad config");
-        let game = games.find("hades2").unwrap();
-        assert_eq!(game.command, vec!["steam", "steam://rungameid/1145350"]);
+        let args = vec!["--sort".to_string(), "stale".to_string()];
+        let Ok((options, tags)) = ListOptions::parse(&args) else {
+            panic!("Bad arguments");
+        };
+        let at = |timestamp| UtcDateTime::from_unix_timestamp(timestamp).unwrap();
+        let all_stats = stats::test_stats(&[("doom", 60, at(2000)), ("quake", 60, at(1000))]);
+        let game_list = list_games(&games, &tags, &options, &all_stats);
+        assert_eq!(
+            game_list,
+            vec![
+                "heretic - Heretic",
+                "hexen - Hexen",
+                "quake - Quake",
+                "doom - Doom"
+            ]
+        );
+        assert!(ListOptions::parse(&["--sort".to_string()]).is_err());
     }
 
     #[test]
-    fn test_steam_game_does_not_use_gamescope() {
+    fn test_list_by_status_and_platform() {
         let config = "
-        [settings]
-        width = 1920
-        height = 1080
-        use_gamescope = true
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        status = \"playing\"
+
+        [games.monkey]
+        name = \"Monkey Island\"
+        cmd = \"scummvm monkey\"
+        status = \"playing\"
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"quakespasm\"";
+        let games = parse_config(config).expect("Bad config");
+        let listed = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let Ok((options, tags)) = ListOptions::parse(&args) else {
+                panic!("Bad arguments");
+            };
+            list_games(&games, &tags, &options, &HashMap::new())
+        };
+        assert_eq!(
+            listed(&["--status", "playing"]),
+            vec!["doom - Doom", "monkey - Monkey Island"]
+        );
+        assert_eq!(
+            listed(&["--status", "playing", "--platform", "native"]),
+            vec!["doom - Doom"]
+        );
+        assert!(listed(&["--platform", "wine"]).is_empty());
+    }
 
+    #[test]
+    fn test_list_by_hours() {
+        let config = "
         [games]
-        [games.hades2]
-        name = \"Hades II\"
-        steam_id=\"1145350\"";
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"quakespasm\"
 
+        [games.hexen]
+        name = \"Hexen\"
+        cmd = \"dsda-doom\"";
         let games = parse_config(config).expect("Bad config");
-        let game = games.find("hades2").unwrap();
-        assert_eq!(game.command, vec!["steam", "steam://rungameid/1145350"]);
+        let now = UtcDateTime::now();
+        let all_stats = stats::test_stats(&[("doom", 12 * 3600, now), ("quake", 3600, now)]);
+        let listed = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let Ok((options, tags)) = ListOptions::parse(&args) else {
+                panic!("Bad arguments");
+            };
+            list_games(&games, &tags, &options, &all_stats)
+        };
+        assert_eq!(listed(&["--min-hours", "10"]), vec!["doom - Doom"]);
+        assert_eq!(
+            listed(&["--max-hours", "1h"]),
+            vec!["hexen - Hexen", "quake - Quake"]
+        );
+        assert_eq!(
+            listed(&["--min-hours", "30m", "--max-hours", "2"]),
+            vec!["quake - Quake"]
+        );
     }
 
     #[test]
-    fn test_steam_game_does_not_use_mangohud() {
+    fn test_list_by_last_played() {
         let config = "
         [games]
-        [games.hades2]
-        use_mangohud = true
-        name = \"Hades II\"
-        steam_id=\"1145350\"";
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"quakespasm\"
 
+        [games.hexen]
+        name = \"Hexen\"
+        cmd = \"dsda-doom\"";
         let games = parse_config(config).expect("Bad config");
-        let game = games.find("hades2").unwrap();
-        assert_eq!(game.command, vec!["steam", "steam://rungameid/1145350"]);
+        let days_ago = |days| UtcDateTime::now() - time::Duration::days(days);
+        let all_stats =
+            stats::test_stats(&[("doom", 3600, days_ago(2)), ("quake", 3600, days_ago(200))]);
+        let listed = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let Ok((options, tags)) = ListOptions::parse(&args) else {
+                panic!("Bad arguments");
+            };
+            list_games(&games, &tags, &options, &all_stats)
+        };
+        assert_eq!(listed(&["--played-since", "30d"]), vec!["doom - Doom"]);
+        assert_eq!(
+            listed(&["--not-played-since", "90d"]),
+            vec!["hexen - Hexen", "quake - Quake"]
+        );
+        assert_eq!(listed(&["--played-since", "1970-01-01"]).len(), 2);
+    }
+
+    #[test]
+    fn test_list_by_rating() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        rating = 9
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"quakespasm\"
+        rating = 7.5
+
+        [games.hexen]
+        name = \"Hexen\"
+        cmd = \"dsda-doom\"
+
+        [games.heretic]
+        name = \"Heretic\"
+        cmd = \"dsda-doom\"
+        rating = 9.5";
+        let games = parse_config(config).expect("Bad config");
+        let listed = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let Ok((options, tags)) = ListOptions::parse(&args) else {
+                panic!("Bad arguments");
+            };
+            list_games(&games, &tags, &options, &HashMap::new())
+        };
+        assert_eq!(
+            listed(&["--sort", "rating"]),
+            vec![
+                "heretic - Heretic",
+                "doom - Doom",
+                "quake - Quake",
+                "hexen - Hexen"
+            ]
+        );
+        assert_eq!(
+            listed(&["--min-rating", "8", "--long"]),
+            vec![
+                "doom - Doom (rated 9, never played)",
+                "heretic - Heretic (rated 9.5, never played)"
+            ]
+        );
+        let invalid = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        rating = 11";
+        assert!(parse_config(invalid).is_err());
+    }
+
+    #[test]
+    fn test_tags_among() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        tags = [\"fps\", \"retro\"]
+
+        [games.witcher]
+        name = \"The Witcher\"
+        wine_exe = \"witcher.exe\"
+        tags = [\"wine\", \"rpg\"]
+
+        [games.fear]
+        name = \"F.E.A.R.\"
+        wine_exe = \"fear.exe\"
+        tags = [\"wine\", \"fps\", \"horror\"]";
+        let games = parse_config(config).expect("Bad config");
+        assert_eq!(
+            tags_among(&games, &[]),
+            vec!["fps", "horror", "retro", "rpg", "wine"]
+        );
+        assert_eq!(
+            tags_among(&games, &["wine".to_string()]),
+            vec!["fps", "horror", "rpg"]
+        );
+        assert_eq!(
+            tags_among(&games, &["wine,!horror".to_string()]),
+            vec!["rpg"]
+        );
+    }
+
+    #[test]
+    fn test_group_games() {
+        let config = "
+        [directories]
+        gog = \"/games/gog\"
+
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        tags = [\"fps\", \"retro\"]
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"quakespasm\"
+        dir_prefix = \"gog\"
+        dir = \"quake\"
+        tags = [\"fps\"]
+
+        [games.monkey]
+        name = \"Monkey Island\"
+        cmd = \"scummvm monkey\"";
+        let games = parse_config(config).expect("Bad config");
+        let listed = listed_games(&games, &[], &ListOptions::default(), &HashMap::new());
+        let ids = |group_by| -> Vec<(String, Vec<String>)> {
+            group_games(&listed, group_by)
+                .into_iter()
+                .map(|(name, games)| (name, games.iter().map(|g| g.id.clone()).collect()))
+                .collect()
+        };
+        let group = |name: &str, ids: &[&str]| {
+            let ids = ids.iter().map(|id| id.to_string()).collect();
+            (name.to_string(), ids)
+        };
+        assert_eq!(
+            ids(ListGroup::Tag),
+            vec![
+                group("fps", &["doom", "quake"]),
+                group("retro", &["doom"]),
+                group("(no tags)", &["monkey"]),
+            ]
+        );
+        assert_eq!(
+            ids(ListGroup::Prefix),
+            vec![
+                group("gog", &["quake"]),
+                group("(none)", &["doom", "monkey"])
+            ]
+        );
+        assert_eq!(
+            ids(ListGroup::Platform),
+            vec![
+                group("native", &["doom", "quake"]),
+                group("scummvm", &["monkey"])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_game_whose_title_matches_the_tag_is_included_in_matches() {
+        let game = Game {
+            id: "test_game".to_string(),
+            name: "Test Game".to_string(),
+            dir: None,
+            command: vec!["test_game".to_string()],
+            env: HashMap::new(),
+            tags: vec!["tag1".to_string(), "tag2".to_string()],
+            installed: true,
+            ..Default::default()
+        };
+        let tags = vec!["test_game".to_string()];
+        assert!(game_matches_tags(&game, &tags));
+    }
+
+    #[test]
+    fn test_steam_game() {
+        let config = "[games]\n[games.hades2]\nname = \"Hades II\"\nsteam_id=\"1145350\"";
+        let games = parse_config(config).expect("Bad config");
+        let game = games.find("hades2").unwrap();
+        assert_eq!(game.command, vec!["steam", "steam://rungameid/1145350"]);
+    }
+
+    #[test]
+    fn test_steam_game_does_not_use_gamescope() {
+        let config = "
+        [settings]
+        width = 1920
+        height = 1080
+        use_gamescope = true
+
+        [games]
+        [games.hades2]
+        name = \"Hades II\"
+        steam_id=\"1145350\"";
+
+        let games = parse_config(config).expect("Bad config");
+        let game = games.find("hades2").unwrap();
+        assert_eq!(game.command, vec!["steam", "steam://rungameid/1145350"]);
+    }
+
+    #[test]
+    fn test_steam_game_does_not_use_mangohud() {
+        let config = "
+        [games]
+        [games.hades2]
+        use_mangohud = true
+        name = \"Hades II\"
+        steam_id=\"1145350\"";
+
+        let games = parse_config(config).expect("Bad config");
+        let game = games.find("hades2").unwrap();
+        assert_eq!(game.command, vec!["steam", "steam://rungameid/1145350"]);
+    }
+
+    #[test]
+    fn test_wine_game_runs_exe_directly_on_windows() {
+        let directories = Table::new();
+        let settings = Settings {
+            width: 1920,
+            height: 1080,
+            use_gamescope: true,
+            platform: Platform::Windows,
+            ..Default::default()
+        };
+        let game = GameBuilder::new("test".to_string(), &directories, &settings)
+            .name("Test Game".to_string())
+            .command(vec!["wine".to_string(), "Test.exe".to_string()])
+            .mangohud(true)
+            .use_vk(false)
+            .build()
+            .expect("Bad game");
+        assert_eq!(game.command, vec!["Test.exe"]);
+        assert!(!game.env.contains_key("WINEDLLOVERRIDES"));
+    }
+
+    #[test]
+    fn test_no_gamescope_or_mangohud_on_macos() {
+        let directories = Table::new();
+        let settings = Settings {
+            width: 1920,
+            height: 1080,
+            use_gamescope: true,
+            platform: Platform::MacOs,
+            ..Default::default()
+        };
+        let game = GameBuilder::new("test".to_string(), &directories, &settings)
+            .name("Test Game".to_string())
+            .command(vec!["wine".to_string(), "Test.exe".to_string()])
+            .build()
+            .expect("Bad game");
+        assert_eq!(game.command, vec!["wine", "Test.exe"]);
+    }
+
+    #[test]
+    fn test_steam_game_on_windows() {
+        let directories = Table::new();
+        let settings = Settings {
+            width: 1920,
+            height: 1080,
+            use_gamescope: false,
+            platform: Platform::Windows,
+            ..Default::default()
+        };
+        let game = GameBuilder::new("hades2".to_string(), &directories, &settings)
+            .name("Hades II".to_string())
+            .steam_id("1145350")
+            .build()
+            .expect("Bad game");
+        assert_eq!(
+            game.command,
+            vec!["cmd", "/C", "start", "", "steam://rungameid/1145350"]
+        );
+    }
+
+    #[test]
+    fn test_added_date() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        added = 2024-05-01
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"vkquake\"
+        added = \"2023-01-15\"";
+        let games = parse_config(config).expect("Bad config");
+        let doom = games.find("doom").unwrap();
+        assert_eq!(doom.added, stats::parse_date("2024-05-01"));
+        let quake = games.find("quake").unwrap();
+        assert_eq!(quake.added, stats::parse_date("2023-01-15"));
+    }
+
+    #[test]
+    fn test_invalid_added_date_produces_error() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        added = \"last year\"";
+        match parse_config(config) {
+            Err(ParseError::InvalidOption(game_id, option)) => {
+                assert_eq!(game_id, "doom");
+                assert_eq!(option, "added");
+            }
+            _ => panic!("This config should produce an error"),
+        }
+    }
+
+    #[test]
+    fn test_roulette_skips_dropped_games_and_favors_favorites() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        favorite = true
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"vkquake\"
+        status = \"dropped\"
+
+        [games.hexen]
+        name = \"Hexen\"
+        cmd = \"dsda-doom -iwad HEXEN.WAD\"";
+        let games = parse_config(config).expect("Bad config");
+        let candidates: Vec<&str> = games
+            .roulette_candidates(&[], None)
+            .iter()
+            .map(|game| game.id.as_str())
+            .collect();
+        assert_eq!(candidates, vec!["doom", "doom", "hexen"]);
+    }
+
+    #[test]
+    fn test_roulette_under_hours() {
+        let config = "
+        [games]
+        [games.balatro]
+        name = \"Balatro\"
+        cmd = \"balatro\"
+        session_length = \"30m\"
+        length_hours = 100
+
+        [games.inside]
+        name = \"Inside\"
+        cmd = \"inside\"
+        length_hours = 3.5
+
+        [games.witcher3]
+        name = \"The Witcher 3\"
+        cmd = \"witcher3\"
+        session_length = 2";
+        let games = parse_config(config).expect("Bad config");
+        let candidates = |hours: f64| -> Vec<String> {
+            games
+                .roulette_candidates(&[], Some(hours))
+                .iter()
+                .map(|game| game.id.to_string())
+                .collect()
+        };
+        assert_eq!(candidates(1.0), vec!["balatro"]);
+        assert_eq!(candidates(4.0), vec!["balatro", "inside", "witcher3"]);
+    }
+
+    #[test]
+    fn test_no_random_game_when_nothing_matches() {
+        let config = "[games]\n[games.doom]\nname = \"Doom\"\ncmd = \"dsda-doom\"";
+        let games = parse_config(config).expect("Bad config");
+        assert!(
+            games
+                .random(&["nonexistent_tag".to_string()], None)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_remind_after_minutes_defaults_to_setting() {
+        let config = "
+        [settings]
+        remind_after_minutes = 120
+
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"vkquake\"
+        remind_after_minutes = 30";
+        let games = parse_config(config).expect("Bad config");
+        assert_eq!(games.find("doom").unwrap().remind_after_minutes, Some(120));
+        assert_eq!(games.find("quake").unwrap().remind_after_minutes, Some(30));
+    }
+
+    #[test]
+    fn test_controller_profile() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        controller_profile = \"/home/test/profiles/doom.amgp\"";
+        let games = parse_config(config).expect("Bad config");
+        let game = games.find("doom").unwrap();
+        assert_eq!(
+            game.controller_command,
+            Some(vec![
+                "antimicrox".to_string(),
+                "--hidden".to_string(),
+                "--profile".to_string(),
+                "/home/test/profiles/doom.amgp".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_custom_controller_mapper() {
+        let config = "
+        [settings]
+        controller_mapper = \"scc-daemon {profile} start\"
+
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        controller_profile = \"doom.sccprofile\"";
+        let games = parse_config(config).expect("Bad config");
+        let game = games.find("doom").unwrap();
+        assert_eq!(
+            game.controller_command,
+            Some(vec![
+                "scc-daemon".to_string(),
+                "doom.sccprofile".to_string(),
+                "start".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_pause_compositor() {
+        let config = "
+        [settings]
+        compositor_pause_cmd = \"pkill picom\"
+        compositor_resume_cmd = \"picom -b\"
+
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        pause_compositor = true
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"vkquake\"";
+        let games = parse_config(config).expect("Bad config");
+        let doom = games.find("doom").unwrap();
+        assert_eq!(doom.before_launch, vec!["pkill picom"]);
+        assert_eq!(doom.after_exit, vec!["picom -b"]);
+        let quake = games.find("quake").unwrap();
+        assert!(quake.before_launch.is_empty());
+        assert!(quake.after_exit.is_empty());
+    }
+
+    #[test]
+    fn test_gamescope_prefer_output() {
+        let config = "
+        [settings]
+        use_gamescope = true
+        prefer_output = \"HDMI-A-1\"
+
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        cmd = \"openmw\"
+        prefer_output = \"DP-1\"
+        display = 1";
+        let games = parse_config(config).expect("Bad config");
+        let game = games.find("morrowind").unwrap();
+        assert_eq!(
+            game.command,
+            vec![
+                "gamescope",
+                "-W",
+                "1280",
+                "-H",
+                "720",
+                "-f",
+                "--force-grab-cursor",
+                "--prefer-output",
+                "DP-1",
+                "--",
+                "openmw"
+            ]
+        );
+        assert_eq!(game.env["SDL_VIDEO_FULLSCREEN_DISPLAY"], "1");
+    }
+
+    #[test]
+    fn test_wm_rules() {
+        let config = "
+        [settings]
+        window_manager = \"sway\"
+
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        cmd = \"openmw\"
+        wm_rules = { workspace = 9, fullscreen = true, class = \"openmw\" }";
+        let games = parse_config(config).expect("Bad config");
+        let rules = games.find("morrowind").unwrap().wm_rules.as_ref().unwrap();
+        assert_eq!(rules.window_manager, Some(WindowManager::Sway));
+        assert_eq!(rules.workspace, Some("9".to_string()));
+        assert!(rules.fullscreen);
+        assert_eq!(rules.class, Some("openmw".to_string()));
+
+        let config = "
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        cmd = \"openmw\"
+        wm_rules = { monitor = 2 }";
+        assert!(matches!(
+            parse_config(config),
+            Err(ParseError::InvalidOption(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_mangohud_config() {
+        let config = "
+        [settings]
+        use_gamescope = true
+
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        cmd = \"openmw\"
+        use_mangohud = true
+        fps_limit = 60
+        mangohud_config = { position = \"top-right\", preset = 2, no_display = true }";
+        let games = parse_config(config).expect("Bad config");
+        let game = games.find("morrowind").unwrap();
+        assert!(game.command.contains(&"--mangoapp".to_string()));
+        assert_eq!(
+            game.env["MANGOHUD_CONFIG"],
+            "fps_limit=60,no_display,position=top-right,preset=2"
+        );
+    }
+
+    #[test]
+    fn test_capture_fps() {
+        let config = "
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        cmd = \"openmw\"
+        capture_fps = true";
+        let games = parse_config(config).expect("Bad config");
+        let game = games.find("morrowind").unwrap();
+        let log_dir = data_dir().join(FPS_LOG_DIR).join("morrowind");
+        assert_eq!(game.command, vec!["mangohud", "openmw"]);
+        assert_eq!(game.fps_log_dir, Some(log_dir.clone()));
+        assert_eq!(
+            game.env["MANGOHUD_CONFIG"],
+            format!("output_folder={},autostart_log=1", log_dir.display())
+        );
+    }
+
+    #[test]
+    fn test_record() {
+        let config = "
+        [settings]
+        capture_cmd = \"wf-recorder -f {output}\"
+        capture_dir = \"/home/test/Clips\"
+        capture_ext = \"mkv\"
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        cmd = \"openmw\"
+        record = true";
+        let games = parse_config(config).expect("Bad config");
+        let capture = games.find("morrowind").unwrap().capture.as_ref().unwrap();
+        assert_eq!(capture.command, "wf-recorder -f {output}");
+        assert_eq!(capture.stop_command, None);
+        assert_eq!(capture.dir, PathBuf::from("/home/test/Clips"));
+        assert_eq!(capture.ext, "mkv");
+    }
+
+    #[test]
+    fn test_wrap_keeps_env_but_replaces_command() {
+        let config = "
+        [games]
+        [games.portal]
+        name = \"Portal\"
+        steam_id = \"400\"
+        dir = \"/games/portal\"
+        installed = false
+        env = { DXVK_HUD = \"fps\" }";
+        let games = parse_config(config).expect("Bad config");
+        let command = vec!["/steam/portal/hl2_linux".to_string(), "-game".to_string()];
+        let wrapped = games.find("portal").unwrap().wrap(command.clone());
+        assert_eq!(wrapped.command, command);
+        assert_eq!(wrapped.env["DXVK_HUD"], "fps");
+        assert_eq!(wrapped.dir, None);
+        assert!(wrapped.is_installed());
+    }
+
+    #[test]
+    fn test_wine_registry() {
+        let config = "
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        wine_exe = \"Morrowind.exe\"
+        wine_dpi = 144
+
+        [games.morrowind.wine_registry.'HKEY_CURRENT_USER\\Software\\Wine\\X11 Driver']
+        Decorated = \"N\"";
+        let games = parse_config(config).expect("Bad config");
+        let commands = &games.find("morrowind").unwrap().registry_commands;
+        assert_eq!(commands.len(), 2);
+        assert_eq!(
+            commands[0][..4],
+            [
+                "wine",
+                "reg",
+                "add",
+                "HKEY_CURRENT_USER\\Software\\Wine\\X11 Driver"
+            ]
+        );
+        assert_eq!(commands[1][5], "LogPixels");
+
+        let invalid = "
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        wine_exe = \"Morrowind.exe\"
+        wine_registry = { Decorated = \"N\" }";
+        assert!(parse_config(invalid).is_err());
+    }
+
+    #[test]
+    fn test_version_play_times() {
+        let at = |timestamp| UtcDateTime::from_unix_timestamp(timestamp).unwrap();
+        let session = |game_id: &str, start, version: &str| {
+            Session::new(game_id.to_string(), at(start), 60, String::new()).with_version(version)
+        };
+        let sessions = vec![
+            Session::new("gzdoom".to_string(), at(0), 30, String::new()),
+            session("gzdoom", 100, "4.10"),
+            session("quake", 200, "1.0"),
+            session("gzdoom", 300, "4.11"),
+            session("gzdoom", 400, "4.10"),
+        ];
+        assert_eq!(
+            version_play_times(&sessions, "gzdoom"),
+            vec![("4.10", 120), ("4.11", 60)]
+        );
+    }
+
+    #[test]
+    fn test_track_stats() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"doom\"
+
+        [games.demo]
+        name = \"Demo\"
+        cmd = \"demo\"
+        track_stats = false";
+        let games = parse_config(config).expect("Bad config");
+        assert!(games.find("doom").unwrap().track_stats);
+        assert!(!games.find("demo").unwrap().track_stats);
+    }
+
+    #[test]
+    fn test_language() {
+        let config = "
+        [games]
+        [games.gothic]
+        name = \"Gothic\"
+        wine_exe = \"Gothic.exe\"
+        language = \"de_DE\"
+
+        [games.witcher]
+        name = \"The Witcher\"
+        cmd = \"witcher\"
+        language = \"pl_PL.ISO-8859-2\"
+        env = { LANGUAGE = \"pl\" }";
+        let games = parse_config(config).expect("Bad config");
+        let gothic = games.find("gothic").unwrap();
+        assert_eq!(gothic.env["LANG"], "de_DE.UTF-8");
+        assert_eq!(gothic.env["LC_ALL"], "de_DE.UTF-8");
+        assert_eq!(gothic.env["LANGUAGE"], "de_DE");
+        let witcher = games.find("witcher").unwrap();
+        assert_eq!(witcher.env["LC_ALL"], "pl_PL.ISO-8859-2");
+        assert_eq!(witcher.env["LANGUAGE"], "pl");
+
+        let invalid = "
+        [games]
+        [games.gothic]
+        name = \"Gothic\"
+        cmd = \"gothic\"
+        language = \"de DE\"";
+        assert!(parse_config(invalid).is_err());
+    }
+
+    #[test]
+    fn test_tricks_command() {
+        let config = "
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        wine_exe = \"Morrowind.exe\"
+        env = { WINEPREFIX = \"/games/prefixes/morrowind\" }
+
+        [games.portal]
+        name = \"Portal\"
+        steam_id = \"400\"
+
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"gzdoom\"";
+        let games = parse_config(config).expect("Bad config");
+        let verbs = vec!["corefonts".to_string()];
+        assert_eq!(
+            games.find("morrowind").unwrap().tricks_command(&verbs),
+            Some(vec!["winetricks".to_string(), "corefonts".to_string()])
+        );
+        assert_eq!(
+            games.find("portal").unwrap().tricks_command(&verbs),
+            Some(vec![
+                "protontricks".to_string(),
+                "400".to_string(),
+                "corefonts".to_string()
+            ])
+        );
+        assert_eq!(games.find("doom").unwrap().tricks_command(&verbs), None);
+
+        let morrowind = games.find("morrowind").unwrap();
+        assert_eq!(
+            morrowind.wine_tool_command("winecfg", &[]),
+            Some(vec!["wine".to_string(), "winecfg".to_string()])
+        );
+        assert_eq!(
+            games
+                .find("portal")
+                .unwrap()
+                .wine_tool_command("regedit", &[]),
+            Some(vec![
+                "protontricks".to_string(),
+                "400".to_string(),
+                "regedit".to_string()
+            ])
+        );
+        assert_eq!(
+            games
+                .find("doom")
+                .unwrap()
+                .wine_tool_command("winecfg", &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_runner() {
+        let directories = Table::new();
+        let settings = Settings {
+            runner: Some("wine-staging".to_string()),
+            runners: OnceLock::from(vec![runners::Runner {
+                name: "GE-Proton9-20".to_string(),
+                wine: PathBuf::from("/compat/GE-Proton9-20/files/bin/wine"),
+            }]),
+            platform: Platform::Linux,
+            ..Default::default()
+        };
+        let game = GameBuilder::new("test".to_string(), &directories, &settings)
+            .name("Test".to_string())
+            .command(vec!["wine".to_string(), "Test.exe".to_string()])
+            .mangohud(false)
+            .runner("GE-Proton9-20".to_string())
+            .kill_wineserver()
+            .build()
+            .expect("Failed to build game");
+        assert_eq!(
+            game.command,
+            vec!["/compat/GE-Proton9-20/files/bin/wine", "Test.exe"]
+        );
+        assert_eq!(game.env["WINE"], "/compat/GE-Proton9-20/files/bin/wine");
+        assert_eq!(
+            game.kill_wineserver,
+            Some(vec![
+                "/compat/GE-Proton9-20/files/bin/wineserver".to_string(),
+                "-k".to_string()
+            ])
+        );
+
+        // The default runner is not installed, which only matters once the
+        // game is launched
+        let game = GameBuilder::new("test".to_string(), &directories, &settings)
+            .name("Test".to_string())
+            .command(vec!["wine".to_string(), "Test.exe".to_string()])
+            .build()
+            .expect("Failed to build game");
+        assert_eq!(game.missing_runner.as_deref(), Some("wine-staging"));
+        assert!(matches!(
+            game.run(),
+            Err(GameError::NoSuchRunner("wine-staging"))
+        ));
+    }
+
+    #[test]
+    fn test_sandbox() {
+        let directories = Table::new();
+        let settings = Settings {
+            platform: Platform::MacOs,
+            ..Default::default()
+        };
+        let game = GameBuilder::new("test".to_string(), &directories, &settings)
+            .name("Test".to_string())
+            .command(vec!["./setup".to_string()])
+            .sandbox(Sandbox::Firejail(None))
+            .build()
+            .expect("Failed to build game");
+        assert_eq!(game.command, vec!["./setup"]);
+        assert_eq!(game.missing_sandbox.as_deref(), Some("firejail"));
+        assert!(matches!(game.run(), Err(GameError::NoSandbox("firejail"))));
+
+        let result = GameBuilder::new("test".to_string(), &directories, &settings)
+            .name("Test".to_string())
+            .steam_id("620")
+            .sandbox(Sandbox::Bwrap { offline: false })
+            .build();
+        assert!(matches!(
+            result,
+            Err(ParseError::InvalidOption(_, option)) if option == "sandbox"
+        ));
+    }
+
+    #[test]
+    fn test_update_cmd() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        update_cmd = \"git pull --ff-only\"";
+        let games = parse_config(config).expect("Bad config");
+        assert_eq!(
+            games.find("doom").unwrap().update_command,
+            Some(vec![
+                "git".to_string(),
+                "pull".to_string(),
+                "--ff-only".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_companions() {
+        let config = "
+        [games]
+        [games.eve]
+        name = \"EVE Online\"
+        cmd = \"eve\"
+        companions = [\"mumble\", \"pyfa --hidden\"]";
+        let games = parse_config(config).expect("Bad config");
+        assert_eq!(
+            games.find("eve").unwrap().companions,
+            vec![
+                vec!["mumble".to_string()],
+                vec!["pyfa".to_string(), "--hidden".to_string()]
+            ]
+        );
+        let config = "[games.eve]\nname = \"EVE\"\ncmd = \"eve\"\ncompanions = [\"\"]";
+        assert!(matches!(
+            parse_config(config),
+            Err(ParseError::InvalidOption(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_required_games() {
+        let config = "
+        [games.factorio]
+        name = \"Factorio\"
+        cmd = \"factorio\"
+        requires = [\"factorio_server\"]
+        stop_required = true
+        [games.factorio_server]
+        name = \"Factorio Server\"
+        cmd = \"factorio --start-server world.zip\"
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"gzdoom\"";
+        let table = config_table(config, None).unwrap();
+        let games = parse_games(&table, Some(&["factorio".to_string()])).expect("Bad config");
+        assert!(games.find("factorio_server").is_some());
+        assert!(games.find("doom").is_none());
+        assert!(games.find("factorio").unwrap().stop_required);
+
+        let config =
+            "[games.factorio]\nname = \"Factorio\"\ncmd = \"factorio\"\nrequires = [\"server\"]";
+        assert!(matches!(
+            parse_config(config),
+            Err(ParseError::NoSuchRequiredGame(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_required_games_chain() {
+        let config = "
+        [games.x]
+        name = \"X\"
+        cmd = \"x\"
+        requires = [\"a\"]
+        [games.a]
+        name = \"A\"
+        cmd = \"a\"
+        requires = [\"b\"]
+        [games.b]
+        name = \"B\"
+        cmd = \"b\"
+        requires = [\"x\"]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"gzdoom\"";
+        let table = config_table(config, None).unwrap();
+        let games = parse_games(&table, Some(&["x".to_string()])).expect("Bad config");
+        let mut ids: Vec<&String> = games.games.keys().collect();
+        ids.sort();
+        assert_eq!(ids, vec!["a", "b", "x"]);
+    }
+
+    #[test]
+    fn test_game_id_with_line_break_is_rejected() {
+        let config = "
+        [games]
+        [games.\"doom\\n2\"]
+        name = \"Doom II\"
+        cmd = \"dsda-doom\"";
+        assert!(matches!(
+            parse_config(config),
+            Err(ParseError::InvalidGameId(_))
+        ));
     }
 }
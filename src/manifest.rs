@@ -0,0 +1,190 @@
+use crate::game::GameError;
+use crate::{Games, MANIFEST_DIR, data_dir};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// The SHA-256 hashes of every file under a game's directory, stored in the
+/// same format as `sha256sum`
+pub struct Manifest {
+    hashes: BTreeMap<String, String>,
+}
+
+pub enum Problem {
+    Missing(String),
+    Changed(String),
+}
+
+impl Problem {
+    pub fn format(&self) -> String {
+        match self {
+            Problem::Missing(path) => format!("missing: {}", path),
+            Problem::Changed(path) => format!("changed: {}", path),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn generate(dir: &Path) -> io::Result<Manifest> {
+        let mut hashes = BTreeMap::new();
+        add_hashes(dir, "", &mut hashes)?;
+        Ok(Manifest { hashes })
+    }
+
+    pub fn parse(content: &str) -> Manifest {
+        let hashes = content
+            .lines()
+            .filter_map(|line| line.split_once("  "))
+            .map(|(hash, path)| (path.to_string(), hash.to_string()))
+            .collect();
+        Manifest { hashes }
+    }
+
+    pub fn format(&self) -> String {
+        let mut s = String::new();
+        for (path, hash) in self.hashes.iter() {
+            s.push_str(&format!("{}  {}\n", hash, path));
+        }
+        s
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Check the files under the directory against the manifest; files that
+    /// are not in the manifest are ignored
+    pub fn verify(&self, dir: &Path) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        for (path, hash) in self.hashes.iter() {
+            match hash_file(&dir.join(path)) {
+                Ok(actual) if actual == *hash => (),
+                Ok(_) => problems.push(Problem::Changed(path.to_string())),
+                Err(_) => problems.push(Problem::Missing(path.to_string())),
+            }
+        }
+        problems
+    }
+}
+
+fn add_hashes(dir: &Path, prefix: &str, hashes: &mut BTreeMap<String, String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            add_hashes(&entry.path(), &format!("{}/", name), hashes)?;
+        } else if file_type.is_file() {
+            hashes.insert(name, hash_file(&entry.path())?);
+        }
+    }
+    Ok(())
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn command_verify<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let init = args.first().is_some_and(|arg| arg == "--init");
+    let args = if init { &args[1..] } else { args };
+    if args.is_empty() {
+        return Err(GameError::NoGameId);
+    }
+    let game = match games.find(&args[0]) {
+        Some(game) => game,
+        None => return Err(GameError::NoSuchGame(&args[0])),
+    };
+    let dir = match &game.dir {
+        Some(dir) => PathBuf::from(dir),
+        None => return Err(GameError::NoGameDir(game.id.clone())),
+    };
+    let manifest_path = data_dir()
+        .join(MANIFEST_DIR)
+        .join(format!("{}.sha256", game.id));
+
+    if init {
+        let manifest = match Manifest::generate(&dir) {
+            Ok(manifest) => manifest,
+            Err(e) => return Err(GameError::CouldNotWriteManifest(e.to_string())),
+        };
+        debug!("Writing manifest: {}", manifest_path.display());
+        let result = fs::create_dir_all(manifest_path.parent().unwrap())
+            .and_then(|_| fs::write(&manifest_path, manifest.format()));
+        if let Err(e) = result {
+            return Err(GameError::CouldNotWriteManifest(e.to_string()));
+        }
+        out!("Recorded {} files for {}", manifest.len(), game.id);
+        return Ok(());
+    }
+
+    debug!("Reading manifest: {}", manifest_path.display());
+    let manifest = match fs::read_to_string(&manifest_path) {
+        Ok(content) => Manifest::parse(&content),
+        Err(_) => return Err(GameError::NoManifest(game.id.clone())),
+    };
+    let problems = manifest.verify(&dir);
+    for problem in problems.iter() {
+        out!("{}", problem.format());
+    }
+    if problems.is_empty() {
+        out!("All {} files OK", manifest.len());
+        Ok(())
+    } else {
+        Err(GameError::VerificationFailed(
+            game.id.clone(),
+            problems.len(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_manifest() {
+        let content =
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  data/abc.txt\n";
+        let manifest = Manifest::parse(content);
+        assert_eq!(manifest.len(), 1);
+        assert_eq!(manifest.format(), content);
+    }
+
+    #[test]
+    fn test_verify_detects_changed_and_missing_files() {
+        let dir = std::env::temp_dir().join(format!("game_rs_manifest_{}", std::process::id()));
+        fs::create_dir_all(dir.join("data")).unwrap();
+        fs::write(dir.join("data/abc.txt"), "abc").unwrap();
+        fs::write(dir.join("game.exe"), "game").unwrap();
+        let manifest = Manifest::generate(&dir).unwrap();
+        assert!(manifest.format().starts_with(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  data/abc.txt\n"
+        ));
+
+        fs::write(dir.join("data/abc.txt"), "abd").unwrap();
+        fs::remove_file(dir.join("game.exe")).unwrap();
+        let problems: Vec<String> = manifest.verify(&dir).iter().map(|p| p.format()).collect();
+        assert_eq!(problems, vec!["changed: data/abc.txt", "missing: game.exe"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+use crate::game::GameError;
+use crate::stats::{GameStats, format_play_time};
+use crate::{Game, Games, icon, json, play_command, play_game, read_all_stats};
+use std::path::Path;
+
+/// The installed games, sorted by name, in the order menus list them
+pub fn installed_games(games: &Games) -> Vec<&Game> {
+    let mut installed: Vec<&Game> = games.games.values().filter(|g| g.is_installed()).collect();
+    installed.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+    installed
+}
+
+/// The line for a game in a dmenu-style menu
+pub fn line(game: &Game) -> String {
+    format!("{} ({})", game.name, game.id)
+}
+
+/// The entry for a game in a JSON menu, as a single line
+pub fn json_entry(game: &Game, stats: Option<&GameStats>, icon: Option<&Path>) -> String {
+    let icon = match icon {
+        Some(icon) => json::string(&icon.to_string_lossy()),
+        None => "null".to_string(),
+    };
+    let play_time_seconds = stats.map_or(0, |s| s.play_time_seconds());
+    json::object(&[
+        ("id", json::string(&game.id)),
+        ("name", json::string(&game.name)),
+        ("icon", icon),
+        (
+            "playtime",
+            json::string(&format_play_time(play_time_seconds)),
+        ),
+        ("play_time_seconds", play_time_seconds.to_string()),
+    ])
+}
+
+/// The ID of the game on a line chosen from the menu, which may also be a
+/// JSON entry or just the ID on its own
+pub fn game_id(line: &str) -> String {
+    let line = line.trim();
+    if line.starts_with('{') {
+        return json_id(line).unwrap_or_default();
+    }
+    let game_id = match line
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once(" ("))
+    {
+        Some((_, game_id)) => game_id,
+        None => line,
+    };
+    game_id.to_string()
+}
+
+/// The value of the `"id"` field of a JSON entry
+fn json_id(entry: &str) -> Option<String> {
+    let (_, rest) = entry.split_once("\"id\"")?;
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let mut chars = rest.strip_prefix('"')?.chars();
+    let mut id = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(id),
+            '\\' => id.push(chars.next()?),
+            c => id.push(c),
+        }
+    }
+    None
+}
+
+pub fn command_menu<'a>(games: &'a Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let mut rofi = false;
+    let mut json_entries = false;
+    let mut play = false;
+    let mut entry = None;
+    for arg in args.iter() {
+        match arg.as_str() {
+            "--dmenu" => rofi = false,
+            "--rofi" => rofi = true,
+            "--json" => json_entries = true,
+            "--play" => play = true,
+            _ if rofi && entry.is_none() => entry = Some(arg.as_str()),
+            _ => return Err(GameError::InvalidArgument(arg)),
+        }
+    }
+    if json_entries {
+        let all_stats = read_all_stats();
+        for game in installed_games(games) {
+            let icon = icon::game_icon(game);
+            out!(
+                "{}",
+                json_entry(game, all_stats.get(&game.id), icon.as_deref())
+            );
+        }
+        return Ok(());
+    }
+    if play && !rofi {
+        return play_from_menu(games);
+    }
+    match entry {
+        // rofi runs the script again with the chosen entry, and stays open
+        // until it exits, so the game is started in the background
+        Some(entry) => {
+            let game_id = game_id(entry);
+            if games.find(&game_id).is_none() {
+                return Err(GameError::NoSuchMenuEntry(entry.to_string()));
+            }
+            let result = play_command(&game_id)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn();
+            if result.is_err() {
+                return Err(GameError::ExecutionFailed);
+            }
+        }
+        None => {
+            for game in installed_games(games) {
+                out!("{}", line(game));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Play the game on the line that a menu wrote to our stdin
+pub fn play_from_menu<'a>(games: &'a Games) -> Result<(), GameError<'a>> {
+    let mut line = String::new();
+    if let Err(e) = std::io::stdin().read_line(&mut line) {
+        return Err(GameError::TerminalError(e.to_string()));
+    }
+    // Closing the menu without choosing anything writes nothing
+    if line.trim().is_empty() {
+        return Ok(());
+    }
+    match games.find(&game_id(&line)) {
+        Some(game) => play_game(games, game),
+        None => Err(GameError::NoSuchMenuEntry(line.trim().to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+    use time::UtcDateTime;
+
+    #[test]
+    fn test_game_id() {
+        assert_eq!(game_id("Doom (doom)\n"), "doom");
+        assert_eq!(game_id("Half-Life (Source) (hl2)"), "hl2");
+        assert_eq!(game_id("quake"), "quake");
+        assert_eq!(game_id("{\"id\": \"doom\", \"name\":\"Doom\"}"), "doom");
+        assert_eq!(game_id("{\"name\":\"Doom\"}"), "");
+    }
+
+    #[test]
+    fn test_json_entry() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        cover = \"/covers/doom.png\"";
+        let games = parse_config(config).expect("Bad config");
+        let stats = GameStats::new("doom".to_string(), 3660, UtcDateTime::now());
+        let game = &games.games["doom"];
+        let entry = json_entry(game, Some(&stats), game.cover.as_deref());
+        assert_eq!(
+            entry,
+            "{\"id\":\"doom\",\"name\":\"Doom\",\"icon\":\"/covers/doom.png\",\"playtime\":\"1h1m\",\"play_time_seconds\":3660}"
+        );
+        assert_eq!(game_id(&entry), "doom");
+    }
+}
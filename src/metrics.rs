@@ -0,0 +1,95 @@
+use crate::Games;
+use crate::sessions::Session;
+use crate::stats::GameStats;
+use std::collections::{HashMap, HashSet};
+
+/// Format play statistics in the Prometheus text exposition format
+pub fn format(
+    games: &Games,
+    all_stats: &HashMap<String, GameStats>,
+    sessions: &[Session],
+    running: &HashSet<String>,
+) -> String {
+    let mut game_ids: Vec<&String> = games.games.keys().collect();
+    game_ids.sort();
+    let mut session_counts: HashMap<&str, usize> = HashMap::new();
+    for session in sessions.iter() {
+        *session_counts.entry(session.id()).or_default() += 1;
+    }
+
+    let mut lines = vec![
+        "# HELP game_rs_play_time_seconds_total Total time each game has been played".to_string(),
+        "# TYPE game_rs_play_time_seconds_total counter".to_string(),
+    ];
+    for game_id in game_ids.iter() {
+        let seconds = all_stats.get(*game_id).map_or(0, |s| s.play_time_seconds());
+        lines.push(format!(
+            "game_rs_play_time_seconds_total{{game=\"{}\"}} {}",
+            escape_label(game_id),
+            seconds
+        ));
+    }
+    lines.push("# HELP game_rs_sessions_total Number of recorded play sessions".to_string());
+    lines.push("# TYPE game_rs_sessions_total counter".to_string());
+    for game_id in game_ids.iter() {
+        lines.push(format!(
+            "game_rs_sessions_total{{game=\"{}\"}} {}",
+            escape_label(game_id),
+            session_counts.get(game_id.as_str()).copied().unwrap_or(0)
+        ));
+    }
+    lines.push("# HELP game_rs_running Whether the game is running right now".to_string());
+    lines.push("# TYPE game_rs_running gauge".to_string());
+    for game_id in game_ids.iter() {
+        lines.push(format!(
+            "game_rs_running{{game=\"{}\"}} {}",
+            escape_label(game_id),
+            running.contains(*game_id) as u8
+        ));
+    }
+    let mut metrics = lines.join("\n");
+    metrics.push('\n');
+    metrics
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+    use crate::stats::test_stats;
+    use time::UtcDateTime;
+
+    #[test]
+    fn test_format_metrics() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"vkquake\"";
+        let games = parse_config(config).expect("Bad config");
+        let all_stats = test_stats(&[("doom", 3600, UtcDateTime::now())]);
+        let sessions = vec![Session::new(
+            "doom".to_string(),
+            UtcDateTime::now(),
+            3600,
+            String::new(),
+        )];
+        let running = HashSet::from(["quake".to_string()]);
+        let metrics = format(&games, &all_stats, &sessions, &running);
+        assert!(metrics.contains("game_rs_play_time_seconds_total{game=\"doom\"} 3600\n"));
+        assert!(metrics.contains("game_rs_sessions_total{game=\"quake\"} 0\n"));
+        assert!(metrics.contains("game_rs_running{game=\"quake\"} 1\n"));
+        assert!(metrics.contains("game_rs_running{game=\"doom\"} 0\n"));
+    }
+}
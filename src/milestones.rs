@@ -0,0 +1,152 @@
+use crate::game::{Game, GameError};
+use crate::stats::{self, format_utc, parse_utc};
+use crate::{Games, data_dir, tsv};
+use std::fs;
+use std::io::Write;
+use time::{UtcDateTime, UtcOffset};
+
+/// The file of milestones for every game, in the data directory
+pub const MILESTONES_FILE: &str = "milestones.tsv";
+
+/// Something reached in a game, like "Beat the final boss", for games that
+/// have no achievements of their own
+pub struct Milestone {
+    id: String,
+    time: UtcDateTime,
+    text: String,
+}
+
+impl Milestone {
+    pub fn new(id: String, time: UtcDateTime, text: &str) -> Milestone {
+        Milestone {
+            id,
+            time,
+            text: text.replace(['\t', '\n', '\r'], " "),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The local date it was reached on
+    pub fn date(&self) -> String {
+        let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+        self.time.to_offset(offset).date().to_string()
+    }
+
+    pub fn to_tsv(&self) -> String {
+        tsv::format_line(&[&self.id, &format_utc(self.time), &self.text])
+    }
+
+    pub fn from_tsv(line: &str) -> Milestone {
+        let parts = tsv::split_line(line);
+        Milestone {
+            id: parts[0].to_string(),
+            time: parse_utc(&parts[1]).expect("Bad timestamp"),
+            text: parts.get(2).cloned().unwrap_or_default(),
+        }
+    }
+}
+
+/// The game's milestones from the file, in the order they were reached
+pub fn for_game(content: &str, game_id: &str) -> Vec<Milestone> {
+    let mut milestones: Vec<Milestone> = content
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(Milestone::from_tsv)
+        .filter(|milestone| milestone.id == game_id)
+        .collect();
+    milestones.sort_by_key(|milestone| milestone.time);
+    milestones
+}
+
+pub fn command_milestone<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let Some(subcommand) = args.first() else {
+        return Err(GameError::UnrecognizedSubcommand(""));
+    };
+    if subcommand != "add" && subcommand != "list" {
+        return Err(GameError::UnrecognizedSubcommand(subcommand));
+    }
+    let Some(game_id) = args.get(1) else {
+        return Err(GameError::NoGameId);
+    };
+    let Some(game) = games.find(game_id) else {
+        return Err(GameError::NoSuchGame(game_id));
+    };
+    if subcommand == "list" {
+        for milestone in read_milestones(game) {
+            out!("{} {}", milestone.date(), milestone.text());
+        }
+        return Ok(());
+    }
+    if args.len() < 3 {
+        return Err(GameError::InvalidArgument(subcommand));
+    }
+    let milestone = Milestone::new(game.id.clone(), UtcDateTime::now(), &args[2..].join(" "));
+    let path = data_dir().join(MILESTONES_FILE);
+    debug!("Appending to milestones: {}", path.display());
+    let result = fs::create_dir_all(data_dir()).and_then(|_| {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        writeln!(file, "{}", milestone.to_tsv())
+    });
+    result.map_err(|e| GameError::CouldNotWriteMilestones(e.to_string()))
+}
+
+fn read_milestones(game: &Game) -> Vec<Milestone> {
+    let path = data_dir().join(MILESTONES_FILE);
+    debug!("Reading milestones: {}", path.display());
+    match fs::read_to_string(&path) {
+        Ok(content) => for_game(&content, &game.id),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn print_goal(game: &Game, play_time_seconds: u32) {
+    if let Some(goal_hours) = game.goal_hours {
+        out!(
+            "{}",
+            tr!(
+                "stats-goal",
+                goal_hours = goal_hours,
+                progress = stats::format_goal_progress(play_time_seconds, goal_hours)
+            )
+        );
+    }
+}
+
+pub fn print_milestones(game: &Game) {
+    for milestone in read_milestones(game) {
+        out!(
+            "{}",
+            tr!(
+                "info-milestone",
+                milestone = milestone.text(),
+                date = milestone.date()
+            )
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_game() {
+        let content = "\
+            doom\t2025-11-04 10:00:00\tBeat E1M8\n\
+            quake\t2025-11-02 10:00:00\tFound all secrets in E1M1\n\
+            doom\t2025-11-03 19:07:00\tFound the BFG\n";
+        let milestones = for_game(content, "doom");
+        let texts: Vec<&str> = milestones.iter().map(|m| m.text()).collect();
+        assert_eq!(texts, vec!["Found the BFG", "Beat E1M8"]);
+        assert_eq!(
+            milestones[1].to_tsv(),
+            "doom\t2025-11-04 10:00:00\tBeat E1M8"
+        );
+    }
+}
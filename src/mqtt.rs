@@ -0,0 +1,206 @@
+use std::io::{self, Read, Write};
+use std::net::{Ipv6Addr, TcpStream, ToSocketAddrs};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const DEFAULT_PORT: u16 = 1883;
+const TIMEOUT: Duration = Duration::from_secs(3);
+const CLIENT_ID: &str = "game_rs";
+const KEEP_ALIVE_SECONDS: u16 = 60;
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const DISCONNECT: u8 = 0xe0;
+const PROTOCOL_LEVEL: u8 = 4;
+const CLEAN_SESSION: u8 = 0x02;
+const PASSWORD_FLAG: u8 = 0x40;
+const USERNAME_FLAG: u8 = 0x80;
+
+/// Where to publish play events, from the `mqtt_*` settings
+#[derive(Clone)]
+pub struct Mqtt {
+    pub broker: String,
+    pub topic: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Mqtt {
+    /// Publish an event under the topic, as `TOPIC/EVENT`, in the background
+    /// so that a broker that is down never holds up a game
+    pub fn publish(&self, event: &str, payload: String) -> Publishing {
+        let mqtt = self.clone();
+        let topic = format!("{}/{}", self.topic, event);
+        Publishing(Some(thread::spawn(move || {
+            debug!("Publishing to MQTT topic {}: {}", topic, payload);
+            if let Err(e) = mqtt.try_publish(&topic, &payload) {
+                warn!("Could not publish to MQTT broker {}: {}", mqtt.broker, e);
+            }
+        })))
+    }
+
+    fn try_publish(&self, topic: &str, payload: &str) -> io::Result<()> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "invalid broker address");
+        let (host, port) = host_and_port(&self.broker).ok_or_else(invalid)?;
+        let mut stream = None;
+        let mut error = io::Error::new(io::ErrorKind::NotFound, "broker address not found");
+        for address in (host, port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, TIMEOUT) {
+                Ok(connected) => {
+                    stream = Some(connected);
+                    break;
+                }
+                Err(e) => error = e,
+            }
+        }
+        let mut stream = stream.ok_or(error)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        stream.write_all(&connect_packet(
+            self.username.as_deref(),
+            self.password.as_deref(),
+        ))?;
+        let mut connack = [0; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[0] != CONNACK || connack[3] != 0 {
+            let message = format!("connection refused (return code {})", connack[3]);
+            return Err(io::Error::new(io::ErrorKind::ConnectionRefused, message));
+        }
+        stream.write_all(&publish_packet(topic, payload.as_bytes()))?;
+        stream.write_all(&[DISCONNECT, 0])
+    }
+}
+
+/// An event being published, which is waited for when this is dropped, so
+/// that events are published in order and before the program exits
+pub struct Publishing(Option<JoinHandle<()>>);
+
+impl Publishing {
+    pub fn is_finished(&self) -> bool {
+        self.0.as_ref().is_none_or(|thread| thread.is_finished())
+    }
+}
+
+impl Drop for Publishing {
+    fn drop(&mut self) {
+        if let Some(thread) = self.0.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The host and port of a broker given as `HOST`, `HOST:PORT`, an IPv6
+/// address, or `[IPV6]:PORT`
+fn host_and_port(broker: &str) -> Option<(&str, u16)> {
+    if broker.parse::<Ipv6Addr>().is_ok() {
+        return Some((broker, DEFAULT_PORT));
+    }
+    if let Some(rest) = broker.strip_prefix('[') {
+        let (host, port) = rest.split_once(']')?;
+        return match port.strip_prefix(':') {
+            Some(port) => Some((host, port.parse().ok()?)),
+            None if port.is_empty() => Some((host, DEFAULT_PORT)),
+            None => None,
+        };
+    }
+    match broker.split_once(':') {
+        Some((host, port)) => Some((host, port.parse().ok()?)),
+        None => Some((broker, DEFAULT_PORT)),
+    }
+}
+
+fn connect_packet(username: Option<&str>, password: Option<&str>) -> Vec<u8> {
+    let mut flags = CLEAN_SESSION;
+    let mut body = encode_string("MQTT");
+    let mut payload = encode_string(CLIENT_ID);
+    if let Some(username) = username {
+        flags |= USERNAME_FLAG;
+        payload.extend(encode_string(username));
+        if let Some(password) = password {
+            flags |= PASSWORD_FLAG;
+            payload.extend(encode_string(password));
+        }
+    }
+    body.push(PROTOCOL_LEVEL);
+    body.push(flags);
+    body.extend(KEEP_ALIVE_SECONDS.to_be_bytes());
+    body.extend(payload);
+    packet(CONNECT, body)
+}
+
+/// A PUBLISH packet with QoS 0, so there is no acknowledgement to wait for
+fn publish_packet(topic: &str, payload: &[u8]) -> Vec<u8> {
+    let mut body = encode_string(topic);
+    body.extend_from_slice(payload);
+    packet(PUBLISH, body)
+}
+
+fn packet(packet_type: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut packet = vec![packet_type];
+    packet.extend(encode_remaining_length(body.len()));
+    packet.extend(body);
+    packet
+}
+
+/// The length of the rest of a packet, seven bits at a time with the high bit
+/// set on every byte but the last
+fn encode_remaining_length(mut length: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        bytes.push(byte);
+        if length == 0 {
+            return bytes;
+        }
+    }
+}
+
+fn encode_string(s: &str) -> Vec<u8> {
+    let mut bytes = (s.len() as u16).to_be_bytes().to_vec();
+    bytes.extend_from_slice(s.as_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_and_port() {
+        assert_eq!(host_and_port("pi.local"), Some(("pi.local", 1883)));
+        assert_eq!(host_and_port("10.0.0.2:1884"), Some(("10.0.0.2", 1884)));
+        assert_eq!(host_and_port("fd00::2"), Some(("fd00::2", 1883)));
+        assert_eq!(host_and_port("[fd00::2]"), Some(("fd00::2", 1883)));
+        assert_eq!(host_and_port("[fd00::2]:1884"), Some(("fd00::2", 1884)));
+        assert_eq!(host_and_port("pi.local:mqtt"), None);
+    }
+
+    #[test]
+    fn test_encode_remaining_length() {
+        assert_eq!(encode_remaining_length(0), vec![0x00]);
+        assert_eq!(encode_remaining_length(127), vec![0x7f]);
+        assert_eq!(encode_remaining_length(321), vec![0xc1, 0x02]);
+    }
+
+    #[test]
+    fn test_publish_packet() {
+        assert_eq!(
+            publish_packet("a/b", b"hi"),
+            vec![0x30, 7, 0, 3, b'a', b'/', b'b', b'h', b'i']
+        );
+    }
+
+    #[test]
+    fn test_connect_packet_with_credentials() {
+        let packet = connect_packet(Some("user"), Some("pass"));
+        assert_eq!(packet[0], CONNECT);
+        // Protocol name, level, and flags
+        assert_eq!(&packet[2..10], &[0, 4, b'M', b'Q', b'T', b'T', 4, 0xc2]);
+        assert!(packet.ends_with(&[0, 4, b'u', b's', b'e', b'r', 0, 4, b'p', b'a', b's', b's']));
+    }
+}
@@ -0,0 +1,119 @@
+use crate::game::GameError;
+use crate::platform::Platform;
+use crate::stats::GameStats;
+use crate::{APP_NAME, Game, Games, STATUS_DROPPED, read_all_stats};
+use std::collections::HashMap;
+use time::{Date, UtcDateTime};
+
+/// How long a game can go unplayed before it is nudged, unless the config
+/// says otherwise
+pub const DEFAULT_NUDGE_AFTER_DAYS: u32 = 30;
+
+/// A game that has not been played for a while
+pub struct Nudge<'a> {
+    pub game: &'a Game,
+    pub days: i64,
+    /// Whether it has ever been played, or has only been waiting since it
+    /// was added
+    pub played: bool,
+}
+
+/// The games that have not been played for at least the given number of
+/// days, the longest untouched first; a game that was never played counts
+/// from the day it was added, and is left out if that is not known
+pub fn find<'a>(
+    games: impl Iterator<Item = &'a Game>,
+    all_stats: &HashMap<String, GameStats>,
+    now: UtcDateTime,
+    after_days: u32,
+) -> Vec<Nudge<'a>> {
+    let today: Date = now.date();
+    let mut nudges: Vec<Nudge> = games
+        .filter_map(|game| {
+            let (since, played) = match all_stats.get(&game.id) {
+                Some(stats) => (stats.last_played_time().date(), true),
+                None => (game.added?, false),
+            };
+            let days = (today - since).whole_days();
+            Some(Nudge { game, days, played })
+        })
+        .filter(|nudge| nudge.days >= after_days as i64)
+        .collect();
+    nudges.sort_by(|a, b| b.days.cmp(&a.days).then(a.game.id.cmp(&b.game.id)));
+    nudges
+}
+
+/// List the installed games that have not been played for a while, and
+/// optionally show them in a desktop notification
+pub fn command_nudge<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let mut after_days = games.settings.nudge_after_days;
+    let mut notify = false;
+    for arg in args.iter() {
+        if arg == "--notify" {
+            notify = true;
+        } else {
+            match arg.parse::<u32>() {
+                Ok(days) => after_days = days,
+                Err(_) => return Err(GameError::InvalidArgument(arg)),
+            }
+        }
+    }
+    let all_stats = read_all_stats();
+    let candidates = games
+        .games
+        .values()
+        .filter(|game| game.is_installed() && game.status.as_deref() != Some(STATUS_DROPPED));
+    let nudges = find(candidates, &all_stats, UtcDateTime::now(), after_days);
+    let messages: Vec<String> = nudges
+        .iter()
+        .map(|nudge| {
+            let key = if nudge.played {
+                "nudge-played"
+            } else {
+                "nudge-never-played"
+            };
+            tr!(key, name = nudge.game.name, days = nudge.days)
+        })
+        .collect();
+    for message in messages.iter() {
+        out!("{}", message);
+    }
+    if notify && !messages.is_empty() {
+        Platform::current().notify(APP_NAME, &messages.join("\n"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::test_stats;
+    use time::Duration;
+
+    #[test]
+    fn test_find_nudges() {
+        let now = UtcDateTime::from_unix_timestamp(1762214646).unwrap();
+        let game = |id: &str, added: Option<Date>| Game {
+            id: id.to_string(),
+            name: id.to_string(),
+            added,
+            ..Default::default()
+        };
+        let games = [
+            game("disco", None),
+            game("doom", None),
+            game("hades", Some(now.date() - Duration::days(40))),
+            game("quake", None),
+        ];
+        let all_stats = test_stats(&[
+            ("disco", 3600, now - Duration::days(94)),
+            ("doom", 3600, now - Duration::days(2)),
+        ]);
+        let nudges = find(games.iter(), &all_stats, now, 30);
+        let found: Vec<(&str, i64, bool)> = nudges
+            .iter()
+            .map(|n| (n.game.id.as_str(), n.days, n.played))
+            .collect();
+        assert_eq!(found, vec![("disco", 94, true), ("hades", 40, false)]);
+    }
+}
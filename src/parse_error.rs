@@ -7,4 +7,29 @@ pub enum ParseError {
     NoSuchDirectoryPrefix(String, String),
     TomlError(String),
     UnrecognizedOption(String),
+    InvalidOption(String, String),
+    InvalidSetting(String),
+    InvalidGameId(String),
+    DirectoryCycle(String),
+    NoSuchRequiredGame(String, String),
+}
+
+impl ParseError {
+    /// A short, machine-readable name for the error
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ParseError::MissingName(_) => "missing_name",
+            ParseError::MissingCommand(_) => "missing_command",
+            ParseError::GameNotTable => "game_not_table",
+            ParseError::MissingGameTable => "missing_game_table",
+            ParseError::NoSuchDirectoryPrefix(_, _) => "no_such_directory_prefix",
+            ParseError::TomlError(_) => "toml_error",
+            ParseError::UnrecognizedOption(_) => "unrecognized_option",
+            ParseError::InvalidOption(_, _) => "invalid_option",
+            ParseError::InvalidSetting(_) => "invalid_setting",
+            ParseError::InvalidGameId(_) => "invalid_game_id",
+            ParseError::DirectoryCycle(_) => "directory_cycle",
+            ParseError::NoSuchRequiredGame(_, _) => "no_such_required_game",
+        }
+    }
 }
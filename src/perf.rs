@@ -0,0 +1,239 @@
+use crate::game::{Game, GameError};
+use crate::parse_error::ParseError;
+use crate::{Games, full_config, game_builder};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use toml::Table;
+
+/// Frame rate statistics from a MangoHud log
+#[derive(Debug, PartialEq)]
+pub struct FpsSummary {
+    pub samples: usize,
+    pub average: f64,
+    pub one_percent_low: f64,
+}
+
+impl FpsSummary {
+    /// Summarize a MangoHud CSV log, where the frame samples follow a header
+    /// row starting with `fps`
+    pub fn from_csv(content: &str) -> Option<FpsSummary> {
+        let mut fps: Vec<f64> = content
+            .lines()
+            .skip_while(|line| !line.starts_with("fps,"))
+            .skip(1)
+            .filter_map(|line| line.split(',').next())
+            .filter_map(|value| value.trim().parse::<f64>().ok())
+            .collect();
+        if fps.is_empty() {
+            return None;
+        }
+        fps.sort_by(|a, b| a.total_cmp(b));
+        let samples = fps.len();
+        let average = fps.iter().sum::<f64>() / samples as f64;
+        let low_count = (samples / 100).max(1);
+        let one_percent_low = fps[..low_count].iter().sum::<f64>() / low_count as f64;
+        Some(FpsSummary {
+            samples,
+            average,
+            one_percent_low,
+        })
+    }
+
+    pub fn format(&self) -> String {
+        format!(
+            "Average FPS: {:.1}\n1% Low FPS: {:.1}\nSamples: {}",
+            self.average, self.one_percent_low, self.samples
+        )
+    }
+}
+
+/// The most recently modified MangoHud log in the given directory, if it was
+/// modified no earlier than `since`
+pub fn latest_log(dir: &Path, since: SystemTime) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "csv"))
+        .filter(|path| {
+            // MangoHud also writes a summary file next to each log
+            !path
+                .file_stem()
+                .is_some_and(|stem| stem.to_string_lossy().ends_with("_summary"))
+        })
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((modified, path))
+        })
+        .filter(|(modified, _)| *modified >= since)
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+pub fn command_perf<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    if args.is_empty() {
+        return Err(GameError::NoGameId);
+    }
+    let game = match games.find(&args[0]) {
+        Some(game) => game,
+        None => return Err(GameError::NoSuchGame(&args[0])),
+    };
+    print_fps_summary(games, game, SystemTime::UNIX_EPOCH)
+}
+
+pub fn command_bench<'a>(games: &'a Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    if args.is_empty() {
+        return Err(GameError::NoGameId);
+    }
+    let game = match games.find(&args[0]) {
+        Some(game) => game,
+        None => return Err(GameError::NoSuchGame(&args[0])),
+    };
+    let time_limit = match args.get(1) {
+        Some(arg) => match arg.parse::<u64>() {
+            Ok(seconds) => Some(Duration::from_secs(seconds)),
+            Err(_) => return Err(GameError::InvalidArgument(arg)),
+        },
+        None => None,
+    };
+    let config = full_config();
+    let Some(game_config) = config
+        .get("games")
+        .and_then(|g| g.get(&game.id))
+        .and_then(|g| g.as_table())
+    else {
+        return Err(GameError::NoSuchGame(&args[0]));
+    };
+    let mut bench_game =
+        benchmark_game(games, game_config, game).map_err(GameError::InvalidConfig)?;
+    bench_game.time_limit = time_limit;
+    let started = SystemTime::now();
+    let start = Instant::now();
+    match bench_game.run() {
+        Ok(()) => (),
+        // Stopping the game at the time limit is not a failure
+        Err(GameError::CommandReturnedFailure(_))
+            if time_limit.is_some_and(|limit| start.elapsed() >= limit) => {}
+        Err(e) => return Err(game.error_from_copy(e)),
+    }
+    // A log left over from an earlier run would give a misleading result
+    print_fps_summary(games, game, started)
+}
+
+/// The game as if it had `capture_fps = true`, so that it is run the same way
+/// but logs its frame rate
+fn benchmark_game(games: &Games, game_config: &Table, game: &Game) -> Result<Game, ParseError> {
+    game_builder(&game.id, game_config, &games.directories, &games.settings)?
+        .capture_fps()
+        .installed(game.installed)
+        .build()
+}
+
+fn print_fps_summary<'a>(
+    games: &Games,
+    game: &Game,
+    since: SystemTime,
+) -> Result<(), GameError<'a>> {
+    let dir = games.settings.fps_log_dir.join(&game.id);
+    let summary = latest_log(&dir, since).and_then(|path| {
+        debug!("Reading FPS log: {}", path.display());
+        let content = fs::read_to_string(&path).ok()?;
+        FpsSummary::from_csv(&content)
+    });
+    match summary {
+        Some(summary) => {
+            out!("Game: {} ({})", game.name, game.id);
+            out!("{}", summary.format());
+            Ok(())
+        }
+        None => Err(GameError::NoFpsLog(game.id.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_table;
+    use crate::parse_games;
+
+    #[test]
+    fn test_summarize_log() {
+        let mut log = String::from(
+            "os,cpu,gpu,ram,kernel,driver,cpuscheduler\n\
+             Arch Linux,Ryzen,Radeon,32GB,6.9,Mesa,none\n\
+             fps,frametime,cpu_load,gpu_load\n",
+        );
+        for _ in 0..99 {
+            log.push_str("60.0,16.6,20,80\n");
+        }
+        log.push_str("30.0,33.3,20,80\n");
+        let summary = FpsSummary::from_csv(&log).unwrap();
+        assert_eq!(summary.samples, 100);
+        assert!((summary.average - 59.7).abs() < 0.001);
+        assert_eq!(summary.one_percent_low, 30.0);
+    }
+
+    #[test]
+    fn test_log_without_samples() {
+        assert_eq!(FpsSummary::from_csv("fps,frametime\n"), None);
+    }
+
+    #[test]
+    fn test_latest_log() {
+        let dir = std::env::temp_dir().join(format!("game_rs_fps_logs_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("openmw_2025-11-03_19-07-00.csv"), "fps\n").unwrap();
+        fs::write(dir.join("openmw_2025-11-03_19-07-00_summary.csv"), "fps\n").unwrap();
+        assert_eq!(
+            latest_log(&dir, SystemTime::UNIX_EPOCH),
+            Some(dir.join("openmw_2025-11-03_19-07-00.csv"))
+        );
+        let later = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(latest_log(&dir, later), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_benchmark_game() {
+        let config = "
+        [settings]
+        use_gamescope = true
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        cmd = \"openmw\"";
+        let config = config_table(config, None).expect("Bad config");
+        let games = parse_games(&config, None).expect("Bad config");
+        let game = games.find("morrowind").expect("No game");
+        let game_config = config["games"]["morrowind"].as_table().unwrap();
+        let bench_game = benchmark_game(&games, game_config, game).expect("Bad config");
+        assert_eq!(bench_game.command[0], "gamescope");
+        assert!(bench_game.command.contains(&"--mangoapp".to_string()));
+        assert!(!bench_game.command.contains(&"mangohud".to_string()));
+        let log_dir = games.settings.fps_log_dir.join("morrowind");
+        assert_eq!(bench_game.fps_log_dir, Some(log_dir.clone()));
+        assert!(
+            bench_game.env["MANGOHUD_CONFIG"]
+                .contains(&format!("output_folder={}", log_dir.display()))
+        );
+        assert!(bench_game.installed);
+    }
+
+    #[test]
+    fn test_benchmark_game_keeps_installed_state() {
+        let config = "
+        [games]
+        [games.morrowind]
+        name = \"Morrowind\"
+        cmd = \"openmw\"";
+        let config = config_table(config, None).expect("Bad config");
+        let games = parse_games(&config, None).expect("Bad config");
+        let mut game = games.find("morrowind").expect("No game").clone();
+        game.installed = false;
+        let game_config = config["games"]["morrowind"].as_table().unwrap();
+        let bench_game = benchmark_game(&games, game_config, &game).expect("Bad config");
+        assert!(!bench_game.installed);
+    }
+}
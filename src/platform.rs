@@ -0,0 +1,145 @@
+use std::env::var_os;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::fsutil;
+
+/// Editors to fall back to, in order of preference
+const LINUX_EDITORS: [&str; 3] = ["nano", "vi", "xdg-open"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Platform {
+    Linux,
+    MacOs,
+    Windows,
+}
+
+impl Default for Platform {
+    fn default() -> Platform {
+        Platform::current()
+    }
+}
+
+impl Platform {
+    pub fn current() -> Platform {
+        if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        }
+    }
+
+    /// The directory under which the application's config directory lives
+    pub fn config_base_dir(&self, home: &Path) -> PathBuf {
+        match self {
+            Platform::Linux => home.join(".config"),
+            Platform::MacOs => home.join("Library").join("Application Support"),
+            Platform::Windows => match var_os("APPDATA") {
+                Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+                _ => home.join("AppData").join("Roaming"),
+            },
+        }
+    }
+
+    /// The directory under which the application's data directory lives
+    pub fn data_base_dir(&self, home: &Path) -> PathBuf {
+        match self {
+            Platform::Linux => home.join(".local").join("share"),
+            Platform::MacOs => home.join("Library").join("Application Support"),
+            Platform::Windows => match var_os("LOCALAPPDATA") {
+                Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+                _ => home.join("AppData").join("Local"),
+            },
+        }
+    }
+
+    /// Where Steam is installed by default, if there is such a place
+    pub fn steam_dir(&self, home: &Path) -> Option<PathBuf> {
+        match self {
+            Platform::Linux | Platform::MacOs => Some(self.data_base_dir(home).join("Steam")),
+            Platform::Windows => {
+                var_os("ProgramFiles(x86)").map(|dir| PathBuf::from(dir).join("Steam"))
+            }
+        }
+    }
+
+    /// Wine is only needed where Windows executables cannot be run directly
+    pub fn needs_wine(&self) -> bool {
+        *self != Platform::Windows
+    }
+
+    /// Mangohud and gamescope are only available on Linux
+    pub fn has_linux_tools(&self) -> bool {
+        *self == Platform::Linux
+    }
+
+    /// The command used to open a URL, such as a steam:// link
+    pub fn open_url_command(&self, url: &str) -> Vec<String> {
+        match self {
+            Platform::Linux => vec!["steam".to_string(), url.to_string()],
+            Platform::MacOs => vec!["open".to_string(), url.to_string()],
+            Platform::Windows => vec![
+                "cmd".to_string(),
+                "/C".to_string(),
+                "start".to_string(),
+                "".to_string(),
+                url.to_string(),
+            ],
+        }
+    }
+
+    /// The editor to use when none has been configured: a terminal editor if
+    /// one is installed, or else whatever opens text files on the desktop
+    pub fn default_editor(&self) -> Option<Vec<String>> {
+        match self {
+            Platform::Linux => LINUX_EDITORS
+                .iter()
+                .find(|editor| fsutil::on_path(editor))
+                .map(|editor| vec![editor.to_string()]),
+            Platform::MacOs => Some(vec!["open".to_string(), "-W".to_string(), "-t".to_string()]),
+            Platform::Windows => Some(vec!["notepad".to_string()]),
+        }
+    }
+
+    /// Show a desktop notification, if the platform has a way to do so
+    pub fn notify(&self, title: &str, message: &str) {
+        let mut command = match self {
+            Platform::Linux => {
+                let mut c = Command::new("notify-send");
+                c.arg(title).arg(message);
+                c
+            }
+            Platform::MacOs => {
+                let script = format!("display notification {:?} with title {:?}", message, title);
+                let mut c = Command::new("osascript");
+                c.arg("-e").arg(script);
+                c
+            }
+            Platform::Windows => return,
+        };
+        let result = command.stdout(Stdio::null()).stderr(Stdio::null()).status();
+        if result.is_err() {
+            debug!("Could not send notification: {}", message);
+        }
+    }
+
+    /// A command that runs the given command line through the platform's shell
+    pub fn shell_command(&self, command_line: &str) -> Command {
+        let mut command = match self {
+            Platform::Windows => {
+                let mut c = Command::new("cmd");
+                c.arg("/C");
+                c
+            }
+            _ => {
+                let mut c = Command::new("sh");
+                c.arg("-c");
+                c
+            }
+        };
+        command.arg(command_line);
+        command
+    }
+}
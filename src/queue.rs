@@ -0,0 +1,153 @@
+use crate::game::GameError;
+use crate::{Games, QUEUE_FILE, data_dir, play_game};
+use std::fs;
+use std::path::PathBuf;
+
+pub struct Queue {
+    game_ids: Vec<String>,
+}
+
+impl Queue {
+    pub fn parse(content: &str) -> Queue {
+        let game_ids = content
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_string())
+            .collect();
+        Queue { game_ids }
+    }
+
+    pub fn format(&self) -> String {
+        let mut s = String::new();
+        for game_id in self.game_ids.iter() {
+            s.push_str(game_id);
+            s.push('\n');
+        }
+        s
+    }
+
+    pub fn game_ids(&self) -> &[String] {
+        &self.game_ids
+    }
+
+    pub fn head(&self) -> Option<&str> {
+        self.game_ids.first().map(|id| id.as_str())
+    }
+
+    pub fn add(&mut self, game_id: &str) {
+        self.game_ids.push(game_id.to_string());
+    }
+
+    /// Remove the first occurrence of the given game ID from the queue
+    pub fn remove(&mut self, game_id: &str) {
+        if let Some(index) = self.game_ids.iter().position(|id| id == game_id) {
+            self.game_ids.remove(index);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.game_ids.clear();
+    }
+}
+
+pub fn command_queue<'a>(games: &'a Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let mut queue = read_queue();
+    let subcommand = if args.is_empty() { "list" } else { &args[0] };
+    match subcommand {
+        "add" => {
+            if args.len() < 2 {
+                return Err(GameError::NoGameId);
+            }
+            for game_id in args[1..].iter() {
+                if games.find(game_id).is_none() {
+                    return Err(GameError::NoSuchGame(game_id));
+                }
+                queue.add(game_id);
+            }
+            write_queue(&queue)
+        }
+        "list" => {
+            for (i, game_id) in queue.game_ids().iter().enumerate() {
+                match games.find(game_id) {
+                    Some(game) => out!("{}. {}", i + 1, game.format()),
+                    None => out!("{}. {}", i + 1, game_id),
+                }
+            }
+            Ok(())
+        }
+        "next" => {
+            let game_id = match queue.head() {
+                Some(game_id) => game_id.to_string(),
+                None => return Err(GameError::QueueEmpty),
+            };
+            let game = match games.find(&game_id) {
+                Some(game) => game,
+                None => return Err(GameError::NoSuchQueuedGame(game_id)),
+            };
+            play_game(games, game)?;
+
+            // The queue may have been edited while the game was running
+            let mut queue = read_queue();
+            queue.remove(&game_id);
+            write_queue(&queue)
+        }
+        "clear" => {
+            queue.clear();
+            write_queue(&queue)
+        }
+        _ => Err(GameError::UnrecognizedSubcommand(&args[0])),
+    }
+}
+
+fn read_queue() -> Queue {
+    match fs::read_to_string(queue_file_path()) {
+        Ok(content) => Queue::parse(&content),
+        Err(_) => Queue::parse(""),
+    }
+}
+
+fn write_queue<'a>(queue: &Queue) -> Result<(), GameError<'a>> {
+    match fs::write(queue_file_path(), queue.format()) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(GameError::CouldNotWriteQueue(e.to_string())),
+    }
+}
+
+fn queue_file_path() -> PathBuf {
+    data_dir().join(QUEUE_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_queue() {
+        let queue = Queue::parse("doom\n\nquake\n");
+        assert_eq!(queue.game_ids(), ["doom", "quake"]);
+        assert_eq!(queue.head(), Some("doom"));
+    }
+
+    #[test]
+    fn test_format_queue() {
+        let mut queue = Queue::parse("");
+        queue.add("doom");
+        queue.add("quake");
+        assert_eq!(queue.format(), "doom\nquake\n");
+    }
+
+    #[test]
+    fn test_remove_only_removes_first_occurrence() {
+        let mut queue = Queue::parse("doom\nquake\ndoom\n");
+        queue.remove("doom");
+        assert_eq!(queue.game_ids(), ["quake", "doom"]);
+    }
+
+    #[test]
+    fn test_empty_queue_has_no_head() {
+        let mut queue = Queue::parse("doom\n");
+        queue.clear();
+        assert_eq!(queue.head(), None);
+    }
+}
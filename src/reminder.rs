@@ -0,0 +1,59 @@
+use crate::platform::Platform;
+use crate::stats::format_play_time;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Periodically reminds the player how long they have been playing, until
+/// stopped
+pub struct Reminder {
+    sender: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl Reminder {
+    pub fn start(game_name: &str, interval_minutes: u32) -> Reminder {
+        let (sender, receiver) = mpsc::channel();
+        let game_name = game_name.to_string();
+        let interval = Duration::from_secs(interval_minutes as u64 * 60);
+        let handle = thread::spawn(move || {
+            let mut elapsed_seconds = 0;
+            // Anything other than a timeout means the game has exited
+            while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(interval) {
+                elapsed_seconds += interval_minutes * 60;
+                let message = reminder_message(&game_name, elapsed_seconds);
+                debug!("Sending reminder: {}", message);
+                Platform::current().notify(APP_TITLE, &message);
+            }
+        });
+        Reminder { sender, handle }
+    }
+
+    pub fn stop(self) {
+        let _ = self.sender.send(());
+        let _ = self.handle.join();
+    }
+}
+
+const APP_TITLE: &str = "game_rs";
+
+fn reminder_message(game_name: &str, elapsed_seconds: u32) -> String {
+    format!(
+        "You've been playing {} for {}",
+        game_name,
+        format_play_time(elapsed_seconds)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reminder_message() {
+        assert_eq!(
+            reminder_message("Doom", 2 * 60 * 60),
+            "You've been playing Doom for 2h"
+        );
+    }
+}
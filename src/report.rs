@@ -0,0 +1,306 @@
+use crate::game::GameError;
+use crate::sessions::Session;
+use crate::stats::{GameStats, format_play_time};
+use crate::summary::{self, Overview};
+use crate::{Games, read_all_stats, read_sessions};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use time::UtcOffset;
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2em; }
+th, td { text-align: left; padding: 0.25em 0.5em; border-bottom: 1px solid #ddd; }
+td.bar { width: 40%; }
+div.bar { background: #4a7ebb; height: 1em; }";
+
+/// One row of the play time table
+pub struct GameRow {
+    pub id: String,
+    pub name: String,
+    pub play_time_seconds: u32,
+    pub last_played: String,
+    pub sessions: usize,
+}
+
+/// Library and play statistics, collected for exporting as a document
+pub struct Report<'a> {
+    pub overview: Overview<'a>,
+    pub games: Vec<GameRow>,
+    pub launchers: Vec<(&'static str, u32)>,
+    /// Total play time for each tag, most played first
+    pub tags: Vec<(String, u32)>,
+    /// The number of sessions in each month, as `YYYY-MM`, oldest first
+    pub months: Vec<(String, u32)>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(
+        games: &Games,
+        all_stats: &HashMap<String, GameStats>,
+        sessions: &'a [Session],
+    ) -> Report<'a> {
+        let mut session_counts: HashMap<&str, usize> = HashMap::new();
+        for session in sessions.iter() {
+            *session_counts.entry(session.id()).or_default() += 1;
+        }
+        let mut rows: Vec<GameRow> = games
+            .games
+            .values()
+            .filter_map(|game| {
+                let stats = all_stats.get(&game.id)?;
+                Some(GameRow {
+                    id: game.id.clone(),
+                    name: game.name.clone(),
+                    play_time_seconds: stats.play_time_seconds(),
+                    last_played: stats.format_last_played_time(),
+                    sessions: session_counts.get(game.id.as_str()).copied().unwrap_or(0),
+                })
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.play_time_seconds
+                .cmp(&a.play_time_seconds)
+                .then(a.id.cmp(&b.id))
+        });
+        let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+        let session_refs: Vec<&Session> = sessions.iter().collect();
+        Report {
+            overview: Overview::new(games, all_stats, sessions),
+            games: rows,
+            launchers: summary::launcher_breakdown(games, all_stats),
+            tags: summary::seconds_per_tag(games, all_stats),
+            months: summary::sessions_per_month(&session_refs, offset),
+        }
+    }
+
+    pub fn markdown(&self) -> String {
+        let mut lines = vec!["# Game Library Report".to_string(), String::new()];
+        lines.push("## Library".to_string());
+        lines.push(String::new());
+        for line in self.overview.format() {
+            lines.push(format!("- {}", escape_markdown(&line)));
+        }
+        lines.push(String::new());
+
+        lines.push("## Play Time".to_string());
+        lines.push(String::new());
+        if self.games.is_empty() {
+            lines.push("No games have been played yet.".to_string());
+        } else {
+            lines.push("| Game | Play Time | Sessions | Last Played |".to_string());
+            lines.push("| --- | ---: | ---: | --- |".to_string());
+            for row in self.games.iter() {
+                lines.push(format!(
+                    "| {} (`{}`) | {} | {} | {} |",
+                    escape_markdown(&row.name),
+                    row.id,
+                    format_play_time(row.play_time_seconds),
+                    row.sessions,
+                    row.last_played
+                ));
+            }
+        }
+
+        if !self.launchers.is_empty() {
+            lines.push(String::new());
+            lines.push("## Launchers".to_string());
+            lines.push(String::new());
+            lines.push("| Launcher | Play Time |".to_string());
+            lines.push("| --- | ---: |".to_string());
+            for (launcher, seconds) in self.launchers.iter() {
+                lines.push(format!("| {} | {} |", launcher, format_play_time(*seconds)));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// A self-contained HTML page, with bars drawn in CSS so that it needs
+    /// nothing but a browser to view
+    pub fn html(&self) -> String {
+        let mut lines = vec![
+            "<!DOCTYPE html>".to_string(),
+            "<html>".to_string(),
+            "<head>".to_string(),
+            "<meta charset=\"utf-8\">".to_string(),
+            "<title>Game Library Report</title>".to_string(),
+            format!("<style>\n{}\n</style>", STYLE),
+            "</head>".to_string(),
+            "<body>".to_string(),
+            "<h1>Game Library Report</h1>".to_string(),
+            "<h2>Library</h2>".to_string(),
+            "<ul>".to_string(),
+        ];
+        for line in self.overview.format() {
+            lines.push(format!("<li>{}</li>", escape_html(&line)));
+        }
+        lines.push("</ul>".to_string());
+
+        lines.push("<h2>Play Time</h2>".to_string());
+        let most = self.games.first().map_or(0, |row| row.play_time_seconds);
+        lines.push(
+            "<table>\n<tr><th>Game</th><th>Play Time</th><th>Sessions</th><th>Last Played</th><th></th></tr>"
+                .to_string(),
+        );
+        for row in self.games.iter() {
+            lines.push(format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td>{}</tr>",
+                escape_html(&row.name),
+                format_play_time(row.play_time_seconds),
+                row.sessions,
+                row.last_played,
+                bar(row.play_time_seconds as usize, most as usize)
+            ));
+        }
+        lines.push("</table>".to_string());
+
+        lines.push("<h2>Sessions by Month</h2>".to_string());
+        let most = self.months.iter().map(|(_, n)| *n).max().unwrap_or(0);
+        lines.push("<table>\n<tr><th>Month</th><th>Sessions</th><th></th></tr>".to_string());
+        for (month, count) in self.months.iter() {
+            lines.push(format!(
+                "<tr><td>{}</td><td>{}</td>{}</tr>",
+                month,
+                count,
+                bar(*count as usize, most as usize)
+            ));
+        }
+        lines.push("</table>".to_string());
+
+        lines.push("<h2>Tags</h2>".to_string());
+        let most = self.tags.first().map_or(0, |(_, seconds)| *seconds);
+        lines.push("<table>\n<tr><th>Tag</th><th>Play Time</th><th></th></tr>".to_string());
+        for (tag, seconds) in self.tags.iter() {
+            lines.push(format!(
+                "<tr><td>{}</td><td>{}</td>{}</tr>",
+                escape_html(tag),
+                format_play_time(*seconds),
+                bar(*seconds as usize, most as usize)
+            ));
+        }
+        lines.push("</table>".to_string());
+        lines.push("</body>".to_string());
+        lines.push("</html>".to_string());
+        lines.join("\n")
+    }
+}
+
+/// A table cell holding a bar as wide as the value's share of the largest
+fn bar(value: usize, most: usize) -> String {
+    let percent = (value * 100).checked_div(most).unwrap_or(0);
+    format!(
+        "<td class=\"bar\"><div class=\"bar\" style=\"width: {}%\"></div></td>",
+        percent
+    )
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escape the characters that would otherwise be read as Markdown formatting
+/// or would break a table row
+fn escape_markdown(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        if matches!(c, '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+pub fn command_report<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let mut html_dir = None;
+    let mut markdown = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // The HTML dashboard has no other formats
+            "--format" if html_dir.is_some() => return Err(GameError::InvalidArgument(arg)),
+            "--html" if markdown => return Err(GameError::InvalidArgument(arg)),
+            "--format" => match args.next() {
+                Some(format) if format == "markdown" => markdown = true,
+                Some(format) => return Err(GameError::InvalidArgument(format)),
+                None => return Err(GameError::InvalidArgument(arg)),
+            },
+            "--html" => match args.next() {
+                Some(dir) => html_dir = Some(PathBuf::from(dir)),
+                None => return Err(GameError::InvalidArgument(arg)),
+            },
+            _ => return Err(GameError::InvalidArgument(arg)),
+        }
+    }
+    let all_stats = read_all_stats();
+    let sessions = read_sessions();
+    let report = Report::new(games, &all_stats, &sessions);
+    let Some(html_dir) = html_dir else {
+        out!("{}", report.markdown());
+        return Ok(());
+    };
+    let html_path = html_dir.join("index.html");
+    debug!("Writing report: {}", html_path.display());
+    let result = fs::create_dir_all(&html_dir).and_then(|_| fs::write(&html_path, report.html()));
+    if let Err(e) = result {
+        return Err(GameError::CouldNotWriteReport(e.to_string()));
+    }
+    out!("Wrote {}", html_path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+    use crate::stats::test_stats;
+    use time::UtcDateTime;
+
+    #[test]
+    fn test_markdown_report() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom | Ultimate\"
+        cmd = \"dsda-doom\"
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"vkquake\"";
+        let games = parse_config(config).expect("Bad config");
+        let all_stats = test_stats(&[("doom", 3600, UtcDateTime::now())]);
+        let sessions = vec![Session::new(
+            "doom".to_string(),
+            UtcDateTime::now(),
+            3600,
+            String::new(),
+        )];
+        let markdown = Report::new(&games, &all_stats, &sessions).markdown();
+        assert!(markdown.starts_with("# Game Library Report\n"));
+        assert!(markdown.contains("- Games: 2\n"));
+        assert!(markdown.contains("| Doom \\| Ultimate (`doom`) | 1h | 1 |"));
+        assert!(!markdown.contains("`quake`"));
+        assert!(markdown.contains("| native | 1h |"));
+    }
+
+    #[test]
+    fn test_html_report() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom & Doom II\"
+        cmd = \"dsda-doom\"
+        tags = [\"fps\"]";
+        let games = parse_config(config).expect("Bad config");
+        let all_stats = test_stats(&[("doom", 3600, UtcDateTime::now())]);
+        let report = Report::new(&games, &all_stats, &[]);
+        assert_eq!(report.tags, vec![("fps".to_string(), 3600)]);
+        let html = report.html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td>Doom &amp; Doom II</td><td>1h</td><td>0</td>"));
+        assert!(html.contains("style=\"width: 100%\""));
+    }
+}
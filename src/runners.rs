@@ -0,0 +1,123 @@
+use crate::game::GameError;
+use crate::{Games, config_edit, config_file_path};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a runner's wine binary lives, relative to its directory: Proton-GE
+/// uses `files`, older Proton builds use `dist`, and plain wine builds have
+/// `bin` at the top level
+const WINE_BINARIES: [&str; 3] = ["files/bin/wine", "dist/bin/wine", "bin/wine"];
+
+/// An installed build of wine or Proton
+#[derive(Debug, Clone, PartialEq)]
+pub struct Runner {
+    pub name: String,
+    pub wine: PathBuf,
+}
+
+impl Runner {
+    /// The runner installed in the given directory, if it has a wine binary
+    pub fn from_dir(dir: &Path) -> Option<Runner> {
+        let name = dir.file_name()?.to_string_lossy().to_string();
+        let wine = WINE_BINARIES
+            .iter()
+            .map(|binary| dir.join(binary))
+            .find(|path| path.is_file())?;
+        Some(Runner { name, wine })
+    }
+}
+
+/// The directories that runners are installed into
+pub fn search_dirs(home: &Path) -> Vec<PathBuf> {
+    vec![
+        home.join(".steam/root/compatibilitytools.d"),
+        home.join(".local/share/Steam/compatibilitytools.d"),
+        PathBuf::from("/opt"),
+    ]
+}
+
+/// Find the runners in the given directories, sorted by name
+pub fn discover(search_dirs: &[PathBuf]) -> Vec<Runner> {
+    let mut runners: Vec<Runner> = Vec::new();
+    for dir in search_dirs.iter() {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if let Some(runner) = Runner::from_dir(&entry.path())
+                && !runners.iter().any(|r| r.name == runner.name)
+            {
+                runners.push(runner);
+            }
+        }
+    }
+    runners.sort_by(|a, b| a.name.cmp(&b.name));
+    runners
+}
+
+/// Find a runner by name, or by the path to its directory
+pub fn find(runners: &[Runner], name: &str) -> Option<Runner> {
+    if Path::new(name).is_absolute() {
+        return Runner::from_dir(Path::new(name));
+    }
+    runners.iter().find(|runner| runner.name == name).cloned()
+}
+
+pub fn command_runners<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let subcommand = if args.is_empty() { "list" } else { &args[0] };
+    match subcommand {
+        "list" => {
+            for runner in games.settings.runners().iter() {
+                let marker = if games.settings.runner.as_ref() == Some(&runner.name) {
+                    "*"
+                } else {
+                    " "
+                };
+                out!("{} {} ({})", marker, runner.name, runner.wine.display());
+            }
+            Ok(())
+        }
+        "use" => {
+            if args.len() < 2 {
+                return Err(GameError::InvalidArgument(&args[0]));
+            }
+            let name = &args[1];
+            if find(games.settings.runners(), name).is_none() {
+                return Err(GameError::NoSuchRunner(name));
+            }
+            match config_edit::set_setting(&config_file_path(), "runner", name.as_str().into()) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(GameError::CouldNotUpdateConfig(e)),
+            }
+        }
+        _ => Err(GameError::UnrecognizedSubcommand(&args[0])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_runners() {
+        let root = std::env::temp_dir().join(format!("game_rs_runners_{}", std::process::id()));
+        let proton = root.join("GE-Proton9-20/files/bin");
+        let wine = root.join("wine-staging/bin");
+        fs::create_dir_all(&proton).unwrap();
+        fs::create_dir_all(&wine).unwrap();
+        fs::create_dir_all(root.join("not-a-runner")).unwrap();
+        fs::write(proton.join("wine"), "").unwrap();
+        fs::write(wine.join("wine"), "").unwrap();
+
+        let runners = discover(std::slice::from_ref(&root));
+        let names: Vec<&str> = runners.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["GE-Proton9-20", "wine-staging"]);
+        assert_eq!(runners[0].wine, proton.join("wine"));
+        assert_eq!(
+            find(&runners, root.join("wine-staging").to_str().unwrap()),
+            Some(runners[1].clone())
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+use std::env::var;
+
+/// A sandbox to run a game in, which restricts what it can touch
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sandbox {
+    /// firejail, with its default profile or the named one
+    Firejail(Option<String>),
+    /// bwrap, with the filesystem read-only apart from the game's own
+    /// directories, and optionally without a network
+    Bwrap { offline: bool },
+}
+
+impl Sandbox {
+    /// Parse `firejail`, `firejail:PROFILE`, `bwrap` or `bwrap:offline`
+    pub fn parse(s: &str) -> Option<Sandbox> {
+        let (tool, profile) = match s.split_once(':') {
+            Some((tool, profile)) => (tool, Some(profile)),
+            None => (s, None),
+        };
+        match (tool, profile) {
+            ("firejail", None) => Some(Sandbox::Firejail(None)),
+            ("firejail", Some(profile)) if !profile.is_empty() => {
+                Some(Sandbox::Firejail(Some(profile.to_string())))
+            }
+            ("bwrap", None) => Some(Sandbox::Bwrap { offline: false }),
+            ("bwrap", Some("offline")) => Some(Sandbox::Bwrap { offline: true }),
+            _ => None,
+        }
+    }
+
+    /// The program that runs the sandbox
+    pub fn tool(&self) -> &'static str {
+        match self {
+            Sandbox::Firejail(_) => "firejail",
+            Sandbox::Bwrap { .. } => "bwrap",
+        }
+    }
+
+    /// The command that runs the game's command in the sandbox. With bwrap,
+    /// the game can write to the given directories, `/tmp` (where the X11
+    /// socket is) and `XDG_RUNTIME_DIR` (where the Wayland and audio sockets
+    /// are), and nowhere else.
+    pub fn wrap(&self, command: Vec<String>, writable: &[String]) -> Vec<String> {
+        let mut wrapped: Vec<String> = match self {
+            Sandbox::Firejail(profile) => {
+                let mut args = vec!["firejail".to_string(), "--quiet".to_string()];
+                if let Some(profile) = profile {
+                    args.push(format!("--profile={}", profile));
+                }
+                args
+            }
+            Sandbox::Bwrap { offline } => {
+                let mut args: Vec<String> = [
+                    "bwrap",
+                    "--ro-bind",
+                    "/",
+                    "/",
+                    "--dev",
+                    "/dev",
+                    "--proc",
+                    "/proc",
+                    "--bind",
+                    "/tmp",
+                    "/tmp",
+                    "--die-with-parent",
+                ]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect();
+                let runtime_dir = var("XDG_RUNTIME_DIR").ok();
+                for dir in writable.iter().chain(runtime_dir.as_ref()) {
+                    args.push("--bind-try".to_string());
+                    args.push(dir.to_string());
+                    args.push(dir.to_string());
+                }
+                if *offline {
+                    args.push("--unshare-net".to_string());
+                }
+                args
+            }
+        };
+        wrapped.push("--".to_string());
+        wrapped.extend(command);
+        wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Sandbox::parse("firejail"), Some(Sandbox::Firejail(None)));
+        assert_eq!(
+            Sandbox::parse("firejail:wine"),
+            Some(Sandbox::Firejail(Some("wine".to_string())))
+        );
+        assert_eq!(
+            Sandbox::parse("bwrap:offline"),
+            Some(Sandbox::Bwrap { offline: true })
+        );
+        assert_eq!(Sandbox::parse("bwrap:wine"), None);
+        assert_eq!(Sandbox::parse("docker"), None);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let command = vec!["wine".to_string(), "setup.exe".to_string()];
+        let wrapped = Sandbox::Firejail(Some("wine".to_string())).wrap(command.clone(), &[]);
+        assert_eq!(
+            wrapped,
+            vec![
+                "firejail",
+                "--quiet",
+                "--profile=wine",
+                "--",
+                "wine",
+                "setup.exe"
+            ]
+        );
+        let writable = vec!["/games/old".to_string()];
+        let wrapped = Sandbox::Bwrap { offline: true }.wrap(command, &writable);
+        assert_eq!(wrapped[0], "bwrap");
+        assert!(
+            wrapped
+                .windows(3)
+                .any(|args| args == ["--bind-try", "/games/old", "/games/old"])
+        );
+        assert!(wrapped.contains(&"--unshare-net".to_string()));
+        assert_eq!(wrapped[wrapped.len() - 3..], ["--", "wine", "setup.exe"]);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::game::GameError;
+use crate::platform::Platform;
+use crate::{Game, Games, config_edit, config_file_path, steam_dir, summary};
+use std::env::home_dir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where Windows games keep their saves, under a user's directory in a wine
+/// prefix, most likely first
+const WINE_SAVE_DIRS: [&str; 8] = [
+    "Documents/My Games",
+    "Saved Games",
+    "Documents",
+    "My Documents",
+    "AppData/Roaming",
+    "AppData/LocalLow",
+    "AppData/Local",
+    "Application Data",
+];
+
+/// The directories on the system that a game's saves could be in, most likely
+/// first: the document and application data directories in the game's wine
+/// prefix (Proton's, for a Steam game), or the XDG data and config
+/// directories for a native game
+pub fn search_dirs(
+    game: &Game,
+    home: &Path,
+    data_base_dir: &Path,
+    steam_dir: Option<&Path>,
+) -> Vec<PathBuf> {
+    let prefix = match (game.env.get("WINEPREFIX"), &game.steam_id) {
+        (Some(prefix), _) => Some(PathBuf::from(prefix)),
+        (None, Some(steam_id)) => steam_dir.map(|steam_dir| {
+            steam_dir
+                .join("steamapps/compatdata")
+                .join(steam_id)
+                .join("pfx")
+        }),
+        (None, None) if summary::launcher(game) == "wine" => Some(home.join(".wine")),
+        (None, None) => None,
+    };
+    let Some(prefix) = prefix else {
+        return vec![data_base_dir.to_path_buf(), home.join(".config")];
+    };
+    let mut users: Vec<PathBuf> = fs::read_dir(prefix.join("drive_c/users"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && !path.ends_with("Public"))
+        .collect();
+    users.sort();
+    WINE_SAVE_DIRS
+        .iter()
+        .flat_map(|dir| users.iter().map(move |user| user.join(dir)))
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// The directories that look like they hold the game's saves, the best guess
+/// first: those named after the game in the search directories (or in a
+/// directory one level down, which is usually the publisher's), then, for a
+/// DOSBox game, the directories of its own that have save files in them
+pub fn detect(game: &Game, search_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let names = [simplify(&game.name), simplify(&game.id)];
+    let mut found: Vec<(u8, PathBuf)> = Vec::new();
+    for dir in search_dirs.iter() {
+        for child in subdirs(dir) {
+            let score = name_score(&child, &names);
+            if score > 0 {
+                found.push((score, child));
+                continue;
+            }
+            for grandchild in subdirs(&child) {
+                let score = name_score(&grandchild, &names);
+                if score > 0 {
+                    found.push((score, grandchild));
+                }
+            }
+        }
+    }
+    // A stable sort keeps the order of the search directories for each score
+    found.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    let mut detected: Vec<PathBuf> = Vec::new();
+    for (_, dir) in found {
+        if !detected.contains(&dir) {
+            detected.push(dir);
+        }
+    }
+    if summary::launcher(game) == "dosbox"
+        && let Some(game_dir) = &game.dir
+    {
+        let game_dir = PathBuf::from(game_dir);
+        let mut dirs = subdirs(&game_dir);
+        dirs.insert(0, game_dir);
+        detected.extend(dirs.into_iter().filter(|dir| has_save_files(dir)));
+    }
+    detected
+}
+
+/// How well a directory's name matches the game's: 2 for the same name,
+/// ignoring case, spaces and punctuation, 1 for one name containing the other
+fn name_score(dir: &Path, names: &[String]) -> u8 {
+    let Some(dir_name) = dir.file_name() else {
+        return 0;
+    };
+    let dir_name = simplify(&dir_name.to_string_lossy());
+    // Very short names would be found inside too many others
+    if dir_name.len() < 3 {
+        return 0;
+    }
+    names
+        .iter()
+        .filter(|name| name.len() >= 3)
+        .map(|name| {
+            if *name == dir_name {
+                2
+            } else if name.contains(&dir_name) || dir_name.contains(name.as_str()) {
+                1
+            } else {
+                0
+            }
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// A name in lowercase with only its letters and digits
+fn simplify(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Whether a directory has files named like DOS games name their saves, like
+/// `SAVEGAME.001` or `GAME1.SAV`
+fn has_save_files(dir: &Path) -> bool {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .any(|entry| {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            name.starts_with("save") || name.ends_with(".sav")
+        })
+}
+
+/// Print the directories that look like they hold the game's saves, best
+/// guess first, and with `--write` store the best one as its `save_dir`
+pub fn command_saves<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let (game_id, write) = match args {
+        [subcommand, game_id] if subcommand == "detect" => (game_id, false),
+        [subcommand, game_id, flag] if subcommand == "detect" && flag == "--write" => {
+            (game_id, true)
+        }
+        [subcommand] if subcommand == "detect" => return Err(GameError::NoGameId),
+        [] => return Err(GameError::NoGameId),
+        [subcommand] => return Err(GameError::UnrecognizedSubcommand(subcommand)),
+        [_, _, arg, ..] | [_, arg] => return Err(GameError::InvalidArgument(arg)),
+    };
+    let Some(game) = games.find(game_id) else {
+        return Err(GameError::NoSuchGame(game_id));
+    };
+    let home = home_dir().unwrap();
+    let data_base_dir = Platform::current().data_base_dir(&home);
+    let search_dirs = search_dirs(game, &home, &data_base_dir, steam_dir().as_deref());
+    for dir in search_dirs.iter() {
+        debug!("Looking for saves in: {}", dir.display());
+    }
+    let detected = detect(game, &search_dirs);
+    let Some(best) = detected.first() else {
+        return Err(GameError::NoSaveDir(game_id));
+    };
+    // Paths under the home directory are written the way `save_dir` takes them
+    let config_value = |dir: &Path| match dir.strip_prefix(&home) {
+        Ok(rest) => format!("~/{}", rest.display()),
+        Err(_) => dir.display().to_string(),
+    };
+    for dir in detected.iter() {
+        out!("save_dir = {}", toml_edit::Value::from(config_value(dir)));
+    }
+    if !write {
+        return Ok(());
+    }
+    let value = toml_edit::Value::from(config_value(best));
+    match config_edit::set_game_option(&config_file_path(), &game.id, "save_dir", value) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(GameError::CouldNotUpdateConfig(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_in_wine_prefix() {
+        let root = std::env::temp_dir().join(format!("game_rs_saves_{}", std::process::id()));
+        let user = root.join("pfx/drive_c/users/steamuser");
+        fs::create_dir_all(user.join("AppData/LocalLow/Team Cherry/Hollow Knight")).unwrap();
+        fs::create_dir_all(user.join("Documents/My Games/Hollow Knight Demo")).unwrap();
+        fs::create_dir_all(user.join("Documents/My Games/Hades")).unwrap();
+        fs::create_dir_all(root.join("pfx/drive_c/users/Public/Documents")).unwrap();
+        let mut game = Game {
+            id: "hollow".to_string(),
+            name: "Hollow Knight".to_string(),
+            command: vec!["wine".to_string(), "hollow_knight.exe".to_string()],
+            ..Default::default()
+        };
+        game.env.insert(
+            "WINEPREFIX".to_string(),
+            root.join("pfx").to_string_lossy().to_string(),
+        );
+
+        let dirs = search_dirs(&game, &root, &root, Some(&root));
+        assert_eq!(
+            dirs,
+            vec![
+                user.join("Documents/My Games"),
+                user.join("Documents"),
+                user.join("AppData/LocalLow")
+            ]
+        );
+        assert_eq!(
+            detect(&game, &dirs),
+            vec![
+                user.join("AppData/LocalLow/Team Cherry/Hollow Knight"),
+                user.join("Documents/My Games/Hollow Knight Demo")
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_detect_dosbox_saves() {
+        let root = std::env::temp_dir().join(format!("game_rs_dos_saves_{}", std::process::id()));
+        fs::create_dir_all(root.join("SAVES")).unwrap();
+        fs::create_dir_all(root.join("DATA")).unwrap();
+        fs::write(root.join("SAVES/GAME1.SAV"), "").unwrap();
+        fs::write(root.join("DATA/MAP.DAT"), "").unwrap();
+        let game = Game {
+            id: "xcom".to_string(),
+            name: "X-COM".to_string(),
+            command: vec!["dosbox".to_string(), "XCOM.EXE".to_string()],
+            dir: Some(root.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        assert_eq!(detect(&game, &[]), vec![root.join("SAVES")]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_name_score() {
+        let names = [simplify("Baldur's Gate 3"), simplify("bg3")];
+        assert_eq!(name_score(Path::new("/x/Baldurs Gate 3"), &names), 2);
+        assert_eq!(name_score(Path::new("/x/BG3"), &names), 2);
+        assert_eq!(name_score(Path::new("/x/Baldur's Gate 3 Saves"), &names), 1);
+        assert_eq!(name_score(Path::new("/x/Larian Studios"), &names), 0);
+    }
+}
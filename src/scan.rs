@@ -0,0 +1,412 @@
+use crate::game::GameError;
+use crate::{Games, ask, ask_yes_no, config_edit, config_file_path};
+use std::collections::HashSet;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use toml_edit::Value;
+
+/// How deep below the scanned directory to look for games
+const MAX_DEPTH: usize = 4;
+/// The launcher script in GOG's Linux installs
+const GOG_START_SCRIPT: &str = "start.sh";
+/// Windows executables that come with games but are not the game itself
+const SKIPPED_EXES: [&str; 8] = [
+    "unins",
+    "setup",
+    "install",
+    "vcredist",
+    "dxsetup",
+    "dotnet",
+    "crashhandler",
+    "crashreport",
+];
+/// Directories that games keep their executables in, which are no help in
+/// naming the game
+const GENERIC_DIRS: [&str; 8] = [
+    "bin", "bin32", "bin64", "binaries", "system", "win32", "win64", "x64",
+];
+/// Extensions of native programs; anything else with an extension is taken to
+/// be a library or data file, even if it is executable
+const NATIVE_EXTENSIONS: [&str; 4] = ["sh", "x86_64", "x86", "appimage"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Native,
+    Wine,
+    Dosbox,
+}
+
+impl Kind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Native => "native",
+            Kind::Wine => "wine",
+            Kind::Dosbox => "dosbox",
+        }
+    }
+}
+
+/// A game found by a scan, with a suggested name and the options that
+/// launch it
+pub struct Found {
+    pub description: String,
+    pub game_id: String,
+    pub name: String,
+    pub options: Vec<(&'static str, Value)>,
+}
+
+/// Something found by a scan that looks like it launches a game
+#[derive(Debug, PartialEq)]
+pub struct Candidate {
+    pub kind: Kind,
+    pub path: PathBuf,
+}
+
+impl Candidate {
+    /// The directory the game would be run from
+    pub fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("/"))
+    }
+
+    pub fn file_name(&self) -> String {
+        match self.path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => String::new(),
+        }
+    }
+
+    pub fn found(&self) -> Found {
+        let file_name = self.file_name();
+        let (key, launch) = match self.kind {
+            Kind::Native => (
+                "cmd",
+                shell_words::quote(&self.path.to_string_lossy()).to_string(),
+            ),
+            Kind::Wine => ("wine_exe", shell_words::quote(&file_name).to_string()),
+            Kind::Dosbox => ("dosbox_config", file_name),
+        };
+        Found {
+            description: format!("{} game: {}", self.kind.name(), self.path.display()),
+            game_id: suggested_id(&self.suggested_name()),
+            name: self.suggested_name(),
+            options: vec![
+                (key, launch.into()),
+                ("dir", self.dir().to_string_lossy().as_ref().into()),
+            ],
+        }
+    }
+
+    /// A name for the game, from the name of its directory, or of the first
+    /// directory above it whose name is not something like `bin`
+    pub fn suggested_name(&self) -> String {
+        let name = self
+            .dir()
+            .ancestors()
+            .filter_map(|dir| dir.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .find(|name| !GENERIC_DIRS.contains(&name.to_lowercase().as_str()));
+        name.unwrap_or(self.file_name())
+    }
+}
+
+/// A game ID made from a game's name, in lower case with anything other than
+/// letters and numbers replaced by underscores
+pub fn suggested_id(name: &str) -> String {
+    let id: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let words: Vec<&str> = id.split('_').filter(|w| !w.is_empty()).collect();
+    words.join("_")
+}
+
+/// Look for things that launch games under the directory: GOG `start.sh`
+/// scripts, Windows executables, DOSBox configs and native executables. A
+/// directory with a `start.sh` is not looked into any further.
+pub fn scan(root: &Path) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    scan_dir(root, 0, &mut candidates);
+    candidates
+}
+
+fn scan_dir(dir: &Path, depth: usize, candidates: &mut Vec<Candidate>) {
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(e) => {
+            debug!("Could not read directory {}: {}", dir.display(), e);
+            return;
+        }
+    };
+    entries.sort();
+    let start_script = dir.join(GOG_START_SCRIPT);
+    if start_script.is_file() {
+        candidates.push(Candidate {
+            kind: Kind::Native,
+            path: start_script,
+        });
+        return;
+    }
+    for path in entries.iter() {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            if depth < MAX_DEPTH {
+                scan_dir(path, depth + 1, candidates);
+            }
+            continue;
+        }
+        let kind = match name.rsplit_once('.') {
+            Some((stem, "exe")) if !SKIPPED_EXES.iter().any(|s| stem.contains(s)) => Kind::Wine,
+            Some((_, "conf")) if is_dosbox_config(path) => Kind::Dosbox,
+            Some((_, ext)) if NATIVE_EXTENSIONS.contains(&ext) && is_executable(path) => {
+                Kind::Native
+            }
+            None if is_executable(path) => Kind::Native,
+            _ => continue,
+        };
+        trace!("Found {:?} game: {}", kind, path.display());
+        candidates.push(Candidate {
+            kind,
+            path: path.clone(),
+        });
+    }
+}
+
+/// A game that ScummVM has been set up to run
+#[derive(Debug, PartialEq)]
+pub struct ScummvmTarget {
+    pub id: String,
+    pub description: String,
+}
+
+impl ScummvmTarget {
+    pub fn found(&self) -> Found {
+        // The description ends with the variant, like "(VGA/DOS/English)"
+        let name = match self.description.rsplit_once(" (") {
+            Some((name, variant)) if variant.ends_with(')') => name,
+            _ => &self.description,
+        };
+        Found {
+            description: format!("ScummVM target {}: {}", self.id, self.description),
+            game_id: suggested_id(&self.id),
+            name: name.to_string(),
+            options: vec![("scummvm_id", self.id.as_str().into())],
+        }
+    }
+}
+
+/// Parse the table printed by `scummvm --list-targets`, which has a heading,
+/// a line of dashes, and then a line for each target
+pub fn parse_scummvm_targets(output: &str) -> Vec<ScummvmTarget> {
+    output
+        .lines()
+        .skip_while(|line| !line.starts_with("---"))
+        .skip(1)
+        .filter_map(|line| line.trim().split_once(char::is_whitespace))
+        .map(|(id, description)| ScummvmTarget {
+            id: id.to_string(),
+            description: description.trim().to_string(),
+        })
+        .collect()
+}
+
+/// Whether the file is a DOSBox config that starts a program
+fn is_dosbox_config(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| content.contains("[autoexec]"))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
+
+pub fn command_scan<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    // Without a terminal to ask whether to add them, the games are printed
+    let print_only = args.iter().any(|arg| arg == "--print") || !std::io::stdin().is_terminal();
+    let use_scummvm = args.iter().any(|arg| arg == "--scummvm");
+    let dirs: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+    let dir = match dirs[..] {
+        [] => None,
+        [dir] => Some(dir.as_str()),
+        [_, extra, ..] => return Err(GameError::InvalidArgument(extra)),
+    };
+    let root = match dir.map(fs::canonicalize) {
+        Some(Ok(root)) => Some(root),
+        Some(Err(_)) => return Err(GameError::InvalidArgument(dir.unwrap())),
+        None => None,
+    };
+    let found = if use_scummvm {
+        scan_scummvm(games, root.as_deref())?
+    } else {
+        find_new_games(games, root.as_deref().unwrap_or(Path::new(".")))
+    };
+    if found.is_empty() {
+        out!("No new games found");
+        return Ok(());
+    }
+    let mut game_ids: HashSet<String> = games.games.keys().cloned().collect();
+    for found in found.into_iter() {
+        let mut name = found.name;
+        let mut game_id = unused_game_id(&game_ids, &found.game_id);
+        if !print_only {
+            out!("Found {}", found.description);
+            if !ask_yes_no("Add it to the config", true) {
+                continue;
+            }
+            game_id = ask("Game ID", &game_id);
+            name = ask("Name", &name);
+        }
+        let mut options = vec![("name", name.as_str().into())];
+        options.extend(found.options);
+        if print_only {
+            out!("{}", config_edit::game_entry(&game_id, &options));
+            game_ids.insert(game_id);
+            continue;
+        }
+        if let Err(e) = config_edit::add_game(&config_file_path(), &game_id, &options) {
+            return Err(GameError::CouldNotUpdateConfig(e));
+        }
+        out!("Added {}", game_id);
+        game_ids.insert(game_id);
+    }
+    Ok(())
+}
+
+/// The games under the directory that are not in the config yet
+fn find_new_games(games: &Games, root: &Path) -> Vec<Found> {
+    let is_known = |candidate: &Candidate| {
+        games.games.values().any(|game| {
+            game.dir.as_deref().map(Path::new) == Some(candidate.dir())
+                || game
+                    .command
+                    .iter()
+                    .any(|arg| Path::new(arg) == candidate.path)
+        })
+    };
+    scan(root)
+        .iter()
+        .filter(|candidate| !is_known(candidate))
+        .map(|candidate| candidate.found())
+        .collect()
+}
+
+/// The ScummVM targets that are not in the config yet, after adding the games
+/// that ScummVM detects in the directory, if there is one, as targets
+fn scan_scummvm<'a>(games: &Games, root: Option<&Path>) -> Result<Vec<Found>, GameError<'a>> {
+    if let Some(root) = root {
+        let path = format!("--path={}", root.display());
+        run_scummvm(&["--add", "--recursive", &path])?;
+    }
+    let targets = parse_scummvm_targets(&run_scummvm(&["--list-targets"])?);
+    let is_known = |target: &ScummvmTarget| {
+        games
+            .games
+            .values()
+            .any(|game| game.command == ["scummvm", target.id.as_str()])
+    };
+    Ok(targets
+        .iter()
+        .filter(|target| !is_known(target))
+        .map(|target| target.found())
+        .collect())
+}
+
+/// Run ScummVM with the arguments, returning what it prints
+fn run_scummvm<'a>(args: &[&str]) -> Result<String, GameError<'a>> {
+    let command_line = format!("scummvm {}", args.join(" "));
+    info!("Running command: {}", command_line);
+    match std::process::Command::new("scummvm").args(args).output() {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        _ => Err(GameError::CommandReturnedFailure(command_line)),
+    }
+}
+
+/// The game ID, with a number added to the end if it is already taken
+fn unused_game_id(game_ids: &HashSet<String>, game_id: &str) -> String {
+    let game_id = if game_id.is_empty() { "game" } else { game_id };
+    (1..)
+        .map(|n| match n {
+            1 => game_id.to_string(),
+            _ => format!("{}_{}", game_id, n),
+        })
+        .find(|id| !game_ids.contains(id))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggested_id() {
+        assert_eq!(suggested_id("Baldur's Gate II"), "baldur_s_gate_ii");
+        assert_eq!(suggested_id("  Doom  "), "doom");
+    }
+
+    #[test]
+    fn test_parse_scummvm_targets() {
+        let output = "\
+Target               Description
+-------------------- ------------------------------------------------------
+atlantis             Indiana Jones and the Fate of Atlantis (CD/DOS/English)
+monkey-1             The Secret of Monkey Island (VGA/DOS/English)
+";
+        let targets = parse_scummvm_targets(output);
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[1].id, "monkey-1");
+        let found = targets[1].found();
+        assert_eq!(found.name, "The Secret of Monkey Island");
+        assert_eq!(found.options[0].1.as_str(), Some("monkey-1"));
+    }
+
+    #[test]
+    fn test_scan() {
+        let root = std::env::temp_dir().join(format!("game_rs_scan_{}", std::process::id()));
+        let gog = root.join("Celeste");
+        let wine = root.join("Morrowind");
+        let dos = root.join("dos");
+        for dir in [&gog, &wine, &dos] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(gog.join("start.sh"), "").unwrap();
+        fs::write(gog.join("Celeste.exe"), "").unwrap();
+        fs::write(wine.join("Morrowind.exe"), "").unwrap();
+        fs::write(wine.join("unins000.exe"), "").unwrap();
+        fs::write(dos.join("keen.conf"), "[autoexec]\nkeen1\n").unwrap();
+        fs::write(dos.join("notes.conf"), "volume=3\n").unwrap();
+
+        let candidates = scan(&root);
+        let found: Vec<(Kind, String)> =
+            candidates.iter().map(|c| (c.kind, c.file_name())).collect();
+        assert_eq!(
+            found,
+            vec![
+                (Kind::Native, "start.sh".to_string()),
+                (Kind::Wine, "Morrowind.exe".to_string()),
+                (Kind::Dosbox, "keen.conf".to_string()),
+            ]
+        );
+        assert_eq!(candidates[1].suggested_name(), "Morrowind");
+        let in_bin = Candidate {
+            kind: Kind::Wine,
+            path: wine.join("bin").join("Game.exe"),
+        };
+        assert_eq!(in_bin.suggested_name(), "Morrowind");
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,338 @@
+use crate::game::GameError;
+use crate::stats::{self, format_local, format_play_time, format_utc, parse_utc};
+use crate::{Games, read_sessions, record_session, tsv};
+use time::UtcDateTime;
+
+/// A single play session, as recorded in the session log
+pub struct Session {
+    id: String,
+    start_time: UtcDateTime,
+    play_time_seconds: u32,
+    note: String,
+    version: String,
+    /// The time the game was open, when it was longer than the play time
+    /// because some of it was idle
+    wall_time_seconds: Option<u32>,
+    /// How the game was launched, like `gamescope=on,fps_limit=60`
+    config: String,
+}
+
+impl Session {
+    pub fn new(
+        id: String,
+        start_time: UtcDateTime,
+        play_time_seconds: u32,
+        note: String,
+    ) -> Session {
+        Session {
+            id,
+            start_time,
+            play_time_seconds,
+            note,
+            version: String::new(),
+            wall_time_seconds: None,
+            config: String::new(),
+        }
+    }
+
+    /// The session, with the configuration the game was launched with
+    pub fn with_config(mut self, config: &str) -> Session {
+        self.config = config.replace(['\t', '\n', '\r'], " ");
+        self
+    }
+
+    /// The session, played on the given version of the game
+    pub fn with_version(mut self, version: &str) -> Session {
+        self.version = version.to_string();
+        self
+    }
+
+    /// The session, with the game open for longer than it was played
+    pub fn with_wall_time(mut self, wall_time_seconds: u32) -> Session {
+        if wall_time_seconds > self.play_time_seconds {
+            self.wall_time_seconds = Some(wall_time_seconds);
+        }
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn start_time(&self) -> UtcDateTime {
+        self.start_time
+    }
+
+    pub fn play_time_seconds(&self) -> u32 {
+        self.play_time_seconds
+    }
+
+    /// The launch configuration, if it was recorded
+    pub fn config(&self) -> Option<&str> {
+        Some(self.config.as_str()).filter(|c| !c.is_empty())
+    }
+
+    /// How long the game was open, idle time included
+    pub fn wall_time_seconds(&self) -> u32 {
+        self.wall_time_seconds.unwrap_or(self.play_time_seconds)
+    }
+
+    /// The version of the game, if it had one
+    pub fn version(&self) -> Option<&str> {
+        Some(self.version.as_str()).filter(|v| !v.is_empty())
+    }
+
+    pub fn to_tsv(&self) -> String {
+        // Notes are a single line, so tabs and newlines would break the format
+        let note = self.note.replace(['\t', '\n', '\r'], " ");
+        let start_time = format_utc(self.start_time);
+        let play_time = self.play_time_seconds.to_string();
+        let wall_time = self
+            .wall_time_seconds
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+        let mut fields = vec![
+            self.id.as_str(),
+            &start_time,
+            &play_time,
+            &note,
+            &self.version,
+            &wall_time,
+            &self.config,
+        ];
+        // Sessions without the newer columns keep the original four
+        while fields.len() > 4 && fields.last().is_some_and(|field| field.is_empty()) {
+            fields.pop();
+        }
+        tsv::format_line(&fields)
+    }
+
+    /// Parse a line of the session log, or `None` if it is missing the game
+    /// ID, start time or play time
+    pub fn from_tsv(line: &str) -> Option<Session> {
+        let parts = tsv::split_line(line);
+        Some(Session {
+            id: parts.first()?.to_string(),
+            start_time: parse_utc(parts.get(1)?)?,
+            play_time_seconds: parts.get(2)?.parse::<u32>().ok()?,
+            note: parts.get(3).cloned().unwrap_or_default(),
+            version: parts.get(4).cloned().unwrap_or_default(),
+            wall_time_seconds: parts.get(5).and_then(|s| s.parse::<u32>().ok()),
+            config: parts.get(6).cloned().unwrap_or_default(),
+        })
+    }
+
+    pub fn format(&self) -> String {
+        let mut s = format!(
+            "{} {} ({})",
+            format_local(self.start_time),
+            self.id,
+            format_play_time(self.play_time_seconds)
+        );
+        if let Some(wall_time) = self.wall_time_seconds {
+            s.push_str(&format!(" ({} open)", format_play_time(wall_time)));
+        }
+        if !self.version.is_empty() {
+            s.push_str(&format!(" [{}]", self.version));
+        }
+        if !self.note.is_empty() {
+            s.push_str(" - ");
+            s.push_str(&self.note);
+        }
+        s
+    }
+}
+
+/// Parse the session log, one session per line, skipping lines that are not
+/// sessions
+pub fn parse_log(content: &str) -> Vec<Session> {
+    let mut sessions = Vec::new();
+    for line in content.lines().filter(|line| !line.is_empty()) {
+        match Session::from_tsv(line) {
+            Some(session) => sessions.push(session),
+            None => warn!("Invalid line in session log: {}", line),
+        }
+    }
+    sessions
+}
+
+pub fn format_log(sessions: &[Session]) -> String {
+    let mut s = String::new();
+    for session in sessions.iter() {
+        s.push_str(&session.to_tsv());
+        s.push('\n');
+    }
+    s
+}
+
+pub fn command_sessions<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let show_config = args.iter().any(|arg| arg == "--launch-config");
+    let game_ids: Vec<&'a String> = args
+        .iter()
+        .filter(|arg| *arg != "--launch-config")
+        .collect();
+    for game_id in game_ids.iter() {
+        if games.find(game_id).is_none() {
+            return Err(GameError::NoSuchGame(game_id));
+        }
+    }
+    for session in read_sessions()
+        .iter()
+        .filter(|session| game_ids.is_empty() || game_ids.iter().any(|id| *id == session.id()))
+    {
+        match session.config() {
+            Some(config) if show_config => out!("{} {{{}}}", session.format(), config),
+            _ => out!("{}", session.format()),
+        }
+    }
+    Ok(())
+}
+
+/// Record a session that was not played through `game`, like one on another
+/// device or from before there were stats
+pub fn command_log_session<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let Some(game_id) = args.first() else {
+        return Err(GameError::NoGameId);
+    };
+    let Some(game) = games.find(game_id) else {
+        return Err(GameError::NoSuchGame(game_id));
+    };
+    let Some(time_arg) = args.get(1) else {
+        return Err(GameError::InvalidArgument("log-session"));
+    };
+    let play_time = match stats::parse_play_time(time_arg) {
+        Some(seconds) if seconds > 0 => seconds,
+        _ => return Err(GameError::InvalidArgument(time_arg)),
+    };
+    let start_time = match &args[2..] {
+        [] => UtcDateTime::now() - time::Duration::seconds(play_time as i64),
+        [flag, date] if flag == "--date" => match stats::parse_date(date) {
+            Some(date) => stats::local_midnight(date),
+            None => return Err(GameError::InvalidArgument(date)),
+        },
+        [arg, ..] => return Err(GameError::InvalidArgument(arg)),
+    };
+    let mut session = Session::new(game.id.clone(), start_time, play_time, String::new());
+    if let Some(version) = &game.version {
+        session = session.with_version(version);
+    }
+    record_session(&session)?;
+    out!("{}", session.format());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start_time() -> UtcDateTime {
+        let date =
+            time::Date::from_calendar_date(2025, time::Month::November, 3).expect("Bad date");
+        let time = time::Time::from_hms(19, 7, 0).expect("Bad time");
+        UtcDateTime::new(date, time)
+    }
+
+    #[test]
+    fn test_serialize() {
+        let session = Session::new(
+            "testgame".to_string(),
+            start_time(),
+            5400,
+            "beat chapter 3".to_string(),
+        );
+        assert_eq!(
+            session.to_tsv(),
+            "testgame\t2025-11-03 19:07:00\t5400\tbeat chapter 3"
+        );
+    }
+
+    #[test]
+    fn test_note_cannot_break_the_line() {
+        let session = Session::new(
+            "testgame".to_string(),
+            start_time(),
+            5400,
+            "beat\tchapter\n3".to_string(),
+        );
+        assert_eq!(
+            session.to_tsv(),
+            "testgame\t2025-11-03 19:07:00\t5400\tbeat chapter 3"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let session = Session::from_tsv("testgame\t2025-11-03 19:07:00\t5400\tbeat chapter 3")
+            .expect("Bad session");
+        assert_eq!(session.id(), "testgame");
+        assert_eq!(session.start_time(), start_time());
+        assert_eq!(session.play_time_seconds(), 5400);
+        assert_eq!(session.note, "beat chapter 3");
+    }
+
+    #[test]
+    fn test_parse_without_note() {
+        let session =
+            Session::from_tsv("testgame\t2025-11-03 19:07:00\t5400\t").expect("Bad session");
+        assert_eq!(session.note, "");
+    }
+
+    #[test]
+    fn test_parse_log_skips_bad_lines() {
+        let log = "doom\t2025-11-03 19:07:00\t60\n\
+                   doom\t2025-11-03\n\
+                   doom\t2025-11-03 19:07:00\tlong\n\
+                   quake\t2025-11-04 20:00:00\t120\t\n";
+        let sessions = parse_log(log);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[1].id(), "quake");
+        assert!(Session::from_tsv("doom").is_none());
+    }
+
+    #[test]
+    fn test_version() {
+        let session = Session::new("gzdoom".to_string(), start_time(), 60, String::new())
+            .with_version("4.11");
+        let line = session.to_tsv();
+        assert_eq!(line, "gzdoom\t2025-11-03 19:07:00\t60\t\t4.11");
+        assert_eq!(
+            Session::from_tsv(&line).expect("Bad session").version(),
+            Some("4.11")
+        );
+        assert_eq!(
+            Session::from_tsv("gzdoom\t2025-11-03 19:07:00\t60\t")
+                .expect("Bad session")
+                .version(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let session = Session::new("doom".to_string(), start_time(), 60, String::new())
+            .with_config("gamescope=on,mangohud=off");
+        let line = session.to_tsv();
+        assert_eq!(
+            line,
+            "doom\t2025-11-03 19:07:00\t60\t\t\t\tgamescope=on,mangohud=off"
+        );
+        let session = Session::from_tsv(&line).expect("Bad session");
+        assert_eq!(session.config(), Some("gamescope=on,mangohud=off"));
+        assert_eq!(session.wall_time_seconds(), 60);
+    }
+
+    #[test]
+    fn test_wall_time() {
+        let session = Session::new("doom".to_string(), start_time(), 3600, String::new())
+            .with_wall_time(5400);
+        let line = session.to_tsv();
+        assert_eq!(line, "doom\t2025-11-03 19:07:00\t3600\t\t\t5400");
+        let session = Session::from_tsv(&line).expect("Bad session");
+        assert_eq!(session.play_time_seconds(), 3600);
+        assert_eq!(session.wall_time_seconds(), 5400);
+        assert!(session.format().ends_with("doom (1h) (1h30m open)"));
+        let session =
+            Session::new("doom".to_string(), start_time(), 60, String::new()).with_wall_time(60);
+        assert_eq!(session.to_tsv(), "doom\t2025-11-03 19:07:00\t60\t");
+    }
+}
@@ -1,5 +1,49 @@
+use crate::backend::LauncherBackend;
+use crate::mqtt::Mqtt;
+use crate::platform::Platform;
+use crate::runners::{self, Runner};
+use crate::wm_rules::WindowManager;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+#[derive(Default)]
 pub struct Settings {
     pub width: u32,
     pub height: u32,
     pub use_gamescope: bool,
+    pub session_notes: bool,
+    pub roulette_prompt: bool,
+    pub remind_after_minutes: Option<u32>,
+    pub idle_after_minutes: Option<u32>,
+    pub pause_file: Option<PathBuf>,
+    pub nudge_after_days: u32,
+    pub controller_mapper: String,
+    pub capture_cmd: String,
+    pub capture_stop_cmd: Option<String>,
+    pub capture_dir: PathBuf,
+    pub capture_ext: String,
+    pub compositor_pause_cmd: String,
+    pub compositor_resume_cmd: String,
+    pub prefer_output: Option<String>,
+    pub display: Option<i64>,
+    pub window_manager: Option<WindowManager>,
+    pub fps_log_dir: PathBuf,
+    pub runner: Option<String>,
+    /// The installed runners, found the first time they are needed
+    pub runners: OnceLock<Vec<Runner>>,
+    /// Where to look for the runners
+    pub runner_search_dirs: Vec<PathBuf>,
+    pub mqtt: Option<Mqtt>,
+    pub platform: Platform,
+    pub conditions: Vec<String>,
+    /// The launcher backends, loaded once for the whole run
+    pub backends: &'static [Box<dyn LauncherBackend>],
+}
+
+impl Settings {
+    /// The installed runners, looking for them the first time
+    pub fn runners(&self) -> &[Runner] {
+        self.runners
+            .get_or_init(|| runners::discover(&self.runner_search_dirs))
+    }
 }
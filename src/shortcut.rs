@@ -0,0 +1,126 @@
+use crate::game::GameError;
+use crate::icon::game_icon;
+use crate::platform::Platform;
+use crate::{Game, Games, self_command};
+use std::env::home_dir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the desktop entry file for a game
+pub fn file_name(game: &Game) -> String {
+    format!("game_rs-{}.desktop", game.id)
+}
+
+/// A desktop entry that plays the game with the given command, so that it can
+/// be pinned to a dock or added to a launcher's menu
+pub fn desktop_entry(game: &Game, play_command: &[String], icon: Option<&Path>) -> String {
+    let exec: Vec<String> = play_command.iter().map(|arg| quote_exec_arg(arg)).collect();
+    let mut entry = String::from("[Desktop Entry]\n");
+    entry.push_str("Type=Application\n");
+    entry.push_str(&format!("Name={}\n", escape_value(&game.name)));
+    entry.push_str(&format!("Exec={}\n", escape_value(&exec.join(" "))));
+    if let Some(icon) = icon {
+        entry.push_str(&format!("Icon={}\n", escape_value(&icon.to_string_lossy())));
+    }
+    entry.push_str("Terminal=false\n");
+    entry.push_str("Categories=Game;\n");
+    entry
+}
+
+/// Quote an argument of `Exec` if it has any of the characters the desktop
+/// entry spec reserves, escaping those that are special inside the quotes
+fn quote_exec_arg(arg: &str) -> String {
+    let reserved = " \t\n\"'\\><~|&;$*?#()`";
+    if !arg.is_empty() && !arg.chars().any(|c| reserved.contains(c)) {
+        return arg.replace('%', "%%");
+    }
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted.replace('%', "%%")
+}
+
+/// Escape a string value of a desktop entry
+fn escape_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+/// Write a desktop entry that plays the game, into the applications directory
+/// or the given one
+pub fn command_shortcut<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let Some(game_id) = args.first() else {
+        return Err(GameError::NoGameId);
+    };
+    let Some(game) = games.find(game_id) else {
+        return Err(GameError::NoSuchGame(game_id));
+    };
+    let dir = match &args[1..] {
+        [] => Platform::current()
+            .data_base_dir(&home_dir().unwrap())
+            .join("applications"),
+        [flag, dir] if flag == "--output" => PathBuf::from(dir),
+        [arg, ..] => return Err(GameError::InvalidArgument(arg)),
+    };
+    let mut command = self_command();
+    command.push("play".to_string());
+    command.push(game.id.clone());
+    let icon = game_icon(game);
+    let entry = desktop_entry(game, &command, icon.as_deref());
+    let path = dir.join(file_name(game));
+    debug!("Writing shortcut: {}", path.display());
+    fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(&path, entry))
+        .map_err(|e| GameError::CouldNotWriteShortcut(e.to_string()))?;
+    out!("{}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_entry() {
+        let game = Game {
+            id: "doom".to_string(),
+            name: "Doom".to_string(),
+            ..Default::default()
+        };
+        let command: Vec<String> = ["/usr/bin/game", "--config", "/home/me/my games.toml"]
+            .iter()
+            .chain(["play", "doom"].iter())
+            .map(|s| s.to_string())
+            .collect();
+        let entry = desktop_entry(&game, &command, Some(Path::new("/covers/doom.png")));
+        assert_eq!(
+            entry,
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name=Doom\n\
+             Exec=/usr/bin/game --config \"/home/me/my games.toml\" play doom\n\
+             Icon=/covers/doom.png\n\
+             Terminal=false\n\
+             Categories=Game;\n"
+        );
+        assert_eq!(file_name(&game), "game_rs-doom.desktop");
+    }
+
+    #[test]
+    fn test_quote_exec_arg() {
+        assert_eq!(quote_exec_arg("doom"), "doom");
+        assert_eq!(quote_exec_arg("100%"), "100%%");
+        assert_eq!(quote_exec_arg("a \"b\" $c"), "\"a \\\"b\\\" \\$c\"");
+        assert_eq!(
+            escape_value(&quote_exec_arg("C:\\x y")),
+            "\"C:\\\\\\\\x y\""
+        );
+    }
+}
@@ -1,19 +1,86 @@
+use crate::sessions::Session;
+use crate::tsv;
 use time::{UtcDateTime, UtcOffset};
 
 const TIMESTAMP_FORMAT: &str = "[year]-[month]-[day] [hour]:[minute]:[second]";
+const DATE_FORMAT: &str = "[year]-[month]-[day]";
 
-fn format_utc(utc_date_time: UtcDateTime) -> String {
+/// The version of the stats file format, written in a comment at the top of
+/// the file
+const STATS_VERSION: u32 = 1;
+const STATS_COLUMNS: [&str; 3] = ["id", "play_time_seconds", "last_played_time"];
+
+/// The log of changes made to the stats by hand, in the data directory
+pub const AUDIT_FILE: &str = "stats_audit.tsv";
+
+/// A line of the audit log, saying when which of a game's stats was changed,
+/// and from what to what
+pub fn audit_line(time: UtcDateTime, game_id: &str, field: &str, old: &str, new: &str) -> String {
+    tsv::format_line(&[&format_utc(time), game_id, field, old, new])
+}
+
+/// Parse a date in the form YYYY-MM-DD
+pub fn parse_date(s: &str) -> Option<time::Date> {
+    let date_format = time::format_description::parse(DATE_FORMAT).expect("Bad format");
+    time::Date::parse(s, &date_format).ok()
+}
+
+/// Parse a point in time given as a date in the form YYYY-MM-DD (the start of
+/// that day) or as a number of days before now, like `90d`
+pub fn parse_since(s: &str, now: UtcDateTime) -> Option<UtcDateTime> {
+    if let Some(days) = s.strip_suffix('d')
+        && let Ok(days) = days.parse::<u32>()
+    {
+        return Some(now - time::Duration::days(days as i64));
+    }
+    parse_date(s).map(local_midnight)
+}
+
+/// The start of the given day in local time
+pub fn local_midnight(date: time::Date) -> UtcDateTime {
+    let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+    date.midnight().assume_offset(offset).to_utc()
+}
+
+pub fn format_date(date: time::Date) -> String {
+    let date_format = time::format_description::parse(DATE_FORMAT).expect("Bad format");
+    date.format(&date_format).expect("Bad format")
+}
+
+pub fn format_utc(utc_date_time: UtcDateTime) -> String {
     let time_format = time::format_description::parse(TIMESTAMP_FORMAT).expect("Bad format");
     utc_date_time.format(&time_format).expect("Bad format")
 }
 
-fn format_local(utc_date_time: UtcDateTime) -> String {
+pub fn format_local(utc_date_time: UtcDateTime) -> String {
     let time_format = time::format_description::parse(TIMESTAMP_FORMAT).expect("Bad format");
     let offset = UtcOffset::current_local_offset().unwrap();
     let local_date_time = utc_date_time.to_offset(offset);
     local_date_time.format(&time_format).unwrap()
 }
 
+/// Parse a UTC timestamp as written by `format_utc`
+pub fn parse_utc(timestamp: &str) -> Option<UtcDateTime> {
+    let (date_str, time_str) = timestamp.split_once(' ')?;
+    let date_parts: Vec<&str> = date_str.split('-').collect();
+    let [year, month, day] = date_parts[..] else {
+        return None;
+    };
+    let time_parts: Vec<&str> = time_str.split(':').collect();
+    let [hour, minute, second] = time_parts[..] else {
+        return None;
+    };
+    let month = time::Month::try_from(month.parse::<u8>().ok()?).ok()?;
+    let date = time::Date::from_calendar_date(year.parse().ok()?, month, day.parse().ok()?).ok()?;
+    let time = time::Time::from_hms(
+        hour.parse().ok()?,
+        minute.parse().ok()?,
+        second.parse().ok()?,
+    )
+    .ok()?;
+    Some(UtcDateTime::new(date, time))
+}
+
 pub struct GameStats {
     id: String,
     play_time_seconds: u32,
@@ -33,6 +100,14 @@ impl GameStats {
         &self.id
     }
 
+    pub fn set_play_time(&mut self, seconds: u32) {
+        self.play_time_seconds = seconds;
+    }
+
+    pub fn remove_time(&mut self, seconds: u32) {
+        self.play_time_seconds = self.play_time_seconds.saturating_sub(seconds);
+    }
+
     pub fn add_time(&mut self, seconds: u32) {
         self.play_time_seconds = self.play_time_seconds.strict_add(seconds);
     }
@@ -42,37 +117,22 @@ impl GameStats {
     }
 
     pub fn to_tsv(&self) -> String {
-        format!(
-            "{}\t{}\t{}",
-            self.id,
-            self.play_time_seconds,
-            format_utc(self.last_played_time)
-        )
-    }
-
-    pub fn from_tsv(line: &str) -> GameStats {
-        let parts: Vec<&str> = line.split("\t").collect();
-        let timestamp_parts: Vec<&str> = parts[2].split(" ").collect();
-        let date_str = &timestamp_parts[0];
-        let date_parts: Vec<&str> = date_str.split("-").collect();
-        let year = date_parts[0].parse::<i32>().expect("Bad year");
-        let month: u8 = date_parts[1].parse().expect("Bad month");
-        let day = date_parts[2].parse::<u8>().expect("Bad day");
-        let time_str = &timestamp_parts[1];
-        let time_parts: Vec<&str> = time_str.split(":").collect();
-        let hour = time_parts[0].parse::<u8>().expect("Bad hour");
-        let minute = time_parts[1].parse::<u8>().expect("Bad minute");
-        let second = time_parts[2].parse::<u8>().expect("Bad second");
-        let date =
-            time::Date::from_calendar_date(year, time::Month::January.nth_next(month - 1), day)
-                .expect("Bad date");
-        let time = time::Time::from_hms(hour, minute, second).expect("Bad time");
-        let last_played_time = UtcDateTime::new(date, time);
-        GameStats {
-            id: parts[0].to_string(),
-            play_time_seconds: parts[1].parse::<u32>().expect("Bad play time"),
-            last_played_time,
-        }
+        tsv::format_line(&[
+            &self.id,
+            &self.play_time_seconds.to_string(),
+            &format_utc(self.last_played_time),
+        ])
+    }
+
+    /// Build stats from the fields of a line, where `columns` holds the
+    /// position of each of the `STATS_COLUMNS`, or `None` if the line is too
+    /// short or its values cannot be parsed
+    fn from_fields(fields: &[String], columns: &[usize; 3]) -> Option<GameStats> {
+        Some(GameStats {
+            id: fields.get(columns[0])?.to_string(),
+            play_time_seconds: fields.get(columns[1])?.parse::<u32>().ok()?,
+            last_played_time: parse_utc(fields.get(columns[2])?)?,
+        })
     }
 
     pub fn format_play_time(&self) -> String {
@@ -86,6 +146,84 @@ impl GameStats {
     pub fn play_time_seconds(&self) -> u32 {
         self.play_time_seconds
     }
+
+    pub fn last_played_time(&self) -> UtcDateTime {
+        self.last_played_time
+    }
+}
+
+/// Parse the stats file, which may be an old file without a header; columns
+/// that are not known are ignored, and so are rows that cannot be parsed
+pub fn parse_stats_file(content: &str) -> Vec<GameStats> {
+    let mut columns = [0, 1, 2];
+    let mut all_stats = Vec::new();
+    let mut is_first_row = true;
+    for line in content.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = tsv::split_line(line);
+        // Only the first row can be the header, since a game could have the
+        // same ID as one of the columns
+        let is_header = is_first_row && fields.iter().any(|field| field == STATS_COLUMNS[1]);
+        is_first_row = false;
+        if is_header {
+            for (i, column) in STATS_COLUMNS.iter().enumerate() {
+                match fields.iter().position(|field| field == column) {
+                    Some(position) => columns[i] = position,
+                    None => warn!("Stats file is missing column: {}", column),
+                }
+            }
+            continue;
+        }
+        match GameStats::from_fields(&fields, &columns) {
+            Some(stats) => all_stats.push(stats),
+            None => warn!("Invalid line in stats file: {}", line),
+        }
+    }
+    all_stats
+}
+
+/// Format the stats file, with its version and a header row
+pub fn format_stats_file(all_stats: &[GameStats]) -> String {
+    let mut s = format!("#version {}\n", STATS_VERSION);
+    s.push_str(&tsv::format_line(&STATS_COLUMNS));
+    s.push('\n');
+    for stats in all_stats.iter() {
+        s.push_str(&stats.to_tsv());
+        s.push('\n');
+    }
+    s
+}
+
+/// Parse a play time as written by `format_play_time`, like `2h30m` or
+/// `45m`, or a number of seconds
+pub fn parse_play_time(s: &str) -> Option<u32> {
+    if let Ok(seconds) = s.parse::<u32>() {
+        return Some(seconds);
+    }
+    let mut total: u32 = 0;
+    let mut number = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 60 * 60,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let n = number.parse::<u32>().ok()?;
+        total = total.checked_add(n.checked_mul(unit)?)?;
+        number.clear();
+    }
+    if number.is_empty() && !s.is_empty() {
+        Some(total)
+    } else {
+        None
+    }
 }
 
 pub fn format_play_time(play_time_seconds: u32) -> String {
@@ -110,10 +248,95 @@ pub fn format_play_time(play_time_seconds: u32) -> String {
     formatted
 }
 
+/// A bar showing how much of a play time goal has been reached, like
+/// `[########------------] 40%`, which stays full once the goal is passed
+pub fn format_goal_progress(play_time_seconds: u32, goal_hours: f64) -> String {
+    const BAR_WIDTH: usize = 20;
+    let goal_seconds = goal_hours * 60.0 * 60.0;
+    let fraction = if goal_seconds > 0.0 {
+        play_time_seconds as f64 / goal_seconds
+    } else {
+        1.0
+    };
+    let filled = ((fraction.min(1.0) * BAR_WIDTH as f64) as usize).min(BAR_WIDTH);
+    format!(
+        "[{}{}] {}%",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        (fraction * 100.0) as u32
+    )
+}
+
+/// Take back the most recently recorded session (of the given game, or of any
+/// game), removing its play time from the stats and putting the last played
+/// time back to the game's session before it; stats left with nothing in them
+/// are removed
+pub fn undo_session(
+    all_stats: &mut Vec<GameStats>,
+    sessions: &mut Vec<Session>,
+    game_id: Option<&str>,
+) -> Option<Session> {
+    let index = sessions
+        .iter()
+        .rposition(|session| game_id.is_none_or(|id| session.id() == id))?;
+    let session = sessions.remove(index);
+    let previous = sessions
+        .iter()
+        .filter(|s| s.id() == session.id())
+        .map(|s| s.start_time())
+        .max();
+    if let Some(position) = all_stats.iter().position(|s| s.id == session.id()) {
+        let stats = &mut all_stats[position];
+        stats.remove_time(session.play_time_seconds());
+        match previous {
+            Some(time) => stats.update_last_played_time(time),
+            None if stats.play_time_seconds == 0 => {
+                all_stats.remove(position);
+            }
+            None => (),
+        }
+    }
+    Some(session)
+}
+
+/// The stats of the given games by their IDs, with the play time in seconds,
+/// for tests
+#[cfg(test)]
+pub fn test_stats(
+    stats: &[(&str, u32, UtcDateTime)],
+) -> std::collections::HashMap<String, GameStats> {
+    stats
+        .iter()
+        .map(|(id, play_time, last_played)| {
+            (
+                id.to_string(),
+                GameStats::new(id.to_string(), *play_time, *last_played),
+            )
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_format_goal_progress() {
+        assert_eq!(
+            format_goal_progress(4 * 60 * 60, 10.0),
+            "[########------------] 40%"
+        );
+        assert_eq!(
+            format_goal_progress(15 * 60 * 60, 10.0),
+            "[####################] 150%"
+        );
+        assert_eq!(format_goal_progress(0, 2.5), "[--------------------] 0%");
+    }
+
+    fn from_tsv(line: &str) -> GameStats {
+        parse_stats_file(line).remove(0)
+    }
+
     #[test]
     fn test_add_play_time() {
         let mut stats = GameStats {
@@ -155,7 +378,7 @@ mod tests {
     #[test]
     fn test_parse() {
         let line = "testgame\t5400\t2025-11-03 19:07:00";
-        let stats = GameStats::from_tsv(line);
+        let stats = from_tsv(line);
         assert_eq!(stats.id, "testgame");
         assert_eq!(stats.play_time_seconds, 5400);
 
@@ -166,6 +389,60 @@ mod tests {
         assert_eq!(stats.last_played_time, last_played_time);
     }
 
+    #[test]
+    fn test_id_with_tab_round_trips() {
+        let stats = GameStats::new("odd\tid".to_string(), 60, UtcDateTime::now());
+        let parsed = from_tsv(&stats.to_tsv());
+        assert_eq!(parsed.id, "odd\tid");
+        assert_eq!(parsed.play_time_seconds, 60);
+    }
+
+    #[test]
+    fn test_stats_file_round_trips() {
+        let stats = from_tsv("testgame\t5400\t2025-11-03 19:07:00");
+        let content = format_stats_file(&[stats]);
+        assert_eq!(
+            content,
+            "#version 1\nid\tplay_time_seconds\tlast_played_time\ntestgame\t5400\t2025-11-03 19:07:00\n"
+        );
+        let all_stats = parse_stats_file(&content);
+        assert_eq!(all_stats.len(), 1);
+        assert_eq!(all_stats[0].id, "testgame");
+    }
+
+    #[test]
+    fn test_parse_headerless_stats_file() {
+        let all_stats =
+            parse_stats_file("doom\t60\t2025-11-03 19:07:00\n\nquake\t30\t2025-11-04 19:07:00\n");
+        assert_eq!(all_stats.len(), 2);
+        assert_eq!(all_stats[1].play_time_seconds, 30);
+    }
+
+    #[test]
+    fn test_parse_stats_file_with_game_named_like_a_column() {
+        let content = "#version 2\nid\tplay_time_seconds\tlast_played_time\ndoom\t60\t2025-11-03 19:07:00\nplay_time_seconds\t30\t2025-11-04 19:07:00\n";
+        let all_stats = parse_stats_file(content);
+        assert_eq!(all_stats.len(), 2);
+        assert_eq!(all_stats[1].id, "play_time_seconds");
+        assert_eq!(all_stats[1].play_time_seconds, 30);
+    }
+
+    #[test]
+    fn test_parse_stats_file_with_new_columns() {
+        let content = "#version 2\nid\trating\tlast_played_time\tplay_time_seconds\ndoom\t5\t2025-11-03 19:07:00\t60\n";
+        let all_stats = parse_stats_file(content);
+        assert_eq!(all_stats[0].id, "doom");
+        assert_eq!(all_stats[0].play_time_seconds, 60);
+    }
+
+    #[test]
+    fn test_parse_stats_file_skips_bad_rows() {
+        let content = "#version 2\nid\trating\tlast_played_time\tplay_time_seconds\ndoom\t5\t2025-11-03 19:07:00\nquake\t4\t2025-11-03 19:07:00\tlong\nhexen\t3\t2025-11-04 19:07:00\t30\n";
+        let all_stats = parse_stats_file(content);
+        assert_eq!(all_stats.len(), 1);
+        assert_eq!(all_stats[0].id, "hexen");
+    }
+
     #[test]
     fn test_format_play_time() {
         let stats = GameStats {
@@ -188,11 +465,79 @@ mod tests {
         assert_eq!(s, "45m");
     }
 
+    #[test]
+    fn test_parse_play_time() {
+        assert_eq!(parse_play_time("1h30m15s"), Some(90 * 60 + 15));
+        assert_eq!(parse_play_time("12h"), Some(12 * 60 * 60));
+        assert_eq!(parse_play_time("45m"), Some(45 * 60));
+        assert_eq!(parse_play_time("90"), Some(90));
+        assert_eq!(parse_play_time("2h30"), None);
+        assert_eq!(parse_play_time("2 hours"), None);
+        assert_eq!(parse_play_time(""), None);
+    }
+
+    #[test]
+    fn test_undo_session() {
+        let time = |s: &str| parse_utc(s).unwrap();
+        let mut all_stats = vec![
+            GameStats::new("doom".to_string(), 5400, time("2025-11-04 10:00:00")),
+            GameStats::new("quake".to_string(), 600, time("2025-11-03 10:00:00")),
+        ];
+        let mut sessions = vec![
+            Session::new(
+                "doom".to_string(),
+                time("2025-11-02 10:00:00"),
+                1800,
+                String::new(),
+            ),
+            Session::new(
+                "quake".to_string(),
+                time("2025-11-03 10:00:00"),
+                600,
+                String::new(),
+            ),
+            Session::new(
+                "doom".to_string(),
+                time("2025-11-04 10:00:00"),
+                3600,
+                String::new(),
+            ),
+        ];
+        let undone = undo_session(&mut all_stats, &mut sessions, Some("doom")).unwrap();
+        assert_eq!(undone.play_time_seconds(), 3600);
+        assert_eq!(all_stats[0].play_time_seconds, 1800);
+        assert_eq!(all_stats[0].last_played_time, time("2025-11-02 10:00:00"));
+        assert_eq!(sessions.len(), 2);
+
+        let undone = undo_session(&mut all_stats, &mut sessions, None).unwrap();
+        assert_eq!(undone.id(), "quake");
+        assert_eq!(all_stats.len(), 1);
+        assert!(undo_session(&mut all_stats, &mut sessions, Some("quake")).is_none());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = parse_utc("2025-11-04 10:00:00").unwrap();
+        assert_eq!(parse_since("90d", now), parse_utc("2025-08-06 10:00:00"));
+        let date = parse_date("2025-01-01").unwrap();
+        assert_eq!(parse_since("2025-01-01", now), Some(local_midnight(date)));
+        assert_eq!(parse_since("soon", now), None);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let date = parse_date("2024-05-01").expect("Bad date");
+        assert_eq!(date.year(), 2024);
+        assert_eq!(date.month(), time::Month::May);
+        assert_eq!(date.day(), 1);
+        assert!(parse_date("2024-13-01").is_none());
+    }
+
     #[test]
     fn test_format_last_played_time_in_local_time() {
         // Saved time is UTC
         let line = "testgame\t5400\t2025-11-03 19:07:00";
-        let stats = GameStats::from_tsv(line);
+        let stats = from_tsv(line);
         let s = stats.format_last_played_time();
 
         let date = time::Date::from_calendar_date(2025, time::Month::November, 3).unwrap();
@@ -0,0 +1,330 @@
+use crate::game::GameError;
+use crate::sessions::Session;
+use crate::stats::{self, GameStats};
+use crate::{Game, Games, STATUS_DROPPED, read_all_stats, read_sessions};
+use std::collections::{HashMap, HashSet};
+use time::{Duration, UtcDateTime};
+
+/// How many games are suggested unless asked for another number
+pub const DEFAULT_SUGGESTIONS: usize = 5;
+
+/// How far back the sessions that tell what has been played lately go
+const RECENT_DAYS: i64 = 14;
+
+/// Why a game is suggested
+#[derive(Debug, PartialEq)]
+pub enum Reason {
+    /// A favorite that has not been played for this many days
+    Favorite(i64),
+    /// Never played, with how many hours it takes to finish if known
+    Untouched(Option<f64>),
+    /// Has a tag of the games played lately
+    Tag(String),
+}
+
+pub struct Suggestion<'a> {
+    pub game: &'a Game,
+    pub reason: Reason,
+}
+
+/// Suggest up to `count` of the games, taking turns between the favorites
+/// played least recently, the untouched games that look shortest (by their
+/// `length_hours`), and the games sharing a tag with the ones played in the last
+/// two weeks (most played tag first)
+pub fn suggest<'a>(
+    games: &[&'a Game],
+    all_stats: &HashMap<String, GameStats>,
+    sessions: &[Session],
+    now: UtcDateTime,
+    count: usize,
+) -> Vec<Suggestion<'a>> {
+    let mut favorites: Vec<(&Game, i64)> = games
+        .iter()
+        .filter(|game| game.favorite)
+        .filter_map(|game| {
+            let stats = all_stats.get(&game.id)?;
+            Some((*game, (now - stats.last_played_time()).whole_days()))
+        })
+        .collect();
+    favorites.sort_by(|(a, x), (b, y)| y.cmp(x).then(a.id.cmp(&b.id)));
+
+    let mut untouched: Vec<&Game> = games
+        .iter()
+        .filter(|game| !all_stats.contains_key(&game.id))
+        .copied()
+        .collect();
+    untouched.sort_by(|a, b| {
+        let hours = |game: &Game| game.length_hours.unwrap_or(f64::MAX);
+        hours(a).total_cmp(&hours(b)).then(a.id.cmp(&b.id))
+    });
+
+    // The play time of each tag lately, and the games already played then
+    let since = now - Duration::days(RECENT_DAYS);
+    let mut tag_play_time: HashMap<&str, u32> = HashMap::new();
+    let mut recent_games = HashSet::new();
+    for session in sessions.iter().filter(|s| s.start_time() >= since) {
+        recent_games.insert(session.id());
+        let Some(game) = games.iter().find(|game| game.id == session.id()) else {
+            continue;
+        };
+        for tag in game.tags.iter() {
+            *tag_play_time.entry(tag.as_str()).or_default() += session.play_time_seconds();
+        }
+    }
+    let mut tags: Vec<(&str, u32)> = tag_play_time.into_iter().collect();
+    tags.sort_by(|(a, x), (b, y)| y.cmp(x).then(a.cmp(b)));
+    let mut tagged: Vec<(&Game, &str)> = Vec::new();
+    for (tag, _) in tags.iter() {
+        for game in games.iter() {
+            if game.tags.iter().any(|t| t == tag) && !recent_games.contains(game.id.as_str()) {
+                tagged.push((game, tag));
+            }
+        }
+    }
+
+    let favorites = favorites.into_iter().map(|(game, days)| Suggestion {
+        game,
+        reason: Reason::Favorite(days),
+    });
+    let untouched = untouched.into_iter().map(|game| Suggestion {
+        game,
+        reason: Reason::Untouched(game.length_hours),
+    });
+    let tagged = tagged.into_iter().map(|(game, tag)| Suggestion {
+        game,
+        reason: Reason::Tag(tag.to_string()),
+    });
+    let mut heuristics: Vec<std::vec::IntoIter<Suggestion>> = vec![
+        favorites.collect::<Vec<_>>().into_iter(),
+        untouched.collect::<Vec<_>>().into_iter(),
+        tagged.collect::<Vec<_>>().into_iter(),
+    ];
+    let mut suggestions: Vec<Suggestion> = Vec::new();
+    let mut exhausted = false;
+    while suggestions.len() < count && !exhausted {
+        exhausted = true;
+        for heuristic in heuristics.iter_mut() {
+            let next = heuristic.find(|s| !suggestions.iter().any(|x| x.game.id == s.game.id));
+            if let Some(suggestion) = next
+                && suggestions.len() < count
+            {
+                suggestions.push(suggestion);
+                exhausted = false;
+            }
+        }
+    }
+    suggestions
+}
+
+/// The games that could be finished in the given number of hours, going by
+/// how long they take to finish and how long they have been played, with the
+/// time left to finish each (the least first); games already played for
+/// longer than that are left out
+pub fn finishable<'a>(
+    games: &[&'a Game],
+    all_stats: &HashMap<String, GameStats>,
+    hours: f64,
+) -> Vec<(&'a Game, u32)> {
+    let available = (hours * 60.0 * 60.0) as u32;
+    let mut finishable: Vec<(&Game, u32)> = games
+        .iter()
+        .filter_map(|game| {
+            let length = (game.length_hours? * 60.0 * 60.0) as u32;
+            let played = all_stats.get(&game.id).map_or(0, |s| s.play_time_seconds());
+            let left = length.checked_sub(played).filter(|left| *left > 0)?;
+            Some((*game, left))
+        })
+        .filter(|(_, left)| *left <= available)
+        .collect();
+    finishable.sort_by(|(a, x), (b, y)| x.cmp(y).then(a.id.cmp(&b.id)));
+    finishable
+}
+
+/// Whether the game suits a sitting of the given number of hours: its
+/// `session_length` fits in it, or, for a game without one, the whole game does
+pub fn fits_session(game: &Game, hours: f64) -> bool {
+    game.session_hours
+        .or(game.length_hours)
+        .is_some_and(|needed| needed <= hours)
+}
+
+/// Parse a number of hours like `5h`, `90m` or `2.5`
+pub fn parse_hours(s: &str) -> Option<f64> {
+    let hours = if let Some(minutes) = s.strip_suffix('m') {
+        minutes.parse::<f64>().ok()? / 60.0
+    } else {
+        s.strip_suffix('h').unwrap_or(s).parse::<f64>().ok()?
+    };
+    Some(hours).filter(|hours| *hours > 0.0)
+}
+
+pub fn command_suggest<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let mut count = DEFAULT_SUGGESTIONS;
+    let mut finishable_in = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--finishable" {
+            match args.next().and_then(|hours| parse_hours(hours)) {
+                Some(hours) => finishable_in = Some(hours),
+                None => return Err(GameError::InvalidArgument(arg)),
+            }
+        } else {
+            match arg.parse::<usize>() {
+                Ok(n) => count = n,
+                Err(_) => return Err(GameError::InvalidArgument(arg)),
+            }
+        }
+    }
+    let mut candidates: Vec<&Game> = games
+        .games
+        .values()
+        .filter(|game| game.is_installed() && game.status.as_deref() != Some(STATUS_DROPPED))
+        .collect();
+    candidates.sort_by(|a, b| a.id.cmp(&b.id));
+    let all_stats = read_all_stats();
+    if let Some(hours) = finishable_in {
+        let finishable = finishable(&candidates, &all_stats, hours);
+        if finishable.is_empty() {
+            return Err(GameError::NoMatchingGames);
+        }
+        for (game, left) in finishable.into_iter().take(count) {
+            out!(
+                "{}",
+                tr!(
+                    "suggest-finishable",
+                    game = game.format(),
+                    left = stats::format_play_time(left)
+                )
+            );
+        }
+        return Ok(());
+    }
+    let sessions = read_sessions();
+    let suggestions = suggest(
+        &candidates,
+        &all_stats,
+        &sessions,
+        UtcDateTime::now(),
+        count,
+    );
+    if suggestions.is_empty() {
+        return Err(GameError::NoMatchingGames);
+    }
+    for suggestion in suggestions {
+        let game = suggestion.game.format();
+        let line = match suggestion.reason {
+            Reason::Favorite(days) => tr!("suggest-favorite", game = game, days = days),
+            Reason::Untouched(Some(hours)) => {
+                tr!("suggest-untouched-length", game = game, hours = hours)
+            }
+            Reason::Untouched(None) => tr!("suggest-untouched", game = game),
+            Reason::Tag(tag) => tr!("suggest-tag", game = game, tag = tag),
+        };
+        out!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::test_stats;
+
+    #[test]
+    fn test_suggest() {
+        let now = UtcDateTime::from_unix_timestamp(1762214646).unwrap();
+        let game = |id: &str, tags: &[&str], favorite: bool, length_hours: Option<f64>| Game {
+            id: id.to_string(),
+            name: id.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            favorite,
+            length_hours,
+            ..Default::default()
+        };
+        let games = [
+            game("celeste", &["platformer"], false, Some(8.0)),
+            game("doom", &["fps"], true, None),
+            game("hades", &["roguelike"], true, None),
+            game("hollow_knight", &["platformer"], false, None),
+            game("quake", &["fps"], false, None),
+            game("dead_cells", &["roguelike"], false, Some(3.0)),
+        ];
+        let games: Vec<&Game> = games.iter().collect();
+        let all_stats = test_stats(&[
+            ("doom", 3600, now - Duration::days(200)),
+            ("hades", 3600, now - Duration::days(3)),
+            ("hollow_knight", 3600, now - Duration::days(1)),
+        ]);
+        let sessions = vec![Session::new(
+            "hollow_knight".to_string(),
+            now - Duration::days(1),
+            3600,
+            String::new(),
+        )];
+        let suggestions = suggest(&games, &all_stats, &sessions, now, 4);
+        let found: Vec<(&str, Reason)> = suggestions
+            .into_iter()
+            .map(|s| (s.game.id.as_str(), s.reason))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("doom", Reason::Favorite(200)),
+                ("dead_cells", Reason::Untouched(Some(3.0))),
+                ("celeste", Reason::Tag("platformer".to_string())),
+                ("hades", Reason::Favorite(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_finishable() {
+        let game = |id: &str, length_hours: Option<f64>| Game {
+            id: id.to_string(),
+            name: id.to_string(),
+            length_hours,
+            ..Default::default()
+        };
+        let games = [
+            game("celeste", Some(8.0)),
+            game("inside", Some(3.5)),
+            game("portal", Some(4.0)),
+            game("witcher3", Some(50.0)),
+            game("doom", None),
+            game("limbo", Some(3.0)),
+        ];
+        let games: Vec<&Game> = games.iter().collect();
+        let now = UtcDateTime::now();
+        let all_stats = test_stats(&[("celeste", 5 * 3600, now), ("limbo", 4 * 3600, now)]);
+        let found: Vec<(&str, u32)> = finishable(&games, &all_stats, 5.0)
+            .into_iter()
+            .map(|(game, left)| (game.id.as_str(), left / 60))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("celeste", 180), ("inside", 210), ("portal", 240)]
+        );
+    }
+
+    #[test]
+    fn test_fits_session() {
+        let game = |session_hours: Option<f64>, length_hours: Option<f64>| Game {
+            session_hours,
+            length_hours,
+            ..Default::default()
+        };
+        assert!(fits_session(&game(Some(0.5), Some(60.0)), 1.0));
+        assert!(!fits_session(&game(Some(2.0), Some(1.0)), 1.0));
+        assert!(fits_session(&game(None, Some(1.0)), 1.0));
+        assert!(!fits_session(&game(None, None), 1.0));
+    }
+
+    #[test]
+    fn test_parse_hours() {
+        assert_eq!(parse_hours("5h"), Some(5.0));
+        assert_eq!(parse_hours("90m"), Some(1.5));
+        assert_eq!(parse_hours("2.5"), Some(2.5));
+        assert_eq!(parse_hours("0h"), None);
+        assert_eq!(parse_hours("soon"), None);
+    }
+}
@@ -0,0 +1,373 @@
+use crate::game::GameError;
+use crate::sessions::Session;
+use crate::stats::{GameStats, format_play_time};
+use crate::{Game, Games, read_all_stats, read_sessions};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use time::{Month, UtcDateTime, UtcOffset};
+
+const WRAPPED_TOP_GAMES: usize = 5;
+
+/// Library-wide numbers for the `overview` command
+pub struct Overview<'a> {
+    pub total_games: usize,
+    pub installed: usize,
+    pub tagged: usize,
+    pub total_seconds: u32,
+    pub top_tag: Option<(String, u32)>,
+    pub last_session: Option<&'a Session>,
+}
+
+impl<'a> Overview<'a> {
+    pub fn new(
+        games: &Games,
+        all_stats: &HashMap<String, GameStats>,
+        sessions: &'a [Session],
+    ) -> Overview<'a> {
+        let top_tag = seconds_per_tag(games, all_stats).into_iter().next();
+        Overview {
+            total_games: games.games.len(),
+            installed: games.games.values().filter(|g| g.is_installed()).count(),
+            tagged: games.games.values().filter(|g| !g.tags.is_empty()).count(),
+            total_seconds: all_stats.values().map(|s| s.play_time_seconds()).sum(),
+            top_tag,
+            last_session: sessions.iter().max_by_key(|s| s.start_time()),
+        }
+    }
+
+    pub fn format(&self) -> Vec<String> {
+        vec![
+            format!("Games: {}", self.total_games),
+            format!(
+                "Installed: {} (not installed: {})",
+                self.installed,
+                self.total_games - self.installed
+            ),
+            format!(
+                "Tagged: {} (untagged: {})",
+                self.tagged,
+                self.total_games - self.tagged
+            ),
+            format!("Total Play Time: {}", format_play_time(self.total_seconds)),
+            match &self.top_tag {
+                Some((tag, seconds)) => {
+                    format!("Most Played Tag: {} ({})", tag, format_play_time(*seconds))
+                }
+                None => "Most Played Tag: none".to_string(),
+            },
+            match self.last_session {
+                Some(session) => format!("Last Session: {}", session.format()),
+                None => "Last Session: none".to_string(),
+            },
+        ]
+    }
+}
+
+/// A year in review, from the session log
+pub struct Wrapped<'a> {
+    pub year: i32,
+    pub total_seconds: u32,
+    pub top_games: Vec<(&'a str, u32)>,
+    pub busiest_month: Option<(Month, u32)>,
+    pub longest_session: Option<&'a Session>,
+    pub distinct_games: usize,
+}
+
+impl<'a> Wrapped<'a> {
+    /// Summarize the sessions that started in the given year, in the given
+    /// time zone
+    pub fn new(sessions: &'a [Session], year: i32, offset: UtcOffset) -> Wrapped<'a> {
+        let sessions: Vec<&Session> = sessions
+            .iter()
+            .filter(|s| s.start_time().to_offset(offset).year() == year)
+            .collect();
+        let mut game_seconds: HashMap<&str, u32> = HashMap::new();
+        let mut month_seconds: HashMap<u8, u32> = HashMap::new();
+        for session in sessions.iter() {
+            *game_seconds.entry(session.id()).or_default() += session.play_time_seconds();
+            let month = session.start_time().to_offset(offset).month() as u8;
+            *month_seconds.entry(month).or_default() += session.play_time_seconds();
+        }
+        let distinct_games = game_seconds.len();
+        let mut top_games: Vec<(&str, u32)> = game_seconds.into_iter().collect();
+        top_games.sort_by(|(a_id, a), (b_id, b)| b.cmp(a).then(a_id.cmp(b_id)));
+        top_games.truncate(WRAPPED_TOP_GAMES);
+        let busiest_month = month_seconds
+            .into_iter()
+            .max_by(|(a_month, a), (b_month, b)| a.cmp(b).then(b_month.cmp(a_month)))
+            .and_then(|(month, seconds)| Some((Month::try_from(month).ok()?, seconds)));
+        Wrapped {
+            year,
+            total_seconds: sessions.iter().map(|s| s.play_time_seconds()).sum(),
+            top_games,
+            busiest_month,
+            longest_session: sessions.into_iter().max_by_key(|s| s.play_time_seconds()),
+            distinct_games,
+        }
+    }
+
+    pub fn format(&self, games: &Games) -> Vec<String> {
+        let name = |game_id: &str| match games.find(game_id) {
+            Some(game) => game.name.clone(),
+            None => game_id.to_string(),
+        };
+        let mut lines = vec![
+            format!("{} Wrapped", self.year),
+            format!("Total Play Time: {}", format_play_time(self.total_seconds)),
+            format!("Games Played: {}", self.distinct_games),
+        ];
+        if let Some((month, seconds)) = self.busiest_month {
+            lines.push(format!(
+                "Busiest Month: {} ({})",
+                month,
+                format_play_time(seconds)
+            ));
+        }
+        if let Some(session) = self.longest_session {
+            lines.push(format!(
+                "Longest Session: {} ({})",
+                name(session.id()),
+                format_play_time(session.play_time_seconds())
+            ));
+        }
+        if !self.top_games.is_empty() {
+            lines.push("Top Games:".to_string());
+        }
+        for (i, (game_id, seconds)) in self.top_games.iter().enumerate() {
+            lines.push(format!(
+                "{}. {} ({})",
+                i + 1,
+                name(game_id),
+                format_play_time(*seconds)
+            ));
+        }
+        lines
+    }
+}
+
+/// How a game is launched, judging by its command
+pub fn launcher(game: &Game) -> &'static str {
+    if game.steam_id.is_some() {
+        return "steam";
+    }
+    let programs: Vec<String> = game
+        .command
+        .iter()
+        .map(|arg| match Path::new(arg).file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => arg.to_string(),
+        })
+        .collect();
+    let mut programs = programs.as_slice();
+    match programs.first().map(|p| p.as_str()) {
+        Some("gamescope") => return "gamescope",
+        Some("mangohud") => programs = &programs[1..],
+        _ => {}
+    }
+    // A sandbox's own arguments end with `--`
+    if let Some("bwrap" | "firejail") = programs.first().map(|p| p.as_str())
+        && let Some(end) = programs.iter().position(|p| p == "--")
+    {
+        programs = &programs[end + 1..];
+    }
+    let program = programs.first();
+    match program.map(|p| p.as_str()) {
+        Some("wine") | Some("wine64") => "wine",
+        Some(p) if p.starts_with("dosbox") => "dosbox",
+        Some("scummvm") => "scummvm",
+        _ => "native",
+    }
+}
+
+/// Total play time for each tag that has been played, most played first
+pub fn seconds_per_tag(
+    games: &Games,
+    all_stats: &HashMap<String, GameStats>,
+) -> Vec<(String, u32)> {
+    let mut tag_seconds: HashMap<&str, u32> = HashMap::new();
+    for game in games.games.values() {
+        let play_time = all_stats.get(&game.id).map_or(0, |s| s.play_time_seconds());
+        for tag in game.tags.iter() {
+            *tag_seconds.entry(tag).or_default() += play_time;
+        }
+    }
+    let mut tags: Vec<(String, u32)> = tag_seconds
+        .into_iter()
+        .filter(|(_, seconds)| *seconds > 0)
+        .map(|(tag, seconds)| (tag.to_string(), seconds))
+        .collect();
+    tags.sort_by(|(a_tag, a), (b_tag, b)| b.cmp(a).then(a_tag.cmp(b_tag)));
+    tags
+}
+
+/// The number of sessions in each month, as `YYYY-MM`, oldest first
+pub fn sessions_per_month(sessions: &[&Session], offset: UtcOffset) -> Vec<(String, u32)> {
+    let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+    for session in sessions.iter() {
+        let start_time = session.start_time().to_offset(offset);
+        let month = format!("{}-{:02}", start_time.year(), start_time.month() as u8);
+        *counts.entry(month).or_default() += 1;
+    }
+    counts.into_iter().collect()
+}
+
+/// Total play time for each launcher, most played first
+pub fn launcher_breakdown(
+    games: &Games,
+    all_stats: &HashMap<String, GameStats>,
+) -> Vec<(&'static str, u32)> {
+    let mut seconds: HashMap<&'static str, u32> = HashMap::new();
+    for game in games.games.values() {
+        if let Some(stats) = all_stats.get(&game.id) {
+            *seconds.entry(launcher(game)).or_default() += stats.play_time_seconds();
+        }
+    }
+    let mut breakdown: Vec<(&'static str, u32)> = seconds.into_iter().collect();
+    breakdown.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+    breakdown
+}
+
+pub fn command_overview<'a>(games: &Games, _: &'a [String]) -> Result<(), GameError<'a>> {
+    let all_stats = read_all_stats();
+    let sessions = read_sessions();
+    for line in Overview::new(games, &all_stats, &sessions).format() {
+        out!("{}", line);
+    }
+    Ok(())
+}
+
+pub fn command_wrapped<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let offset = time::UtcOffset::current_local_offset().unwrap_or(time::UtcOffset::UTC);
+    let year = match args.first() {
+        Some(arg) => match arg.parse::<i32>() {
+            Ok(year) => year,
+            Err(_) => return Err(GameError::InvalidArgument(arg)),
+        },
+        None => UtcDateTime::now().to_offset(offset).year(),
+    };
+    let sessions = read_sessions();
+    for line in Wrapped::new(&sessions, year, offset).format(games) {
+        out!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+    use crate::stats::test_stats;
+
+    #[test]
+    fn test_overview() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        tags = [\"fps\"]
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"vkquake\"
+        tags = [\"fps\", \"3d\"]
+        installed = false
+
+        [games.tetris]
+        name = \"Tetris\"
+        cmd = \"tetris\"";
+        let games = parse_config(config).expect("Bad config");
+        let now = UtcDateTime::now();
+        let all_stats = test_stats(&[
+            ("doom", 3600, now),
+            ("quake", 1800, now),
+            ("tetris", 7200, now),
+        ]);
+        let overview = Overview::new(&games, &all_stats, &[]);
+        assert_eq!(overview.total_games, 3);
+        assert_eq!(overview.installed, 2);
+        assert_eq!(overview.tagged, 2);
+        assert_eq!(overview.total_seconds, 12600);
+        assert_eq!(overview.top_tag, Some(("fps".to_string(), 5400)));
+        assert!(overview.last_session.is_none());
+    }
+
+    #[test]
+    fn test_launcher_breakdown() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        dosbox_config = \"doom.conf\"
+
+        [games.morrowind]
+        name = \"Morrowind\"
+        wine_exe = \"Morrowind.exe\"
+        sandbox = \"bwrap:offline\"
+
+        [games.monkey]
+        name = \"Monkey Island\"
+        scummvm_id = \"monkey\"";
+        let games = parse_config(config).expect("Bad config");
+        assert_eq!(launcher(games.find("doom").unwrap()), "dosbox");
+        assert_eq!(launcher(games.find("morrowind").unwrap()), "wine");
+        assert_eq!(launcher(games.find("monkey").unwrap()), "scummvm");
+
+        let now = UtcDateTime::now();
+        let all_stats = test_stats(&[("doom", 3600, now), ("morrowind", 7200, now)]);
+        assert_eq!(
+            launcher_breakdown(&games, &all_stats),
+            vec![("wine", 7200), ("dosbox", 3600)]
+        );
+    }
+
+    #[test]
+    fn test_sessions_per_month() {
+        let session = |month: Month, day: u8| {
+            let date = time::Date::from_calendar_date(2025, month, day).unwrap();
+            let start_time = UtcDateTime::new(date, time::Time::from_hms(23, 30, 0).unwrap());
+            Session::new("doom".to_string(), start_time, 60, String::new())
+        };
+        let sessions = [
+            session(Month::March, 1),
+            session(Month::March, 2),
+            session(Month::March, 31),
+        ];
+        let sessions: Vec<&Session> = sessions.iter().collect();
+        assert_eq!(
+            sessions_per_month(&sessions, UtcOffset::UTC),
+            vec![("2025-03".to_string(), 3)]
+        );
+        // The last session was in April where it was played
+        let offset = UtcOffset::from_hms(1, 0, 0).unwrap();
+        assert_eq!(
+            sessions_per_month(&sessions, offset),
+            vec![("2025-03".to_string(), 2), ("2025-04".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_wrapped() {
+        let session = |id: &str, month: Month, day: u8, seconds: u32| {
+            let date = time::Date::from_calendar_date(2025, month, day).unwrap();
+            let start_time = UtcDateTime::new(date, time::Time::from_hms(12, 0, 0).unwrap());
+            Session::new(id.to_string(), start_time, seconds, String::new())
+        };
+        let sessions = vec![
+            session("doom", Month::March, 1, 3600),
+            session("doom", Month::March, 2, 1800),
+            session("quake", Month::July, 4, 4000),
+            session("tetris", Month::July, 5, 600),
+        ];
+        let wrapped = Wrapped::new(&sessions, 2025, UtcOffset::UTC);
+        assert_eq!(wrapped.total_seconds, 10000);
+        assert_eq!(wrapped.distinct_games, 3);
+        assert_eq!(wrapped.top_games[0], ("doom", 5400));
+        assert_eq!(wrapped.busiest_month, Some((Month::March, 5400)));
+        assert_eq!(wrapped.longest_session.unwrap().id(), "quake");
+        assert_eq!(
+            Wrapped::new(&sessions, 2024, UtcOffset::UTC).total_seconds,
+            0
+        );
+    }
+}
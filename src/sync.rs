@@ -0,0 +1,327 @@
+use crate::sessions::{self, Session};
+use crate::stats::{self, GameStats};
+use crate::{
+    SESSIONS_FILE, STATS_FILE, UtilityCommandError, data_dir, profile_path, read_sessions,
+    read_stats, sessions_file_path, stats_file_path,
+};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// The stats as of the last sync, kept in the data directory, so that the
+/// play time added on each side since then can be told apart
+pub const SYNC_BASE_FILE: &str = "sync_base.tsv";
+
+/// rclone's exit codes for a missing directory or file
+const RCLONE_NOT_FOUND: [i32; 2] = [3, 4];
+
+/// How play times that differ between the two sides are merged
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// Add up the time played on each side since the last sync
+    Sum,
+    /// Take the larger of the two
+    Max,
+}
+
+impl Strategy {
+    pub fn parse(s: &str) -> Option<Strategy> {
+        match s {
+            "sum" => Some(Strategy::Sum),
+            "max" => Some(Strategy::Max),
+            _ => None,
+        }
+    }
+}
+
+/// Where the other copy of the stats is kept
+#[derive(Debug, PartialEq)]
+pub enum Remote {
+    Dir(PathBuf),
+    Rclone(String),
+}
+
+impl Remote {
+    /// A directory (like a Syncthing or Dropbox folder), or an rclone remote
+    /// (like `dropbox:game_rs`) if there is no directory by that name
+    pub fn parse(remote: &str) -> Remote {
+        let path = PathBuf::from(remote);
+        if !path.is_dir() && remote.contains(':') {
+            Remote::Rclone(remote.to_string())
+        } else {
+            Remote::Dir(path)
+        }
+    }
+
+    fn rclone_path(remote: &str, file_name: &str) -> String {
+        if remote.ends_with(':') || remote.ends_with('/') {
+            format!("{}{}", remote, file_name)
+        } else {
+            format!("{}/{}", remote, file_name)
+        }
+    }
+
+    /// The content of the remote copy of the file, or `None` if there is none
+    pub fn pull(&self, file_name: &str) -> Result<Option<String>, String> {
+        match self {
+            Remote::Dir(dir) => match fs::read_to_string(dir.join(file_name)) {
+                Ok(content) => Ok(Some(content)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(format!("{}: {}", dir.join(file_name).display(), e)),
+            },
+            Remote::Rclone(remote) => {
+                let path = Remote::rclone_path(remote, file_name);
+                debug!("Running: rclone cat {}", path);
+                let output = match Command::new("rclone").args(["cat", &path]).output() {
+                    Ok(output) => output,
+                    Err(e) => return Err(format!("rclone: {}", e)),
+                };
+                match output.status.code() {
+                    Some(0) => Ok(Some(String::from_utf8_lossy(&output.stdout).to_string())),
+                    Some(code) if RCLONE_NOT_FOUND.contains(&code) => Ok(None),
+                    _ => Err(format!(
+                        "rclone cat {}: {}",
+                        path,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Replace the remote copy of the file
+    pub fn push(&self, file_name: &str, content: &str) -> Result<(), String> {
+        match self {
+            Remote::Dir(dir) => {
+                let path = dir.join(file_name);
+                fs::create_dir_all(path.parent().unwrap_or(dir))
+                    .and_then(|_| fs::write(&path, content))
+                    .map_err(|e| format!("{}: {}", path.display(), e))
+            }
+            Remote::Rclone(remote) => {
+                let path = Remote::rclone_path(remote, file_name);
+                debug!("Running: rclone rcat {}", path);
+                let child = Command::new("rclone")
+                    .args(["rcat", &path])
+                    .stdin(Stdio::piped())
+                    .spawn();
+                let mut child = match child {
+                    Ok(child) => child,
+                    Err(e) => return Err(format!("rclone: {}", e)),
+                };
+                if let Some(mut stdin) = child.stdin.take()
+                    && let Err(e) = stdin.write_all(content.as_bytes())
+                {
+                    return Err(format!("rclone rcat {}: {}", path, e));
+                }
+                match child.wait() {
+                    Ok(status) if status.success() => Ok(()),
+                    _ => Err(format!("rclone rcat {} failed", path)),
+                }
+            }
+        }
+    }
+}
+
+/// Merge the stats of each game: the last played time is the later one, and
+/// the play time follows the strategy. Without the stats as of the last sync
+/// (as on the first one) there is no telling what was added since, so the
+/// larger play time is used.
+pub fn merge_stats(
+    base: Option<&[GameStats]>,
+    local: &[GameStats],
+    remote: &[GameStats],
+    strategy: Strategy,
+) -> Vec<GameStats> {
+    let strategy = if base.is_some() {
+        strategy
+    } else {
+        Strategy::Max
+    };
+    let by_id = |all_stats: &[GameStats]| -> BTreeMap<String, (u32, time::UtcDateTime)> {
+        all_stats
+            .iter()
+            .map(|s| {
+                (
+                    s.id().to_string(),
+                    (s.play_time_seconds(), s.last_played_time()),
+                )
+            })
+            .collect()
+    };
+    let base = by_id(base.unwrap_or_default());
+    let local = by_id(local);
+    let mut merged = local.clone();
+    for (game_id, (remote_time, remote_last_played)) in by_id(remote) {
+        let Some((local_time, local_last_played)) = local.get(&game_id) else {
+            merged.insert(game_id, (remote_time, remote_last_played));
+            continue;
+        };
+        let play_time = match strategy {
+            Strategy::Max => remote_time.max(*local_time),
+            Strategy::Sum => {
+                // The time as of the last sync, plus what each side added
+                let base_time = base.get(&game_id).map(|(t, _)| *t as i64).unwrap_or(0);
+                let sum = *local_time as i64 + remote_time as i64 - base_time;
+                sum.clamp(0, u32::MAX as i64) as u32
+            }
+        };
+        let last_played = remote_last_played.max(*local_last_played);
+        merged.insert(game_id, (play_time, last_played));
+    }
+    merged
+        .into_iter()
+        .map(|(game_id, (play_time, last_played))| GameStats::new(game_id, play_time, last_played))
+        .collect()
+}
+
+/// All the sessions from both logs, in the order they started, where a
+/// session in both (the same game started at the same time) is only kept once
+pub fn merge_sessions(local: Vec<Session>, remote: Vec<Session>) -> Vec<Session> {
+    let mut merged = BTreeMap::new();
+    for session in remote.into_iter().chain(local) {
+        let key = (session.start_time(), session.id().to_string());
+        merged.insert(key, session);
+    }
+    merged.into_values().collect()
+}
+
+pub fn command_sync(args: &[String]) -> Result<(), UtilityCommandError> {
+    let mut remote = None;
+    let mut strategy = Strategy::Sum;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--play-time" => match args.next().and_then(|s| Strategy::parse(s)) {
+                Some(s) => strategy = s,
+                None => return Err(UtilityCommandError::InvalidArgument(arg.to_string())),
+            },
+            _ if remote.is_none() && !arg.starts_with("--") => remote = Some(arg.as_str()),
+            _ => return Err(UtilityCommandError::InvalidArgument(arg.to_string())),
+        }
+    }
+    let Some(remote_name) = remote else {
+        return Err(UtilityCommandError::InvalidArgument("REMOTE".to_string()));
+    };
+    let remote = Remote::parse(remote_name);
+    debug!("Syncing with {:?}", remote);
+    let remote_stats = remote
+        .pull(&profile_path(STATS_FILE))
+        .map_err(UtilityCommandError::CouldNotSync)?;
+    let remote_sessions = remote
+        .pull(&profile_path(SESSIONS_FILE))
+        .map_err(UtilityCommandError::CouldNotSync)?;
+
+    let base_path = data_dir().join(profile_path(SYNC_BASE_FILE));
+    let base = fs::read_to_string(&base_path)
+        .ok()
+        .map(|content| stats::parse_stats_file(&content));
+    let local_stats = stats::parse_stats_file(&read_stats().unwrap_or_default());
+    let all_stats = merge_stats(
+        base.as_deref(),
+        &local_stats,
+        &stats::parse_stats_file(&remote_stats.unwrap_or_default()),
+        strategy,
+    );
+    let sessions = merge_sessions(
+        read_sessions(),
+        sessions::parse_log(&remote_sessions.unwrap_or_default()),
+    );
+    let stats_content = stats::format_stats_file(&all_stats);
+    let sessions_content = sessions::format_log(&sessions);
+
+    // The remote is updated first, so that a failure leaves this side as it
+    // was and the sync can simply be run again
+    remote
+        .push(&profile_path(STATS_FILE), &stats_content)
+        .map_err(UtilityCommandError::CouldNotSync)?;
+    remote
+        .push(&profile_path(SESSIONS_FILE), &sessions_content)
+        .map_err(UtilityCommandError::CouldNotSync)?;
+    let result = fs::write(stats_file_path(), &stats_content)
+        .and_then(|_| fs::write(sessions_file_path(), &sessions_content))
+        .and_then(|_| fs::write(&base_path, &stats_content));
+    if let Err(e) = result {
+        return Err(UtilityCommandError::CouldNotSync(e.to_string()));
+    }
+    out!(
+        "Synced {} games and {} sessions with {}",
+        all_stats.len(),
+        sessions.len(),
+        remote_name
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::UtcDateTime;
+
+    fn at(timestamp: i64) -> UtcDateTime {
+        UtcDateTime::from_unix_timestamp(timestamp).unwrap()
+    }
+
+    fn stats(game_id: &str, play_time: u32, last_played: i64) -> GameStats {
+        GameStats::new(game_id.to_string(), play_time, at(last_played))
+    }
+
+    #[test]
+    fn test_merge_stats() {
+        let base = vec![stats("doom", 100, 1000)];
+        let local = vec![stats("doom", 150, 2000), stats("quake", 10, 1500)];
+        let remote = vec![stats("doom", 130, 3000), stats("hexen", 20, 1200)];
+        let merged = merge_stats(Some(&base), &local, &remote, Strategy::Sum);
+        let merged: Vec<(&str, u32, UtcDateTime)> = merged
+            .iter()
+            .map(|s| (s.id(), s.play_time_seconds(), s.last_played_time()))
+            .collect();
+        assert_eq!(
+            merged,
+            vec![
+                ("doom", 180, at(3000)),
+                ("hexen", 20, at(1200)),
+                ("quake", 10, at(1500)),
+            ]
+        );
+
+        let merged = merge_stats(Some(&base), &local, &remote, Strategy::Max);
+        assert_eq!(merged[0].play_time_seconds(), 150);
+        // Without a base, both copies may well hold the same time
+        let merged = merge_stats(None, &local, &remote, Strategy::Sum);
+        assert_eq!(merged[0].play_time_seconds(), 150);
+    }
+
+    #[test]
+    fn test_merge_sessions() {
+        let session = |game_id: &str, start: i64, note: &str| {
+            Session::new(game_id.to_string(), at(start), 60, note.to_string())
+        };
+        let local = vec![session("doom", 1000, "local"), session("doom", 3000, "")];
+        let remote = vec![session("doom", 1000, "remote"), session("quake", 2000, "")];
+        let merged = merge_sessions(local, remote);
+        let lines: Vec<String> = merged.iter().map(|s| s.to_tsv()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "doom\t1970-01-01 00:16:40\t60\tlocal",
+                "quake\t1970-01-01 00:33:20\t60\t",
+                "doom\t1970-01-01 00:50:00\t60\t",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_remote() {
+        assert_eq!(
+            Remote::parse("dropbox:game_rs"),
+            Remote::Rclone("dropbox:game_rs".to_string())
+        );
+        assert_eq!(
+            Remote::parse("/home/test/Sync"),
+            Remote::Dir(PathBuf::from("/home/test/Sync"))
+        );
+    }
+}
@@ -27,6 +27,14 @@ impl TagGroup {
         TagGroup { tags }
     }
 
+    /// The tags a game must have to match
+    pub fn required(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .iter()
+            .filter(|tag| !tag.is_negated)
+            .map(|tag| tag.name.as_str())
+    }
+
     pub fn matches(&self, tags: &[&str]) -> bool {
         let mut tag_set: HashSet<&str> = HashSet::new();
         for s in tags.iter() {
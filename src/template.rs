@@ -0,0 +1,43 @@
+/// Replace each `{name}` in the template with the matching value
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut rendered = template.to_string();
+    for (name, value) in vars.iter() {
+        rendered = rendered.replace(&format!("{{{}}}", name), value);
+    }
+    rendered
+}
+
+/// Split a command template into words and render each word, so that values
+/// containing spaces stay a single argument
+pub fn render_command(template: &str, vars: &[(&str, &str)]) -> Option<Vec<String>> {
+    let words = shell_words::split(template).ok()?;
+    Some(words.iter().map(|word| render(word, vars)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let s = render("{dir}/mods/{id}", &[("dir", "/games/doom"), ("id", "doom")]);
+        assert_eq!(s, "/games/doom/mods/doom");
+    }
+
+    #[test]
+    fn test_render_command_keeps_values_with_spaces_together() {
+        let command = render_command(
+            "antimicrox --hidden --profile {profile}",
+            &[("profile", "/home/test/My Profiles/doom.amgp")],
+        );
+        assert_eq!(
+            command,
+            Some(vec![
+                "antimicrox".to_string(),
+                "--hidden".to_string(),
+                "--profile".to_string(),
+                "/home/test/My Profiles/doom.amgp".to_string(),
+            ])
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use crate::global_options;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long each step took, in the order they finished
+static TIMINGS: Mutex<Vec<(&'static str, Duration)>> = Mutex::new(Vec::new());
+
+/// Run a step, noting how long it took if `--timings` was given
+pub fn time<T>(step: &'static str, f: impl FnOnce() -> T) -> T {
+    if !global_options::get().timings {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    record(step, start.elapsed());
+    result
+}
+
+fn record(step: &'static str, duration: Duration) {
+    if let Ok(mut timings) = TIMINGS.lock() {
+        timings.push((step, duration));
+    }
+}
+
+/// Print how long each step took on stderr, with the same steps added up,
+/// if `--timings` was given
+pub fn report() {
+    if !global_options::get().timings {
+        return;
+    }
+    let Ok(timings) = TIMINGS.lock() else {
+        return;
+    };
+    for (step, duration) in total_by_step(&timings) {
+        eprintln!("timings: {} {}", step, format_duration(duration));
+    }
+}
+
+/// The time taken by each step, adding up steps that ran more than once, in
+/// the order they first ran
+fn total_by_step(timings: &[(&'static str, Duration)]) -> Vec<(&'static str, Duration)> {
+    let mut totals: Vec<(&'static str, Duration)> = Vec::new();
+    for (step, duration) in timings.iter() {
+        match totals.iter_mut().find(|(s, _)| s == step) {
+            Some((_, total)) => *total += *duration,
+            None => totals.push((step, *duration)),
+        }
+    }
+    totals
+}
+
+/// A duration in milliseconds, to a thousandth
+fn format_duration(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_by_step() {
+        let timings = [
+            ("config", Duration::from_millis(3)),
+            ("stats read", Duration::from_micros(250)),
+            ("stats read", Duration::from_micros(500)),
+        ];
+        let totals: Vec<(&str, String)> = total_by_step(&timings)
+            .into_iter()
+            .map(|(step, duration)| (step, format_duration(duration)))
+            .collect();
+        assert_eq!(
+            totals,
+            vec![
+                ("config", "3.000ms".to_string()),
+                ("stats read", "0.750ms".to_string())
+            ]
+        );
+    }
+}
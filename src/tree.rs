@@ -0,0 +1,106 @@
+use crate::game::{Game, GameError};
+use crate::{Games, ListOptions, listed_games};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A heading with the lines under it, drawn as a branch of the tree
+pub struct Branch {
+    pub heading: String,
+    pub leaves: Vec<String>,
+}
+
+/// Draw the branches as a tree, like `tree` does, one line per heading and
+/// per leaf
+pub fn render(branches: &[Branch]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for branch in branches.iter() {
+        lines.push(branch.heading.to_string());
+        for (i, leaf) in branch.leaves.iter().enumerate() {
+            let connector = if i + 1 == branch.leaves.len() {
+                "└──"
+            } else {
+                "├──"
+            };
+            lines.push(format!("{} {}", connector, leaf));
+        }
+    }
+    lines
+}
+
+/// Show where the installed games are on disk, under the shared directory
+/// each is in, the most crowded first
+pub fn command_tree<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    if let Some(arg) = args.first() {
+        return Err(GameError::InvalidArgument(arg));
+    }
+    let listed = listed_games(games, &[], &ListOptions::default(), &HashMap::new());
+    let mut branches: Vec<(Option<&str>, Vec<&Game>)> = Vec::new();
+    for game in listed.into_iter() {
+        let prefix = game.dir_prefix.as_deref();
+        match branches.iter_mut().find(|(p, _)| *p == prefix) {
+            Some((_, in_prefix)) => in_prefix.push(game),
+            None => branches.push((prefix, vec![game])),
+        }
+    }
+    // Games outside the shared directories go last
+    branches.sort_by(|(a, x), (b, y)| (a.is_none(), y.len(), a).cmp(&(b.is_none(), x.len(), b)));
+    let branches: Vec<Branch> = branches
+        .into_iter()
+        .map(|(prefix, in_prefix)| {
+            let prefix_dir = prefix.and_then(|p| games.directories.get(p)?.as_str());
+            let heading = match (prefix, prefix_dir) {
+                (Some(prefix), Some(dir)) => format!("{}: {}", prefix, dir),
+                _ => "(none)".to_string(),
+            };
+            let leaves = in_prefix
+                .iter()
+                .map(|game| match &game.dir {
+                    Some(dir) => {
+                        let dir = Path::new(dir);
+                        let relative = prefix_dir.and_then(|p| dir.strip_prefix(p).ok());
+                        let shown = relative.unwrap_or(dir);
+                        format!("{} ({})", game.format(), shown.display())
+                    }
+                    None => game.format(),
+                })
+                .collect();
+            Branch {
+                heading: format!("{} ({})", heading, in_prefix.len()),
+                leaves,
+            }
+        })
+        .collect();
+    for line in render(&branches) {
+        out!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let branches = [
+            Branch {
+                heading: "gog (/games/gog)".to_string(),
+                leaves: vec!["quake".to_string(), "witcher".to_string()],
+            },
+            Branch {
+                heading: "(none)".to_string(),
+                leaves: vec!["doom".to_string()],
+            },
+        ];
+        assert_eq!(
+            render(&branches),
+            vec![
+                "gog (/games/gog)",
+                "├── quake",
+                "└── witcher",
+                "(none)",
+                "└── doom"
+            ]
+        );
+    }
+}
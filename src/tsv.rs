@@ -0,0 +1,72 @@
+const QUOTE: char = '"';
+
+/// Join fields into a tab-separated line, quoting any field that contains a
+/// tab or a quote so that it cannot be mistaken for more than one field
+pub fn format_line(fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains(['\t', QUOTE]) {
+                format!("\"{}\"", field.replace(QUOTE, "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\t")
+}
+
+/// Split a tab-separated line into fields, where a field may be quoted and
+/// a doubled quote inside a quoted field is a literal quote
+pub fn split_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut in_quotes = false;
+    let mut at_field_start = true;
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == QUOTE {
+                if chars.peek() == Some(&QUOTE) {
+                    field.push(QUOTE);
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+        match c {
+            '\t' => {
+                fields.push(std::mem::take(&mut field));
+                at_field_start = true;
+                continue;
+            }
+            QUOTE if at_field_start => in_quotes = true,
+            _ => field.push(c),
+        }
+        at_field_start = false;
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_fields_are_not_quoted() {
+        assert_eq!(format_line(&["doom", "60"]), "doom\t60");
+        assert_eq!(split_line("doom\t60"), vec!["doom", "60"]);
+    }
+
+    #[test]
+    fn test_quoted_fields_round_trip() {
+        let line = format_line(&["odd\tid", "say \"hi\"", "60"]);
+        assert_eq!(line, "\"odd\tid\"\t\"say \"\"hi\"\"\"\t60");
+        assert_eq!(split_line(&line), vec!["odd\tid", "say \"hi\"", "60"]);
+    }
+}
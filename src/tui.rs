@@ -0,0 +1,312 @@
+use crate::game::GameError;
+use crate::sessions::Session;
+use crate::stats::{GameStats, format_play_time};
+use crate::{Game, Games, fuzzy, play_game, read_all_stats, read_sessions};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame, try_init, try_restore};
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+
+const RECENT_SESSIONS: usize = 10;
+const HELP: &str = "↑/↓ move  t/T tag  Enter play  q quit";
+
+/// What to do once the dashboard has been closed
+pub enum Action<'a> {
+    Play(&'a Game),
+    Quit,
+}
+
+/// The state of the dashboard, which is kept while a game is being played
+pub struct App {
+    tags: Vec<String>,
+    /// Which of the tags the library is filtered by, if any
+    tag_index: Option<usize>,
+    list_state: ListState,
+    all_stats: HashMap<String, GameStats>,
+    sessions: Vec<Session>,
+}
+
+impl App {
+    pub fn new(games: &Games) -> App {
+        let tags: BTreeSet<&String> = games.games.values().flat_map(|g| g.tags.iter()).collect();
+        App {
+            tags: tags.into_iter().cloned().collect(),
+            tag_index: None,
+            list_state: ListState::default().with_selected(Some(0)),
+            all_stats: HashMap::new(),
+            sessions: Vec::new(),
+        }
+    }
+
+    /// Replace the stats and sessions, such as after a game has been played
+    pub fn load(&mut self, all_stats: HashMap<String, GameStats>, sessions: Vec<Session>) {
+        self.all_stats = all_stats;
+        self.sessions = sessions;
+    }
+
+    fn tag(&self) -> Option<&str> {
+        self.tag_index.map(|i| self.tags[i].as_str())
+    }
+
+    /// The games in the library pane, sorted by name
+    pub fn visible_games<'a>(&self, games: &'a Games) -> Vec<&'a Game> {
+        let mut visible: Vec<&Game> = games
+            .games
+            .values()
+            .filter(|game| {
+                self.tag()
+                    .is_none_or(|tag| game.tags.iter().any(|t| t == tag))
+            })
+            .collect();
+        visible.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+        visible
+    }
+
+    /// Filter by the next tag, or the previous one if going backwards, where
+    /// no filter comes before the first tag and after the last
+    pub fn cycle_tag(&mut self, forwards: bool) {
+        let count = self.tags.len();
+        self.tag_index = match (self.tag_index, forwards) {
+            _ if count == 0 => None,
+            (None, true) => Some(0),
+            (None, false) => Some(count - 1),
+            (Some(i), true) if i + 1 < count => Some(i + 1),
+            (Some(i), false) if i > 0 => Some(i - 1),
+            (Some(_), _) => None,
+        };
+        self.list_state.select(Some(0));
+    }
+
+    fn move_selection(&mut self, forwards: bool, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let selected = self.list_state.selected().unwrap_or(0).min(count - 1);
+        let selected = if forwards {
+            (selected + 1) % count
+        } else {
+            (selected + count - 1) % count
+        };
+        self.list_state.select(Some(selected));
+    }
+
+    fn draw(&mut self, frame: &mut Frame, games: &Games) {
+        let [main_area, help_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [library_area, details_area] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main_area);
+        let [stats_area, sessions_area] =
+            Layout::vertical([Constraint::Length(8), Constraint::Min(0)]).areas(details_area);
+
+        let visible = self.visible_games(games);
+        let title = match self.tag() {
+            Some(tag) => format!("Library [{}]", tag),
+            None => "Library".to_string(),
+        };
+        let items: Vec<String> = visible.iter().map(|game| game.name.clone()).collect();
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, library_area, &mut self.list_state);
+
+        let selected = self.list_state.selected().and_then(|i| visible.get(i));
+        let stats_lines = match selected {
+            Some(game) => self.stats_lines(game),
+            None => vec![Line::from("No games")],
+        };
+        frame.render_widget(
+            Paragraph::new(stats_lines).block(Block::bordered().title("Stats")),
+            stats_area,
+        );
+
+        let mut sessions: Vec<&Session> = self
+            .sessions
+            .iter()
+            .filter(|s| selected.is_some_and(|game| s.id() == game.id))
+            .collect();
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.start_time()));
+        let session_lines: Vec<Line> = sessions
+            .iter()
+            .take(RECENT_SESSIONS)
+            .map(|s| Line::from(s.format()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(session_lines).block(Block::bordered().title("Recent Sessions")),
+            sessions_area,
+        );
+        frame.render_widget(Paragraph::new(HELP), help_area);
+    }
+
+    fn stats_lines(&self, game: &Game) -> Vec<Line<'static>> {
+        let session_count = self.sessions.iter().filter(|s| s.id() == game.id).count();
+        let (play_time, last_played) = match self.all_stats.get(&game.id) {
+            Some(stats) => (
+                format_play_time(stats.play_time_seconds()),
+                stats.format_last_played_time(),
+            ),
+            None => (format_play_time(0), "never".to_string()),
+        };
+        vec![
+            Line::from(format!("{} ({})", game.name, game.id)),
+            Line::from(format!("Play Time: {}", play_time)),
+            Line::from(format!("Last Played: {}", last_played)),
+            Line::from(format!("Sessions: {}", session_count)),
+            Line::from(format!("Tags: {}", game.tags.join(", "))),
+            Line::from(if game.is_installed() {
+                "Installed"
+            } else {
+                "Not installed"
+            }),
+        ]
+    }
+}
+
+/// Show the dashboard until a game is chosen or it is closed
+pub fn run<'a>(app: &mut App, games: &'a Games) -> io::Result<Action<'a>> {
+    let mut terminal = try_init()?;
+    let result = event_loop(&mut terminal, app, games);
+    try_restore()?;
+    result
+}
+
+fn event_loop<'a>(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    games: &'a Games,
+) -> io::Result<Action<'a>> {
+    loop {
+        terminal.draw(|frame| app.draw(frame, games))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let visible = app.visible_games(games);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::Quit),
+            KeyCode::Down | KeyCode::Char('j') => app.move_selection(true, visible.len()),
+            KeyCode::Up | KeyCode::Char('k') => app.move_selection(false, visible.len()),
+            KeyCode::Char('t') => app.cycle_tag(true),
+            KeyCode::Char('T') => app.cycle_tag(false),
+            KeyCode::Enter => {
+                if let Some(game) = app.list_state.selected().and_then(|i| visible.get(i)) {
+                    return Ok(Action::Play(game));
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Pick one of the items by typing part of it, returning its index, or
+/// nothing if the selector was closed
+pub fn select(items: &[String]) -> io::Result<Option<usize>> {
+    let mut terminal = try_init()?;
+    let result = select_loop(&mut terminal, items);
+    try_restore()?;
+    result
+}
+
+fn select_loop(terminal: &mut DefaultTerminal, items: &[String]) -> io::Result<Option<usize>> {
+    let mut query = String::new();
+    let mut list_state = ListState::default().with_selected(Some(0));
+    loop {
+        let matches = fuzzy::filter(&query, items);
+        terminal.draw(|frame| {
+            let [list_area, query_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+            let lines: Vec<String> = matches.iter().map(|i| items[*i].clone()).collect();
+            let list = List::new(lines)
+                .block(Block::bordered().title(format!("{}/{}", matches.len(), items.len())))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list, list_area, &mut list_state);
+            frame.render_widget(Paragraph::new(format!("> {}", query)), query_area);
+        })?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let selected = list_state.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(None);
+            }
+            KeyCode::Enter => return Ok(matches.get(selected).copied()),
+            KeyCode::Down if selected + 1 < matches.len() => list_state.select(Some(selected + 1)),
+            KeyCode::Up if selected > 0 => list_state.select(Some(selected - 1)),
+            KeyCode::Backspace => {
+                query.pop();
+                list_state.select(Some(0));
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                list_state.select(Some(0));
+            }
+            _ => (),
+        }
+    }
+}
+
+pub fn command_tui<'a>(games: &'a Games, _: &'a [String]) -> Result<(), GameError<'a>> {
+    let mut app = App::new(games);
+    loop {
+        app.load(read_all_stats(), read_sessions());
+        match run(&mut app, games) {
+            Ok(Action::Play(game)) => play_game(games, game)?,
+            Ok(Action::Quit) => return Ok(()),
+            Err(e) => return Err(GameError::TerminalError(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+
+    #[test]
+    fn test_filter_by_tag() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"dsda-doom\"
+        tags = [\"fps\"]
+
+        [games.tetris]
+        name = \"Tetris\"
+        cmd = \"tetris\"
+        tags = [\"puzzle\"]
+
+        [games.quake]
+        name = \"Quake\"
+        cmd = \"vkquake\"
+        tags = [\"fps\"]";
+        let games = parse_config(config).expect("Bad config");
+        let mut app = App::new(&games);
+        let names = |app: &App| -> Vec<String> {
+            app.visible_games(&games)
+                .iter()
+                .map(|g| g.name.clone())
+                .collect()
+        };
+        assert_eq!(names(&app), vec!["Doom", "Quake", "Tetris"]);
+        app.cycle_tag(true);
+        assert_eq!(names(&app), vec!["Doom", "Quake"]);
+        app.cycle_tag(true);
+        assert_eq!(names(&app), vec!["Tetris"]);
+        app.cycle_tag(true);
+        assert_eq!(names(&app).len(), 3);
+        app.cycle_tag(false);
+        assert_eq!(names(&app), vec!["Tetris"]);
+    }
+}
@@ -0,0 +1,400 @@
+use crate::game::GameError;
+use crate::hooks::Hook;
+use crate::http::{self, Request, Response};
+use crate::sessions::Session;
+use crate::{
+    Game, Games, api, data_dir, game_error_message, hooks_dir, metrics, mqtt, mqtt_event,
+    read_all_stats, read_sessions, record_session, run_hook, run_session_hook,
+};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use time::UtcDateTime;
+
+/// Set in the environment of games started by `game`, so that `watch` does not
+/// record their sessions a second time
+pub const GAME_ID_ENV_VAR: &str = "GAME_RS_GAME_ID";
+
+/// Programs that run many different games, so they say nothing about which
+/// game is running unless `process_name` is set
+const LAUNCHERS: [&str; 10] = [
+    "bwrap",
+    "dosbox",
+    "firejail",
+    "gamescope",
+    "mangohud",
+    "proton",
+    "scummvm",
+    "steam",
+    "wine",
+    "wine64",
+];
+
+/// The kernel truncates a process's `comm` name to this many bytes
+const COMM_LENGTH: usize = 15;
+
+/// How often the running processes are checked
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Where `watch --api` listens without `--listen`
+const DEFAULT_LISTEN_ADDRESS: &str = "127.0.0.1:9185";
+
+/// A running process, as seen in `/proc`
+pub struct Process {
+    /// The name of the executable, possibly truncated
+    pub comm: String,
+    /// The file name of the first argument of the command line
+    pub program: String,
+    /// The ID of the game, if the process was started by `game` or by one of
+    /// its games
+    pub launched_game: Option<String>,
+}
+
+impl Process {
+    fn read(dir: &Path) -> Option<Process> {
+        let comm = fs::read_to_string(dir.join("comm")).ok()?;
+        let cmdline = fs::read(dir.join("cmdline")).ok()?;
+        let argv0 = cmdline.split(|b| *b == 0).next().unwrap_or_default();
+        let argv0 = String::from_utf8_lossy(argv0);
+        let program = file_name(&argv0.replace('\\', "/"));
+        let environ = fs::read(dir.join("environ")).unwrap_or_default();
+        let marker = format!("{}=", GAME_ID_ENV_VAR);
+        let launched_game = environ
+            .split(|b| *b == 0)
+            .find_map(|var| var.strip_prefix(marker.as_bytes()))
+            .map(|game_id| String::from_utf8_lossy(game_id).to_string());
+        Some(Process {
+            comm: comm.trim_end().to_string(),
+            program,
+            launched_game,
+        })
+    }
+
+    fn matches(&self, process_name: &str) -> bool {
+        if self.program == process_name {
+            return true;
+        }
+        let truncated = match process_name.char_indices().nth(COMM_LENGTH) {
+            Some((i, _)) => &process_name[..i],
+            None => process_name,
+        };
+        self.comm == truncated
+    }
+}
+
+/// All processes that can be read from `/proc`
+pub fn list_processes() -> Vec<Process> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().parse::<u32>().is_ok())
+        .filter_map(|entry| Process::read(&entry.path()))
+        .collect()
+}
+
+/// The name of the process that shows the game is running: its
+/// `process_name`, or else the program in its command if that is not a
+/// launcher shared by other games
+pub fn process_name(game: &Game) -> Option<String> {
+    if let Some(process_name) = &game.process_name {
+        return Some(process_name.clone());
+    }
+    let program = file_name(game.command.first()?);
+    if LAUNCHERS.contains(&program.as_str()) {
+        None
+    } else {
+        Some(program)
+    }
+}
+
+/// The IDs of the games that have a process running that was not started by
+/// `game`
+pub fn running_games(process_names: &[(String, String)], processes: &[Process]) -> HashSet<String> {
+    process_names
+        .iter()
+        .filter(|(_, name)| {
+            processes
+                .iter()
+                .any(|process| process.launched_game.is_none() && process.matches(name))
+        })
+        .map(|(game_id, _)| game_id.clone())
+        .collect()
+}
+
+/// Whether the game is running, whether or not it was started by `game`
+pub fn is_running(game: &Game, processes: &[Process]) -> bool {
+    let process_name = process_name(game);
+    processes.iter().any(|process| {
+        process.launched_game.as_ref() == Some(&game.id)
+            || process_name
+                .as_ref()
+                .is_some_and(|name| process.matches(name))
+    })
+}
+
+/// The IDs of the games that were started by `game` and are still running
+pub fn launched_games(processes: &[Process]) -> HashSet<String> {
+    processes
+        .iter()
+        .filter_map(|process| process.launched_game.clone())
+        .collect()
+}
+
+fn file_name(path: &str) -> String {
+    match Path::new(path).file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// Keeps track of when each running game was first seen, so that a session
+/// can be recorded once it is no longer running
+#[derive(Default)]
+pub struct Watcher {
+    started: HashMap<String, UtcDateTime>,
+}
+
+impl Watcher {
+    /// Whether the game has been seen running and has not stopped since
+    pub fn is_watching(&self, game_id: &str) -> bool {
+        self.started.contains_key(game_id)
+    }
+
+    /// Update the running games, returning a session for each game that has
+    /// stopped running
+    pub fn update(&mut self, running: &HashSet<String>, now: UtcDateTime) -> Vec<Session> {
+        for game_id in running.iter() {
+            if !self.started.contains_key(game_id) {
+                info!("Game started: {}", game_id);
+                self.started.insert(game_id.clone(), now);
+            }
+        }
+        let stopped: Vec<String> = self
+            .started
+            .keys()
+            .filter(|game_id| !running.contains(*game_id))
+            .cloned()
+            .collect();
+        stopped
+            .into_iter()
+            .map(|game_id| {
+                info!("Game stopped: {}", game_id);
+                let start_time = self.started.remove(&game_id).unwrap();
+                let play_time = (now - start_time).whole_seconds().max(0) as u32;
+                Session::new(game_id, start_time, play_time, String::new())
+            })
+            .collect()
+    }
+}
+
+pub fn command_watch<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    let mut listen_address = None;
+    let mut enable_api = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => match args.next() {
+                Some(address) => listen_address = Some(address.as_str()),
+                None => return Err(GameError::InvalidArgument(arg)),
+            },
+            "--api" => enable_api = true,
+            _ => return Err(GameError::InvalidArgument(arg)),
+        }
+    }
+    if enable_api && listen_address.is_none() {
+        listen_address = Some(DEFAULT_LISTEN_ADDRESS);
+    }
+    let listener = match listen_address {
+        Some(address) => match TcpListener::bind(address) {
+            Ok(listener) => Some(listener),
+            Err(e) => return Err(GameError::CouldNotListen(e.to_string())),
+        },
+        None => None,
+    };
+    // Anyone who can reach the API can start games, so keep it to this machine
+    if enable_api
+        && let Some(listener) = &listener
+        && !listener.local_addr().is_ok_and(|a| a.ip().is_loopback())
+    {
+        return Err(GameError::CouldNotListen(
+            "the API can only listen on localhost".to_string(),
+        ));
+    }
+    let mut process_names: Vec<(String, String)> = games
+        .games
+        .values()
+        .filter_map(|game| Some((game.id.clone(), process_name(game)?)))
+        .collect();
+    process_names.sort();
+    for (game_id, process_name) in process_names.iter() {
+        debug!("Watching for {}: {}", game_id, process_name);
+    }
+    out!("Watching for {} games", process_names.len());
+    let api_token = if enable_api {
+        let path = data_dir().join(api::TOKEN_FILE);
+        let token = api::token(&path).map_err(|e| GameError::CouldNotListen(e.to_string()))?;
+        out!("API token in {}", path.display());
+        token
+    } else {
+        String::new()
+    };
+    let running_now: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    thread::scope(|scope| {
+        if let Some(listener) = listener {
+            out!("Listening on {}", listen_address.unwrap());
+            scope.spawn(|| {
+                http::serve(listener, |request| {
+                    handle_request(games, request, &running_now, enable_api, &api_token)
+                })
+            });
+        }
+        let hooks_dir = hooks_dir();
+        let mut watcher = Watcher::default();
+        let mut publishing = Vec::new();
+        loop {
+            publishing.retain(|event: &mqtt::Publishing| !event.is_finished());
+            let processes = list_processes();
+            let running = running_games(&process_names, &processes);
+            let now = UtcDateTime::now();
+            for game_id in running.iter().filter(|id| !watcher.is_watching(id)) {
+                let Some(game) = games.find(game_id) else {
+                    continue;
+                };
+                if let Some(mqtt) = &games.settings.mqtt {
+                    publishing.push(mqtt.publish("start", mqtt_event(game, None)));
+                }
+                let session_start = [("GAME_RS_SESSION_START", now.unix_timestamp().to_string())];
+                if let Err(e) = run_hook(&hooks_dir, Hook::PreSession, game, &session_start) {
+                    warn!("Hook failed: {}", e);
+                }
+            }
+            for mut session in watcher.update(&running, now) {
+                if let Some(mqtt) = &games.settings.mqtt
+                    && let Some(game) = games.find(session.id())
+                {
+                    let event = mqtt_event(game, Some(session.play_time_seconds()));
+                    publishing.push(mqtt.publish("stop", event));
+                }
+                if games
+                    .find(session.id())
+                    .is_some_and(|game| !game.track_stats)
+                {
+                    continue;
+                }
+                if let Some(version) = games.find(session.id()).and_then(|g| g.version.as_ref()) {
+                    session = session.with_version(version);
+                }
+                out!("{}", session.format());
+                if let Err(e) = record_session(&session) {
+                    warn!("Could not record session: {}", game_error_message(&e));
+                } else if let Some(game) = games.find(session.id()) {
+                    run_session_hook(&hooks_dir, game, &session);
+                }
+            }
+            let mut all_running = launched_games(&processes);
+            all_running.extend(running);
+            *running_now.lock().unwrap() = all_running;
+            thread::sleep(WATCH_INTERVAL);
+        }
+    })
+}
+
+/// Answer a request to the `watch` daemon
+fn handle_request(
+    games: &Games,
+    request: &Request,
+    running: &Mutex<HashSet<String>>,
+    enable_api: bool,
+    api_token: &str,
+) -> Response {
+    if enable_api && let Some(route) = request.path.strip_prefix("/api/") {
+        if let Some(response) = api::refuse(request, api_token) {
+            return response;
+        }
+        return api::handle_api_request(games, &request.method, route);
+    }
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/metrics") => {
+            let all_stats = read_all_stats();
+            let sessions = read_sessions();
+            let running = running.lock().unwrap();
+            let body = metrics::format(games, &all_stats, &sessions, &running);
+            Response::new(200, "text/plain; version=0.0.4", body)
+        }
+        _ => Response::not_found(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+    use time::Duration;
+
+    #[test]
+    fn test_process_name() {
+        let config = "
+        [games]
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"/usr/bin/dsda-doom -iwad doom.wad\"
+
+        [games.morrowind]
+        name = \"Morrowind\"
+        wine_exe = \"Morrowind.exe\"
+
+        [games.oblivion]
+        name = \"Oblivion\"
+        wine_exe = \"Oblivion.exe\"
+        process_name = \"Oblivion.exe\"";
+        let games = parse_config(config).expect("Bad config");
+        let name = |id: &str| process_name(games.find(id).unwrap());
+        assert_eq!(name("doom"), Some("dsda-doom".to_string()));
+        assert_eq!(name("morrowind"), None);
+        assert_eq!(name("oblivion"), Some("Oblivion.exe".to_string()));
+    }
+
+    #[test]
+    fn test_running_games() {
+        let process = |comm: &str, program: &str, launched_game: Option<&str>| Process {
+            comm: comm.to_string(),
+            program: program.to_string(),
+            launched_game: launched_game.map(|id| id.to_string()),
+        };
+        let processes = vec![
+            process("dsda-doom", "dsda-doom", None),
+            process("TheElderScrolls", "wine64-preloader", None),
+            process("vkquake", "vkquake", Some("quake")),
+        ];
+        let process_names = vec![
+            ("doom".to_string(), "dsda-doom".to_string()),
+            ("eso".to_string(), "TheElderScrollsOnline.exe".to_string()),
+            ("quake".to_string(), "vkquake".to_string()),
+        ];
+        let running = running_games(&process_names, &processes);
+        assert_eq!(running.len(), 2);
+        assert!(running.contains("doom") && running.contains("eso"));
+        assert_eq!(
+            launched_games(&processes),
+            HashSet::from(["quake".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_watcher_records_stopped_games() {
+        let mut watcher = Watcher::default();
+        let start = UtcDateTime::now();
+        let running = HashSet::from(["doom".to_string()]);
+        assert!(watcher.update(&running, start).is_empty());
+        let sessions = watcher.update(&HashSet::new(), start + Duration::minutes(10));
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id(), "doom");
+        assert_eq!(sessions[0].play_time_seconds(), 600);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::game::{Game, GameError};
+use crate::{Games, fsutil};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn command_tricks<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    if args.is_empty() {
+        return Err(GameError::NoGameId);
+    }
+    let game = match games.find(&args[0]) {
+        Some(game) => game,
+        None => return Err(GameError::NoSuchGame(&args[0])),
+    };
+    let tricks = match game.tricks_command(&args[1..]) {
+        Some(tricks) => tricks,
+        None => return Err(GameError::NoWinePrefix(game.id.clone())),
+    };
+    run_in_prefix(game, tricks)
+}
+
+pub fn command_winecfg<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    command_wine_tool(games, args, "winecfg")
+}
+
+pub fn command_regedit<'a>(games: &Games, args: &'a [String]) -> Result<(), GameError<'a>> {
+    command_wine_tool(games, args, "regedit")
+}
+
+fn command_wine_tool<'a>(
+    games: &Games,
+    args: &'a [String],
+    tool: &str,
+) -> Result<(), GameError<'a>> {
+    if args.is_empty() {
+        return Err(GameError::NoGameId);
+    }
+    let game = match games.find(&args[0]) {
+        Some(game) => game,
+        None => return Err(GameError::NoSuchGame(&args[0])),
+    };
+    let command = match game.wine_tool_command(tool, &args[1..]) {
+        Some(command) => command,
+        None => return Err(GameError::NoWinePrefix(game.id.clone())),
+    };
+    run_in_prefix(game, command)
+}
+
+/// Run a command with the game's env, which sets its wine prefix
+fn run_in_prefix<'a>(game: &Game, command: Vec<String>) -> Result<(), GameError<'a>> {
+    info!("Running command: {:?}", command);
+    let status = std::process::Command::new(&command[0])
+        .args(&command[1..])
+        .envs(game.env.iter())
+        .status();
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(_) => Err(GameError::CommandReturnedFailure(command.join(" "))),
+        Err(_) => Err(GameError::ExecutionFailed),
+    }
+}
+
+pub fn command_prefixes<'a>(games: &Games, _: &'a [String]) -> Result<(), GameError<'a>> {
+    let mut wine_versions: HashMap<String, Option<String>> = HashMap::new();
+    for (prefix, prefix_games) in wine_prefixes(games) {
+        let path = PathBuf::from(&prefix);
+        let size = fsutil::format_size(fsutil::dir_size(&path));
+        let wine = prefix_games[0]
+            .env
+            .get("WINE")
+            .map_or("wine", |w| w.as_str());
+        let version = proton_version(&path).or_else(|| {
+            wine_versions
+                .entry(wine.to_string())
+                .or_insert_with(|| wine_version(wine))
+                .clone()
+        });
+        let ids: Vec<&str> = prefix_games.iter().map(|game| game.id.as_str()).collect();
+        out!(
+            "{} ({}, {})",
+            prefix,
+            size,
+            version.as_deref().unwrap_or("unknown version")
+        );
+        out!("  {}", ids.join(", "));
+    }
+    Ok(())
+}
+
+/// The games using each WINEPREFIX, sorted by prefix and then game ID
+fn wine_prefixes(games: &Games) -> BTreeMap<String, Vec<&Game>> {
+    let mut prefixes: BTreeMap<String, Vec<&Game>> = BTreeMap::new();
+    for game in games.games.values() {
+        if let Some(prefix) = game.env.get("WINEPREFIX") {
+            prefixes.entry(prefix.to_string()).or_default().push(game);
+        }
+    }
+    for prefix_games in prefixes.values_mut() {
+        prefix_games.sort_by(|a, b| a.id.cmp(&b.id));
+    }
+    prefixes
+}
+
+/// Proton keeps the version that created a prefix next to it
+fn proton_version(prefix: &Path) -> Option<String> {
+    let version_file = prefix.parent()?.join("version");
+    let content = fs::read_to_string(version_file).ok()?;
+    content.split_whitespace().last().map(|s| s.to_string())
+}
+
+fn wine_version(wine: &str) -> Option<String> {
+    let output = std::process::Command::new(wine)
+        .arg("--version")
+        .output()
+        .ok()?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !version.is_empty() {
+        Some(version)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_config;
+
+    #[test]
+    fn test_wine_prefixes() {
+        let config = "
+        [games]
+        [games.oblivion]
+        name = \"Oblivion\"
+        wine_exe = \"Oblivion.exe\"
+        env = { WINEPREFIX = \"/prefixes/bethesda\" }
+
+        [games.morrowind]
+        name = \"Morrowind\"
+        wine_exe = \"Morrowind.exe\"
+        env = { WINEPREFIX = \"/prefixes/bethesda\" }
+
+        [games.doom]
+        name = \"Doom\"
+        cmd = \"gzdoom\"";
+        let games = parse_config(config).expect("Bad config");
+        let prefixes = wine_prefixes(&games);
+        assert_eq!(prefixes.len(), 1);
+        let ids: Vec<&str> = prefixes["/prefixes/bethesda"]
+            .iter()
+            .map(|game| game.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["morrowind", "oblivion"]);
+    }
+}
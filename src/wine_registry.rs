@@ -0,0 +1,77 @@
+/// The registry key that wine reads the screen DPI from
+const DPI_KEY: &str = r"HKEY_CURRENT_USER\Control Panel\Desktop";
+
+/// A registry value, as its `reg add` type and data
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegValue {
+    Dword(u32),
+    Sz(String),
+}
+
+/// A value to set in the game's wine prefix before launching it, like
+/// `HKEY_CURRENT_USER\Software\Wine\X11 Driver` `Decorated` = `"N"`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryTweak {
+    pub key: String,
+    pub name: String,
+    pub value: RegValue,
+}
+
+impl RegistryTweak {
+    /// Set the DPI that wine scales the game's windows and fonts for
+    pub fn dpi(dpi: u32) -> RegistryTweak {
+        RegistryTweak {
+            key: DPI_KEY.to_string(),
+            name: "LogPixels".to_string(),
+            value: RegValue::Dword(dpi),
+        }
+    }
+
+    /// The arguments to `reg` that set the value, replacing any already there
+    pub fn reg_add_args(&self) -> Vec<String> {
+        let (reg_type, data) = match &self.value {
+            RegValue::Dword(n) => ("REG_DWORD", n.to_string()),
+            RegValue::Sz(s) => ("REG_SZ", s.to_string()),
+        };
+        vec![
+            "add".to_string(),
+            self.key.to_string(),
+            "/v".to_string(),
+            self.name.to_string(),
+            "/t".to_string(),
+            reg_type.to_string(),
+            "/d".to_string(),
+            data,
+            "/f".to_string(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reg_add_args() {
+        assert_eq!(
+            RegistryTweak::dpi(144).reg_add_args(),
+            vec![
+                "add",
+                r"HKEY_CURRENT_USER\Control Panel\Desktop",
+                "/v",
+                "LogPixels",
+                "/t",
+                "REG_DWORD",
+                "/d",
+                "144",
+                "/f"
+            ]
+        );
+        let tweak = RegistryTweak {
+            key: r"HKEY_CURRENT_USER\Software\Wine\X11 Driver".to_string(),
+            name: "Decorated".to_string(),
+            value: RegValue::Sz("N".to_string()),
+        };
+        assert_eq!(tweak.reg_add_args()[5..8], ["REG_SZ", "/d", "N"]);
+    }
+}
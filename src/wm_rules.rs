@@ -0,0 +1,401 @@
+use std::env::var_os;
+use std::fs;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_DELAY_SECONDS: u64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowManager {
+    Hyprland,
+    Sway,
+    Wmctrl,
+}
+
+impl WindowManager {
+    pub fn parse(s: &str) -> Option<WindowManager> {
+        match s.to_lowercase().as_str() {
+            "hyprland" => Some(WindowManager::Hyprland),
+            "sway" => Some(WindowManager::Sway),
+            "wmctrl" => Some(WindowManager::Wmctrl),
+            _ => None,
+        }
+    }
+
+    /// Guess the window manager from the environment, falling back to
+    /// `wmctrl` under X11
+    pub fn detect() -> Option<WindowManager> {
+        if var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            Some(WindowManager::Hyprland)
+        } else if var_os("SWAYSOCK").is_some() {
+            Some(WindowManager::Sway)
+        } else if var_os("DISPLAY").is_some() {
+            Some(WindowManager::Wmctrl)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where and how a game's window should be placed once it has opened
+#[derive(Debug, Clone, PartialEq)]
+pub struct WmRules {
+    pub window_manager: Option<WindowManager>,
+    pub workspace: Option<String>,
+    pub fullscreen: bool,
+    pub delay_seconds: u64,
+    /// The class of the game's window, for when it is not opened by the
+    /// launched process or one of its children (like with Steam)
+    pub class: Option<String>,
+    /// The title of the game's window, if it has no class to match
+    pub title: Option<String>,
+}
+
+/// How the window manager is told which window the rules are for
+#[derive(Debug, Clone, PartialEq)]
+pub enum Window {
+    Pid(u32),
+    /// An X11 window ID, as listed by `wmctrl -l`
+    Id(String),
+    Class(String),
+    Title(String),
+}
+
+impl WmRules {
+    pub fn new() -> WmRules {
+        WmRules {
+            window_manager: None,
+            workspace: None,
+            fullscreen: false,
+            delay_seconds: DEFAULT_DELAY_SECONDS,
+            class: None,
+            title: None,
+        }
+    }
+
+    /// The commands that apply the rules to the given window
+    pub fn commands(&self, window: &Window) -> Vec<Vec<String>> {
+        let mut commands = Vec::new();
+        match self.window_manager {
+            None => warn!("No window manager found to apply window rules"),
+            Some(WindowManager::Hyprland) => {
+                let window = match window {
+                    Window::Pid(pid) => format!("pid:{}", pid),
+                    Window::Id(id) => format!("address:{}", id),
+                    Window::Class(class) => format!("class:{}", class),
+                    Window::Title(title) => format!("title:{}", title),
+                };
+                if let Some(workspace) = &self.workspace {
+                    commands.push(hyprctl(&[
+                        "movetoworkspacesilent",
+                        &format!("{},{}", workspace, window),
+                    ]));
+                }
+                if self.fullscreen {
+                    commands.push(hyprctl(&["focuswindow", &window]));
+                    commands.push(hyprctl(&["fullscreen", "0"]));
+                }
+            }
+            Some(WindowManager::Sway) => {
+                let criteria = match window {
+                    Window::Pid(pid) => format!("[pid={}]", pid),
+                    Window::Id(id) => format!("[con_id={}]", id),
+                    Window::Class(class) => format!("[class=\"{}\"]", class),
+                    Window::Title(title) => format!("[title=\"{}\"]", title),
+                };
+                if let Some(workspace) = &self.workspace {
+                    commands.push(to_command(&[
+                        "swaymsg",
+                        &criteria,
+                        "move",
+                        "container",
+                        "to",
+                        "workspace",
+                        workspace,
+                    ]));
+                }
+                if self.fullscreen {
+                    commands.push(to_command(&["swaymsg", &criteria, "fullscreen", "enable"]));
+                }
+            }
+            Some(WindowManager::Wmctrl) => {
+                let window = match window {
+                    Window::Pid(_) => {
+                        warn!("wmctrl can not find windows by process ID");
+                        return commands;
+                    }
+                    Window::Id(id) => vec!["-i", "-r", id],
+                    Window::Class(class) => vec!["-x", "-r", class],
+                    Window::Title(title) => vec!["-r", title],
+                };
+                if let Some(workspace) = &self.workspace {
+                    let mut args = vec!["wmctrl"];
+                    args.extend(window.iter());
+                    args.extend(["-t", workspace]);
+                    commands.push(to_command(&args));
+                }
+                if self.fullscreen {
+                    let mut args = vec!["wmctrl"];
+                    args.extend(window.iter());
+                    args.extend(["-b", "add,fullscreen"]);
+                    commands.push(to_command(&args));
+                }
+            }
+        }
+        commands
+    }
+
+    /// Apply the rules in the background once the game's window has had time
+    /// to open
+    pub fn apply(&self, pid: u32) {
+        let rules = self.clone();
+        let delay = Duration::from_secs(self.delay_seconds);
+        thread::spawn(move || {
+            thread::sleep(delay);
+            let Some(window) = rules.find_window(pid) else {
+                warn!("Could not find the game's window to apply window rules");
+                return;
+            };
+            for command in rules.commands(&window).iter() {
+                info!("Applying window rule: {:?}", command);
+                let result = Command::new(&command[0])
+                    .args(&command[1..])
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status();
+                match result {
+                    Ok(status) if status.success() => (),
+                    _ => warn!("Could not apply window rule: {:?}", command),
+                }
+            }
+        });
+    }
+
+    /// The game's window: the one with the configured class or title, or else
+    /// the first one opened by the launched process or its descendants, since
+    /// wrappers like `wine`, `gamescope` or a sandbox leave the window to
+    /// another process
+    fn find_window(&self, pid: u32) -> Option<Window> {
+        if let Some(class) = &self.class {
+            return Some(Window::Class(class.clone()));
+        }
+        if let Some(title) = &self.title {
+            return Some(Window::Title(title.clone()));
+        }
+        let windows = list_windows(self.window_manager?);
+        process_tree(pid).iter().find_map(|pid| {
+            windows
+                .iter()
+                .find(|(window_pid, _)| window_pid == pid)
+                .map(|(_, window)| window.clone())
+        })
+    }
+}
+
+/// The windows that are open and the processes they belong to
+fn list_windows(window_manager: WindowManager) -> Vec<(u32, Window)> {
+    let command: &[&str] = match window_manager {
+        WindowManager::Hyprland => &["hyprctl", "clients"],
+        WindowManager::Sway => &["swaymsg", "-t", "get_tree"],
+        WindowManager::Wmctrl => &["wmctrl", "-l", "-p"],
+    };
+    match Command::new(command[0])
+        .args(&command[1..])
+        .stderr(Stdio::null())
+        .output()
+    {
+        Ok(output) if output.status.success() => {
+            parse_windows(window_manager, &String::from_utf8_lossy(&output.stdout))
+        }
+        _ => {
+            warn!("Could not list windows: {:?}", command);
+            Vec::new()
+        }
+    }
+}
+
+/// Read the windows from the output of the window manager's list command
+fn parse_windows(window_manager: WindowManager, output: &str) -> Vec<(u32, Window)> {
+    match window_manager {
+        WindowManager::Hyprland => output
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("pid: "))
+            .filter_map(|pid| pid.parse().ok())
+            .map(|pid| (pid, Window::Pid(pid)))
+            .collect(),
+        WindowManager::Sway => output
+            .split("\"pid\":")
+            .skip(1)
+            .filter_map(|rest| {
+                let rest = rest.trim_start();
+                let end = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                rest[..end].parse().ok()
+            })
+            .map(|pid| (pid, Window::Pid(pid)))
+            .collect(),
+        WindowManager::Wmctrl => output
+            .lines()
+            .filter_map(|line| {
+                let mut columns = line.split_whitespace();
+                let id = columns.next()?;
+                let pid = columns.nth(1)?.parse().ok()?;
+                Some((pid, Window::Id(id.to_string())))
+            })
+            .collect(),
+    }
+}
+
+/// The process and all of its descendants, closest first
+fn process_tree(pid: u32) -> Vec<u32> {
+    let mut parents: Vec<(u32, u32)> = Vec::new();
+    for entry in fs::read_dir("/proc").into_iter().flatten().flatten() {
+        let Ok(child) = entry.file_name().to_string_lossy().parse::<u32>() else {
+            continue;
+        };
+        let Ok(stat) = fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        if let Some(parent) = parse_parent(&stat) {
+            parents.push((child, parent));
+        }
+    }
+    descendants(pid, &parents)
+}
+
+/// The parent process ID from the contents of `/proc/<pid>/stat`, which is
+/// the second field after the name, which is in parentheses and may contain
+/// spaces
+fn parse_parent(stat: &str) -> Option<u32> {
+    stat.rsplit_once(')')
+        .and_then(|(_, rest)| rest.split_whitespace().nth(1))
+        .and_then(|parent| parent.parse::<u32>().ok())
+}
+
+/// The process and all of its descendants, closest first, given the parent of
+/// each process as `(child, parent)` pairs
+fn descendants(pid: u32, parents: &[(u32, u32)]) -> Vec<u32> {
+    let mut tree = vec![pid];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        tree.extend(
+            parents
+                .iter()
+                .filter(|(_, p)| *p == parent)
+                .map(|(child, _)| *child),
+        );
+        i += 1;
+    }
+    tree
+}
+
+fn hyprctl(args: &[&str]) -> Vec<String> {
+    let mut command = to_command(&["hyprctl", "dispatch"]);
+    command.extend(args.iter().map(|s| s.to_string()));
+    command
+}
+
+fn to_command(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sway_commands() {
+        let mut rules = WmRules::new();
+        rules.window_manager = Some(WindowManager::Sway);
+        rules.workspace = Some("9".to_string());
+        rules.fullscreen = true;
+        assert_eq!(
+            rules.commands(&Window::Pid(1234)),
+            vec![
+                vec![
+                    "swaymsg",
+                    "[pid=1234]",
+                    "move",
+                    "container",
+                    "to",
+                    "workspace",
+                    "9"
+                ],
+                vec!["swaymsg", "[pid=1234]", "fullscreen", "enable"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hyprland_commands() {
+        let mut rules = WmRules::new();
+        rules.window_manager = Some(WindowManager::Hyprland);
+        rules.workspace = Some("games".to_string());
+        assert_eq!(
+            rules.commands(&Window::Pid(42)),
+            vec![vec![
+                "hyprctl",
+                "dispatch",
+                "movetoworkspacesilent",
+                "games,pid:42"
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_wmctrl_commands() {
+        let mut rules = WmRules::new();
+        rules.window_manager = Some(WindowManager::Wmctrl);
+        rules.workspace = Some("2".to_string());
+        assert_eq!(
+            rules.commands(&Window::Class("steam_app_22320".to_string())),
+            vec![vec!["wmctrl", "-x", "-r", "steam_app_22320", "-t", "2"]]
+        );
+    }
+
+    #[test]
+    fn test_parse_windows() {
+        let hyprland =
+            "Window 55d3c1a0b1e0 -> Morrowind:\n\tmapped: 1\n\tpid: 4321\n\tclass: openmw\n";
+        assert_eq!(
+            parse_windows(WindowManager::Hyprland, hyprland),
+            vec![(4321, Window::Pid(4321))]
+        );
+        let sway = "{\"id\": 1, \"nodes\": [{\"id\": 7, \"pid\": 4321, \"app_id\": null}]}";
+        assert_eq!(
+            parse_windows(WindowManager::Sway, sway),
+            vec![(4321, Window::Pid(4321))]
+        );
+        let wmctrl = "0x03a00003  0 4321   host Morrowind\n0x01200007 -1 1000   host Desktop\n";
+        assert_eq!(
+            parse_windows(WindowManager::Wmctrl, wmctrl),
+            vec![
+                (4321, Window::Id("0x03a00003".to_string())),
+                (1000, Window::Id("0x01200007".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_parent() {
+        let stat = "4321 (Morrowind (x86)) S 1200 4321 1200 0 -1 4194560";
+        assert_eq!(parse_parent(stat), Some(1200));
+        assert_eq!(parse_parent("4321 (openmw"), None);
+    }
+
+    #[test]
+    fn test_descendants() {
+        let parents = [
+            (1200, 1),
+            (4321, 1200),
+            (4400, 4321),
+            (4322, 1200),
+            (999, 1),
+        ];
+        assert_eq!(descendants(1200, &parents), vec![1200, 4321, 4322, 4400]);
+        assert_eq!(descendants(999, &parents), vec![999]);
+    }
+}